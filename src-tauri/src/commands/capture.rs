@@ -53,7 +53,20 @@ pub async fn crop_region(
     width: u32,
     height: u32,
 ) -> Result<String, String> {
-    let img = load_image_oriented(&image_path)?;
+    crop_to_captures(&app, &image_path, x, y, width, height)
+}
+
+/// Crops `image_path` to the given rectangle and saves the result as a new PNG
+/// in the captures directory, returning its path.
+pub fn crop_to_captures(
+    app: &tauri::AppHandle,
+    image_path: &str,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+) -> Result<String, String> {
+    let img = load_image_oriented(image_path)?;
 
     let (img_w, img_h) = img.dimensions();
     let x = x.min(img_w.saturating_sub(1));
@@ -63,7 +76,7 @@ pub async fn crop_region(
 
     let cropped = img.crop_imm(x, y, width, height);
 
-    let captures_dir = get_captures_dir(&app)?;
+    let captures_dir = get_captures_dir(app)?;
     let filename = format!("{}.png", Uuid::new_v4());
     let output_path = captures_dir.join(&filename);
 
@@ -0,0 +1,267 @@
+//! Taking cards out of rotation and changing their schedule by hand. A card is
+//! active, suspended (until unsuspended) or buried (until `buried_until`).
+//! Rescheduling, resetting and forgetting write an audit row into `reviews`
+//! with the matching `kind`, so the history explains every schedule jump;
+//! study stats count only `kind = 'review'` rows.
+
+use chrono::{DateTime, Local, NaiveDate, TimeZone, Utc};
+use sqlx::{Sqlite, Transaction};
+use uuid::Uuid;
+
+use super::database::{get_pool, now_iso};
+
+const DEFAULT_EASE: f64 = 2.5;
+
+#[tauri::command]
+pub async fn set_cards_suspended(
+    app: tauri::AppHandle,
+    flashcard_ids: Vec<String>,
+    suspended: bool,
+) -> Result<usize, String> {
+    let pool = get_pool(&app).await?;
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let now = now_iso();
+    for id in &flashcard_ids {
+        sqlx::query("UPDATE flashcards SET suspended = $1, updated_at = $2 WHERE id = $3")
+            .bind(suspended)
+            .bind(&now)
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to suspend card: {}", e))?;
+    }
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    Ok(flashcard_ids.len())
+}
+
+/// Hides cards until `until` (a date or timestamp), or until the start of
+/// tomorrow when no date is given. Their schedule is left alone, so they come
+/// back due as before.
+#[tauri::command]
+pub async fn bury_cards(
+    app: tauri::AppHandle,
+    flashcard_ids: Vec<String>,
+    until: Option<String>,
+) -> Result<usize, String> {
+    let until = match until {
+        Some(until) => parse_date(&until)?,
+        None => tomorrow_start(),
+    };
+    set_buried_until(&app, &flashcard_ids, Some(format_iso(until))).await
+}
+
+#[tauri::command]
+pub async fn unbury_cards(
+    app: tauri::AppHandle,
+    flashcard_ids: Vec<String>,
+) -> Result<usize, String> {
+    set_buried_until(&app, &flashcard_ids, None).await
+}
+
+/// Moves cards to a new due date as review cards, skipping any remaining
+/// learning steps. The interval becomes the time until then, so the next
+/// successful review grows from the new spacing.
+#[tauri::command]
+pub async fn reschedule_cards(
+    app: tauri::AppHandle,
+    flashcard_ids: Vec<String>,
+    due_date: String,
+) -> Result<usize, String> {
+    let due = parse_date(&due_date)?;
+    let interval_days = days_until(due, Utc::now());
+    let due = format_iso(due);
+    let pool = get_pool(&app).await?;
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let now = now_iso();
+    for id in &flashcard_ids {
+        record_audit(&mut tx, id, "reschedule", None, interval_days, &now).await?;
+        sqlx::query(
+            "UPDATE flashcards SET due_date = $1, interval_days = $2, base_interval_days = NULL,
+                buried_until = NULL, learning_state = 'review', learning_step = 0, updated_at = $3
+             WHERE id = $4",
+        )
+        .bind(&due)
+        .bind(interval_days)
+        .bind(&now)
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to reschedule card: {}", e))?;
+    }
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    Ok(flashcard_ids.len())
+}
+
+/// Restarts the schedule: default ease, no repetitions, due now. Lapses, the
+/// leech tag and suspension are kept.
+#[tauri::command]
+pub async fn reset_cards(
+    app: tauri::AppHandle,
+    flashcard_ids: Vec<String>,
+) -> Result<usize, String> {
+    restart_cards(&app, &flashcard_ids, "reset").await
+}
+
+/// Like `reset_cards`, but also clears lapses, the leech tag, suspension and
+/// burial, so the card counts as new again.
+#[tauri::command]
+pub async fn forget_cards(
+    app: tauri::AppHandle,
+    flashcard_ids: Vec<String>,
+) -> Result<usize, String> {
+    restart_cards(&app, &flashcard_ids, "forget").await
+}
+
+async fn restart_cards(
+    app: &tauri::AppHandle,
+    flashcard_ids: &[String],
+    kind: &str,
+) -> Result<usize, String> {
+    let forget = kind == "forget";
+    let pool = get_pool(app).await?;
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let now = now_iso();
+    for id in flashcard_ids {
+        record_audit(&mut tx, id, kind, Some(DEFAULT_EASE), 0.0, &now).await?;
+        sqlx::query(
+            "UPDATE flashcards SET
+                ease_factor = $1, interval_days = 0, base_interval_days = NULL,
+                repetitions = 0, due_date = $2,
+                buried_until = NULL, learning_state = 'new', learning_step = 0,
+                updated_at = $2,
+                lapses = CASE WHEN $3 THEN 0 ELSE lapses END,
+                leech = CASE WHEN $3 THEN 0 ELSE leech END,
+                suspended = CASE WHEN $3 THEN 0 ELSE suspended END,
+                last_reviewed = CASE WHEN $3 THEN NULL ELSE last_reviewed END,
+                introduced_at = CASE WHEN $3 THEN NULL ELSE introduced_at END
+             WHERE id = $4",
+        )
+        .bind(DEFAULT_EASE)
+        .bind(&now)
+        .bind(forget)
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to {} card: {}", kind, e))?;
+    }
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    Ok(flashcard_ids.len())
+}
+
+async fn set_buried_until(
+    app: &tauri::AppHandle,
+    flashcard_ids: &[String],
+    until: Option<String>,
+) -> Result<usize, String> {
+    let pool = get_pool(app).await?;
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let now = now_iso();
+    for id in flashcard_ids {
+        sqlx::query("UPDATE flashcards SET buried_until = $1, updated_at = $2 WHERE id = $3")
+            .bind(&until)
+            .bind(&now)
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to bury card: {}", e))?;
+    }
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    Ok(flashcard_ids.len())
+}
+
+/// Logs a manual change from the card's current state, before it is updated.
+/// `ease_after` of `None` means the ease is unchanged.
+async fn record_audit(
+    tx: &mut Transaction<'_, Sqlite>,
+    flashcard_id: &str,
+    kind: &str,
+    ease_after: Option<f64>,
+    interval_after: f64,
+    now: &str,
+) -> Result<(), String> {
+    sqlx::query(
+        "INSERT INTO reviews
+            (id, flashcard_id, kind, correct, ease_before, ease_after,
+             interval_before, interval_after, reviewed_at)
+         SELECT $1, id, $2, 0, ease_factor, COALESCE($3, ease_factor), interval_days, $4, $5
+         FROM flashcards WHERE id = $6",
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(kind)
+    .bind(ease_after)
+    .bind(interval_after)
+    .bind(now)
+    .bind(flashcard_id)
+    .execute(&mut **tx)
+    .await
+    .map_err(|e| format!("Failed to record {}: {}", kind, e))?;
+    Ok(())
+}
+
+/// Accepts an RFC 3339 timestamp or a plain `YYYY-MM-DD` date, which means
+/// the start of that day in local time.
+fn parse_date(value: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(value) {
+        return Ok(timestamp.with_timezone(&Utc));
+    }
+    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| format!("Invalid date: {}", value))?;
+    date.and_hms_opt(0, 0, 0)
+        .and_then(|dt| Local.from_local_datetime(&dt).earliest())
+        .map(|dt| dt.with_timezone(&Utc))
+        .ok_or_else(|| format!("Invalid date: {}", value))
+}
+
+fn tomorrow_start() -> DateTime<Utc> {
+    let tomorrow = Local::now().date_naive().succ_opt().unwrap_or_default();
+    tomorrow
+        .and_hms_opt(0, 0, 0)
+        .and_then(|dt| Local.from_local_datetime(&dt).earliest())
+        .map(|dt| dt.with_timezone(&Utc))
+        .unwrap_or_else(|| Utc::now() + chrono::Duration::days(1))
+}
+
+fn days_until(due: DateTime<Utc>, now: DateTime<Utc>) -> f64 {
+    ((due - now).num_minutes() as f64 / 1440.0).max(0.0)
+}
+
+fn format_iso(value: DateTime<Utc>) -> String {
+    value.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_dates_and_intervals() {
+        let due = parse_date("2026-03-10T12:00:00Z").unwrap();
+        assert_eq!(format_iso(due), "2026-03-10T12:00:00.000Z");
+        assert!(parse_date("2026-03-10").is_ok());
+        assert!(parse_date("next week").is_err());
+
+        let now = parse_date("2026-03-01T12:00:00Z").unwrap();
+        assert_eq!(days_until(due, now), 9.0);
+        assert_eq!(days_until(now, due), 0.0);
+    }
+}
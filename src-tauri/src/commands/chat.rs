@@ -0,0 +1,615 @@
+//! The AI chat assistant's tool loop. Data tools (folders, cards, stats) run
+//! here against the database; UI-only tools such as `set_editor_question` are
+//! handed back to the frontend, which runs them and calls again.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sqlx::sqlite::SqliteRow;
+use sqlx::{Column, Pool, Row, Sqlite, TypeInfo, ValueRef};
+use tauri::Emitter;
+
+use super::database::{get_pool, insert_flashcard, now_iso, study_stats, CreateFlashcardInput};
+use super::llm::{load_llm_config, send_chat_request};
+
+const DEFAULT_MAX_ITERATIONS: u32 = 6;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolCallFunction {
+    pub name: String,
+    pub arguments: String,
+}
+
+/// A tool call in OpenAI format, whichever provider produced it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolCall {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub function: ToolCallFunction,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProposedCard {
+    pub question: String,
+    pub answer: String,
+    pub folder_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatResponse {
+    pub content: Option<String>,
+    /// The conversation including every assistant turn and tool result added
+    /// during this call. Send it back as-is to continue.
+    pub messages: Vec<Value>,
+    /// UI tool calls for the frontend to run. Append one `tool` message per
+    /// call to `messages` and call `chat_completion` again.
+    pub pending_tool_calls: Vec<ToolCall>,
+    /// Cards from `propose_flashcards`, awaiting the user's confirmation.
+    pub proposed_cards: Vec<ProposedCard>,
+    /// Whether a tool wrote to the database.
+    pub data_changed: bool,
+}
+
+/// Runs the conversation until the model answers without calling tools, a UI
+/// tool needs the frontend, or `max_iterations` rounds of tool calls are used
+/// up (after which the model is asked to answer without tools). Rounds already
+/// in `messages` since the last user message count toward the limit, so
+/// handing UI tool results back does not start a fresh budget. Pass 0 for a
+/// single completion with no tools offered.
+#[tauri::command]
+pub async fn chat_completion(
+    app: tauri::AppHandle,
+    messages: Vec<Value>,
+    client_tools: Option<Vec<Value>>,
+    max_iterations: Option<u32>,
+) -> Result<ChatResponse, String> {
+    let config = load_llm_config(&app)?;
+    let pool = get_pool(&app).await?;
+    let client_tools = client_tools.unwrap_or_default();
+    let client_tool_names: Vec<&str> = client_tools
+        .iter()
+        .filter_map(|tool| tool["function"]["name"].as_str())
+        .collect();
+    let mut tools = data_tools();
+    tools.extend(client_tools.iter().cloned());
+    let tools = Value::Array(tools);
+
+    let mut response = ChatResponse {
+        content: None,
+        messages,
+        pending_tool_calls: Vec::new(),
+        proposed_cards: Vec::new(),
+        data_changed: false,
+    };
+
+    let rounds_used = tool_rounds_since_user(&response.messages);
+    for _ in rounds_used..max_iterations.unwrap_or(DEFAULT_MAX_ITERATIONS) {
+        let raw = send_chat_request(
+            &app,
+            &config,
+            &Value::Array(response.messages.clone()),
+            Some(&tools),
+            true,
+        )
+        .await?;
+        let (content, tool_calls) = normalize_response(&raw)?;
+        response.content = content.clone();
+        if tool_calls.is_empty() {
+            response
+                .messages
+                .push(json!({ "role": "assistant", "content": content }));
+            return Ok(response);
+        }
+
+        response.messages.push(json!({
+            "role": "assistant",
+            "content": content,
+            "tool_calls": tool_calls
+        }));
+        for call in tool_calls {
+            if client_tool_names.contains(&call.function.name.as_str()) {
+                response.pending_tool_calls.push(call);
+                continue;
+            }
+            let _ = app.emit("chat-tool-call", &call.function.name);
+            let args: Value = serde_json::from_str(&call.function.arguments).unwrap_or(json!({}));
+            let result = match execute_tool(&pool, &call.function.name, &args, &mut response).await
+            {
+                Ok(result) => result,
+                Err(e) => json!({ "error": e }),
+            };
+            response.messages.push(json!({
+                "role": "tool",
+                "tool_call_id": call.id,
+                "name": call.function.name,
+                "content": result.to_string()
+            }));
+        }
+        if !response.pending_tool_calls.is_empty() {
+            return Ok(response);
+        }
+    }
+
+    // Out of tool rounds: ask for a final answer without tool calls. Tools stay
+    // declared when the history holds tool blocks.
+    let has_tool_blocks = response
+        .messages
+        .iter()
+        .any(|message| message["role"] == "tool" || message.get("tool_calls").is_some());
+    let raw = send_chat_request(
+        &app,
+        &config,
+        &Value::Array(response.messages.clone()),
+        has_tool_blocks.then_some(&tools),
+        false,
+    )
+    .await?;
+    let (content, _) = normalize_response(&raw)?;
+    response
+        .messages
+        .push(json!({ "role": "assistant", "content": content }));
+    response.content = content;
+    Ok(response)
+}
+
+/// Assistant turns with tool calls after the last user message.
+fn tool_rounds_since_user(messages: &[Value]) -> u32 {
+    messages
+        .iter()
+        .rev()
+        .take_while(|message| message["role"] != "user")
+        .filter(|message| {
+            message["role"] == "assistant"
+                && message["tool_calls"].as_array().is_some_and(|calls| !calls.is_empty())
+        })
+        .count() as u32
+}
+
+/// Reads the text and tool calls from an OpenAI-style or Anthropic response.
+fn normalize_response(raw: &Value) -> Result<(Option<String>, Vec<ToolCall>), String> {
+    if let Some(message) = raw["choices"][0].get("message") {
+        let content = message["content"].as_str().map(str::to_string);
+        let tool_calls = message["tool_calls"]
+            .as_array()
+            .map(|calls| {
+                calls
+                    .iter()
+                    .enumerate()
+                    .map(|(i, call)| {
+                        tool_call(
+                            &call["id"],
+                            &call["function"]["name"],
+                            &call["function"]["arguments"],
+                            i,
+                        )
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        return Ok((content.filter(|c| !c.is_empty()), tool_calls));
+    }
+
+    if let Some(blocks) = raw["content"].as_array() {
+        let mut text = String::new();
+        let mut tool_calls = Vec::new();
+        for (i, block) in blocks.iter().enumerate() {
+            match block["type"].as_str() {
+                Some("text") => text.push_str(block["text"].as_str().unwrap_or("")),
+                Some("tool_use") => {
+                    tool_calls.push(tool_call(&block["id"], &block["name"], &block["input"], i))
+                }
+                _ => {}
+            }
+        }
+        return Ok((Some(text).filter(|t| !t.is_empty()), tool_calls));
+    }
+
+    Err(format!("Unexpected response format: {}", raw))
+}
+
+fn tool_call(id: &Value, name: &Value, arguments: &Value, index: usize) -> ToolCall {
+    ToolCall {
+        id: id
+            .as_str()
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("call_{}", index)),
+        kind: "function".to_string(),
+        function: ToolCallFunction {
+            name: name.as_str().unwrap_or("").to_string(),
+            // Some OpenAI-compatible servers send arguments as an object.
+            arguments: match arguments {
+                Value::String(s) => s.clone(),
+                Value::Null => "{}".to_string(),
+                other => other.to_string(),
+            },
+        },
+    }
+}
+
+fn data_tools() -> Vec<Value> {
+    let tool = |name: &str, description: &str, parameters: Value| {
+        json!({
+            "type": "function",
+            "function": { "name": name, "description": description, "parameters": parameters }
+        })
+    };
+    let folder_param = |description: &str, required: bool| {
+        json!({
+            "type": "object",
+            "properties": { "folder_id": { "type": "string", "description": description } },
+            "required": if required { json!(["folder_id"]) } else { json!([]) }
+        })
+    };
+    let card_param = json!({
+        "type": "object",
+        "properties": { "flashcard_id": { "type": "string", "description": "The flashcard ID" } },
+        "required": ["flashcard_id"]
+    });
+
+    vec![
+        tool(
+            "get_all_folders",
+            "List every deck/folder the user has, including card counts and deadlines.",
+            json!({ "type": "object", "properties": {}, "required": [] }),
+        ),
+        tool(
+            "get_flashcards",
+            "Get all flashcards in a specific folder. Returns card content, SRS stats (ease, interval, reps, due date), and timer settings.",
+            folder_param("The folder ID to fetch cards from", true),
+        ),
+        tool(
+            "get_due_flashcards",
+            "Get flashcards that are currently due for review. Optionally filter by folder.",
+            folder_param("Optional folder ID to filter by", false),
+        ),
+        tool(
+            "get_study_stats",
+            "Get study statistics: total cards, due today, overdue, reviewed today, accuracy today. Optionally scoped to a folder.",
+            folder_param("Optional folder ID", false),
+        ),
+        tool(
+            "get_flashcard_detail",
+            "Get full details of a single flashcard by ID, including its question content (LaTeX or image path), answer, SRS parameters, and review history.",
+            card_param.clone(),
+        ),
+        tool(
+            "get_review_history",
+            "Get the review history for a flashcard — timestamps, correct/incorrect, speed ratios, ease changes.",
+            card_param,
+        ),
+        tool(
+            "create_flashcard",
+            "Create a single flashcard in a specific folder. Use $...$ for inline math and $$...$$ for display math in question/answer text. The card is saved immediately.",
+            json!({
+                "type": "object",
+                "properties": {
+                    "folder_id": { "type": "string", "description": "The folder ID to create the card in" },
+                    "question": { "type": "string", "description": "The question text (use $...$ for math)" },
+                    "answer": { "type": "string", "description": "The answer text (use $...$ for math)" }
+                },
+                "required": ["folder_id", "question"]
+            }),
+        ),
+        tool(
+            "propose_flashcards",
+            "Propose multiple flashcards for the user to review before creating them. Returns a preview. The user must confirm before cards are saved. Use this when the user asks to create many cards at once or when bulk-generating cards on a topic.",
+            json!({
+                "type": "object",
+                "properties": {
+                    "folder_id": { "type": "string", "description": "The folder ID to create cards in" },
+                    "cards": {
+                        "type": "array",
+                        "description": "Array of proposed flashcards",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "question": { "type": "string", "description": "The question text" },
+                                "answer": { "type": "string", "description": "The answer text" }
+                            },
+                            "required": ["question", "answer"]
+                        }
+                    }
+                },
+                "required": ["folder_id", "cards"]
+            }),
+        ),
+    ]
+}
+
+async fn execute_tool(
+    pool: &Pool<Sqlite>,
+    name: &str,
+    args: &Value,
+    response: &mut ChatResponse,
+) -> Result<Value, String> {
+    let folder_id = args["folder_id"].as_str().filter(|id| !id.is_empty());
+    let flashcard_id = || {
+        args["flashcard_id"]
+            .as_str()
+            .ok_or_else(|| "flashcard_id is required".to_string())
+    };
+
+    match name {
+        "get_all_folders" => {
+            let folders =
+                fetch_json(pool, "SELECT id, name, emoji, deadline FROM folders ORDER BY position ASC, created_at ASC", &[])
+                    .await?;
+            let mut results = Vec::new();
+            for folder in folders {
+                let stats = study_stats(pool, folder["id"].as_str()).await?;
+                results.push(json!({
+                    "id": folder["id"],
+                    "name": folder["name"],
+                    "emoji": folder["emoji"],
+                    "deadline": folder["deadline"],
+                    "total_cards": stats.total_cards,
+                    "due_today": stats.due_today,
+                    "reviewed_today": stats.reviewed_today,
+                    "accuracy": format!("{}%", (stats.accuracy_today * 100.0).round()),
+                }));
+            }
+            Ok(Value::Array(results))
+        }
+
+        "get_flashcards" => {
+            let folder_id = folder_id.ok_or_else(|| "folder_id is required".to_string())?;
+            let cards = fetch_json(
+                pool,
+                "SELECT * FROM flashcards WHERE folder_id = ? ORDER BY created_at DESC",
+                &[folder_id],
+            )
+            .await?;
+            Ok(cards
+                .iter()
+                .map(|c| {
+                    json!({
+                        "id": c["id"],
+                        "title": c["title"],
+                        "question_type": c["question_type"],
+                        "question_preview": question_preview(c),
+                        "has_answer": c["answer_content"].as_str().map(|a| !a.is_empty()).unwrap_or(false),
+                        "timer_mode": c["timer_mode"],
+                        "ease_factor": c["ease_factor"],
+                        "interval_days": round_interval(&c["interval_days"]),
+                        "repetitions": c["repetitions"],
+                        "due_date": c["due_date"],
+                        "last_reviewed": c["last_reviewed"],
+                    })
+                })
+                .collect())
+        }
+
+        "get_due_flashcards" => {
+            let now = now_iso();
+            let cards = match folder_id {
+                Some(folder_id) => {
+                    fetch_json(
+                        pool,
+                        "SELECT * FROM flashcards WHERE folder_id = ? AND suspended = 0 AND (buried_until IS NULL OR buried_until <= ?) AND (due_date IS NULL OR due_date <= ?) ORDER BY due_date ASC",
+                        &[folder_id, &now, &now],
+                    )
+                    .await?
+                }
+                None => {
+                    fetch_json(
+                        pool,
+                        "SELECT * FROM flashcards WHERE suspended = 0 AND (buried_until IS NULL OR buried_until <= ?) AND (due_date IS NULL OR due_date <= ?) ORDER BY due_date ASC",
+                        &[&now, &now],
+                    )
+                    .await?
+                }
+            };
+            Ok(cards
+                .iter()
+                .map(|c| {
+                    json!({
+                        "id": c["id"],
+                        "title": c["title"],
+                        "question_preview": question_preview(c),
+                        "ease_factor": c["ease_factor"],
+                        "interval_days": round_interval(&c["interval_days"]),
+                        "repetitions": c["repetitions"],
+                        "due_date": c["due_date"],
+                    })
+                })
+                .collect())
+        }
+
+        "get_study_stats" => {
+            let stats = study_stats(pool, folder_id).await?;
+            serde_json::to_value(stats).map_err(|e| format!("Failed to serialize: {}", e))
+        }
+
+        "get_flashcard_detail" => {
+            let mut card = fetch_json(
+                pool,
+                "SELECT * FROM flashcards WHERE id = ?",
+                &[flashcard_id()?],
+            )
+            .await?
+            .pop()
+            .ok_or_else(|| "Flashcard not found".to_string())?;
+            if card["question_type"] != "latex" {
+                card["question_content"] = json!(format!(
+                    "[image at {}]",
+                    card["question_content"].as_str().unwrap_or("")
+                ));
+            }
+            if card["answer_type"] == "image" && !card["answer_content"].is_null() {
+                card["answer_content"] = json!("[image]");
+            }
+            Ok(card)
+        }
+
+        "get_review_history" => {
+            let reviews = fetch_json(
+                pool,
+                "SELECT * FROM reviews WHERE flashcard_id = ? ORDER BY reviewed_at DESC LIMIT 20",
+                &[flashcard_id()?],
+            )
+            .await?;
+            Ok(Value::Array(reviews))
+        }
+
+        "create_flashcard" => {
+            let question = args["question"]
+                .as_str()
+                .ok_or_else(|| "question is required".to_string())?;
+            let answer = args["answer"].as_str().filter(|a| !a.is_empty());
+            let card_id = insert_flashcard(
+                pool,
+                &CreateFlashcardInput {
+                    folder_id: folder_id.map(str::to_string),
+                    title: None,
+                    question_type: "latex".to_string(),
+                    question_content: question.to_string(),
+                    answer_type: answer.map(|_| "latex".to_string()),
+                    answer_content: answer.map(str::to_string),
+                    timer_mode: None,
+                    timer_seconds: None,
+                },
+            )
+            .await?;
+            response.data_changed = true;
+            Ok(json!({
+                "success": true,
+                "card_id": card_id,
+                "message": "Flashcard created successfully"
+            }))
+        }
+
+        "propose_flashcards" => {
+            let cards: Vec<ProposedCard> = args["cards"]
+                .as_array()
+                .ok_or_else(|| "cards must be a list".to_string())?
+                .iter()
+                .map(|c| ProposedCard {
+                    question: c["question"].as_str().unwrap_or("").to_string(),
+                    answer: c["answer"].as_str().unwrap_or("").to_string(),
+                    folder_id: folder_id.map(str::to_string),
+                })
+                .collect();
+            let count = cards.len();
+            response.proposed_cards.extend(cards);
+            Ok(json!({
+                "success": true,
+                "count": count,
+                "message": format!("Proposed {} cards. The user will review and confirm them in the UI.", count)
+            }))
+        }
+
+        _ => Err(format!("Unknown tool: {}", name)),
+    }
+}
+
+fn question_preview(card: &Value) -> Value {
+    if card["question_type"] == "latex" {
+        json!(card["question_content"]
+            .as_str()
+            .unwrap_or("")
+            .chars()
+            .take(120)
+            .collect::<String>())
+    } else {
+        json!("[image]")
+    }
+}
+
+fn round_interval(value: &Value) -> Value {
+    json!((value.as_f64().unwrap_or(0.0) * 10.0).round() / 10.0)
+}
+
+/// Runs a query and returns each row as a JSON object keyed by column name.
+async fn fetch_json(pool: &Pool<Sqlite>, sql: &str, binds: &[&str]) -> Result<Vec<Value>, String> {
+    let mut query = sqlx::query(sql);
+    for bind in binds {
+        query = query.bind(*bind);
+    }
+    let rows = query
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("Failed to query database: {}", e))?;
+    Ok(rows.iter().map(row_to_json).collect())
+}
+
+fn row_to_json(row: &SqliteRow) -> Value {
+    let mut object = serde_json::Map::new();
+    for column in row.columns() {
+        let i = column.ordinal();
+        let value = match row.try_get_raw(i) {
+            Ok(raw) if !raw.is_null() => match raw.type_info().name() {
+                "INTEGER" => row.try_get::<i64, _>(i).map(Value::from),
+                "REAL" => row.try_get::<f64, _>(i).map(Value::from),
+                _ => row.try_get::<String, _>(i).map(Value::from),
+            }
+            .unwrap_or(Value::Null),
+            _ => Value::Null,
+        };
+        object.insert(column.name().to_string(), value);
+    }
+    Value::Object(object)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_openai_tool_calls() {
+        let raw = json!({
+            "choices": [{ "message": {
+                "content": null,
+                "tool_calls": [{
+                    "id": "call_1",
+                    "type": "function",
+                    "function": { "name": "get_study_stats", "arguments": "{}" }
+                }]
+            }}]
+        });
+        let (content, calls) = normalize_response(&raw).unwrap();
+        assert_eq!(content, None);
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].id, "call_1");
+        assert_eq!(calls[0].function.name, "get_study_stats");
+    }
+
+    #[test]
+    fn normalizes_anthropic_tool_use() {
+        let raw = json!({
+            "content": [
+                { "type": "text", "text": "Looking that up." },
+                { "type": "tool_use", "id": "toolu_1", "name": "get_flashcards", "input": { "folder_id": "f1" } }
+            ]
+        });
+        let (content, calls) = normalize_response(&raw).unwrap();
+        assert_eq!(content.as_deref(), Some("Looking that up."));
+        assert_eq!(calls[0].id, "toolu_1");
+        let args: Value = serde_json::from_str(&calls[0].function.arguments).unwrap();
+        assert_eq!(args["folder_id"], "f1");
+    }
+
+    #[test]
+    fn rejects_unknown_response_shape() {
+        assert!(normalize_response(&json!({ "error": "overloaded" })).is_err());
+    }
+
+    #[test]
+    fn counts_tool_rounds_since_the_last_user_message() {
+        let round = || json!({ "role": "assistant", "content": null, "tool_calls": [{ "id": "c" }] });
+        let result = || json!({ "role": "tool", "tool_call_id": "c", "content": "{}" });
+        let messages = vec![
+            json!({ "role": "user", "content": "Earlier question" }),
+            round(),
+            result(),
+            json!({ "role": "assistant", "content": "Earlier answer" }),
+            json!({ "role": "user", "content": "Fill in the editor" }),
+            round(),
+            result(),
+            round(),
+            result(),
+        ];
+        assert_eq!(tool_rounds_since_user(&messages), 2);
+        assert_eq!(tool_rounds_since_user(&messages[..4]), 1);
+        assert_eq!(tool_rounds_since_user(&messages[..5]), 0);
+    }
+}
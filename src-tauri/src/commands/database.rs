@@ -1,5 +1,14 @@
 use serde::{Deserialize, Serialize};
-use tauri_plugin_sql::{Migration, MigrationKind};
+use sqlx::{Pool, Sqlite};
+use tauri::Manager;
+use tauri_plugin_sql::{DbInstances, DbPool, Migration, MigrationKind};
+use uuid::Uuid;
+
+use crate::review_policy::{
+    nearest_deadline, scheduled_due_count, CardState, FolderPolicy, Milestone,
+};
+
+pub const DB_URL: &str = "sqlite:flashmath.db";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Folder {
@@ -11,6 +20,15 @@ pub struct Folder {
     pub review_cards_per_day: i32,
     pub review_target_mode: String,
     pub auto_target_reps: i32,
+    pub leech_threshold: i32,
+    pub leech_action: String,
+    pub learning_steps: String,
+    pub relearning_steps: String,
+    pub new_cards_per_day: i32,
+    /// `created`, `random` or `difficulty`.
+    pub new_card_order: String,
+    /// Recall the exam planner aims for on the deadline, e.g. 0.9.
+    pub target_recall: f64,
     pub created_at: String,
     pub updated_at: String,
 }
@@ -28,9 +46,26 @@ pub struct Flashcard {
     pub timer_seconds: i32,
     pub ease_factor: f64,
     pub interval_days: f64,
+    /// Interval before deadline compression, while it is compressed.
+    pub base_interval_days: Option<f64>,
     pub repetitions: i32,
     pub due_date: Option<String>,
     pub last_reviewed: Option<String>,
+    pub verification_status: Option<String>,
+    pub verification_note: Option<String>,
+    pub template_params: Option<String>,
+    pub question_ocr: Option<String>,
+    pub answer_ocr: Option<String>,
+    pub lapses: i32,
+    pub leech: bool,
+    pub suspended: bool,
+    pub buried_until: Option<String>,
+    /// `new`, `learning`, `review` or `relearning`.
+    pub learning_state: String,
+    /// Index into the folder's (re)learning steps while learning.
+    pub learning_step: i32,
+    /// First review; cards without one are new.
+    pub introduced_at: Option<String>,
     pub created_at: String,
     pub updated_at: String,
 }
@@ -72,6 +107,16 @@ pub struct ReviewRecord {
     pub ease_after: f64,
     pub interval_before: f64,
     pub interval_after: f64,
+    pub hints_used: i32,
+    pub template_seed: Option<i64>,
+    pub typed_answer: Option<String>,
+    pub work_image: Option<String>,
+    pub work_verdict: Option<String>,
+    /// `review`, or the manual change this row records: `reschedule`,
+    /// `reset` or `forget`.
+    pub kind: String,
+    /// The study session the review was answered in, if any.
+    pub session_id: Option<String>,
     pub reviewed_at: String,
 }
 
@@ -80,6 +125,12 @@ pub struct ReviewInputCmd {
     pub flashcard_id: String,
     pub correct: bool,
     pub response_time_seconds: f64,
+    pub hints_used: i32,
+    pub template_seed: Option<i64>,
+    pub typed_answer: Option<String>,
+    pub work_image: Option<String>,
+    pub work_verdict: Option<String>,
+    pub session_id: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -100,6 +151,200 @@ pub struct StudyStats {
     pub accuracy_today: f64,
 }
 
+/// Returns the pool the SQL plugin opened for `DB_URL`, so backend commands read
+/// and write the same database (with the same migrations) as the frontend.
+pub async fn get_pool(app: &tauri::AppHandle) -> Result<Pool<Sqlite>, String> {
+    let instances = app.state::<DbInstances>();
+    let instances = instances.0.read().await;
+    match instances.get(DB_URL) {
+        Some(DbPool::Sqlite(pool)) => Ok(pool.clone()),
+        _ => Err("Database not loaded".to_string()),
+    }
+}
+
+pub fn now_iso() -> String {
+    chrono::Utc::now()
+        .format("%Y-%m-%dT%H:%M:%S%.3fZ")
+        .to_string()
+}
+
+/// Inserts a new card with default SRS state, due immediately. Mirrors
+/// `createFlashcard` in `src/lib/database.ts`.
+pub async fn insert_flashcard<'e, E>(
+    executor: E,
+    data: &CreateFlashcardInput,
+) -> Result<String, String>
+where
+    E: sqlx::Executor<'e, Database = Sqlite>,
+{
+    let id = Uuid::new_v4().to_string();
+    let now = now_iso();
+    sqlx::query(
+        r#"INSERT INTO flashcards
+            (id, folder_id, title, question_type, question_content, answer_type, answer_content,
+             timer_mode, timer_seconds, ease_factor, interval_days, repetitions, due_date,
+             created_at, updated_at)
+           VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, 2.5, 0, 0, ?, ?, ?)"#,
+    )
+    .bind(&id)
+    .bind(&data.folder_id)
+    .bind(&data.title)
+    .bind(&data.question_type)
+    .bind(&data.question_content)
+    .bind(&data.answer_type)
+    .bind(&data.answer_content)
+    .bind(data.timer_mode.as_deref().unwrap_or("5min"))
+    .bind(data.timer_seconds.unwrap_or(300))
+    .bind(&now)
+    .bind(&now)
+    .bind(&now)
+    .execute(executor)
+    .await
+    .map_err(|e| format!("Failed to create flashcard: {}", e))?;
+    Ok(id)
+}
+
+/// Start of the local day as a UTC ISO timestamp, comparable with `reviewed_at`.
+pub fn today_start_iso() -> String {
+    let midnight = chrono::Local::now()
+        .date_naive()
+        .and_hms_opt(0, 0, 0)
+        .and_then(|dt| dt.and_local_timezone(chrono::Local).earliest())
+        .map(|dt| dt.with_timezone(&chrono::Utc))
+        .unwrap_or_else(chrono::Utc::now);
+    midnight.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string()
+}
+
+/// Folder policies, each with `deadline` moved up to its nearest upcoming
+/// milestone so daily limits and interleaving work toward that one first.
+pub async fn load_folder_policies(pool: &Pool<Sqlite>) -> Result<Vec<FolderPolicy>, String> {
+    let mut folders = sqlx::query_as::<_, FolderPolicy>(
+        r#"SELECT id, deadline,
+                  COALESCE(review_cards_per_day, 20) AS review_cards_per_day,
+                  COALESCE(review_target_mode, 'fixed') AS review_target_mode,
+                  COALESCE(auto_target_reps, 3) AS auto_target_reps,
+                  COALESCE(new_cards_per_day, 20) AS new_cards_per_day,
+                  COALESCE(new_card_order, 'created') AS new_card_order
+           FROM folders
+           ORDER BY position, name"#,
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load folders: {}", e))?;
+
+    let milestones = load_milestones(pool, None).await?;
+    let today = chrono::Local::now().date_naive();
+    for folder in &mut folders {
+        let own: Vec<Milestone> = milestones
+            .iter()
+            .filter(|m| m.folder_id == folder.id)
+            .cloned()
+            .collect();
+        folder.deadline = nearest_deadline(folder.deadline.as_deref(), &own, None, today)
+            .map(str::to_string);
+    }
+    Ok(folders)
+}
+
+/// Milestones for one folder, or for every folder when `folder_id` is `None`.
+pub async fn load_milestones(
+    pool: &Pool<Sqlite>,
+    folder_id: Option<&str>,
+) -> Result<Vec<Milestone>, String> {
+    let rows: Vec<(String, String, Option<String>)> = sqlx::query_as(
+        "SELECT folder_id, date, card_ids FROM folder_milestones
+         WHERE $1 IS NULL OR folder_id = $1
+         ORDER BY date",
+    )
+    .bind(folder_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load milestones: {}", e))?;
+    Ok(rows
+        .into_iter()
+        .map(|(folder_id, date, card_ids)| Milestone {
+            folder_id,
+            date,
+            card_ids: card_ids.and_then(|ids| serde_json::from_str(&ids).ok()),
+        })
+        .collect())
+}
+
+/// Card scheduling state for one folder, or for every foldered card (plus
+/// unfiled ones with `include_unfiled`) when `folder_id` is `None`.
+pub async fn load_card_states(
+    pool: &Pool<Sqlite>,
+    folder_id: Option<&str>,
+    include_unfiled: bool,
+) -> Result<Vec<CardState>, String> {
+    sqlx::query_as::<_, CardState>(
+        r#"SELECT id, folder_id,
+                  COALESCE(repetitions, 0) AS repetitions,
+                  CAST(COALESCE(interval_days, 0) AS REAL) AS interval_days,
+                  due_date, last_reviewed,
+                  COALESCE(suspended, 0) != 0 AS suspended, buried_until,
+                  COALESCE(learning_state, 'review') AS learning_state,
+                  introduced_at,
+                  COALESCE(timer_seconds, 0) AS timer_seconds, created_at
+           FROM flashcards
+           WHERE (?1 IS NULL AND (?2 OR folder_id IS NOT NULL)) OR folder_id = ?1"#,
+    )
+    .bind(folder_id)
+    .bind(include_unfiled)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load flashcards: {}", e))
+}
+
+/// Mirrors `getStudyStats` in `src/lib/database.ts`.
+pub async fn study_stats(pool: &Pool<Sqlite>, folder_id: Option<&str>) -> Result<StudyStats, String> {
+    let now = now_iso();
+    let today = today_start_iso();
+    let folders = load_folder_policies(pool).await?;
+    let cards = load_card_states(pool, folder_id, false).await?;
+
+    let due: Vec<&CardState> = cards.iter().filter(|card| card.is_due(&now)).collect();
+    // Cards reviewed correctly today are reviewed today and now scheduled for a
+    // later day; cards still in (re)learning come back within the day.
+    let reviewed_correctly: Vec<&CardState> = cards
+        .iter()
+        .filter(|card| {
+            !card.is_learning()
+                && card.last_reviewed.as_deref().map(|r| r >= today.as_str()).unwrap_or(false)
+                && card.due_date.as_deref().map(|d| d > now.as_str()).unwrap_or(false)
+        })
+        .collect();
+    let mut original_pool = due.clone();
+    original_pool.extend(reviewed_correctly.iter().copied());
+    let original_scheduled = scheduled_due_count(&original_pool, &folders, &cards, &now, &today);
+    let due_today = (original_scheduled - reviewed_correctly.len() as i64).max(0);
+
+    let (reviewed_today, correct_today): (i64, i64) = sqlx::query_as(
+        r#"SELECT COUNT(*), COALESCE(SUM(CASE WHEN r.correct = 1 THEN 1 ELSE 0 END), 0)
+           FROM reviews r
+           JOIN flashcards f ON f.id = r.flashcard_id
+           WHERE r.reviewed_at >= ?1 AND r.kind = 'review'
+             AND ((?2 IS NULL AND f.folder_id IS NOT NULL) OR f.folder_id = ?2)"#,
+    )
+    .bind(&today)
+    .bind(folder_id)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| format!("Failed to load reviews: {}", e))?;
+
+    Ok(StudyStats {
+        total_cards: cards.len() as i32,
+        due_today: due_today as i32,
+        overdue: due.len() as i32,
+        reviewed_today: reviewed_today as i32,
+        accuracy_today: if reviewed_today > 0 {
+            correct_today as f64 / reviewed_today as f64
+        } else {
+            0.0
+        },
+    })
+}
+
 pub fn get_migrations() -> Vec<Migration> {
     vec![
         Migration {
@@ -187,5 +432,501 @@ pub fn get_migrations() -> Vec<Migration> {
             sql: "ALTER TABLE folders ADD COLUMN auto_target_reps INTEGER NOT NULL DEFAULT 3;",
             kind: MigrationKind::Up,
         },
+        Migration {
+            version: 6,
+            description: "create generation job tables",
+            sql: r#"
+            CREATE TABLE IF NOT EXISTS generation_jobs (
+                id TEXT PRIMARY KEY,
+                folder_id TEXT REFERENCES folders(id) ON DELETE SET NULL,
+                status TEXT CHECK(status IN ('queued', 'running', 'paused', 'completed', 'cancelled')) NOT NULL DEFAULT 'queued',
+                concurrency INTEGER NOT NULL DEFAULT 2,
+                ocr INTEGER NOT NULL DEFAULT 1,
+                generate_answer INTEGER NOT NULL DEFAULT 1,
+                generate_title INTEGER NOT NULL DEFAULT 1,
+                assess_difficulty INTEGER NOT NULL DEFAULT 1,
+                created_at TEXT DEFAULT (datetime('now')),
+                updated_at TEXT DEFAULT (datetime('now'))
+            );
+
+            CREATE TABLE IF NOT EXISTS generation_job_items (
+                id TEXT PRIMARY KEY,
+                job_id TEXT NOT NULL REFERENCES generation_jobs(id) ON DELETE CASCADE,
+                position INTEGER NOT NULL,
+                image_path TEXT NOT NULL,
+                crop_x INTEGER,
+                crop_y INTEGER,
+                crop_width INTEGER,
+                crop_height INTEGER,
+                status TEXT CHECK(status IN ('pending', 'running', 'done', 'failed', 'cancelled', 'accepted')) NOT NULL DEFAULT 'pending',
+                title TEXT,
+                question_type TEXT,
+                question_content TEXT,
+                answer_content TEXT,
+                timer_seconds INTEGER,
+                error TEXT,
+                flashcard_id TEXT REFERENCES flashcards(id) ON DELETE SET NULL,
+                updated_at TEXT DEFAULT (datetime('now'))
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_generation_job_items_job ON generation_job_items(job_id, position);
+        "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 7,
+            description: "create llm usage table",
+            sql: r#"
+            CREATE TABLE IF NOT EXISTS llm_usage (
+                id TEXT PRIMARY KEY,
+                provider TEXT NOT NULL,
+                model TEXT NOT NULL,
+                command TEXT NOT NULL,
+                input_tokens INTEGER NOT NULL DEFAULT 0,
+                output_tokens INTEGER NOT NULL DEFAULT 0,
+                latency_ms INTEGER NOT NULL DEFAULT 0,
+                success INTEGER NOT NULL,
+                error TEXT,
+                cost_usd REAL,
+                created_at TEXT DEFAULT (datetime('now'))
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_llm_usage_created ON llm_usage(created_at);
+        "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 8,
+            description: "add flashcard answer verification",
+            sql: r#"
+            ALTER TABLE flashcards ADD COLUMN verification_status TEXT CHECK(verification_status IN ('verified', 'unverified', 'disagreement'));
+            ALTER TABLE flashcards ADD COLUMN verification_note TEXT;
+        "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 9,
+            description: "allow solution answer type",
+            // SQLite cannot alter a CHECK constraint, so the table is rebuilt.
+            // Migrations run inside a transaction where foreign keys cannot be
+            // switched off, and dropping the old table cascades to reviews and
+            // job items, so both are saved first and restored afterwards.
+            sql: r#"
+            CREATE TABLE reviews_backup AS SELECT * FROM reviews;
+            CREATE TABLE job_item_cards_backup AS
+                SELECT id, flashcard_id FROM generation_job_items WHERE flashcard_id IS NOT NULL;
+
+            CREATE TABLE flashcards_new (
+                id TEXT PRIMARY KEY,
+                folder_id TEXT REFERENCES folders(id) ON DELETE SET NULL,
+                question_type TEXT CHECK(question_type IN ('image', 'latex')) NOT NULL,
+                question_content TEXT NOT NULL,
+                answer_type TEXT CHECK(answer_type IN ('image', 'latex', 'solution')),
+                answer_content TEXT,
+                timer_mode TEXT CHECK(timer_mode IN ('1min', '5min', '10min', 'llm', 'custom')) DEFAULT '5min',
+                timer_seconds INTEGER DEFAULT 300,
+                ease_factor REAL DEFAULT 2.5,
+                interval_days REAL DEFAULT 0,
+                repetitions INTEGER DEFAULT 0,
+                due_date TEXT,
+                last_reviewed TEXT,
+                created_at TEXT DEFAULT (datetime('now')),
+                updated_at TEXT DEFAULT (datetime('now')),
+                title TEXT,
+                verification_status TEXT CHECK(verification_status IN ('verified', 'unverified', 'disagreement')),
+                verification_note TEXT
+            );
+
+            INSERT INTO flashcards_new (
+                id, folder_id, question_type, question_content, answer_type, answer_content,
+                timer_mode, timer_seconds, ease_factor, interval_days, repetitions, due_date,
+                last_reviewed, created_at, updated_at, title, verification_status, verification_note
+            )
+            SELECT
+                id, folder_id, question_type, question_content, answer_type, answer_content,
+                timer_mode, timer_seconds, ease_factor, interval_days, repetitions, due_date,
+                last_reviewed, created_at, updated_at, title, verification_status, verification_note
+            FROM flashcards;
+
+            DROP TABLE flashcards;
+            ALTER TABLE flashcards_new RENAME TO flashcards;
+
+            CREATE INDEX IF NOT EXISTS idx_flashcards_due ON flashcards(due_date);
+            CREATE INDEX IF NOT EXISTS idx_flashcards_folder ON flashcards(folder_id);
+
+            INSERT INTO reviews SELECT * FROM reviews_backup;
+            UPDATE generation_job_items
+                SET flashcard_id = (
+                    SELECT flashcard_id FROM job_item_cards_backup b WHERE b.id = generation_job_items.id
+                )
+                WHERE id IN (SELECT id FROM job_item_cards_backup);
+
+            DROP TABLE reviews_backup;
+            DROP TABLE job_item_cards_backup;
+        "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 10,
+            description: "create card hints and record hints used",
+            sql: r#"
+            CREATE TABLE IF NOT EXISTS card_hints (
+                flashcard_id TEXT PRIMARY KEY REFERENCES flashcards(id) ON DELETE CASCADE,
+                hints TEXT NOT NULL,
+                source_hash TEXT NOT NULL,
+                created_at TEXT DEFAULT (datetime('now'))
+            );
+
+            ALTER TABLE reviews ADD COLUMN hints_used INTEGER NOT NULL DEFAULT 0;
+        "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 11,
+            description: "create card family table",
+            sql: r#"
+            CREATE TABLE IF NOT EXISTS card_family (
+                flashcard_id TEXT PRIMARY KEY REFERENCES flashcards(id) ON DELETE CASCADE,
+                family_id TEXT NOT NULL,
+                position INTEGER NOT NULL DEFAULT 0,
+                created_at TEXT DEFAULT (datetime('now'))
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_card_family_family ON card_family(family_id);
+        "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 12,
+            description: "add template params and review seeds",
+            sql: r#"
+            ALTER TABLE flashcards ADD COLUMN template_params TEXT;
+            ALTER TABLE reviews ADD COLUMN template_seed INTEGER;
+        "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 13,
+            description: "record typed answers on reviews",
+            sql: r#"
+            ALTER TABLE reviews ADD COLUMN typed_answer TEXT;
+        "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 14,
+            description: "record graded written work on reviews",
+            sql: r#"
+            ALTER TABLE reviews ADD COLUMN work_image TEXT;
+            ALTER TABLE reviews ADD COLUMN work_verdict TEXT;
+        "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 15,
+            description: "create card embeddings table",
+            sql: r#"
+            CREATE TABLE IF NOT EXISTS card_embeddings (
+                flashcard_id TEXT PRIMARY KEY REFERENCES flashcards(id) ON DELETE CASCADE,
+                model TEXT NOT NULL,
+                source_hash TEXT NOT NULL,
+                vector BLOB NOT NULL,
+                created_at TEXT DEFAULT (datetime('now'))
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_card_embeddings_model ON card_embeddings(model);
+        "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 16,
+            description: "create full-text search index over cards",
+            sql: r#"
+            -- `ocr` is NULL until the image content of a card has been read.
+            CREATE VIRTUAL TABLE IF NOT EXISTS cards_fts USING fts5(
+                flashcard_id UNINDEXED,
+                title,
+                question,
+                answer,
+                ocr,
+                tokenize = 'unicode61 remove_diacritics 2',
+                prefix = '2 3'
+            );
+
+            INSERT INTO cards_fts (flashcard_id, title, question, answer, ocr)
+            SELECT f.id, COALESCE(f.title, ''),
+                CASE WHEN f.question_type = 'image' THEN '' ELSE f.question_content END,
+                CASE
+                    WHEN f.answer_type = 'image' OR f.answer_content IS NULL THEN ''
+                    WHEN f.answer_type = 'solution' AND json_valid(f.answer_content) THEN
+                        (SELECT group_concat(json_extract(value, '$.latex') || ' ' || json_extract(value, '$.justification'), ' ')
+                         FROM json_each(f.answer_content))
+                    ELSE f.answer_content
+                END,
+                CASE WHEN f.question_type = 'image' OR f.answer_type = 'image' THEN NULL ELSE '' END
+            FROM flashcards f;
+
+            CREATE TRIGGER IF NOT EXISTS flashcards_fts_insert AFTER INSERT ON flashcards BEGIN
+                INSERT INTO cards_fts (flashcard_id, title, question, answer, ocr)
+                VALUES (new.id, COALESCE(new.title, ''),
+                    CASE WHEN new.question_type = 'image' THEN '' ELSE new.question_content END,
+                    CASE
+                        WHEN new.answer_type = 'image' OR new.answer_content IS NULL THEN ''
+                        WHEN new.answer_type = 'solution' AND json_valid(new.answer_content) THEN
+                            (SELECT group_concat(json_extract(value, '$.latex') || ' ' || json_extract(value, '$.justification'), ' ')
+                             FROM json_each(new.answer_content))
+                        ELSE new.answer_content
+                    END,
+                    CASE WHEN new.question_type = 'image' OR new.answer_type = 'image' THEN NULL ELSE '' END);
+            END;
+
+            CREATE TRIGGER IF NOT EXISTS flashcards_fts_update
+            AFTER UPDATE OF title, question_type, question_content, answer_type, answer_content ON flashcards
+            BEGIN
+                UPDATE cards_fts SET
+                    title = COALESCE(new.title, ''),
+                    question = CASE WHEN new.question_type = 'image' THEN '' ELSE new.question_content END,
+                    answer = CASE
+                        WHEN new.answer_type = 'image' OR new.answer_content IS NULL THEN ''
+                        WHEN new.answer_type = 'solution' AND json_valid(new.answer_content) THEN
+                            (SELECT group_concat(json_extract(value, '$.latex') || ' ' || json_extract(value, '$.justification'), ' ')
+                             FROM json_each(new.answer_content))
+                        ELSE new.answer_content
+                    END,
+                    ocr = CASE
+                        WHEN old.question_content IS new.question_content
+                            AND old.answer_content IS new.answer_content THEN ocr
+                        ELSE CASE WHEN new.question_type = 'image' OR new.answer_type = 'image' THEN NULL ELSE '' END
+                    END
+                WHERE flashcard_id = new.id;
+            END;
+
+            CREATE TRIGGER IF NOT EXISTS flashcards_fts_delete AFTER DELETE ON flashcards BEGIN
+                DELETE FROM cards_fts WHERE flashcard_id = old.id;
+            END;
+        "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 17,
+            description: "store OCR text of image cards",
+            sql: r#"
+            -- NULL until an image side has been read; the search index takes its
+            -- `ocr` column from these from now on.
+            ALTER TABLE flashcards ADD COLUMN question_ocr TEXT;
+            ALTER TABLE flashcards ADD COLUMN answer_ocr TEXT;
+
+            -- Keep text already read for search where it belongs to one side.
+            UPDATE flashcards SET question_ocr =
+                (SELECT ocr FROM cards_fts WHERE cards_fts.flashcard_id = flashcards.id)
+            WHERE question_type = 'image' AND answer_type IS NOT 'image';
+            UPDATE flashcards SET answer_ocr =
+                (SELECT ocr FROM cards_fts WHERE cards_fts.flashcard_id = flashcards.id)
+            WHERE answer_type = 'image' AND question_type != 'image';
+            UPDATE cards_fts SET ocr = COALESCE(
+                (SELECT trim(COALESCE(f.question_ocr, '') || ' ' || COALESCE(f.answer_ocr, ''))
+                 FROM flashcards f WHERE f.id = cards_fts.flashcard_id), '');
+
+            DROP TRIGGER IF EXISTS flashcards_fts_insert;
+            DROP TRIGGER IF EXISTS flashcards_fts_update;
+
+            CREATE TRIGGER IF NOT EXISTS flashcards_fts_insert AFTER INSERT ON flashcards BEGIN
+                INSERT INTO cards_fts (flashcard_id, title, question, answer, ocr)
+                VALUES (new.id, COALESCE(new.title, ''),
+                    CASE WHEN new.question_type = 'image' THEN '' ELSE new.question_content END,
+                    CASE
+                        WHEN new.answer_type = 'image' OR new.answer_content IS NULL THEN ''
+                        WHEN new.answer_type = 'solution' AND json_valid(new.answer_content) THEN
+                            (SELECT group_concat(json_extract(value, '$.latex') || ' ' || json_extract(value, '$.justification'), ' ')
+                             FROM json_each(new.answer_content))
+                        ELSE new.answer_content
+                    END,
+                    trim(COALESCE(new.question_ocr, '') || ' ' || COALESCE(new.answer_ocr, '')));
+            END;
+
+            CREATE TRIGGER IF NOT EXISTS flashcards_fts_update
+            AFTER UPDATE OF title, question_type, question_content, answer_type, answer_content,
+                question_ocr, answer_ocr ON flashcards
+            BEGIN
+                UPDATE cards_fts SET
+                    title = COALESCE(new.title, ''),
+                    question = CASE WHEN new.question_type = 'image' THEN '' ELSE new.question_content END,
+                    answer = CASE
+                        WHEN new.answer_type = 'image' OR new.answer_content IS NULL THEN ''
+                        WHEN new.answer_type = 'solution' AND json_valid(new.answer_content) THEN
+                            (SELECT group_concat(json_extract(value, '$.latex') || ' ' || json_extract(value, '$.justification'), ' ')
+                             FROM json_each(new.answer_content))
+                        ELSE new.answer_content
+                    END,
+                    -- Read from the table: `flashcards_ocr_reset` may have cleared it since.
+                    ocr = (SELECT trim(COALESCE(f.question_ocr, '') || ' ' || COALESCE(f.answer_ocr, ''))
+                           FROM flashcards f WHERE f.id = new.id)
+                WHERE flashcard_id = new.id;
+            END;
+
+            -- Replacing an image drops its stale text unless the same update set new text.
+            CREATE TRIGGER IF NOT EXISTS flashcards_ocr_reset
+            AFTER UPDATE OF question_content, answer_content ON flashcards
+            WHEN (old.question_content IS NOT new.question_content AND old.question_ocr IS new.question_ocr)
+                OR (old.answer_content IS NOT new.answer_content AND old.answer_ocr IS new.answer_ocr)
+            BEGIN
+                UPDATE flashcards SET
+                    question_ocr = CASE
+                        WHEN old.question_content IS NOT new.question_content
+                            AND old.question_ocr IS new.question_ocr THEN NULL
+                        ELSE question_ocr
+                    END,
+                    answer_ocr = CASE
+                        WHEN old.answer_content IS NOT new.answer_content
+                            AND old.answer_ocr IS new.answer_ocr THEN NULL
+                        ELSE answer_ocr
+                    END
+                WHERE id = new.id;
+            END;
+        "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 18,
+            description: "track lapses and leeches",
+            sql: r#"
+            ALTER TABLE flashcards ADD COLUMN lapses INTEGER NOT NULL DEFAULT 0;
+            ALTER TABLE flashcards ADD COLUMN leech INTEGER NOT NULL DEFAULT 0;
+            ALTER TABLE flashcards ADD COLUMN suspended INTEGER NOT NULL DEFAULT 0;
+            ALTER TABLE folders ADD COLUMN leech_threshold INTEGER NOT NULL DEFAULT 8;
+            ALTER TABLE folders ADD COLUMN leech_action TEXT NOT NULL DEFAULT 'suspend';
+
+            UPDATE flashcards SET lapses =
+                (SELECT COUNT(*) FROM reviews r WHERE r.flashcard_id = flashcards.id AND r.correct = 0);
+            -- Existing leeches are tagged for the report but stay in rotation.
+            UPDATE flashcards SET leech = 1 WHERE lapses >= 8;
+
+            CREATE INDEX IF NOT EXISTS idx_flashcards_leech ON flashcards(leech) WHERE leech = 1;
+        "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 19,
+            description: "bury cards and audit manual schedule changes",
+            sql: r#"
+            ALTER TABLE flashcards ADD COLUMN buried_until TEXT;
+            -- 'review' for answered cards; 'reschedule', 'reset' and 'forget' record
+            -- manual schedule changes so the history explains every jump.
+            ALTER TABLE reviews ADD COLUMN kind TEXT NOT NULL DEFAULT 'review';
+        "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 20,
+            description: "add learning steps",
+            sql: r#"
+            ALTER TABLE flashcards ADD COLUMN learning_state TEXT NOT NULL DEFAULT 'new';
+            ALTER TABLE flashcards ADD COLUMN learning_step INTEGER NOT NULL DEFAULT 0;
+            ALTER TABLE folders ADD COLUMN learning_steps TEXT NOT NULL DEFAULT '1m 10m 1h';
+            ALTER TABLE folders ADD COLUMN relearning_steps TEXT NOT NULL DEFAULT '10m';
+
+            UPDATE flashcards SET learning_state = CASE
+                WHEN repetitions > 0 THEN 'review'
+                WHEN last_reviewed IS NULL THEN 'new'
+                WHEN lapses > 0 THEN 'relearning'
+                ELSE 'learning'
+            END;
+        "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 21,
+            description: "limit new cards per day",
+            sql: r#"
+            ALTER TABLE folders ADD COLUMN new_cards_per_day INTEGER NOT NULL DEFAULT 20;
+            -- 'created', 'random' or 'difficulty' (shortest assessed timer first).
+            ALTER TABLE folders ADD COLUMN new_card_order TEXT NOT NULL DEFAULT 'created';
+            ALTER TABLE flashcards ADD COLUMN introduced_at TEXT;
+
+            UPDATE flashcards SET introduced_at = (
+                SELECT MIN(r.reviewed_at) FROM reviews r
+                WHERE r.flashcard_id = flashcards.id AND r.kind = 'review'
+            );
+        "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 22,
+            description: "persist study sessions",
+            sql: r#"
+            -- folder_ids, queue (card ids) and answers are JSON arrays. An empty
+            -- folder_ids means every deck; mode is 'due' or 'all'.
+            CREATE TABLE IF NOT EXISTS study_sessions (
+                id TEXT PRIMARY KEY,
+                folder_ids TEXT NOT NULL DEFAULT '[]',
+                mode TEXT NOT NULL DEFAULT 'due',
+                queue TEXT NOT NULL DEFAULT '[]',
+                position INTEGER NOT NULL DEFAULT 0,
+                answers TEXT NOT NULL DEFAULT '[]',
+                started_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                ended_at TEXT
+            );
+            CREATE INDEX IF NOT EXISTS idx_study_sessions_started ON study_sessions(started_at);
+            ALTER TABLE reviews ADD COLUMN session_id TEXT REFERENCES study_sessions(id) ON DELETE SET NULL;
+            CREATE INDEX IF NOT EXISTS idx_reviews_session ON reviews(session_id);
+        "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 23,
+            description: "add exam plans",
+            sql: r#"
+            ALTER TABLE folders ADD COLUMN target_recall REAL NOT NULL DEFAULT 0.9;
+            -- Dates are local YYYY-MM-DD; final_review marks the review just
+            -- before the exam.
+            CREATE TABLE IF NOT EXISTS exam_plan (
+                folder_id TEXT NOT NULL REFERENCES folders(id) ON DELETE CASCADE,
+                flashcard_id TEXT NOT NULL REFERENCES flashcards(id) ON DELETE CASCADE,
+                planned_date TEXT NOT NULL,
+                final_review INTEGER NOT NULL DEFAULT 0,
+                PRIMARY KEY (flashcard_id, planned_date)
+            );
+            CREATE INDEX IF NOT EXISTS idx_exam_plan_folder ON exam_plan(folder_id, planned_date);
+        "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 24,
+            description: "add folder milestones",
+            sql: r#"
+            -- Extra deadlines within a folder, e.g. a quiz before the final.
+            -- card_ids is a JSON array of the cards the milestone covers, or
+            -- NULL for the whole folder.
+            CREATE TABLE IF NOT EXISTS folder_milestones (
+                id TEXT PRIMARY KEY,
+                folder_id TEXT NOT NULL REFERENCES folders(id) ON DELETE CASCADE,
+                name TEXT NOT NULL,
+                date TEXT NOT NULL,
+                card_ids TEXT,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_folder_milestones_folder ON folder_milestones(folder_id, date);
+            -- The interval a card would have had without deadline compression,
+            -- kept while compressed so it can grow back once the deadline passes.
+            ALTER TABLE flashcards ADD COLUMN base_interval_days REAL;
+        "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 25,
+            description: "add answer regions to generation job items",
+            sql: r#"
+            -- An item can carry the answer region drawn next to its question,
+            -- which is used instead of generating an answer.
+            ALTER TABLE generation_job_items ADD COLUMN answer_crop_x INTEGER;
+            ALTER TABLE generation_job_items ADD COLUMN answer_crop_y INTEGER;
+            ALTER TABLE generation_job_items ADD COLUMN answer_crop_width INTEGER;
+            ALTER TABLE generation_job_items ADD COLUMN answer_crop_height INTEGER;
+            ALTER TABLE generation_job_items ADD COLUMN answer_type TEXT CHECK(answer_type IN ('image', 'latex'));
+        "#,
+            kind: MigrationKind::Up,
+        },
     ]
 }
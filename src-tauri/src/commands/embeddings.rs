@@ -0,0 +1,543 @@
+//! Card embeddings and near-duplicate detection.
+//!
+//! Each card is embedded from its title, question and answer (image cards from
+//! their OCR text), either with the provider's embeddings endpoint or with a
+//! local hashed n-gram embedding that needs no network. Vectors are stored per
+//! model and only recomputed when the card's text changes.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::FromRow;
+use tauri::{Emitter, Manager};
+
+use super::database::{get_pool, now_iso};
+use super::llm::{call_embeddings, load_llm_config, LLMConfig, SolutionStep};
+
+const PROGRESS_EVENT: &str = "embedding-progress";
+const LOCAL_MODEL: &str = "local-hash-256";
+const LOCAL_DIMS: usize = 256;
+const BATCH_SIZE: usize = 64;
+const DEFAULT_THRESHOLD: f32 = 0.92;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbeddingConfig {
+    /// "local" (default) uses the built-in hashed embedding; "llm" calls the
+    /// configured provider's embeddings endpoint with `model`.
+    pub provider: String,
+    pub model: String,
+}
+
+impl Default for EmbeddingConfig {
+    fn default() -> Self {
+        EmbeddingConfig {
+            provider: "local".to_string(),
+            model: String::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EmbeddingProgress {
+    pub done: usize,
+    pub total: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EmbeddingRunResult {
+    pub model: String,
+    pub embedded: usize,
+    /// Cards whose stored vector was still current.
+    pub unchanged: usize,
+    /// Cards with no text to embed.
+    pub failed: usize,
+    /// Image cards skipped because the OCR indexer hasn't read them yet.
+    pub unindexed: usize,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct DuplicateCard {
+    pub id: String,
+    pub folder_id: Option<String>,
+    pub title: Option<String>,
+    pub question_type: String,
+    pub question_content: String,
+    pub review_count: i64,
+    pub repetitions: i32,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DuplicateCluster {
+    pub cards: Vec<DuplicateCard>,
+    /// The card to keep when merging: the one with the richest review history.
+    pub keep_id: String,
+    /// Highest similarity between two cards in the cluster.
+    pub similarity: f32,
+}
+
+#[derive(Clone, FromRow)]
+struct CardSource {
+    id: String,
+    title: Option<String>,
+    question_type: String,
+    question_content: String,
+    answer_type: Option<String>,
+    answer_content: Option<String>,
+    question_ocr: Option<String>,
+    answer_ocr: Option<String>,
+}
+
+#[derive(FromRow)]
+struct EmbeddedCard {
+    #[sqlx(flatten)]
+    card: DuplicateCard,
+    vector: Vec<u8>,
+}
+
+#[tauri::command]
+pub async fn get_embedding_config(app: tauri::AppHandle) -> Result<EmbeddingConfig, String> {
+    load_embedding_config(&app)
+}
+
+#[tauri::command]
+pub async fn set_embedding_config(
+    app: tauri::AppHandle,
+    config: EmbeddingConfig,
+) -> Result<(), String> {
+    if config.provider == "llm" && config.model.trim().is_empty() {
+        return Err("Choose an embedding model".to_string());
+    }
+    let app_data = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+    std::fs::create_dir_all(&app_data)
+        .map_err(|e| format!("Failed to create config dir: {}", e))?;
+    let contents =
+        serde_json::to_string_pretty(&config).map_err(|e| format!("Failed to serialize: {}", e))?;
+    std::fs::write(app_data.join("embedding_config.json"), contents)
+        .map_err(|e| format!("Failed to write config: {}", e))
+}
+
+/// Embeds every card whose text changed since it was last embedded with the
+/// current model, emitting `embedding-progress` as batches finish.
+#[tauri::command]
+pub async fn embed_cards(app: tauri::AppHandle) -> Result<EmbeddingRunResult, String> {
+    let embedder = Embedder::load(&app)?;
+    let pool = get_pool(&app).await?;
+    let cards: Vec<CardSource> = sqlx::query_as(
+        "SELECT id, title, question_type, question_content, answer_type, answer_content,
+                question_ocr, answer_ocr
+         FROM flashcards",
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| format!("Failed to load flashcards: {}", e))?;
+    let stored: std::collections::HashMap<String, String> = sqlx::query_as::<_, (String, String)>(
+        "SELECT flashcard_id, source_hash FROM card_embeddings WHERE model = $1",
+    )
+    .bind(embedder.model())
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| format!("Failed to load embeddings: {}", e))?
+    .into_iter()
+    .collect();
+
+    let total = cards.len();
+    let mut result = EmbeddingRunResult {
+        model: embedder.model().to_string(),
+        embedded: 0,
+        unchanged: 0,
+        failed: 0,
+        unindexed: 0,
+    };
+    let mut pending = Vec::new();
+    for card in cards {
+        let id = card.id.clone();
+        let text = match card_text(card) {
+            Some(text) if !text.trim().is_empty() => text,
+            Some(_) => {
+                result.failed += 1;
+                continue;
+            }
+            None => {
+                result.unindexed += 1;
+                continue;
+            }
+        };
+        let hash = format!(
+            "{:x}",
+            Sha256::digest(format!("{}\0{}", embedder.model(), text))
+        );
+        if stored.get(&id) == Some(&hash) {
+            result.unchanged += 1;
+        } else {
+            pending.push((id, hash, text));
+        }
+    }
+
+    let _ = app.emit(
+        PROGRESS_EVENT,
+        EmbeddingProgress {
+            done: result.unchanged + result.failed + result.unindexed,
+            total,
+        },
+    );
+    for batch in pending.chunks(BATCH_SIZE) {
+        let texts: Vec<String> = batch.iter().map(|(_, _, text)| text.clone()).collect();
+        let vectors = embedder.embed(&app, &texts).await?;
+        for ((id, hash, _), vector) in batch.iter().zip(vectors) {
+            sqlx::query(
+                "INSERT INTO card_embeddings (flashcard_id, model, source_hash, vector, created_at)
+                 VALUES ($1, $2, $3, $4, $5)
+                 ON CONFLICT(flashcard_id) DO UPDATE SET
+                    model = excluded.model, source_hash = excluded.source_hash,
+                    vector = excluded.vector, created_at = excluded.created_at",
+            )
+            .bind(id)
+            .bind(embedder.model())
+            .bind(hash)
+            .bind(encode_vector(&normalized(vector)))
+            .bind(now_iso())
+            .execute(&pool)
+            .await
+            .map_err(|e| format!("Failed to save embedding: {}", e))?;
+        }
+        result.embedded += batch.len();
+        let _ = app.emit(
+            PROGRESS_EVENT,
+            EmbeddingProgress {
+                done: result.embedded + result.unchanged + result.failed + result.unindexed,
+                total,
+            },
+        );
+    }
+    Ok(result)
+}
+
+/// Groups cards whose embeddings are at least `threshold` cosine-similar.
+/// Only cards embedded with the current model are compared.
+#[tauri::command]
+pub async fn find_duplicate_cards(
+    app: tauri::AppHandle,
+    threshold: Option<f32>,
+) -> Result<Vec<DuplicateCluster>, String> {
+    let model = Embedder::load(&app)?.model().to_string();
+    let pool = get_pool(&app).await?;
+    let rows: Vec<EmbeddedCard> = sqlx::query_as(
+        "SELECT f.id, f.folder_id, f.title, f.question_type, f.question_content,
+                (SELECT COUNT(*) FROM reviews r WHERE r.flashcard_id = f.id AND r.kind = 'review')
+                    AS review_count,
+                f.repetitions, f.created_at, e.vector
+         FROM card_embeddings e
+         JOIN flashcards f ON f.id = e.flashcard_id
+         WHERE e.model = $1",
+    )
+    .bind(&model)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| format!("Failed to load embeddings: {}", e))?;
+
+    let vectors: Vec<Vec<f32>> = rows.iter().map(|row| decode_vector(&row.vector)).collect();
+    let cards: Vec<DuplicateCard> = rows.into_iter().map(|row| row.card).collect();
+
+    let threshold = threshold.unwrap_or(DEFAULT_THRESHOLD).clamp(0.5, 1.0);
+    Ok(clusters(&vectors, threshold)
+        .into_iter()
+        .map(|(members, similarity)| {
+            let cards: Vec<DuplicateCard> = members.into_iter().map(|i| cards[i].clone()).collect();
+            let keep_id = richest_history(&cards).id.clone();
+            DuplicateCluster {
+                cards,
+                keep_id,
+                similarity,
+            }
+        })
+        .collect())
+}
+
+/// Folds duplicates into `keep_id`: their reviews move to the kept card so
+/// no history is lost, then the duplicates are deleted.
+#[tauri::command]
+pub async fn merge_duplicate_cards(
+    app: tauri::AppHandle,
+    keep_id: String,
+    remove_ids: Vec<String>,
+) -> Result<(), String> {
+    if remove_ids.contains(&keep_id) {
+        return Err("Cannot merge a card into itself".to_string());
+    }
+    let pool = get_pool(&app).await?;
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    for id in &remove_ids {
+        sqlx::query("UPDATE reviews SET flashcard_id = $1 WHERE flashcard_id = $2")
+            .bind(&keep_id)
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to move reviews: {}", e))?;
+        sqlx::query("DELETE FROM flashcards WHERE id = $1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to delete flashcard: {}", e))?;
+    }
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to merge cards: {}", e))
+}
+
+pub enum Embedder {
+    Local,
+    Remote(LLMConfig),
+}
+
+impl Embedder {
+    pub fn load(app: &tauri::AppHandle) -> Result<Self, String> {
+        let config = load_embedding_config(app)?;
+        if config.provider != "llm" {
+            return Ok(Embedder::Local);
+        }
+        // Usage is logged against the embedding model, not the chat model.
+        Ok(Embedder::Remote(LLMConfig {
+            model: config.model,
+            ..load_llm_config(app)?
+        }))
+    }
+
+    pub fn model(&self) -> &str {
+        match self {
+            Embedder::Local => LOCAL_MODEL,
+            Embedder::Remote(config) => &config.model,
+        }
+    }
+
+    pub async fn embed(
+        &self,
+        app: &tauri::AppHandle,
+        texts: &[String],
+    ) -> Result<Vec<Vec<f32>>, String> {
+        match self {
+            Embedder::Local => Ok(texts.iter().map(|t| local_embedding(t)).collect()),
+            Embedder::Remote(config) => call_embeddings(app, config, texts).await,
+        }
+    }
+}
+
+fn load_embedding_config(app: &tauri::AppHandle) -> Result<EmbeddingConfig, String> {
+    let app_data = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+    let config_path = app_data.join("embedding_config.json");
+    if !config_path.exists() {
+        return Ok(EmbeddingConfig::default());
+    }
+    let contents = std::fs::read_to_string(&config_path)
+        .map_err(|e| format!("Failed to read config: {}", e))?;
+    serde_json::from_str(&contents).map_err(|e| format!("Failed to parse config: {}", e))
+}
+
+/// The text a card is embedded from. Image content is replaced by the OCR
+/// text the indexer stored, so `None` means an image hasn't been indexed yet.
+fn card_text(card: CardSource) -> Option<String> {
+    let mut parts: Vec<String> = card.title.into_iter().collect();
+    parts.push(match card.question_type.as_str() {
+        "image" => card.question_ocr?,
+        _ => card.question_content,
+    });
+    match (card.answer_type.as_deref(), card.answer_content) {
+        (_, None) => {}
+        (Some("image"), Some(_)) => parts.push(card.answer_ocr?),
+        (Some("solution"), Some(content)) => {
+            if let Ok(steps) = serde_json::from_str::<Vec<SolutionStep>>(&content) {
+                parts.extend(steps.into_iter().map(|step| step.latex));
+            }
+        }
+        (_, Some(content)) => parts.push(content),
+    }
+    Some(parts.join("\n"))
+}
+
+/// Feature-hashed character trigrams and words, so reworded or reformatted
+/// copies of the same problem land close together. Whitespace and LaTeX
+/// spacing are ignored.
+fn local_embedding(text: &str) -> Vec<f32> {
+    let mut clean = text.to_lowercase();
+    for spacing in ["\\,", "\\;", "\\!", "\\quad", "$"] {
+        clean = clean.replace(spacing, " ");
+    }
+    let mut vector = vec![0f32; LOCAL_DIMS];
+    let mut add = |feature: &str| {
+        let hash = feature
+            .bytes()
+            .fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| {
+                (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+            });
+        let sign = if hash >> 63 == 0 { 1.0 } else { -1.0 };
+        vector[(hash % LOCAL_DIMS as u64) as usize] += sign;
+    };
+    let chars: Vec<char> = clean.chars().filter(|c| !c.is_whitespace()).collect();
+    for window in chars.windows(3) {
+        add(&window.iter().collect::<String>());
+    }
+    for word in clean
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+    {
+        add(&format!("w:{}", word));
+    }
+    normalized(vector)
+}
+
+pub fn normalized(mut vector: Vec<f32>) -> Vec<f32> {
+    let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|x| *x /= norm);
+    }
+    vector
+}
+
+fn encode_vector(vector: &[f32]) -> Vec<u8> {
+    vector.iter().flat_map(|x| x.to_le_bytes()).collect()
+}
+
+pub fn decode_vector(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect()
+}
+
+/// Dot product of unit vectors.
+pub fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+/// Connected components of the "at least `threshold` similar" graph, with
+/// the highest similarity inside each. Singletons are dropped.
+fn clusters(vectors: &[Vec<f32>], threshold: f32) -> Vec<(Vec<usize>, f32)> {
+    fn find(parent: &mut [usize], i: usize) -> usize {
+        let mut root = i;
+        while parent[root] != root {
+            root = parent[root];
+        }
+        parent[i] = root;
+        root
+    }
+
+    let mut parent: Vec<usize> = (0..vectors.len()).collect();
+    let mut best = vec![0f32; vectors.len()];
+    for i in 0..vectors.len() {
+        for j in i + 1..vectors.len() {
+            let similarity = cosine(&vectors[i], &vectors[j]);
+            if similarity >= threshold {
+                let (a, b) = (find(&mut parent, i), find(&mut parent, j));
+                parent[b] = a;
+                best[a] = best[a].max(best[b]).max(similarity);
+            }
+        }
+    }
+
+    let mut groups: std::collections::BTreeMap<usize, Vec<usize>> = Default::default();
+    for i in 0..vectors.len() {
+        let root = find(&mut parent, i);
+        groups.entry(root).or_default().push(i);
+    }
+    groups
+        .into_iter()
+        .filter(|(_, members)| members.len() > 1)
+        .map(|(root, members)| (members, best[root]))
+        .collect()
+}
+
+/// Most reviews, then most successful repetitions, then the oldest card.
+fn richest_history(cards: &[DuplicateCard]) -> &DuplicateCard {
+    cards
+        .iter()
+        .max_by(|a, b| {
+            (a.review_count, a.repetitions)
+                .cmp(&(b.review_count, b.repetitions))
+                .then_with(|| b.created_at.cmp(&a.created_at))
+        })
+        .expect("clusters are never empty")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn local_embedding_finds_near_duplicates() {
+        let a = local_embedding(r"Evaluate $\int_0^1 x^2 \, dx$");
+        let b = local_embedding(r"evaluate  $\int_0^1 x^2\,dx$");
+        let c = local_embedding(r"Find the derivative of $\sin(3x)$");
+        assert!(cosine(&a, &b) > 0.9, "{}", cosine(&a, &b));
+        assert!(cosine(&a, &c) < 0.5, "{}", cosine(&a, &c));
+        assert_eq!(decode_vector(&encode_vector(&a)), a);
+    }
+
+    #[test]
+    fn clusters_connected_duplicates() {
+        let vectors = vec![
+            normalized(vec![1.0, 0.0, 0.0]),
+            normalized(vec![0.0, 1.0, 0.0]),
+            normalized(vec![1.0, 0.05, 0.0]),
+            normalized(vec![1.0, 0.1, 0.0]),
+        ];
+        let found = clusters(&vectors, 0.99);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].0, vec![0, 2, 3]);
+        assert!(found[0].1 > 0.99);
+    }
+
+    #[test]
+    fn keeps_card_with_most_reviews() {
+        let card = |id: &str, review_count: i64, created_at: &str| DuplicateCard {
+            id: id.to_string(),
+            folder_id: None,
+            title: None,
+            question_type: "latex".to_string(),
+            question_content: String::new(),
+            review_count,
+            repetitions: 0,
+            created_at: created_at.to_string(),
+        };
+        let cards = vec![
+            card("a", 2, "2024-01-02"),
+            card("b", 5, "2024-03-01"),
+            card("c", 5, "2024-02-01"),
+        ];
+        assert_eq!(richest_history(&cards).id, "c");
+    }
+
+    #[test]
+    fn image_cards_use_stored_ocr_only() {
+        let card = CardSource {
+            id: "a".to_string(),
+            title: Some("Limits".to_string()),
+            question_type: "image".to_string(),
+            question_content: "/captures/q.png".to_string(),
+            answer_type: Some("image".to_string()),
+            answer_content: Some("/captures/a.png".to_string()),
+            question_ocr: Some("Find $\\lim_{x \\to 0} x$".to_string()),
+            answer_ocr: None,
+        };
+        assert_eq!(card_text(card.clone()), None);
+
+        let indexed = CardSource {
+            answer_ocr: Some("$0$".to_string()),
+            ..card
+        };
+        assert_eq!(
+            card_text(indexed).as_deref(),
+            Some("Limits\nFind $\\lim_{x \\to 0} x$\n$0$")
+        );
+    }
+}
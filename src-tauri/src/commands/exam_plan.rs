@@ -0,0 +1,488 @@
+//! Exam planner: schedules each card's remaining reviews on specific dates
+//! before its next exam, the nearest of its folder's deadline and the
+//! milestones covering it. Every card gets a final review 1–2 days
+//! before the exam; earlier reviews are spaced so recall stays above the
+//! folder's `target_recall`, weakest cards are placed first, and each day is
+//! kept under the folder's daily review limit where possible. The plan is
+//! stored in `exam_plan` and moves each card's `due_date` to its next planned
+//! day, so the study queue follows it. The study screen re-plans after every
+//! session.
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Duration, Local, NaiveDate, TimeZone, Utc};
+use serde::Serialize;
+use sqlx::{FromRow, Pool, Sqlite};
+
+use super::database::{get_pool, load_card_states, load_folder_policies, load_milestones, now_iso};
+use crate::review_policy::{effective_daily_review_limit, nearest_deadline, FolderPolicy};
+
+/// Recall at a gap equal to the card's interval under the SM-2 reading of
+/// intervals; other targets scale the gap by the ratio of logarithms.
+const INTERVAL_RECALL: f64 = 0.9;
+
+#[derive(Debug, Clone)]
+pub struct PlanCard {
+    pub id: String,
+    pub ease_factor: f64,
+    pub interval_days: f64,
+    pub repetitions: i64,
+    pub lapses: i64,
+    /// Local date the card is next due, if scheduled.
+    pub due: Option<NaiveDate>,
+    /// Earliest day the card may be planned, e.g. tomorrow if it was studied today.
+    pub earliest: NaiveDate,
+    /// The exam this card is preparing for.
+    pub exam: NaiveDate,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlannedReview {
+    pub card_id: String,
+    pub date: NaiveDate,
+    pub final_review: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PlanDay {
+    pub date: String,
+    pub card_ids: Vec<String>,
+    pub final_reviews: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExamPlan {
+    pub folder_id: String,
+    /// The folder's next deadline, milestones included.
+    pub deadline: Option<String>,
+    pub target_recall: f64,
+    pub daily_cap: i64,
+    pub days: Vec<PlanDay>,
+    /// Days planned above the cap because the cards didn't fit otherwise.
+    pub overloaded_days: usize,
+}
+
+#[derive(FromRow)]
+struct PlanCardRow {
+    id: String,
+    ease_factor: f64,
+    interval_days: f64,
+    repetitions: i64,
+    lapses: i64,
+    due_date: Option<String>,
+    last_reviewed: Option<String>,
+}
+
+#[derive(FromRow)]
+struct PlanRow {
+    flashcard_id: String,
+    planned_date: String,
+    final_review: bool,
+}
+
+/// Re-plans one folder and returns the new plan.
+#[tauri::command]
+pub async fn plan_exam(app: tauri::AppHandle, folder_id: String) -> Result<ExamPlan, String> {
+    let pool = get_pool(&app).await?;
+    replan_folder(&pool, &folder_id).await?;
+    load_plan(&pool, &folder_id).await
+}
+
+/// The stored plan, without re-planning.
+#[tauri::command]
+pub async fn get_exam_plan(app: tauri::AppHandle, folder_id: String) -> Result<ExamPlan, String> {
+    let pool = get_pool(&app).await?;
+    load_plan(&pool, &folder_id).await
+}
+
+/// Re-plans every folder with a deadline or milestones; returns how many were
+/// planned.
+#[tauri::command]
+pub async fn replan_exams(app: tauri::AppHandle) -> Result<usize, String> {
+    let pool = get_pool(&app).await?;
+    let folder_ids: Vec<(String,)> = sqlx::query_as(
+        "SELECT id FROM folders
+         WHERE deadline IS NOT NULL
+            OR id IN (SELECT folder_id FROM folder_milestones)
+            OR id IN (SELECT folder_id FROM exam_plan)",
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| format!("Failed to load folders: {}", e))?;
+    for (folder_id,) in &folder_ids {
+        replan_folder(&pool, folder_id).await?;
+    }
+    Ok(folder_ids.len())
+}
+
+async fn replan_folder(pool: &Pool<Sqlite>, folder_id: &str) -> Result<(), String> {
+    let folders = load_folder_policies(pool).await?;
+    let folder = folders
+        .iter()
+        .find(|f| f.id == folder_id)
+        .ok_or_else(|| format!("Folder not found: {}", folder_id))?;
+    let (deadline, target_recall): (Option<String>, f64) =
+        sqlx::query_as("SELECT deadline, target_recall FROM folders WHERE id = $1")
+            .bind(folder_id)
+            .fetch_one(pool)
+            .await
+            .map_err(|e| format!("Failed to load folder: {}", e))?;
+    let milestones = load_milestones(pool, Some(folder_id)).await?;
+
+    let today = Local::now().date_naive();
+
+    let rows: Vec<PlanCardRow> = sqlx::query_as(
+        "SELECT id, ease_factor,
+                CAST(COALESCE(base_interval_days, interval_days) AS REAL) AS interval_days,
+                repetitions,
+                COALESCE(lapses, 0) AS lapses, due_date, last_reviewed
+         FROM flashcards
+         WHERE folder_id = $1 AND suspended = 0
+           AND learning_state NOT IN ('learning', 'relearning')",
+    )
+    .bind(folder_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load flashcards: {}", e))?;
+
+    let cards: Vec<PlanCard> = rows
+        .into_iter()
+        .filter_map(|row| {
+            let exam = nearest_deadline(deadline.as_deref(), &milestones, Some(&row.id), today)
+                .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+                // On the day of its exam a card has nothing left to plan
+                .filter(|exam| *exam > today)?;
+            Some(plan_card(row, today, exam))
+        })
+        .collect();
+    let planned = if cards.is_empty() {
+        Vec::new()
+    } else {
+        plan_reviews(&cards, today, daily_cap(pool, folder).await?, target_recall)
+    };
+    save_plan(pool, folder_id, &planned).await
+}
+
+async fn daily_cap(pool: &Pool<Sqlite>, folder: &FolderPolicy) -> Result<i64, String> {
+    let cards = load_card_states(pool, Some(&folder.id), false).await?;
+    Ok(effective_daily_review_limit(Some(folder), &cards, &now_iso()).max(1))
+}
+
+fn plan_card(row: PlanCardRow, today: NaiveDate, exam: NaiveDate) -> PlanCard {
+    let reviewed_today = row.last_reviewed.as_deref().and_then(local_date) == Some(today);
+    PlanCard {
+        due: row.due_date.as_deref().and_then(local_date),
+        earliest: if reviewed_today {
+            today + Duration::days(1)
+        } else {
+            today
+        },
+        id: row.id,
+        ease_factor: row.ease_factor,
+        interval_days: row.interval_days,
+        repetitions: row.repetitions,
+        lapses: row.lapses,
+        exam,
+    }
+}
+
+/// Replaces the folder's plan and moves each planned card's due date to the
+/// start of its next planned day.
+async fn save_plan(
+    pool: &Pool<Sqlite>,
+    folder_id: &str,
+    planned: &[PlannedReview],
+) -> Result<(), String> {
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    sqlx::query("DELETE FROM exam_plan WHERE folder_id = $1")
+        .bind(folder_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to clear exam plan: {}", e))?;
+
+    let mut next_day: BTreeMap<&str, NaiveDate> = BTreeMap::new();
+    for review in planned {
+        sqlx::query(
+            "INSERT OR IGNORE INTO exam_plan (folder_id, flashcard_id, planned_date, final_review)
+             VALUES ($1, $2, $3, $4)",
+        )
+        .bind(folder_id)
+        .bind(&review.card_id)
+        .bind(review.date.format("%Y-%m-%d").to_string())
+        .bind(review.final_review)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to save exam plan: {}", e))?;
+        let next = next_day.entry(&review.card_id).or_insert(review.date);
+        *next = (*next).min(review.date);
+    }
+
+    let now = now_iso();
+    for (card_id, date) in next_day {
+        sqlx::query("UPDATE flashcards SET due_date = $1, updated_at = $2 WHERE id = $3")
+            .bind(start_of_day(date))
+            .bind(&now)
+            .bind(card_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to reschedule card: {}", e))?;
+    }
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit transaction: {}", e))
+}
+
+async fn load_plan(pool: &Pool<Sqlite>, folder_id: &str) -> Result<ExamPlan, String> {
+    let folders = load_folder_policies(pool).await?;
+    let folder = folders
+        .iter()
+        .find(|f| f.id == folder_id)
+        .ok_or_else(|| format!("Folder not found: {}", folder_id))?;
+    let target_recall: f64 = sqlx::query_scalar("SELECT target_recall FROM folders WHERE id = $1")
+        .bind(folder_id)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| format!("Failed to load folder: {}", e))?;
+    let rows: Vec<PlanRow> = sqlx::query_as(
+        "SELECT flashcard_id, planned_date, final_review != 0 AS final_review
+         FROM exam_plan WHERE folder_id = $1
+         ORDER BY planned_date, final_review, flashcard_id",
+    )
+    .bind(folder_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load exam plan: {}", e))?;
+
+    let daily_cap = daily_cap(pool, folder).await?;
+    let mut days: Vec<PlanDay> = Vec::new();
+    for row in rows {
+        match days.last_mut() {
+            Some(day) if day.date == row.planned_date => {
+                day.card_ids.push(row.flashcard_id);
+                day.final_reviews += row.final_review as usize;
+            }
+            _ => days.push(PlanDay {
+                date: row.planned_date,
+                card_ids: vec![row.flashcard_id],
+                final_reviews: row.final_review as usize,
+            }),
+        }
+    }
+    Ok(ExamPlan {
+        folder_id: folder_id.to_string(),
+        deadline: folder.deadline.clone(),
+        target_recall,
+        daily_cap,
+        overloaded_days: days
+            .iter()
+            .filter(|day| day.card_ids.len() as i64 > daily_cap)
+            .count(),
+        days,
+    })
+}
+
+/// Plans every card's reviews up to its exam. Final reviews are placed first,
+/// on whichever of the two days before the exam has more room; then each
+/// card's earlier reviews, weakest card first, start from when it's due and
+/// grow by its ease. A review that doesn't fit under `daily_cap` moves to the
+/// nearest day with room (earlier first) within part of its gap, then to any
+/// earlier day with room, then to the next later one, and only otherwise stays
+/// on its ideal day over the cap.
+pub fn plan_reviews(
+    cards: &[PlanCard],
+    today: NaiveDate,
+    daily_cap: i64,
+    target_recall: f64,
+) -> Vec<PlannedReview> {
+    let cap = daily_cap.max(1) as usize;
+    // Gaps are shortened for targets above the interval's own recall.
+    let gap_scale = (target_recall.clamp(0.5, 0.99).ln() / INTERVAL_RECALL.ln()).clamp(0.2, 3.0);
+    let mut load: BTreeMap<NaiveDate, usize> = BTreeMap::new();
+    let mut planned = Vec::new();
+
+    let mut order: Vec<&PlanCard> = cards.iter().filter(|card| card.exam > today).collect();
+    order.sort_by(|a, b| {
+        weakness(b)
+            .total_cmp(&weakness(a))
+            .then_with(|| a.id.cmp(&b.id))
+    });
+
+    let mut finals: Vec<(&PlanCard, NaiveDate)> = Vec::new();
+    for card in &order {
+        let last = (card.exam - Duration::days(1)).max(card.earliest);
+        let first = (card.exam - Duration::days(2)).max(card.earliest).min(last);
+        let date = if load_on(&load, first) < load_on(&load, last) {
+            first
+        } else {
+            last
+        };
+        *load.entry(date).or_default() += 1;
+        finals.push((card, date));
+    }
+
+    for (card, final_date) in &finals {
+        let mut stability = card.interval_days.max(1.0);
+        let mut previous: Option<NaiveDate> = None;
+        let mut ideal = card.due.unwrap_or(card.earliest).max(card.earliest);
+        let last_allowed = *final_date - Duration::days(1);
+        while ideal <= last_allowed {
+            let gap = ((stability * gap_scale).round() as i64).max(1);
+            let first_allowed = previous
+                .map(|p| p + Duration::days(1))
+                .unwrap_or(card.earliest);
+            let lo = first_allowed.max(ideal - Duration::days((gap / 2).max(1)));
+            let hi = last_allowed.min(ideal + Duration::days((gap / 4).max(1)));
+            // Reviewing early never hurts recall, so a full window falls back
+            // to any earlier day with room, then to the next later one.
+            let date = find_slot(&load, ideal, lo, hi, cap)
+                .or_else(|| find_slot(&load, lo, first_allowed, lo, cap))
+                .or_else(|| find_slot(&load, hi, hi, last_allowed, cap))
+                .unwrap_or(ideal);
+            *load.entry(date).or_default() += 1;
+            planned.push(PlannedReview {
+                card_id: card.id.clone(),
+                date,
+                final_review: false,
+            });
+            stability *= card.ease_factor.max(1.3);
+            previous = Some(date);
+            ideal = date + Duration::days(((stability * gap_scale).round() as i64).max(1));
+        }
+        planned.push(PlannedReview {
+            card_id: card.id.clone(),
+            date: *final_date,
+            final_review: true,
+        });
+    }
+    planned.sort_by(|a, b| a.date.cmp(&b.date).then_with(|| a.card_id.cmp(&b.card_id)));
+    planned
+}
+
+/// Higher for cards that lapse, have low ease or few successful repetitions.
+fn weakness(card: &PlanCard) -> f64 {
+    (1.0 + card.lapses as f64) * (2.5 / card.ease_factor.max(1.3)) / (1.0 + card.repetitions as f64)
+}
+
+fn load_on(load: &BTreeMap<NaiveDate, usize>, date: NaiveDate) -> usize {
+    load.get(&date).copied().unwrap_or(0)
+}
+
+/// The day nearest `ideal` in `[lo, hi]` with room, checking earlier days first.
+fn find_slot(
+    load: &BTreeMap<NaiveDate, usize>,
+    ideal: NaiveDate,
+    lo: NaiveDate,
+    hi: NaiveDate,
+    cap: usize,
+) -> Option<NaiveDate> {
+    let span = (hi - lo).num_days().max(0);
+    (0..=span)
+        .flat_map(|offset| {
+            [
+                ideal - Duration::days(offset),
+                ideal + Duration::days(offset),
+            ]
+        })
+        .filter(|date| *date >= lo && *date <= hi)
+        .find(|date| load_on(load, *date) < cap)
+}
+
+fn local_date(value: &str) -> Option<NaiveDate> {
+    DateTime::parse_from_rfc3339(value)
+        .ok()
+        .map(|dt| dt.with_timezone(&Local).date_naive())
+}
+
+fn start_of_day(date: NaiveDate) -> String {
+    date.and_hms_opt(0, 0, 0)
+        .and_then(|dt| Local.from_local_datetime(&dt).earliest())
+        .map(|dt| dt.with_timezone(&Utc))
+        .unwrap_or_else(Utc::now)
+        .format("%Y-%m-%dT%H:%M:%S%.3fZ")
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 5, day).unwrap()
+    }
+
+    fn card(id: &str, ease: f64, interval: f64, lapses: i64) -> PlanCard {
+        PlanCard {
+            id: id.to_string(),
+            ease_factor: ease,
+            interval_days: interval,
+            repetitions: if interval > 1.0 { 3 } else { 0 },
+            lapses,
+            due: Some(date(1)),
+            earliest: date(1),
+            exam: date(21),
+        }
+    }
+
+    fn days_for<'a>(plan: &'a [PlannedReview], id: &str) -> Vec<&'a PlannedReview> {
+        plan.iter().filter(|r| r.card_id == id).collect()
+    }
+
+    #[test]
+    fn ends_with_a_final_review_before_the_exam() {
+        let cards = vec![card("weak", 1.3, 1.0, 4), card("strong", 2.8, 10.0, 0)];
+        let plan = plan_reviews(&cards, date(1), 50, 0.9);
+
+        for id in ["weak", "strong"] {
+            let reviews = days_for(&plan, id);
+            let last = reviews.last().unwrap();
+            assert!(last.final_review);
+            assert!(last.date >= date(19) && last.date <= date(20));
+            assert_eq!(reviews.iter().filter(|r| r.final_review).count(), 1);
+            assert!(reviews.windows(2).all(|w| w[0].date < w[1].date));
+        }
+        // Weak cards get reviewed more often.
+        assert!(days_for(&plan, "weak").len() > days_for(&plan, "strong").len());
+        // A higher target means shorter gaps and more reviews.
+        let strict = plan_reviews(&cards, date(1), 50, 0.97);
+        assert!(days_for(&strict, "strong").len() > days_for(&plan, "strong").len());
+    }
+
+    #[test]
+    fn balances_load_and_front_loads_weak_cards() {
+        let mut cards: Vec<PlanCard> = (0..5)
+            .map(|i| card(&format!("c{}", i), 2.5, 1.0, 0))
+            .collect();
+        cards.push(card("leech", 1.3, 1.0, 5));
+        let plan = plan_reviews(&cards, date(1), 3, 0.9);
+
+        let mut load: BTreeMap<NaiveDate, usize> = BTreeMap::new();
+        for review in &plan {
+            *load.entry(review.date).or_default() += 1;
+        }
+        assert!(load.values().all(|&n| n <= 3));
+        assert_eq!(plan.iter().filter(|r| r.final_review).count(), 6);
+        assert_eq!(days_for(&plan, "leech")[0].date, date(1));
+        assert_eq!(load[&date(1)], 3);
+
+        // Past exams plan nothing.
+        assert!(plan_reviews(&cards, date(21), 3, 0.9).is_empty());
+    }
+
+    #[test]
+    fn plans_each_card_for_its_own_exam() {
+        let mut quiz = card("quiz", 2.5, 3.0, 0);
+        quiz.exam = date(8);
+        let cards = vec![quiz, card("final", 2.5, 3.0, 0)];
+        let plan = plan_reviews(&cards, date(1), 50, 0.9);
+
+        let quiz_final = *days_for(&plan, "quiz").last().unwrap();
+        assert!(
+            quiz_final.final_review && quiz_final.date >= date(6) && quiz_final.date <= date(7)
+        );
+        let final_final = *days_for(&plan, "final").last().unwrap();
+        assert!(final_final.final_review && final_final.date >= date(19));
+    }
+}
@@ -0,0 +1,186 @@
+//! Automatic grading of answers given during review: typed answers are checked
+//! by the math engine, photos of written work by a vision model.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use super::database::get_pool;
+use super::llm::{call_llm_vision_structured, command_name, load_llm_config, read_image};
+use super::llm_cache::{self, CacheKey};
+use super::ocr_index::ocr_images;
+use super::verify::solution_text;
+use crate::math::grade::{self, Grade};
+
+/// Grades a typed LaTeX or ASCII-math answer against a card's answer.
+#[tauri::command]
+pub fn grade_typed_answer(
+    answer_type: Option<String>,
+    answer_content: String,
+    typed_answer: String,
+) -> Result<Grade, String> {
+    let expected = match answer_type.as_deref() {
+        Some("image") => return Err("Image answers can't be graded from typed text".to_string()),
+        Some("solution") => solution_text(&answer_content)?,
+        _ => answer_content,
+    };
+    Ok(grade::grade(&expected, &typed_answer))
+}
+
+const GRADE_WORK_TEMPLATE: &str = "grade_handwritten_work/v1";
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WorkGrade {
+    /// `correct`, `partial` or `incorrect`.
+    pub verdict: String,
+    /// The first step that goes wrong, as written by the student.
+    pub first_wrong_step: Option<String>,
+    pub feedback: String,
+}
+
+/// Grades a photo or drawing of the student's written work against the
+/// card's answer with a vision model.
+#[tauri::command]
+pub async fn grade_handwritten_work(
+    app: tauri::AppHandle,
+    flashcard_id: String,
+    image_path: String,
+    regenerate: Option<bool>,
+) -> Result<WorkGrade, String> {
+    let pool = get_pool(&app).await?;
+    let (question_type, question_content, answer_type, answer_content, question_ocr, answer_ocr): (
+        String,
+        String,
+        Option<String>,
+        Option<String>,
+        Option<String>,
+        Option<String>,
+    ) = sqlx::query_as(
+        "SELECT question_type, question_content, answer_type, answer_content, question_ocr, answer_ocr
+         FROM flashcards WHERE id = $1",
+    )
+    .bind(&flashcard_id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| format!("Failed to load flashcard: {}", e))?
+    .ok_or_else(|| format!("Flashcard not found: {}", flashcard_id))?;
+
+    // Image cards use the text the OCR indexer stored, and are read now otherwise.
+    let question = match (question_type.as_str(), question_ocr) {
+        ("image", Some(text)) => text,
+        ("image", None) => ocr_images(&app, &question_content).await?,
+        _ => question_content,
+    };
+    let answer = match (answer_type.as_deref(), answer_content) {
+        (_, None) => None,
+        (Some("image"), Some(content)) => Some(match answer_ocr {
+            Some(text) => text,
+            None => ocr_images(&app, &content).await?,
+        }),
+        (Some("solution"), Some(content)) => Some(solution_text(&content)?),
+        (_, Some(content)) => Some(content),
+    }
+    .filter(|a| !a.trim().is_empty());
+
+    let config = load_llm_config(&app)?;
+    let (image_bytes, mime_type) = read_image(&image_path).await?;
+    let prompt = format!(
+        "The image shows a student's handwritten work on this problem. Grade it against \
+         the reference answer. The verdict is \"correct\" when the work reaches the right \
+         result with valid reasoning, \"partial\" when the method is right but there is a \
+         slip or the work is unfinished, and \"incorrect\" otherwise. Quote the first step \
+         that goes wrong in first_wrong_step (LaTeX, empty if none) and give one or two \
+         sentences of feedback; use $...$ around math.\n\nQuestion: {}\n\nReference answer: {}",
+        question,
+        answer
+            .as_deref()
+            .unwrap_or("(none given; solve the problem yourself)")
+    );
+    let schema = json!({
+        "type": "object",
+        "properties": {
+            "verdict": { "type": "string", "enum": ["correct", "partial", "incorrect"] },
+            "first_wrong_step": { "type": "string" },
+            "feedback": { "type": "string" }
+        },
+        "required": ["verdict", "first_wrong_step", "feedback"],
+        "additionalProperties": false
+    });
+
+    let key = CacheKey::new(
+        &config,
+        GRADE_WORK_TEMPLATE,
+        &[prompt.as_bytes(), &image_bytes],
+    );
+    let cached = if regenerate.unwrap_or(false) {
+        None
+    } else {
+        llm_cache::get(&app, &config, &key)
+            .await
+            .and_then(|c| serde_json::from_str(&c).ok())
+    };
+    let value = match cached {
+        Some(value) => value,
+        None => {
+            let value = call_llm_vision_structured(
+                &app,
+                &config,
+                command_name(GRADE_WORK_TEMPLATE),
+                &prompt,
+                &image_bytes,
+                mime_type,
+                "work_grade",
+                &schema,
+            )
+            .await?;
+            llm_cache::put(&app, &config, &key, &value.to_string()).await;
+            value
+        }
+    };
+    parse_work_grade(&value)
+}
+
+fn parse_work_grade(value: &Value) -> Result<WorkGrade, String> {
+    let verdict = value["verdict"].as_str().unwrap_or_default();
+    if !["correct", "partial", "incorrect"].contains(&verdict) {
+        return Err(format!("Model returned an invalid verdict: {}", value));
+    }
+    let first_wrong_step = value["first_wrong_step"]
+        .as_str()
+        .map(str::trim)
+        .filter(|s| !s.is_empty() && verdict != "correct")
+        .map(str::to_string);
+    Ok(WorkGrade {
+        verdict: verdict.to_string(),
+        first_wrong_step,
+        feedback: value["feedback"]
+            .as_str()
+            .unwrap_or_default()
+            .trim()
+            .to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_work_grades() {
+        let grade = parse_work_grade(&json!({
+            "verdict": "partial",
+            "first_wrong_step": " 2x = 8 ",
+            "feedback": "Subtract 3, not add."
+        }))
+        .unwrap();
+        assert_eq!(grade.first_wrong_step.as_deref(), Some("2x = 8"));
+
+        let correct = parse_work_grade(&json!({
+            "verdict": "correct",
+            "first_wrong_step": "n/a",
+            "feedback": ""
+        }))
+        .unwrap();
+        assert_eq!(correct.first_wrong_step, None);
+        assert!(parse_work_grade(&json!({ "verdict": "maybe" })).is_err());
+    }
+}
@@ -0,0 +1,829 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, Pool, Sqlite};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tauri::{Emitter, Manager};
+use tokio::sync::{watch, Mutex, Semaphore};
+use uuid::Uuid;
+
+use super::capture::crop_to_captures;
+use super::database::{get_pool, insert_flashcard, now_iso, CreateFlashcardInput};
+use super::llm;
+
+const PROGRESS_EVENT: &str = "generation-job-progress";
+const MAX_CONCURRENCY: i64 = 8;
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct GenerationJob {
+    pub id: String,
+    pub folder_id: Option<String>,
+    pub status: String,
+    pub concurrency: i64,
+    pub ocr: bool,
+    pub generate_answer: bool,
+    pub generate_title: bool,
+    pub assess_difficulty: bool,
+    pub total_items: i64,
+    pub done_items: i64,
+    pub failed_items: i64,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct GenerationJobItem {
+    pub id: String,
+    pub job_id: String,
+    pub position: i64,
+    pub image_path: String,
+    pub crop_x: Option<i64>,
+    pub crop_y: Option<i64>,
+    pub crop_width: Option<i64>,
+    pub crop_height: Option<i64>,
+    pub answer_crop_x: Option<i64>,
+    pub answer_crop_y: Option<i64>,
+    pub answer_crop_width: Option<i64>,
+    pub answer_crop_height: Option<i64>,
+    pub status: String,
+    pub title: Option<String>,
+    pub question_type: Option<String>,
+    pub question_content: Option<String>,
+    pub answer_type: Option<String>,
+    pub answer_content: Option<String>,
+    pub timer_seconds: Option<i64>,
+    pub error: Option<String>,
+    pub flashcard_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CropRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GenerationJobItemInput {
+    pub image_path: String,
+    pub region: Option<CropRect>,
+    /// Region of the same image holding the answer. Items with one skip answer
+    /// generation.
+    pub answer_region: Option<CropRect>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateGenerationJobInput {
+    pub folder_id: Option<String>,
+    pub items: Vec<GenerationJobItemInput>,
+    pub concurrency: Option<i64>,
+    pub ocr: Option<bool>,
+    pub generate_answer: Option<bool>,
+    pub generate_title: Option<bool>,
+    pub assess_difficulty: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GenerationJobProgress {
+    pub job_id: String,
+    pub item_id: Option<String>,
+    pub item_status: Option<String>,
+    pub status: String,
+    pub total_items: i64,
+    pub done_items: i64,
+    pub failed_items: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum JobControl {
+    Run,
+    Pause,
+    Cancel,
+}
+
+/// Control channels for jobs that currently have a runner task. Jobs that are
+/// only persisted (e.g. after a restart) have no entry until resumed.
+#[derive(Default)]
+pub struct JobRegistry(Mutex<HashMap<String, watch::Sender<JobControl>>>);
+
+struct Draft {
+    title: Option<String>,
+    question_type: String,
+    question_content: String,
+    answer_type: Option<String>,
+    answer_content: Option<String>,
+    timer_seconds: Option<i64>,
+}
+
+const ITEM_COLUMNS: &str = r#"
+    id, job_id, position, image_path, crop_x, crop_y, crop_width, crop_height,
+    answer_crop_x, answer_crop_y, answer_crop_width, answer_crop_height,
+    status, title, question_type, question_content, answer_type, answer_content,
+    timer_seconds, error, flashcard_id
+"#;
+
+const JOB_SELECT: &str = r#"
+    SELECT j.id, j.folder_id, j.status, j.concurrency, j.ocr, j.generate_answer,
+           j.generate_title, j.assess_difficulty, j.created_at, j.updated_at,
+           (SELECT COUNT(*) FROM generation_job_items i WHERE i.job_id = j.id) AS total_items,
+           (SELECT COUNT(*) FROM generation_job_items i
+             WHERE i.job_id = j.id AND i.status IN ('done', 'accepted')) AS done_items,
+           (SELECT COUNT(*) FROM generation_job_items i
+             WHERE i.job_id = j.id AND i.status = 'failed') AS failed_items
+    FROM generation_jobs j
+"#;
+
+#[tauri::command]
+pub async fn create_generation_job(
+    app: tauri::AppHandle,
+    input: CreateGenerationJobInput,
+) -> Result<GenerationJob, String> {
+    if input.items.is_empty() {
+        return Err("A generation job needs at least one image".to_string());
+    }
+    let pool = get_pool(&app).await?;
+    let job_id = Uuid::new_v4().to_string();
+    let now = now_iso();
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    sqlx::query(
+        r#"INSERT INTO generation_jobs
+            (id, folder_id, status, concurrency, ocr, generate_answer, generate_title,
+             assess_difficulty, created_at, updated_at)
+           VALUES (?, ?, 'queued', ?, ?, ?, ?, ?, ?, ?)"#,
+    )
+    .bind(&job_id)
+    .bind(&input.folder_id)
+    .bind(input.concurrency.unwrap_or(2).clamp(1, MAX_CONCURRENCY))
+    .bind(input.ocr.unwrap_or(true))
+    .bind(input.generate_answer.unwrap_or(true))
+    .bind(input.generate_title.unwrap_or(true))
+    .bind(input.assess_difficulty.unwrap_or(true))
+    .bind(&now)
+    .bind(&now)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to create job: {}", e))?;
+
+    for (position, item) in input.items.iter().enumerate() {
+        sqlx::query(
+            r#"INSERT INTO generation_job_items
+                (id, job_id, position, image_path, crop_x, crop_y, crop_width, crop_height,
+                 answer_crop_x, answer_crop_y, answer_crop_width, answer_crop_height,
+                 status, updated_at)
+               VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, 'pending', ?)"#,
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(&job_id)
+        .bind(position as i64)
+        .bind(&item.image_path)
+        .bind(item.region.as_ref().map(|r| r.x as i64))
+        .bind(item.region.as_ref().map(|r| r.y as i64))
+        .bind(item.region.as_ref().map(|r| r.width as i64))
+        .bind(item.region.as_ref().map(|r| r.height as i64))
+        .bind(item.answer_region.as_ref().map(|r| r.x as i64))
+        .bind(item.answer_region.as_ref().map(|r| r.y as i64))
+        .bind(item.answer_region.as_ref().map(|r| r.width as i64))
+        .bind(item.answer_region.as_ref().map(|r| r.height as i64))
+        .bind(&now)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to create job item: {}", e))?;
+    }
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit job: {}", e))?;
+
+    start_runner(&app, &job_id).await;
+    load_job(&pool, &job_id).await
+}
+
+#[tauri::command]
+pub async fn list_generation_jobs(app: tauri::AppHandle) -> Result<Vec<GenerationJob>, String> {
+    let pool = get_pool(&app).await?;
+    recover_interrupted_jobs(&app, &pool).await?;
+    sqlx::query_as::<_, GenerationJob>(&format!("{} ORDER BY j.created_at DESC", JOB_SELECT))
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| format!("Failed to load jobs: {}", e))
+}
+
+#[tauri::command]
+pub async fn get_generation_job_items(
+    app: tauri::AppHandle,
+    job_id: String,
+) -> Result<Vec<GenerationJobItem>, String> {
+    let pool = get_pool(&app).await?;
+    load_items(&pool, &job_id).await
+}
+
+#[tauri::command]
+pub async fn pause_generation_job(app: tauri::AppHandle, job_id: String) -> Result<(), String> {
+    let pool = get_pool(&app).await?;
+    set_job_status(&pool, &job_id, "paused").await?;
+    send_control(&app, &job_id, JobControl::Pause).await;
+    emit_progress(&app, &pool, &job_id, None).await;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn resume_generation_job(app: tauri::AppHandle, job_id: String) -> Result<(), String> {
+    let pool = get_pool(&app).await?;
+    recover_interrupted_jobs(&app, &pool).await?;
+    let job = load_job(&pool, &job_id).await?;
+    if job.status == "completed" || job.status == "cancelled" {
+        return Err(format!("Job is already {}", job.status));
+    }
+    set_job_status(&pool, &job_id, "running").await?;
+    if !send_control(&app, &job_id, JobControl::Run).await {
+        start_runner(&app, &job_id).await;
+    }
+    emit_progress(&app, &pool, &job_id, None).await;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn cancel_generation_job(app: tauri::AppHandle, job_id: String) -> Result<(), String> {
+    let pool = get_pool(&app).await?;
+    if !send_control(&app, &job_id, JobControl::Cancel).await {
+        // No runner to clean up after itself, so finish the job here.
+        mark_cancelled(&pool, &job_id).await?;
+        emit_progress(&app, &pool, &job_id, None).await;
+    }
+    Ok(())
+}
+
+/// Turns finished drafts into real flashcards. Returns the new flashcard IDs.
+#[tauri::command]
+pub async fn accept_generation_job_items(
+    app: tauri::AppHandle,
+    job_id: String,
+    item_ids: Vec<String>,
+) -> Result<Vec<String>, String> {
+    let pool = get_pool(&app).await?;
+    let job = load_job(&pool, &job_id).await?;
+    let items = load_items(&pool, &job_id).await?;
+
+    let mut created = Vec::new();
+    for item in items
+        .into_iter()
+        .filter(|item| item.status == "done" && item_ids.contains(&item.id))
+    {
+        let Some(question_content) = item.question_content else {
+            continue;
+        };
+        let input = CreateFlashcardInput {
+            folder_id: job.folder_id.clone(),
+            title: item.title,
+            question_type: item.question_type.unwrap_or_else(|| "image".to_string()),
+            question_content,
+            answer_type: item
+                .answer_content
+                .as_ref()
+                .map(|_| item.answer_type.unwrap_or_else(|| "latex".to_string())),
+            answer_content: item.answer_content,
+            timer_mode: Some(
+                if item.timer_seconds.is_some() {
+                    "llm"
+                } else {
+                    "5min"
+                }
+                .to_string(),
+            ),
+            timer_seconds: Some(item.timer_seconds.unwrap_or(300) as i32),
+        };
+        let flashcard_id = insert_flashcard(&pool, &input).await?;
+        sqlx::query(
+            "UPDATE generation_job_items SET status = 'accepted', flashcard_id = ?, updated_at = ? WHERE id = ?",
+        )
+        .bind(&flashcard_id)
+        .bind(now_iso())
+        .bind(&item.id)
+        .execute(&pool)
+        .await
+        .map_err(|e| format!("Failed to update job item: {}", e))?;
+        created.push(flashcard_id);
+    }
+    Ok(created)
+}
+
+async fn load_job(pool: &Pool<Sqlite>, job_id: &str) -> Result<GenerationJob, String> {
+    sqlx::query_as::<_, GenerationJob>(&format!("{} WHERE j.id = ?", JOB_SELECT))
+        .bind(job_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("Failed to load job: {}", e))?
+        .ok_or_else(|| "Generation job not found".to_string())
+}
+
+async fn load_items(pool: &Pool<Sqlite>, job_id: &str) -> Result<Vec<GenerationJobItem>, String> {
+    sqlx::query_as::<_, GenerationJobItem>(&format!(
+        "SELECT {} FROM generation_job_items WHERE job_id = ? ORDER BY position ASC",
+        ITEM_COLUMNS
+    ))
+    .bind(job_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load job items: {}", e))
+}
+
+async fn set_job_status(pool: &Pool<Sqlite>, job_id: &str, status: &str) -> Result<(), String> {
+    sqlx::query("UPDATE generation_jobs SET status = ?, updated_at = ? WHERE id = ?")
+        .bind(status)
+        .bind(now_iso())
+        .bind(job_id)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to update job: {}", e))?;
+    Ok(())
+}
+
+async fn mark_cancelled(pool: &Pool<Sqlite>, job_id: &str) -> Result<(), String> {
+    sqlx::query(
+        "UPDATE generation_job_items SET status = 'cancelled', updated_at = ? WHERE job_id = ? AND status IN ('pending', 'running')",
+    )
+    .bind(now_iso())
+    .bind(job_id)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to cancel job items: {}", e))?;
+    set_job_status(pool, job_id, "cancelled").await
+}
+
+/// Jobs left `running` or `queued` by a previous app session have no runner.
+/// Park them as `paused` and hand their in-flight items back to the queue.
+async fn recover_interrupted_jobs(
+    app: &tauri::AppHandle,
+    pool: &Pool<Sqlite>,
+) -> Result<(), String> {
+    let live: Vec<String> = app
+        .state::<JobRegistry>()
+        .0
+        .lock()
+        .await
+        .keys()
+        .cloned()
+        .collect();
+    park_stale_jobs(pool, &live).await
+}
+
+/// Pauses every unfinished job not in `live` and puts its running items back
+/// to pending.
+async fn park_stale_jobs(pool: &Pool<Sqlite>, live: &[String]) -> Result<(), String> {
+    let stale: Vec<(String,)> = sqlx::query_as(
+        "SELECT id FROM generation_jobs WHERE status IN ('queued', 'running', 'paused')",
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load jobs: {}", e))?;
+
+    for (job_id,) in stale.into_iter().filter(|(id,)| !live.contains(id)) {
+        sqlx::query(
+            "UPDATE generation_job_items SET status = 'pending', updated_at = ? WHERE job_id = ? AND status = 'running'",
+        )
+        .bind(now_iso())
+        .bind(&job_id)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to recover job items: {}", e))?;
+        sqlx::query(
+            "UPDATE generation_jobs SET status = 'paused', updated_at = ? WHERE id = ? AND status != 'paused'",
+        )
+        .bind(now_iso())
+        .bind(&job_id)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to recover job: {}", e))?;
+    }
+    Ok(())
+}
+
+/// Sends `control` to the job's runner. Returns false if no runner is live.
+async fn send_control(app: &tauri::AppHandle, job_id: &str, control: JobControl) -> bool {
+    let registry = app.state::<JobRegistry>();
+    let runners = registry.0.lock().await;
+    match runners.get(job_id) {
+        Some(tx) => tx.send(control).is_ok(),
+        None => false,
+    }
+}
+
+async fn start_runner(app: &tauri::AppHandle, job_id: &str) {
+    let (tx, rx) = watch::channel(JobControl::Run);
+    {
+        let registry = app.state::<JobRegistry>();
+        let mut runners = registry.0.lock().await;
+        if runners.contains_key(job_id) {
+            return;
+        }
+        runners.insert(job_id.to_string(), tx);
+    }
+
+    let app = app.clone();
+    let job_id = job_id.to_string();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = run_job(&app, &job_id, rx).await {
+            log::error!("Generation job {} stopped: {}", job_id, e);
+        }
+        app.state::<JobRegistry>().0.lock().await.remove(&job_id);
+    });
+}
+
+async fn run_job(
+    app: &tauri::AppHandle,
+    job_id: &str,
+    mut control: watch::Receiver<JobControl>,
+) -> Result<(), String> {
+    let pool = get_pool(app).await?;
+    let job = Arc::new(load_job(&pool, job_id).await?);
+    set_job_status(&pool, job_id, "running").await?;
+    emit_progress(app, &pool, job_id, None).await;
+
+    let semaphore = Arc::new(Semaphore::new(
+        job.concurrency.clamp(1, MAX_CONCURRENCY) as usize
+    ));
+    let mut handles = Vec::new();
+
+    let cancelled = loop {
+        let state = *control.borrow_and_update();
+        match state {
+            JobControl::Cancel => break true,
+            JobControl::Pause => {
+                if control.changed().await.is_err() {
+                    break true;
+                }
+                continue;
+            }
+            JobControl::Run => {}
+        }
+
+        let permit = tokio::select! {
+            permit = semaphore.clone().acquire_owned() => {
+                permit.map_err(|e| format!("Job semaphore closed: {}", e))?
+            }
+            _ = control.changed() => continue,
+        };
+        // Pausing or cancelling while waiting for a slot must not start new work.
+        if *control.borrow() != JobControl::Run {
+            continue;
+        }
+
+        let Some(item) = claim_next_item(&pool, job_id).await? else {
+            break false;
+        };
+        let app = app.clone();
+        let pool = pool.clone();
+        let job = job.clone();
+        handles.push(tauri::async_runtime::spawn(async move {
+            let _permit = permit;
+            process_item(&app, &pool, &job, item).await;
+        }));
+    };
+
+    // A cancel can still arrive while the last claimed items are running.
+    let cancelled = cancelled || !finish_in_flight(&mut handles, &mut control).await;
+    if cancelled {
+        for handle in &handles {
+            handle.abort();
+        }
+        mark_cancelled(&pool, job_id).await?;
+    } else {
+        set_job_status(&pool, job_id, "completed").await?;
+    }
+    emit_progress(app, &pool, job_id, None).await;
+    Ok(())
+}
+
+/// Waits for the in-flight items. Returns false as soon as the job is
+/// cancelled instead.
+async fn finish_in_flight(
+    handles: &mut [tauri::async_runtime::JoinHandle<()>],
+    control: &mut watch::Receiver<JobControl>,
+) -> bool {
+    for handle in handles.iter_mut() {
+        tokio::select! {
+            _ = handle => {}
+            _ = cancel_requested(control) => return false,
+        }
+    }
+    true
+}
+
+async fn cancel_requested(control: &mut watch::Receiver<JobControl>) {
+    while *control.borrow_and_update() != JobControl::Cancel {
+        if control.changed().await.is_err() {
+            return;
+        }
+    }
+}
+
+async fn claim_next_item(
+    pool: &Pool<Sqlite>,
+    job_id: &str,
+) -> Result<Option<GenerationJobItem>, String> {
+    sqlx::query_as::<_, GenerationJobItem>(&format!(
+        r#"UPDATE generation_job_items SET status = 'running', updated_at = ?
+           WHERE id = (
+               SELECT id FROM generation_job_items
+               WHERE job_id = ? AND status = 'pending'
+               ORDER BY position ASC LIMIT 1
+           )
+           RETURNING {}"#,
+        ITEM_COLUMNS
+    ))
+    .bind(now_iso())
+    .bind(job_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("Failed to claim job item: {}", e))
+}
+
+async fn process_item(
+    app: &tauri::AppHandle,
+    pool: &Pool<Sqlite>,
+    job: &GenerationJob,
+    item: GenerationJobItem,
+) {
+    let result = match generate_draft(app, job, &item).await {
+        Ok(draft) => sqlx::query(
+            r#"UPDATE generation_job_items SET
+                status = 'done', title = ?, question_type = ?, question_content = ?,
+                answer_type = ?, answer_content = ?, timer_seconds = ?, error = NULL,
+                updated_at = ?
+               WHERE id = ?"#,
+        )
+        .bind(&draft.title)
+        .bind(&draft.question_type)
+        .bind(&draft.question_content)
+        .bind(&draft.answer_type)
+        .bind(&draft.answer_content)
+        .bind(draft.timer_seconds)
+        .bind(now_iso())
+        .bind(&item.id)
+        .execute(pool)
+        .await
+        .map(|_| "done"),
+        Err(e) => sqlx::query(
+            "UPDATE generation_job_items SET status = 'failed', error = ?, updated_at = ? WHERE id = ?",
+        )
+        .bind(&e)
+        .bind(now_iso())
+        .bind(&item.id)
+        .execute(pool)
+        .await
+        .map(|_| "failed"),
+    };
+
+    match result {
+        Ok(status) => emit_progress(app, pool, &job.id, Some((&item.id, status))).await,
+        Err(e) => log::error!("Failed to store result for job item {}: {}", item.id, e),
+    }
+}
+
+async fn generate_draft(
+    app: &tauri::AppHandle,
+    job: &GenerationJob,
+    item: &GenerationJobItem,
+) -> Result<Draft, String> {
+    let region = (item.crop_x, item.crop_y, item.crop_width, item.crop_height);
+    let image_path = crop_item_region(app, &item.image_path, region)?;
+
+    let (question_type, question_content) = if job.ocr {
+        let text =
+            llm::convert_image_to_text(app.clone(), image_path, "question".to_string(), None)
+                .await?;
+        ("latex".to_string(), text)
+    } else {
+        ("image".to_string(), image_path)
+    };
+
+    let answer_region = (
+        item.answer_crop_x,
+        item.answer_crop_y,
+        item.answer_crop_width,
+        item.answer_crop_height,
+    );
+    let (answer_type, answer_content) = if answer_region.0.is_some() {
+        let answer_path = crop_item_region(app, &item.image_path, answer_region)?;
+        if job.ocr {
+            let text =
+                llm::convert_image_to_text(app.clone(), answer_path, "answer".to_string(), None)
+                    .await?;
+            (Some("latex".to_string()), Some(text))
+        } else {
+            (Some("image".to_string()), Some(answer_path))
+        }
+    } else if job.generate_answer {
+        let answer = llm::generate_answer(
+            app.clone(),
+            question_content.clone(),
+            question_type.clone(),
+            None,
+        )
+        .await?;
+        (Some("latex".to_string()), Some(answer.trim().to_string()))
+    } else {
+        (None, None)
+    };
+
+    let title = if !job.generate_title {
+        None
+    } else if question_type == "latex" {
+        Some(llm::generate_latex_title(app.clone(), question_content.clone(), None).await?)
+    } else {
+        Some(llm::generate_image_title(app.clone(), question_content.clone(), None).await?)
+    };
+
+    // Difficulty is assessed from text, so image-only drafts keep the default timer.
+    let timer_seconds = if job.assess_difficulty && question_type == "latex" {
+        let assessment = llm::assess_difficulty(app.clone(), question_content.clone(), None).await?;
+        Some(assessment.estimated_seconds as i64)
+    } else {
+        None
+    };
+
+    Ok(Draft {
+        title,
+        question_type,
+        question_content,
+        answer_type,
+        answer_content,
+        timer_seconds,
+    })
+}
+
+/// Crops `region` out of `image_path`, or returns the whole image when the
+/// item has no region.
+fn crop_item_region(
+    app: &tauri::AppHandle,
+    image_path: &str,
+    region: (Option<i64>, Option<i64>, Option<i64>, Option<i64>),
+) -> Result<String, String> {
+    match region {
+        (Some(x), Some(y), Some(width), Some(height)) => crop_to_captures(
+            app,
+            image_path,
+            x as u32,
+            y as u32,
+            width as u32,
+            height as u32,
+        ),
+        _ => Ok(image_path.to_string()),
+    }
+}
+
+async fn emit_progress(
+    app: &tauri::AppHandle,
+    pool: &Pool<Sqlite>,
+    job_id: &str,
+    item: Option<(&str, &str)>,
+) {
+    let job = match load_job(pool, job_id).await {
+        Ok(job) => job,
+        Err(e) => {
+            log::error!("Failed to load job {} for progress: {}", job_id, e);
+            return;
+        }
+    };
+    let payload = GenerationJobProgress {
+        job_id: job.id,
+        item_id: item.map(|(id, _)| id.to_string()),
+        item_status: item.map(|(_, status)| status.to_string()),
+        status: job.status,
+        total_items: job.total_items,
+        done_items: job.done_items,
+        failed_items: job.failed_items,
+    };
+    let _ = app.emit(PROGRESS_EVENT, payload);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::database::get_migrations;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn test_pool() -> Pool<Sqlite> {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        for migration in get_migrations() {
+            sqlx::raw_sql(migration.sql).execute(&pool).await.unwrap();
+        }
+        pool
+    }
+
+    /// Inserts a job with one item per `(position, status)` pair. Item IDs are
+    /// `<job_id>-<position>`.
+    async fn insert_job(pool: &Pool<Sqlite>, job_id: &str, status: &str, items: &[(i64, &str)]) {
+        let now = now_iso();
+        sqlx::query(
+            "INSERT INTO generation_jobs (id, status, created_at, updated_at) VALUES (?, ?, ?, ?)",
+        )
+        .bind(job_id)
+        .bind(status)
+        .bind(&now)
+        .bind(&now)
+        .execute(pool)
+        .await
+        .unwrap();
+        for (position, item_status) in items {
+            sqlx::query(
+                r#"INSERT INTO generation_job_items
+                    (id, job_id, position, image_path, status, updated_at)
+                   VALUES (?, ?, ?, 'page.png', ?, ?)"#,
+            )
+            .bind(format!("{}-{}", job_id, position))
+            .bind(job_id)
+            .bind(position)
+            .bind(item_status)
+            .bind(&now)
+            .execute(pool)
+            .await
+            .unwrap();
+        }
+    }
+
+    async fn item_statuses(pool: &Pool<Sqlite>, job_id: &str) -> Vec<String> {
+        load_items(pool, job_id)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|item| item.status)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn claims_pending_items_in_position_order() {
+        let pool = test_pool().await;
+        insert_job(
+            &pool,
+            "job",
+            "running",
+            &[(2, "pending"), (0, "pending"), (3, "done"), (1, "pending")],
+        )
+        .await;
+
+        let mut claimed = Vec::new();
+        while let Some(item) = claim_next_item(&pool, "job").await.unwrap() {
+            assert_eq!(item.status, "running");
+            claimed.push(item.position);
+        }
+
+        assert_eq!(claimed, vec![0, 1, 2]);
+        assert_eq!(
+            item_statuses(&pool, "job").await,
+            vec!["running", "running", "running", "done"]
+        );
+    }
+
+    #[tokio::test]
+    async fn parks_jobs_without_a_runner() {
+        let pool = test_pool().await;
+        insert_job(
+            &pool,
+            "stale",
+            "running",
+            &[(0, "done"), (1, "running"), (2, "pending")],
+        )
+        .await;
+        insert_job(&pool, "live", "running", &[(0, "running")]).await;
+
+        park_stale_jobs(&pool, &["live".to_string()]).await.unwrap();
+
+        assert_eq!(load_job(&pool, "stale").await.unwrap().status, "paused");
+        assert_eq!(
+            item_statuses(&pool, "stale").await,
+            vec!["done", "pending", "pending"]
+        );
+        assert_eq!(load_job(&pool, "live").await.unwrap().status, "running");
+        assert_eq!(item_statuses(&pool, "live").await, vec!["running"]);
+    }
+
+    #[tokio::test]
+    async fn cancelling_keeps_finished_items() {
+        let pool = test_pool().await;
+        insert_job(
+            &pool,
+            "job",
+            "running",
+            &[(0, "done"), (1, "running"), (2, "pending"), (3, "failed")],
+        )
+        .await;
+
+        mark_cancelled(&pool, "job").await.unwrap();
+
+        assert_eq!(load_job(&pool, "job").await.unwrap().status, "cancelled");
+        assert_eq!(
+            item_statuses(&pool, "job").await,
+            vec!["done", "cancelled", "cancelled", "failed"]
+        );
+    }
+}
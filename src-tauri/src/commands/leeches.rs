@@ -0,0 +1,193 @@
+//! Leeches: cards that keep being forgotten. Reviews count lapses and tag a
+//! card once it reaches its folder's `leech_threshold` (see `submitReview` in
+//! `src/lib/database.ts`); this module reports them and suggests simpler
+//! prerequisite cards to learn first.
+
+use serde::Serialize;
+use serde_json::{json, Value};
+use sqlx::FromRow;
+
+use super::database::get_pool;
+use super::llm::{call_llm_structured, command_name, load_llm_config};
+use super::ocr_index::ocr_images;
+use super::verify::solution_text;
+
+const PREREQUISITES_TEMPLATE: &str = "suggest_prerequisite_cards/v1";
+const DEFAULT_PREREQUISITE_COUNT: u32 = 3;
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct LeechCard {
+    pub id: String,
+    pub folder_id: Option<String>,
+    pub title: Option<String>,
+    pub question_type: String,
+    pub question_content: String,
+    pub lapses: i32,
+    pub suspended: bool,
+    pub ease_factor: f64,
+    pub last_reviewed: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PrerequisiteCard {
+    pub question: String,
+    pub answer: String,
+    /// The skill this card drills, e.g. "chain rule".
+    pub skill: String,
+}
+
+#[derive(FromRow)]
+struct CardSource {
+    question_type: String,
+    question_content: String,
+    answer_type: Option<String>,
+    answer_content: Option<String>,
+    question_ocr: Option<String>,
+    answer_ocr: Option<String>,
+}
+
+/// Tagged leeches, most lapses first.
+#[tauri::command]
+pub async fn get_leeches(
+    app: tauri::AppHandle,
+    folder_id: Option<String>,
+) -> Result<Vec<LeechCard>, String> {
+    let pool = get_pool(&app).await?;
+    sqlx::query_as(
+        "SELECT id, folder_id, title, question_type, question_content, lapses,
+                suspended != 0 AS suspended, ease_factor, last_reviewed
+         FROM flashcards
+         WHERE leech = 1 AND ($1 IS NULL OR folder_id = $1)
+         ORDER BY lapses DESC, last_reviewed DESC",
+    )
+    .bind(folder_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| format!("Failed to load leeches: {}", e))
+}
+
+/// Asks the LLM for simpler cards covering the skills a leech depends on. The
+/// suggestions are returned for the user to pick from, not saved.
+#[tauri::command]
+pub async fn suggest_prerequisite_cards(
+    app: tauri::AppHandle,
+    flashcard_id: String,
+    count: Option<u32>,
+) -> Result<Vec<PrerequisiteCard>, String> {
+    let count = count.unwrap_or(DEFAULT_PREREQUISITE_COUNT).clamp(1, 8);
+    let pool = get_pool(&app).await?;
+    let card: CardSource = sqlx::query_as(
+        "SELECT question_type, question_content, answer_type, answer_content,
+                question_ocr, answer_ocr
+         FROM flashcards WHERE id = $1",
+    )
+    .bind(&flashcard_id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| format!("Failed to load flashcard: {}", e))?
+    .ok_or_else(|| format!("Flashcard not found: {}", flashcard_id))?;
+
+    let question = match (card.question_type.as_str(), card.question_ocr) {
+        ("image", Some(text)) => text,
+        ("image", None) => ocr_images(&app, &card.question_content).await?,
+        _ => card.question_content,
+    };
+    let answer = match (card.answer_type.as_deref(), card.answer_content) {
+        (_, None) => None,
+        (Some("image"), Some(content)) => Some(match card.answer_ocr {
+            Some(text) => text,
+            None => ocr_images(&app, &content).await?,
+        }),
+        (Some("solution"), Some(content)) => Some(solution_text(&content).unwrap_or(content)),
+        (_, Some(content)) => Some(content),
+    };
+
+    let config = load_llm_config(&app)?;
+    let prompt = format!(
+        "A student keeps getting this flashcard wrong. Identify the prerequisite skills it \
+         relies on that they most likely haven't mastered, and write {} simpler flashcards \
+         that each drill one of those skills in isolation. Order them from most basic to \
+         closest to the original problem. Give each a correct, concise answer and name the \
+         skill it practices in a few words.\n\n\
+         FORMATTING RULES:\n\
+         - Use $...$ around inline math and $$...$$ for display math\n\
+         - Write regular text outside of dollar signs\n\n\
+         Question: {}{}",
+        count,
+        question,
+        answer
+            .map(|a| format!("\n\nAnswer: {}", a))
+            .unwrap_or_default()
+    );
+    let schema = json!({
+        "type": "object",
+        "properties": {
+            "cards": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "skill": { "type": "string" },
+                        "question": { "type": "string" },
+                        "answer": { "type": "string" }
+                    },
+                    "required": ["skill", "question", "answer"],
+                    "additionalProperties": false
+                }
+            }
+        },
+        "required": ["cards"],
+        "additionalProperties": false
+    });
+    let value = call_llm_structured(
+        &app,
+        &config,
+        command_name(PREREQUISITES_TEMPLATE),
+        &prompt,
+        "prerequisite_cards",
+        &schema,
+    )
+    .await?;
+    parse_prerequisites(&value, count as usize)
+}
+
+fn parse_prerequisites(value: &Value, count: usize) -> Result<Vec<PrerequisiteCard>, String> {
+    let field = |item: &Value, key: &str| item[key].as_str().unwrap_or_default().trim().to_string();
+    let cards: Vec<PrerequisiteCard> = value["cards"]
+        .as_array()
+        .ok_or_else(|| format!("Prerequisites are missing `cards`: {}", value))?
+        .iter()
+        .map(|item| PrerequisiteCard {
+            question: field(item, "question"),
+            answer: field(item, "answer"),
+            skill: field(item, "skill"),
+        })
+        .filter(|card| !card.question.is_empty() && !card.answer.is_empty())
+        .take(count)
+        .collect();
+    if cards.is_empty() {
+        return Err(format!("Model returned no prerequisite cards: {}", value));
+    }
+    Ok(cards)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_prerequisites() {
+        let value = json!({
+            "cards": [
+                { "skill": "power rule", "question": "Differentiate $x^3$", "answer": "$3x^2$" },
+                { "skill": "empty", "question": " ", "answer": "$1$" },
+                { "skill": "chain rule", "question": "Differentiate $(2x)^2$", "answer": "$8x$" }
+            ]
+        });
+        let cards = parse_prerequisites(&value, 5).unwrap();
+        assert_eq!(cards.len(), 2);
+        assert_eq!(cards[0].skill, "power rule");
+        assert_eq!(parse_prerequisites(&value, 1).unwrap().len(), 1);
+        assert!(parse_prerequisites(&json!({ "cards": [] }), 3).is_err());
+    }
+}
@@ -2,39 +2,58 @@ use base64::Engine;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
 use std::path::Path;
+use std::time::Instant;
 use tauri::Manager;
 
+use super::llm_cache::{self, CacheKey};
+use super::llm_usage::{self, ModelPrice};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LLMConfig {
     pub provider: String,
     pub api_key: String,
     pub model: String,
     pub base_url: String,
+    #[serde(default)]
+    pub cache_ttl_days: Option<u64>,
+    #[serde(default)]
+    pub cache_max_mb: Option<u64>,
+    /// Per-model price overrides, keyed by model name or name prefix.
+    #[serde(default)]
+    pub price_table: Option<HashMap<String, ModelPrice>>,
+    #[serde(default)]
+    pub monthly_budget_usd: Option<f64>,
 }
 
+// Prompt template versions, named `<command>/v<n>`. Bump one whenever its prompt
+// changes so cached responses produced by the old wording are no longer served.
+// The command part also labels the call in the usage log.
+const OCR_IMAGE_TEMPLATE: &str = "ocr_image/v1";
+const ASSESS_DIFFICULTY_TEMPLATE: &str = "assess_difficulty/v2";
+const IMAGE_TITLE_TEMPLATE: &str = "generate_image_title/v1";
+const LATEX_TITLE_TEMPLATE: &str = "generate_latex_title/v1";
+const ANSWER_TEMPLATE: &str = "generate_answer/v1";
+const QUESTION_TEMPLATE: &str = "generate_question/v1";
+const SOLUTION_TEMPLATE: &str = "generate_solution/v1";
+const HINTS_TEMPLATE: &str = "generate_hints/v1";
+const VARIANTS_TEMPLATE: &str = "generate_variants/v1";
+const DEFAULT_VARIANT_COUNT: u32 = 3;
+const CONVERT_IMAGE_TEMPLATE: &str = "convert_image_to_text/v1";
+
 #[tauri::command]
 pub async fn ocr_image(
     app: tauri::AppHandle,
     image_path: String,
+    regenerate: Option<bool>,
 ) -> Result<String, String> {
+    if let Some(latex) = super::local_ocr::try_local_ocr(&app, &image_path).await? {
+        return Ok(latex);
+    }
     let config = load_llm_config(&app)?;
-    let image_bytes = tokio::fs::read(&image_path)
-        .await
-        .map_err(|e| format!("Failed to read image: {}", e))?;
-
-    let base64_image = base64::engine::general_purpose::STANDARD.encode(&image_bytes);
-
-    let ext = Path::new(&image_path)
-        .extension()
-        .and_then(|e| e.to_str())
-        .unwrap_or("png");
-    let mime_type = match ext {
-        "jpg" | "jpeg" => "image/jpeg",
-        "gif" => "image/gif",
-        "webp" => "image/webp",
-        _ => "image/png",
-    };
+    let (image_bytes, mime_type) = read_image(&image_path).await?;
 
     let prompt = "You are a math OCR system. Convert the content of this image to text with LaTeX math.\n\n\
         Rules:\n\
@@ -44,31 +63,113 @@ pub async fn ocr_image(
         4. If you cannot read part, use [illegible]\n\
         5. Return ONLY the content, no explanations or code fences";
 
-    let response = call_llm_vision(&config, prompt, &base64_image, mime_type).await?;
+    let response = call_llm_vision_cached(
+        &app,
+        &config,
+        OCR_IMAGE_TEMPLATE,
+        prompt,
+        &image_bytes,
+        mime_type,
+        regenerate.unwrap_or(false),
+    )
+    .await?;
     Ok(response.trim().to_string())
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DifficultyAssessment {
+    /// Time a student needs to solve the problem, clamped to 30..=3600.
+    pub estimated_seconds: i32,
+    /// 1 (routine) to 5 (very hard).
+    pub difficulty: i32,
+    pub topics: Vec<String>,
+    pub prerequisites: Vec<String>,
+}
+
 #[tauri::command]
 pub async fn assess_difficulty(
     app: tauri::AppHandle,
     latex: String,
-) -> Result<i32, String> {
+    regenerate: Option<bool>,
+) -> Result<DifficultyAssessment, String> {
     let config = load_llm_config(&app)?;
 
     let prompt = format!(
-        "Given this math problem, estimate how many seconds a student would need to solve it. \
-         Return ONLY a number (seconds), nothing else.\n\nProblem: {}",
+        "Assess this math problem for a flashcard timer. Estimate how many seconds a \
+         student would need to solve it, rate its difficulty from 1 (routine) to 5 \
+         (very hard), list the topics it covers and the prerequisite concepts a student \
+         must already know. Keep topic and concept labels short (1-4 words).\n\nProblem: {}",
         latex
     );
+    let schema = json!({
+        "type": "object",
+        "properties": {
+            "estimated_seconds": { "type": "integer", "description": "Seconds needed to solve the problem" },
+            "difficulty": { "type": "integer", "description": "1 (routine) to 5 (very hard)" },
+            "topics": { "type": "array", "items": { "type": "string" } },
+            "prerequisites": { "type": "array", "items": { "type": "string" } }
+        },
+        "required": ["estimated_seconds", "difficulty", "topics", "prerequisites"],
+        "additionalProperties": false
+    });
 
-    let response = call_llm_text(&config, &prompt).await?;
-    let seconds = response
-        .trim()
-        .parse::<i32>()
-        .unwrap_or(300);
+    let key = CacheKey::new(&config, ASSESS_DIFFICULTY_TEMPLATE, &[prompt.as_bytes()]);
+    let cached: Option<Value> = if regenerate.unwrap_or(false) {
+        None
+    } else {
+        llm_cache::get(&app, &config, &key)
+            .await
+            .and_then(|c| serde_json::from_str(&c).ok())
+    };
+    if let Some(assessment) = cached.as_ref().and_then(|v| parse_difficulty_assessment(v).ok()) {
+        return Ok(assessment);
+    }
 
-    // Clamp to reasonable range
-    Ok(seconds.clamp(30, 3600))
+    let value = call_llm_structured(
+        &app,
+        &config,
+        command_name(ASSESS_DIFFICULTY_TEMPLATE),
+        &prompt,
+        "difficulty_assessment",
+        &schema,
+    )
+    .await?;
+    let assessment = parse_difficulty_assessment(&value)?;
+    llm_cache::put(&app, &config, &key, &value.to_string()).await;
+    Ok(assessment)
+}
+
+fn parse_difficulty_assessment(value: &Value) -> Result<DifficultyAssessment, String> {
+    let field = |name: &str| {
+        value
+            .get(name)
+            .ok_or_else(|| format!("Difficulty assessment is missing `{}`: {}", name, value))
+    };
+    let number = |name: &str| -> Result<f64, String> {
+        let v = field(name)?;
+        v.as_f64()
+            .or_else(|| v.as_str().and_then(|s| s.trim().parse().ok()))
+            .ok_or_else(|| format!("Difficulty assessment `{}` is not a number: {}", name, v))
+    };
+    let labels = |name: &str| -> Result<Vec<String>, String> {
+        let v = field(name)?;
+        let items = v
+            .as_array()
+            .ok_or_else(|| format!("Difficulty assessment `{}` is not a list: {}", name, v))?;
+        Ok(items
+            .iter()
+            .filter_map(|item| item.as_str())
+            .map(|item| item.trim().to_string())
+            .filter(|item| !item.is_empty())
+            .collect())
+    };
+
+    Ok(DifficultyAssessment {
+        estimated_seconds: (number("estimated_seconds")?.round() as i32).clamp(30, 3600),
+        difficulty: (number("difficulty")?.round() as i32).clamp(1, 5),
+        topics: labels("topics")?,
+        prerequisites: labels("prerequisites")?,
+    })
 }
 
 #[tauri::command]
@@ -87,7 +188,8 @@ pub async fn set_llm_config(
 #[tauri::command]
 pub async fn test_llm_connection(app: tauri::AppHandle) -> Result<String, String> {
     let config = load_llm_config(&app)?;
-    let response = call_llm_text(&config, "Respond with 'ok'").await?;
+    let response =
+        call_llm_text(&app, &config, "test_llm_connection", "Respond with 'ok'").await?;
     Ok(response)
 }
 
@@ -95,31 +197,26 @@ pub async fn test_llm_connection(app: tauri::AppHandle) -> Result<String, String
 pub async fn generate_image_title(
     app: tauri::AppHandle,
     image_path: String,
+    regenerate: Option<bool>,
 ) -> Result<String, String> {
     let config = load_llm_config(&app)?;
-    let image_bytes = tokio::fs::read(&image_path)
-        .await
-        .map_err(|e| format!("Failed to read image: {}", e))?;
-
-    let base64_image = base64::engine::general_purpose::STANDARD.encode(&image_bytes);
-
-    let ext = Path::new(&image_path)
-        .extension()
-        .and_then(|e| e.to_str())
-        .unwrap_or("png");
-    let mime_type = match ext {
-        "jpg" | "jpeg" => "image/jpeg",
-        "gif" => "image/gif",
-        "webp" => "image/webp",
-        _ => "image/png",
-    };
+    let (image_bytes, mime_type) = read_image(&image_path).await?;
 
     let prompt = "You are a helpful assistant that generates concise, descriptive titles for flashcard questions. \
         Look at this image which contains a question (likely a math problem or similar academic question). \
         Generate a short, descriptive title (maximum 60 characters) that summarizes what the question is about. \
         Return ONLY the title, no quotes, no explanations.";
 
-    let response = call_llm_vision(&config, prompt, &base64_image, mime_type).await?;
+    let response = call_llm_vision_cached(
+        &app,
+        &config,
+        IMAGE_TITLE_TEMPLATE,
+        prompt,
+        &image_bytes,
+        mime_type,
+        regenerate.unwrap_or(false),
+    )
+    .await?;
     let mut title = response.trim().to_string();
 
     // Truncate if too long
@@ -135,6 +232,7 @@ pub async fn generate_image_title(
 pub async fn generate_latex_title(
     app: tauri::AppHandle,
     latex: String,
+    regenerate: Option<bool>,
 ) -> Result<String, String> {
     let config = load_llm_config(&app)?;
 
@@ -145,7 +243,14 @@ pub async fn generate_latex_title(
         latex
     );
 
-    let response = call_llm_text(&config, &prompt).await?;
+    let response = call_llm_text_cached(
+        &app,
+        &config,
+        LATEX_TITLE_TEMPLATE,
+        &prompt,
+        regenerate.unwrap_or(false),
+    )
+    .await?;
     let mut title = response.trim().to_string();
     if title.len() > 80 {
         title.truncate(77);
@@ -159,25 +264,14 @@ pub async fn generate_answer(
     app: tauri::AppHandle,
     question_content: String,
     question_type: String,
+    regenerate: Option<bool>,
 ) -> Result<String, String> {
     let config = load_llm_config(&app)?;
+    let regenerate = regenerate.unwrap_or(false);
 
     if question_type == "image" {
         // Vision-based: read image and ask LLM to solve it
-        let image_bytes = tokio::fs::read(&question_content)
-            .await
-            .map_err(|e| format!("Failed to read image: {}", e))?;
-        let base64_image = base64::engine::general_purpose::STANDARD.encode(&image_bytes);
-        let ext = Path::new(&question_content)
-            .extension()
-            .and_then(|e| e.to_str())
-            .unwrap_or("png");
-        let mime_type = match ext {
-            "jpg" | "jpeg" => "image/jpeg",
-            "gif" => "image/gif",
-            "webp" => "image/webp",
-            _ => "image/png",
-        };
+        let (image_bytes, mime_type) = read_image(&question_content).await?;
 
         let prompt = "Look at this flashcard question image. Provide a clear, concise answer.\n\n\
             FORMATTING RULES:\n\
@@ -186,7 +280,16 @@ pub async fn generate_answer(
             - Write regular text outside of dollar signs — NEVER put entire sentences inside $...$\n\
             - If non-math (definitions, vocab, etc.): answer in plain text without dollar signs\n\n\
             Return ONLY the answer. No explanations, no markdown code fences.";
-        call_llm_vision(&config, prompt, &base64_image, mime_type).await
+        call_llm_vision_cached(
+            &app,
+            &config,
+            ANSWER_TEMPLATE,
+            prompt,
+            &image_bytes,
+            mime_type,
+            regenerate,
+        )
+        .await
     } else {
         let prompt = format!(
             "Answer this flashcard question. Provide a clear, concise answer.\n\n\
@@ -198,33 +301,452 @@ pub async fn generate_answer(
              Return ONLY the answer. No explanations, no markdown code fences.\n\nQuestion: {}",
             question_content
         );
-        call_llm_text(&config, &prompt).await
+        call_llm_text_cached(&app, &config, ANSWER_TEMPLATE, &prompt, regenerate).await
     }
 }
 
+/// One step of a worked solution. A `solution` answer stores these as a JSON
+/// array in `answer_content`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SolutionStep {
+    pub latex: String,
+    pub justification: String,
+}
+
+#[tauri::command]
+pub async fn generate_solution(
+    app: tauri::AppHandle,
+    question_content: String,
+    question_type: String,
+    regenerate: Option<bool>,
+) -> Result<Vec<SolutionStep>, String> {
+    let question = if question_type == "image" {
+        ocr_image(app.clone(), question_content, None).await?
+    } else {
+        question_content
+    };
+    let config = load_llm_config(&app)?;
+
+    let prompt = format!(
+        "Solve this flashcard problem as a short worked solution a student can reveal one \
+         step at a time. Each step has:\n\
+         - latex: the expression or equation reached in this step, in LaTeX WITHOUT dollar signs\n\
+         - justification: one short plain-text sentence saying what was done (a rule, \
+         substitution or simplification). Use $...$ for any math inside it.\n\n\
+         Keep steps small but skip trivial arithmetic. The last step must state the final \
+         answer.\n\nProblem: {}",
+        question
+    );
+    let schema = json!({
+        "type": "object",
+        "properties": {
+            "steps": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "latex": { "type": "string" },
+                        "justification": { "type": "string" }
+                    },
+                    "required": ["latex", "justification"],
+                    "additionalProperties": false
+                }
+            }
+        },
+        "required": ["steps"],
+        "additionalProperties": false
+    });
+
+    let key = CacheKey::new(&config, SOLUTION_TEMPLATE, &[prompt.as_bytes()]);
+    let cached: Option<Value> = if regenerate.unwrap_or(false) {
+        None
+    } else {
+        llm_cache::get(&app, &config, &key)
+            .await
+            .and_then(|c| serde_json::from_str(&c).ok())
+    };
+    if let Some(steps) = cached.as_ref().and_then(|v| parse_solution_steps(v).ok()) {
+        return Ok(steps);
+    }
+
+    let value = call_llm_structured(
+        &app,
+        &config,
+        command_name(SOLUTION_TEMPLATE),
+        &prompt,
+        "worked_solution",
+        &schema,
+    )
+    .await?;
+    let steps = parse_solution_steps(&value)?;
+    llm_cache::put(&app, &config, &key, &value.to_string()).await;
+    Ok(steps)
+}
+
+fn parse_solution_steps(value: &Value) -> Result<Vec<SolutionStep>, String> {
+    let items = value["steps"]
+        .as_array()
+        .ok_or_else(|| format!("Solution is missing `steps`: {}", value))?;
+    let steps: Vec<SolutionStep> = items
+        .iter()
+        .map(|item| SolutionStep {
+            latex: strip_math_delimiters(item["latex"].as_str().unwrap_or_default()).to_string(),
+            justification: item["justification"]
+                .as_str()
+                .unwrap_or_default()
+                .trim()
+                .to_string(),
+        })
+        .filter(|step| !step.latex.is_empty() || !step.justification.is_empty())
+        .collect();
+    if steps.is_empty() {
+        return Err(format!("Solution has no steps: {}", value));
+    }
+    Ok(steps)
+}
+
+/// Models sometimes wrap step expressions in `$...$` despite being asked not to.
+fn strip_math_delimiters(latex: &str) -> &str {
+    let trimmed = latex.trim();
+    trimmed
+        .strip_prefix("$$")
+        .and_then(|rest| rest.strip_suffix("$$"))
+        .or_else(|| {
+            trimmed
+                .strip_prefix('$')
+                .and_then(|rest| rest.strip_suffix('$'))
+        })
+        .map(str::trim)
+        .unwrap_or(trimmed)
+}
+
+/// Returns 2-3 progressive hints for a card, from the `card_hints` table when
+/// they were generated for the card's current question and answer.
+#[tauri::command]
+pub async fn generate_hints(
+    app: tauri::AppHandle,
+    flashcard_id: String,
+    regenerate: Option<bool>,
+) -> Result<Vec<String>, String> {
+    let pool = super::database::get_pool(&app).await?;
+    let (question_type, question_content, answer_type, answer_content): (
+        String,
+        String,
+        Option<String>,
+        Option<String>,
+    ) = sqlx::query_as(
+        "SELECT question_type, question_content, answer_type, answer_content FROM flashcards WHERE id = $1",
+    )
+    .bind(&flashcard_id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| format!("Failed to load flashcard: {}", e))?
+    .ok_or_else(|| format!("Flashcard not found: {}", flashcard_id))?;
+
+    let source_hash = format!(
+        "{:x}",
+        Sha256::digest(
+            [
+                HINTS_TEMPLATE,
+                &question_content,
+                answer_content.as_deref().unwrap_or(""),
+            ]
+            .join("\0")
+        )
+    );
+    if !regenerate.unwrap_or(false) {
+        let stored: Option<(String, String)> =
+            sqlx::query_as("SELECT hints, source_hash FROM card_hints WHERE flashcard_id = $1")
+                .bind(&flashcard_id)
+                .fetch_optional(&pool)
+                .await
+                .map_err(|e| format!("Failed to load hints: {}", e))?;
+        if let Some((hints, hash)) = stored {
+            if hash == source_hash {
+                if let Ok(hints) = serde_json::from_str::<Vec<String>>(&hints) {
+                    return Ok(hints);
+                }
+            }
+        }
+    }
+
+    let question = if question_type == "image" {
+        ocr_image(app.clone(), question_content, None).await?
+    } else {
+        question_content
+    };
+    let answer = match (answer_type.as_deref(), answer_content) {
+        (Some("image"), _) | (_, None) => None,
+        (Some("solution"), Some(content)) => serde_json::from_str::<Vec<SolutionStep>>(&content)
+            .ok()
+            .map(|steps| {
+                steps
+                    .iter()
+                    .map(|step| format!("$${}$$ ({})", step.latex, step.justification))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            }),
+        (_, Some(content)) => Some(content),
+    };
+
+    let config = load_llm_config(&app)?;
+    let prompt = format!(
+        "Write 2 or 3 progressive hints for a student who is stuck on this flashcard. \
+         The first hint points to the right idea or method, each later hint gives a more \
+         concrete next step, and no hint states the final answer. Keep each hint to one \
+         or two sentences; use $...$ around math.\n\nQuestion: {}{}",
+        question,
+        answer
+            .map(|a| format!("\n\nAnswer (do not reveal): {}", a))
+            .unwrap_or_default()
+    );
+    let schema = json!({
+        "type": "object",
+        "properties": {
+            "hints": { "type": "array", "items": { "type": "string" } }
+        },
+        "required": ["hints"],
+        "additionalProperties": false
+    });
+    let value = call_llm_structured(
+        &app,
+        &config,
+        command_name(HINTS_TEMPLATE),
+        &prompt,
+        "hint_ladder",
+        &schema,
+    )
+    .await?;
+    let hints = parse_hints(&value)?;
+
+    let hints_json =
+        serde_json::to_string(&hints).map_err(|e| format!("Failed to serialize hints: {}", e))?;
+    sqlx::query(
+        "INSERT INTO card_hints (flashcard_id, hints, source_hash, created_at)
+         VALUES ($1, $2, $3, $4)
+         ON CONFLICT(flashcard_id) DO UPDATE SET
+            hints = excluded.hints, source_hash = excluded.source_hash, created_at = excluded.created_at",
+    )
+    .bind(&flashcard_id)
+    .bind(&hints_json)
+    .bind(&source_hash)
+    .bind(super::database::now_iso())
+    .execute(&pool)
+    .await
+    .map_err(|e| format!("Failed to save hints: {}", e))?;
+    Ok(hints)
+}
+
+/// Keeps at most three non-empty hints.
+fn parse_hints(value: &Value) -> Result<Vec<String>, String> {
+    let hints: Vec<String> = value["hints"]
+        .as_array()
+        .ok_or_else(|| format!("Hints are missing `hints`: {}", value))?
+        .iter()
+        .filter_map(|hint| hint.as_str())
+        .map(|hint| hint.trim().to_string())
+        .filter(|hint| !hint.is_empty())
+        .take(3)
+        .collect();
+    if hints.is_empty() {
+        return Err(format!("Model returned no hints: {}", value));
+    }
+    Ok(hints)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct VariantProblem {
+    pub question: String,
+    pub answer: String,
+}
+
+/// Creates `count` structurally similar problems from a card and stores them
+/// as siblings in its `card_family`. Siblings share the original's schedule;
+/// the study queue shows one member of a family per review. Returns the new
+/// card ids.
+#[tauri::command]
+pub async fn generate_variants(
+    app: tauri::AppHandle,
+    flashcard_id: String,
+    count: Option<u32>,
+) -> Result<Vec<String>, String> {
+    let count = count.unwrap_or(DEFAULT_VARIANT_COUNT).clamp(1, 10);
+    let pool = super::database::get_pool(&app).await?;
+    let (folder_id, question_type, question_content, answer_type, answer_content, timer_mode, timer_seconds): (
+        Option<String>,
+        String,
+        String,
+        Option<String>,
+        Option<String>,
+        String,
+        i32,
+    ) = sqlx::query_as(
+        "SELECT folder_id, question_type, question_content, answer_type, answer_content, timer_mode, timer_seconds
+         FROM flashcards WHERE id = $1",
+    )
+    .bind(&flashcard_id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| format!("Failed to load flashcard: {}", e))?
+    .ok_or_else(|| format!("Flashcard not found: {}", flashcard_id))?;
+
+    let question = if question_type == "image" {
+        ocr_image(app.clone(), question_content, None).await?
+    } else {
+        question_content
+    };
+    let answer = match (answer_type.as_deref(), answer_content) {
+        (_, None) => None,
+        (Some("image"), Some(content)) => {
+            Some(super::ocr_index::ocr_images(&app, &content).await?)
+        }
+        (_, Some(content)) => Some(content),
+    };
+
+    let config = load_llm_config(&app)?;
+    let prompt = format!(
+        "Write {} new practice problems that are structurally similar to this flashcard: \
+         same technique and difficulty, but different numbers, functions or setup, so a \
+         student cannot reuse the memorized result. Give each with a correct, concise \
+         answer.\n\n\
+         FORMATTING RULES:\n\
+         - Use $...$ around inline math and $$...$$ for display math\n\
+         - Write regular text outside of dollar signs\n\n\
+         Question: {}{}",
+        count,
+        question,
+        answer
+            .map(|a| format!("\n\nAnswer: {}", a))
+            .unwrap_or_default()
+    );
+    let schema = json!({
+        "type": "object",
+        "properties": {
+            "variants": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "question": { "type": "string" },
+                        "answer": { "type": "string" }
+                    },
+                    "required": ["question", "answer"],
+                    "additionalProperties": false
+                }
+            }
+        },
+        "required": ["variants"],
+        "additionalProperties": false
+    });
+    let value = call_llm_structured(
+        &app,
+        &config,
+        command_name(VARIANTS_TEMPLATE),
+        &prompt,
+        "variant_problems",
+        &schema,
+    )
+    .await?;
+    let variants = parse_variants(&value, count as usize)?;
+
+    // The family, the new cards and their copied schedule land together or not at all.
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let family_id: String = sqlx::query_scalar(
+        "SELECT family_id FROM card_family WHERE flashcard_id = $1",
+    )
+    .bind(&flashcard_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to load card family: {}", e))?
+    .unwrap_or_else(|| flashcard_id.clone());
+    sqlx::query(
+        "INSERT OR IGNORE INTO card_family (flashcard_id, family_id, position) VALUES ($1, $2, 0)",
+    )
+    .bind(&flashcard_id)
+    .bind(&family_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to save card family: {}", e))?;
+
+    let mut ids = Vec::with_capacity(variants.len());
+    for variant in variants {
+        let id = super::database::insert_flashcard(
+            &mut *tx,
+            &super::database::CreateFlashcardInput {
+                folder_id: folder_id.clone(),
+                title: None,
+                question_type: "latex".to_string(),
+                question_content: variant.question,
+                answer_type: Some("latex".to_string()),
+                answer_content: Some(variant.answer),
+                timer_mode: Some(timer_mode.clone()),
+                timer_seconds: Some(timer_seconds),
+            },
+        )
+        .await?;
+        // Join the family's shared schedule instead of starting as a new card.
+        sqlx::query(
+            "UPDATE flashcards SET
+                (ease_factor, interval_days, base_interval_days, repetitions, due_date) =
+                (SELECT ease_factor, interval_days, base_interval_days, repetitions, due_date
+                 FROM flashcards WHERE id = $1)
+             WHERE id = $2",
+        )
+        .bind(&flashcard_id)
+        .bind(&id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to copy schedule: {}", e))?;
+        sqlx::query(
+            "INSERT INTO card_family (flashcard_id, family_id, position)
+             VALUES ($1, $2, (SELECT COALESCE(MAX(position), 0) + 1 FROM card_family WHERE family_id = $2))",
+        )
+        .bind(&id)
+        .bind(&family_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to save card family: {}", e))?;
+        ids.push(id);
+    }
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    Ok(ids)
+}
+
+fn parse_variants(value: &Value, count: usize) -> Result<Vec<VariantProblem>, String> {
+    let variants: Vec<VariantProblem> = value["variants"]
+        .as_array()
+        .ok_or_else(|| format!("Variants are missing `variants`: {}", value))?
+        .iter()
+        .map(|item| VariantProblem {
+            question: item["question"].as_str().unwrap_or_default().trim().to_string(),
+            answer: item["answer"].as_str().unwrap_or_default().trim().to_string(),
+        })
+        .filter(|variant| !variant.question.is_empty() && !variant.answer.is_empty())
+        .take(count)
+        .collect();
+    if variants.is_empty() {
+        return Err(format!("Model returned no variants: {}", value));
+    }
+    Ok(variants)
+}
+
 #[tauri::command]
 pub async fn generate_question(
     app: tauri::AppHandle,
     answer_content: String,
     answer_type: String,
+    regenerate: Option<bool>,
 ) -> Result<String, String> {
     let config = load_llm_config(&app)?;
+    let regenerate = regenerate.unwrap_or(false);
 
     if answer_type == "image" {
-        let image_bytes = tokio::fs::read(&answer_content)
-            .await
-            .map_err(|e| format!("Failed to read image: {}", e))?;
-        let base64_image = base64::engine::general_purpose::STANDARD.encode(&image_bytes);
-        let ext = Path::new(&answer_content)
-            .extension()
-            .and_then(|e| e.to_str())
-            .unwrap_or("png");
-        let mime_type = match ext {
-            "jpg" | "jpeg" => "image/jpeg",
-            "gif" => "image/gif",
-            "webp" => "image/webp",
-            _ => "image/png",
-        };
+        let (image_bytes, mime_type) = read_image(&answer_content).await?;
 
         let prompt = "Look at this flashcard answer image. Generate a clear, concise question that this answers.\n\n\
             FORMATTING RULES:\n\
@@ -233,7 +755,16 @@ pub async fn generate_question(
             - Write regular text outside of dollar signs — NEVER put entire sentences inside $...$\n\
             - If non-math: write the question in plain text without dollar signs\n\n\
             Return ONLY the question. No explanations, no markdown code fences.";
-        call_llm_vision(&config, prompt, &base64_image, mime_type).await
+        call_llm_vision_cached(
+            &app,
+            &config,
+            QUESTION_TEMPLATE,
+            prompt,
+            &image_bytes,
+            mime_type,
+            regenerate,
+        )
+        .await
     } else {
         let prompt = format!(
             "Given this answer, generate a clear, concise flashcard question.\n\n\
@@ -245,7 +776,7 @@ pub async fn generate_question(
              Return ONLY the question. No explanations, no markdown code fences.\n\nAnswer: {}",
             answer_content
         );
-        call_llm_text(&config, &prompt).await
+        call_llm_text_cached(&app, &config, QUESTION_TEMPLATE, &prompt, regenerate).await
     }
 }
 
@@ -254,22 +785,13 @@ pub async fn convert_image_to_text(
     app: tauri::AppHandle,
     image_path: String,
     role: String,
+    regenerate: Option<bool>,
 ) -> Result<String, String> {
+    if let Some(latex) = super::local_ocr::try_local_ocr(&app, &image_path).await? {
+        return Ok(latex);
+    }
     let config = load_llm_config(&app)?;
-    let image_bytes = tokio::fs::read(&image_path)
-        .await
-        .map_err(|e| format!("Failed to read image: {}", e))?;
-    let base64_image = base64::engine::general_purpose::STANDARD.encode(&image_bytes);
-    let ext = Path::new(&image_path)
-        .extension()
-        .and_then(|e| e.to_str())
-        .unwrap_or("png");
-    let mime_type = match ext {
-        "jpg" | "jpeg" => "image/jpeg",
-        "gif" => "image/gif",
-        "webp" => "image/webp",
-        _ => "image/png",
-    };
+    let (image_bytes, mime_type) = read_image(&image_path).await?;
 
     let prompt = format!(
         "You are converting a flashcard {} image to text. Analyze the image and respond appropriately:\n\n\
@@ -285,11 +807,87 @@ pub async fn convert_image_to_text(
         role
     );
 
-    let response = call_llm_vision(&config, &prompt, &base64_image, mime_type).await?;
+    let response = call_llm_vision_cached(
+        &app,
+        &config,
+        CONVERT_IMAGE_TEMPLATE,
+        &prompt,
+        &image_bytes,
+        mime_type,
+        regenerate.unwrap_or(false),
+    )
+    .await?;
     Ok(response.trim().to_string())
 }
 
-fn load_llm_config(app: &tauri::AppHandle) -> Result<LLMConfig, String> {
+/// Reads an image from disk and returns its bytes with the MIME type sent to
+/// the provider.
+pub async fn read_image(image_path: &str) -> Result<(Vec<u8>, &'static str), String> {
+    let image_bytes = tokio::fs::read(image_path)
+        .await
+        .map_err(|e| format!("Failed to read image: {}", e))?;
+    Ok((image_bytes, image_mime_type(image_path)))
+}
+
+fn image_mime_type(image_path: &str) -> &'static str {
+    let ext = Path::new(image_path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("png");
+    match ext {
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        _ => "image/png",
+    }
+}
+
+async fn call_llm_vision_cached(
+    app: &tauri::AppHandle,
+    config: &LLMConfig,
+    template: &str,
+    prompt: &str,
+    image_bytes: &[u8],
+    mime_type: &str,
+    regenerate: bool,
+) -> Result<String, String> {
+    let key = CacheKey::new(config, template, &[prompt.as_bytes(), image_bytes]);
+    if !regenerate {
+        if let Some(cached) = llm_cache::get(app, config, &key).await {
+            return Ok(cached);
+        }
+    }
+    let base64_image = base64::engine::general_purpose::STANDARD.encode(image_bytes);
+    let response =
+        call_llm_vision(app, config, command_name(template), prompt, &base64_image, mime_type)
+            .await?;
+    llm_cache::put(app, config, &key, &response).await;
+    Ok(response)
+}
+
+async fn call_llm_text_cached(
+    app: &tauri::AppHandle,
+    config: &LLMConfig,
+    template: &str,
+    prompt: &str,
+    regenerate: bool,
+) -> Result<String, String> {
+    let key = CacheKey::new(config, template, &[prompt.as_bytes()]);
+    if !regenerate {
+        if let Some(cached) = llm_cache::get(app, config, &key).await {
+            return Ok(cached);
+        }
+    }
+    let response = call_llm_text(app, config, command_name(template), prompt).await?;
+    llm_cache::put(app, config, &key, &response).await;
+    Ok(response)
+}
+
+pub fn command_name(template: &str) -> &str {
+    template.split('/').next().unwrap_or(template)
+}
+
+pub fn load_llm_config(app: &tauri::AppHandle) -> Result<LLMConfig, String> {
     let app_data = app
         .path()
         .app_data_dir()
@@ -322,12 +920,24 @@ fn save_llm_config(app: &tauri::AppHandle, config: &LLMConfig) -> Result<(), Str
 }
 
 async fn call_llm_vision(
+    app: &tauri::AppHandle,
     config: &LLMConfig,
+    command: &str,
     prompt: &str,
     base64_image: &str,
     mime_type: &str,
 ) -> Result<String, String> {
-    let (url, headers, body) = match config.provider.as_str() {
+    let (url, headers, body) = build_vision_request(config, prompt, base64_image, mime_type);
+    send_llm_request(app, config, command, &url, &headers, &body).await
+}
+
+fn build_vision_request(
+    config: &LLMConfig,
+    prompt: &str,
+    base64_image: &str,
+    mime_type: &str,
+) -> (String, Vec<(String, String)>, Value) {
+    match config.provider.as_str() {
         "anthropic" => {
             let url = if config.base_url.is_empty() {
                 "https://api.anthropic.com/v1/messages".to_string()
@@ -435,13 +1045,154 @@ async fn call_llm_vision(
 
             (url, headers, body)
         }
+    }
+}
+
+async fn call_llm_text(
+    app: &tauri::AppHandle,
+    config: &LLMConfig,
+    command: &str,
+    prompt: &str,
+) -> Result<String, String> {
+    let (url, headers, body) = build_text_request(config, prompt);
+    send_llm_request(app, config, command, &url, &headers, &body).await
+}
+
+/// Asks for a JSON object matching `schema` using the provider's native
+/// structured output: a forced tool call on Anthropic, a `json_schema`
+/// response format on OpenAI-compatible APIs.
+pub async fn call_llm_structured(
+    app: &tauri::AppHandle,
+    config: &LLMConfig,
+    command: &str,
+    prompt: &str,
+    schema_name: &str,
+    schema: &Value,
+) -> Result<Value, String> {
+    let (url, headers, mut body) = build_text_request(config, prompt);
+    request_structured(config, &mut body, schema_name, schema);
+    let json = send_llm_request_raw(app, config, command, &url, &headers, &body).await?;
+    structured_output(&json)
+}
+
+/// `call_llm_structured` with an image attached.
+#[allow(clippy::too_many_arguments)]
+pub async fn call_llm_vision_structured(
+    app: &tauri::AppHandle,
+    config: &LLMConfig,
+    command: &str,
+    prompt: &str,
+    image_bytes: &[u8],
+    mime_type: &str,
+    schema_name: &str,
+    schema: &Value,
+) -> Result<Value, String> {
+    let base64_image = base64::engine::general_purpose::STANDARD.encode(image_bytes);
+    let (url, headers, mut body) = build_vision_request(config, prompt, &base64_image, mime_type);
+    request_structured(config, &mut body, schema_name, schema);
+    let json = send_llm_request_raw(app, config, command, &url, &headers, &body).await?;
+    structured_output(&json)
+}
+
+/// Embeds `texts` with `config.model` through the provider's OpenAI-compatible
+/// `/v1/embeddings` endpoint. Vectors come back in input order.
+pub async fn call_embeddings(
+    app: &tauri::AppHandle,
+    config: &LLMConfig,
+    texts: &[String],
+) -> Result<Vec<Vec<f32>>, String> {
+    let url = match config.provider.as_str() {
+        "anthropic" => {
+            return Err("Anthropic has no embeddings endpoint; use local embeddings or another provider".to_string())
+        }
+        "openrouter" if config.base_url.is_empty() => {
+            "https://openrouter.ai/api/v1/embeddings".to_string()
+        }
+        _ if config.base_url.is_empty() => "https://api.openai.com/v1/embeddings".to_string(),
+        _ => format!("{}/v1/embeddings", config.base_url.trim_end_matches('/')),
     };
+    let mut headers = vec![("content-type".to_string(), "application/json".to_string())];
+    if !config.api_key.is_empty() {
+        headers.push((
+            "authorization".to_string(),
+            format!("Bearer {}", config.api_key),
+        ));
+    }
+    let body = json!({ "model": config.model, "input": texts });
+    let json = send_llm_request_raw(app, config, "embed_cards", &url, &headers, &body).await?;
+    let data = json["data"]
+        .as_array()
+        .ok_or_else(|| format!("Unexpected embeddings response: {}", json))?;
+    let mut vectors = vec![Vec::new(); texts.len()];
+    for (position, item) in data.iter().enumerate() {
+        let index = item["index"].as_u64().map(|i| i as usize).unwrap_or(position);
+        let vector = item["embedding"]
+            .as_array()
+            .ok_or_else(|| format!("Embedding is missing a vector: {}", item))?
+            .iter()
+            .map(|x| x.as_f64().unwrap_or_default() as f32)
+            .collect();
+        if let Some(slot) = vectors.get_mut(index) {
+            *slot = vector;
+        }
+    }
+    if vectors.iter().any(Vec::is_empty) {
+        return Err("Embeddings response is missing vectors".to_string());
+    }
+    Ok(vectors)
+}
+
+fn request_structured(config: &LLMConfig, body: &mut Value, schema_name: &str, schema: &Value) {
+    // Worked solutions and variant sets run long; match the vision calls' limit.
+    body["max_tokens"] = json!(4096);
+    if config.provider == "anthropic" {
+        body["tools"] = json!([{
+            "name": schema_name,
+            "description": "Record the result.",
+            "input_schema": schema
+        }]);
+        body["tool_choice"] = json!({ "type": "tool", "name": schema_name });
+    } else {
+        body["response_format"] = json!({
+            "type": "json_schema",
+            "json_schema": { "name": schema_name, "strict": true, "schema": schema }
+        });
+    }
+}
+
+fn structured_output(json: &Value) -> Result<Value, String> {
+    if json["stop_reason"] == "max_tokens" || json["choices"][0]["finish_reason"] == "length" {
+        return Err(
+            "The model's answer was cut off at the token limit before it was complete".to_string(),
+        );
+    }
+    if let Some(blocks) = json["content"].as_array() {
+        return blocks
+            .iter()
+            .find(|block| block["type"] == "tool_use")
+            .map(|block| block["input"].clone())
+            .ok_or_else(|| format!("Model did not return structured output: {}", json));
+    }
+    let content = json["choices"][0]["message"]["content"]
+        .as_str()
+        .ok_or_else(|| format!("Unexpected response format: {}", json))?;
+    serde_json::from_str(strip_code_fence(content))
+        .map_err(|e| format!("Model returned invalid JSON ({}): {}", e, content))
+}
 
-    send_llm_request(&url, &headers, &body).await
+/// Some OpenAI-compatible servers wrap JSON output in a markdown code fence.
+fn strip_code_fence(content: &str) -> &str {
+    let trimmed = content.trim();
+    trimmed
+        .strip_prefix("```json")
+        .or_else(|| trimmed.strip_prefix("```"))
+        .and_then(|rest| rest.strip_suffix("```"))
+        .map(str::trim)
+        .unwrap_or(trimmed)
 }
 
-async fn call_llm_text(config: &LLMConfig, prompt: &str) -> Result<String, String> {
-    let (url, headers, body) = match config.provider.as_str() {
+fn build_text_request(config: &LLMConfig, prompt: &str) -> (String, Vec<(String, String)>, Value) {
+    match config.provider.as_str() {
         "anthropic" => {
             let url = if config.base_url.is_empty() {
                 "https://api.anthropic.com/v1/messages".to_string()
@@ -523,28 +1274,32 @@ async fn call_llm_text(config: &LLMConfig, prompt: &str) -> Result<String, Strin
 
             (url, headers, body)
         }
-    };
-
-    send_llm_request(&url, &headers, &body).await
+    }
 }
 
 // --- Chat completion with tool support ---
 
-#[tauri::command]
-pub async fn chat_completion(
-    app: tauri::AppHandle,
-    messages: Value,
-    tools: Option<Value>,
+/// Sends one turn of an OpenAI-format conversation and returns the provider's
+/// raw response. The tool loop lives in `chat.rs`. With `allow_tool_calls`
+/// false the tools are still declared, since Anthropic rejects a history with
+/// tool blocks otherwise, but the model must answer in text.
+pub async fn send_chat_request(
+    app: &tauri::AppHandle,
+    config: &LLMConfig,
+    messages: &Value,
+    tools: Option<&Value>,
+    allow_tool_calls: bool,
 ) -> Result<Value, String> {
-    let config = load_llm_config(&app)?;
-    let (url, headers, body) = build_chat_request(&config, &messages, tools.as_ref());
-    send_llm_request_raw(&url, &headers, &body).await
+    let messages = inline_local_images(app, messages).await?;
+    let (url, headers, body) = build_chat_request(config, &messages, tools, allow_tool_calls);
+    send_llm_request_raw(app, config, "chat_completion", &url, &headers, &body).await
 }
 
 fn build_chat_request(
     config: &LLMConfig,
     messages: &Value,
     tools: Option<&Value>,
+    allow_tool_calls: bool,
 ) -> (String, Vec<(String, String)>, Value) {
     match config.provider.as_str() {
         "anthropic" => {
@@ -554,57 +1309,7 @@ fn build_chat_request(
                 format!("{}/v1/messages", config.base_url.trim_end_matches('/'))
             };
 
-            // Convert OpenAI-format messages to Anthropic format
-            let mut system_prompt = String::new();
-            let mut anthropic_messages: Vec<Value> = Vec::new();
-            if let Some(msgs) = messages.as_array() {
-                for msg in msgs {
-                    let role = msg["role"].as_str().unwrap_or("user");
-                    if role == "system" {
-                        system_prompt = msg["content"].as_str().unwrap_or("").to_string();
-                    } else if role == "tool" {
-                        anthropic_messages.push(json!({
-                            "role": "user",
-                            "content": [{
-                                "type": "tool_result",
-                                "tool_use_id": msg["tool_call_id"].as_str().unwrap_or(""),
-                                "content": msg["content"].as_str().unwrap_or("")
-                            }]
-                        }));
-                    } else if role == "assistant" {
-                        if let Some(tool_calls) = msg.get("tool_calls").and_then(|v| v.as_array()) {
-                            let mut content_blocks: Vec<Value> = Vec::new();
-                            if let Some(text) = msg["content"].as_str() {
-                                if !text.is_empty() {
-                                    content_blocks.push(json!({"type": "text", "text": text}));
-                                }
-                            }
-                            for tc in tool_calls {
-                                content_blocks.push(json!({
-                                    "type": "tool_use",
-                                    "id": tc["id"].as_str().unwrap_or(""),
-                                    "name": tc["function"]["name"].as_str().unwrap_or(""),
-                                    "input": serde_json::from_str::<Value>(
-                                        tc["function"]["arguments"].as_str().unwrap_or("{}")
-                                    ).unwrap_or(json!({}))
-                                }));
-                            }
-                            anthropic_messages.push(json!({"role": "assistant", "content": content_blocks}));
-                        } else {
-                            anthropic_messages.push(json!({
-                                "role": "assistant",
-                                "content": msg["content"].as_str().unwrap_or("")
-                            }));
-                        }
-                    } else {
-                        anthropic_messages.push(json!({
-                            "role": role,
-                            "content": msg["content"].as_str().unwrap_or("")
-                        }));
-                    }
-                }
-            }
-
+            let (system_prompt, anthropic_messages) = to_anthropic_messages(messages);
             let mut body = json!({
                 "model": config.model,
                 "max_tokens": 4096,
@@ -623,6 +1328,9 @@ fn build_chat_request(
                         })
                     }).collect();
                     body["tools"] = json!(anthropic_tools);
+                    if !allow_tool_calls {
+                        body["tool_choice"] = json!({ "type": "none" });
+                    }
                 }
             }
 
@@ -646,9 +1354,16 @@ fn build_chat_request(
                 },
             };
 
-            let mut body = json!({"model": config.model, "messages": messages, "max_tokens": 4096});
+            let mut body = json!({
+                "model": config.model,
+                "messages": to_openai_messages(messages),
+                "max_tokens": 4096
+            });
             if let Some(tools_val) = tools {
                 body["tools"] = tools_val.clone();
+                if !allow_tool_calls {
+                    body["tool_choice"] = json!("none");
+                }
             }
 
             let mut headers = vec![("content-type".to_string(), "application/json".to_string())];
@@ -664,7 +1379,265 @@ fn build_chat_request(
     }
 }
 
+/// Converts an OpenAI-format conversation to Anthropic's `system` prompt and
+/// messages. Content may be a string or an array of text and image parts;
+/// every system message is kept, and consecutive tool results are grouped into
+/// the single user turn Anthropic expects after a tool call.
+fn to_anthropic_messages(messages: &Value) -> (String, Vec<Value>) {
+    let mut system: Vec<String> = Vec::new();
+    let mut converted: Vec<Value> = Vec::new();
+
+    for msg in messages.as_array().into_iter().flatten() {
+        match msg["role"].as_str().unwrap_or("user") {
+            "system" => {
+                let text = content_text(&msg["content"]);
+                if !text.is_empty() {
+                    system.push(text);
+                }
+            }
+            "tool" => {
+                let content = match &msg["content"] {
+                    Value::Array(_) => json!(anthropic_blocks(&msg["content"])),
+                    other => json!(other.as_str().unwrap_or("")),
+                };
+                let result = json!({
+                    "type": "tool_result",
+                    "tool_use_id": msg["tool_call_id"].as_str().unwrap_or(""),
+                    "content": content
+                });
+                let grouped = converted
+                    .last_mut()
+                    .filter(|last| last["role"] == "user")
+                    .and_then(|last| last["content"].as_array_mut())
+                    .filter(|blocks| blocks.iter().all(|b| b["type"] == "tool_result"));
+                match grouped {
+                    Some(blocks) => blocks.push(result),
+                    None => converted.push(json!({ "role": "user", "content": [result] })),
+                }
+            }
+            role => {
+                let mut blocks = anthropic_blocks(&msg["content"]);
+                if role == "assistant" {
+                    for tc in msg["tool_calls"].as_array().into_iter().flatten() {
+                        blocks.push(json!({
+                            "type": "tool_use",
+                            "id": tc["id"].as_str().unwrap_or(""),
+                            "name": tc["function"]["name"].as_str().unwrap_or(""),
+                            "input": serde_json::from_str::<Value>(
+                                tc["function"]["arguments"].as_str().unwrap_or("{}")
+                            ).unwrap_or(json!({}))
+                        }));
+                    }
+                }
+                // Anthropic rejects empty turns.
+                if !blocks.is_empty() {
+                    let role = if role == "assistant" { "assistant" } else { "user" };
+                    converted.push(json!({ "role": role, "content": blocks }));
+                }
+            }
+        }
+    }
+
+    (system.join("\n\n"), converted)
+}
+
+fn anthropic_blocks(content: &Value) -> Vec<Value> {
+    match content {
+        Value::String(text) if !text.is_empty() => vec![json!({ "type": "text", "text": text })],
+        Value::Array(parts) => parts
+            .iter()
+            .filter_map(|part| match part["type"].as_str() {
+                Some("text") => Some(json!({ "type": "text", "text": part["text"] })),
+                Some("image_url") => {
+                    let url = part["image_url"]["url"]
+                        .as_str()
+                        .or_else(|| part["image_url"].as_str())?;
+                    anthropic_image(url)
+                }
+                Some("image") => Some(part.clone()),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Local image paths are inlined by `inline_local_images` before this runs,
+/// so only remote and data URLs are expected here.
+fn anthropic_image(url: &str) -> Option<Value> {
+    if url.starts_with("http://") || url.starts_with("https://") {
+        return Some(json!({ "type": "image", "source": { "type": "url", "url": url } }));
+    }
+    let (media_type, data) = parse_data_url(url)?;
+    Some(json!({
+        "type": "image",
+        "source": { "type": "base64", "media_type": media_type, "data": data }
+    }))
+}
+
+/// Normalizes an OpenAI-format conversation for OpenAI-compatible APIs:
+/// Anthropic-style image parts become `image_url` data URLs,
+/// and images returned by tools (which tool messages cannot carry) follow the
+/// tool results as a user message.
+fn to_openai_messages(messages: &Value) -> Value {
+    let mut converted: Vec<Value> = Vec::new();
+    let mut tool_images: Vec<Value> = Vec::new();
+
+    for msg in messages.as_array().into_iter().flatten() {
+        let is_tool = msg["role"] == "tool";
+        if !is_tool && !tool_images.is_empty() {
+            converted.push(tool_images_message(std::mem::take(&mut tool_images)));
+        }
+
+        let mut msg = msg.clone();
+        if let Some(parts) = msg["content"].as_array() {
+            let parts: Vec<Value> = parts.iter().filter_map(openai_part).collect();
+            if is_tool {
+                let (images, texts): (Vec<Value>, Vec<Value>) =
+                    parts.into_iter().partition(|p| p["type"] == "image_url");
+                tool_images.extend(images);
+                msg["content"] = json!(content_text(&Value::Array(texts)));
+            } else {
+                msg["content"] = Value::Array(parts);
+            }
+        }
+        converted.push(msg);
+    }
+    if !tool_images.is_empty() {
+        converted.push(tool_images_message(tool_images));
+    }
+
+    Value::Array(converted)
+}
+
+fn openai_part(part: &Value) -> Option<Value> {
+    match part["type"].as_str() {
+        Some("image_url") => {
+            let url = part["image_url"]["url"]
+                .as_str()
+                .or_else(|| part["image_url"].as_str())?;
+            if !url.starts_with("data:") && !url.starts_with("http") {
+                return None;
+            }
+            Some(json!({ "type": "image_url", "image_url": { "url": url } }))
+        }
+        Some("image") => {
+            let source = &part["source"];
+            let url = match source["type"].as_str() {
+                Some("base64") => format!(
+                    "data:{};base64,{}",
+                    source["media_type"].as_str().unwrap_or("image/png"),
+                    source["data"].as_str().unwrap_or("")
+                ),
+                _ => source["url"].as_str()?.to_string(),
+            };
+            Some(json!({ "type": "image_url", "image_url": { "url": url } }))
+        }
+        Some("text") => Some(json!({ "type": "text", "text": part["text"] })),
+        _ => None,
+    }
+}
+
+fn tool_images_message(images: Vec<Value>) -> Value {
+    let mut content = vec![json!({ "type": "text", "text": "Images returned by the tool calls above:" })];
+    content.extend(images);
+    json!({ "role": "user", "content": content })
+}
+
+/// Plain text of a string or array-of-parts message content.
+fn content_text(content: &Value) -> String {
+    match content {
+        Value::String(text) => text.clone(),
+        Value::Array(parts) => parts
+            .iter()
+            .filter_map(|part| part["text"].as_str())
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
+
+fn parse_data_url(url: &str) -> Option<(&str, &str)> {
+    let rest = url.strip_prefix("data:")?;
+    let (media_type, data) = rest.split_once(";base64,")?;
+    Some((media_type, data))
+}
+
+/// Lets chat messages reference card images by their path on disk: every
+/// `image_url` part that is not a remote or data URL is read and inlined.
+async fn inline_local_images(app: &tauri::AppHandle, messages: &Value) -> Result<Value, String> {
+    let app_data = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+    let mut messages = messages.clone();
+    for msg in messages.as_array_mut().into_iter().flatten() {
+        for part in msg["content"].as_array_mut().into_iter().flatten() {
+            if part["type"] != "image_url" {
+                continue;
+            }
+            let url = &mut part["image_url"];
+            let url = if url.is_string() { url } else { &mut url["url"] };
+            let Some(path) = url.as_str() else {
+                continue;
+            };
+            if path.starts_with("data:") || path.starts_with("http") {
+                continue;
+            }
+            *url = json!(local_image_data_url(&app_data, path).await?);
+        }
+    }
+    Ok(messages)
+}
+
+/// Reads an image saved under the app data directory (captures and imported
+/// images) as a data URL. Anything else is refused, so a conversation cannot
+/// send arbitrary local files to the provider.
+async fn local_image_data_url(app_data: &Path, path: &str) -> Result<String, String> {
+    let ext = Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase());
+    if !matches!(ext.as_deref(), Some("png" | "jpg" | "jpeg" | "gif" | "webp")) {
+        return Err(format!("Not a supported image file: {}", path));
+    }
+    let resolved = tokio::fs::canonicalize(path)
+        .await
+        .map_err(|e| format!("Image not found: {} ({})", path, e))?;
+    let app_data = tokio::fs::canonicalize(app_data)
+        .await
+        .map_err(|e| format!("Failed to resolve app data dir: {}", e))?;
+    if !resolved.starts_with(&app_data) {
+        return Err(format!("Image is not in the app data folder: {}", path));
+    }
+    let bytes = tokio::fs::read(&resolved)
+        .await
+        .map_err(|e| format!("Failed to read image: {}", e))?;
+    Ok(format!(
+        "data:{};base64,{}",
+        image_mime_type(path),
+        base64::engine::general_purpose::STANDARD.encode(bytes)
+    ))
+}
+
+/// Sends a request to the provider and logs its token usage, latency and
+/// estimated cost. Refuses to send once the monthly budget is spent.
 async fn send_llm_request_raw(
+    app: &tauri::AppHandle,
+    config: &LLMConfig,
+    command: &str,
+    url: &str,
+    headers: &[(String, String)],
+    body: &Value,
+) -> Result<Value, String> {
+    llm_usage::check_budget(app, config).await?;
+    let started = Instant::now();
+    let result = post_llm_request(url, headers, body).await;
+    llm_usage::record(app, config, command, started.elapsed(), &result).await;
+    result
+}
+
+async fn post_llm_request(
     url: &str,
     headers: &[(String, String)],
     body: &Value,
@@ -684,11 +1657,14 @@ async fn send_llm_request_raw(
 }
 
 async fn send_llm_request(
+    app: &tauri::AppHandle,
+    config: &LLMConfig,
+    command: &str,
     url: &str,
     headers: &[(String, String)],
     body: &Value,
 ) -> Result<String, String> {
-    let json = send_llm_request_raw(url, headers, body).await?;
+    let json = send_llm_request_raw(app, config, command, url, headers, body).await?;
     if let Some(content) = json["choices"][0]["message"]["content"].as_str() {
         Ok(content.to_string())
     } else if let Some(content) = json["content"][0]["text"].as_str() {
@@ -697,3 +1673,227 @@ async fn send_llm_request(
         Err(format!("Unexpected response format: {}", json))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn final_chat_turn_declares_tools_but_forbids_calls() {
+        let config = LLMConfig {
+            provider: "anthropic".to_string(),
+            api_key: String::new(),
+            model: "claude".to_string(),
+            base_url: String::new(),
+            cache_ttl_days: None,
+            cache_max_mb: None,
+            price_table: None,
+            monthly_budget_usd: None,
+        };
+        let tools = json!([{
+            "type": "function",
+            "function": { "name": "get_all_folders", "description": "", "parameters": {} }
+        }]);
+        let messages = json!([{ "role": "user", "content": "Hi" }]);
+        let (_, _, body) = build_chat_request(&config, &messages, Some(&tools), false);
+        assert_eq!(body["tools"][0]["name"], "get_all_folders");
+        assert_eq!(body["tool_choice"], json!({ "type": "none" }));
+
+        let openai = LLMConfig {
+            provider: "openai".to_string(),
+            ..config
+        };
+        let (_, _, body) = build_chat_request(&openai, &messages, Some(&tools), false);
+        assert_eq!(body["tool_choice"], "none");
+        let (_, _, body) = build_chat_request(&openai, &messages, Some(&tools), true);
+        assert!(body.get("tool_choice").is_none());
+    }
+
+    #[test]
+    fn drops_incomplete_variants() {
+        let value = json!({
+            "variants": [
+                { "question": "Find $\\int x^3 \\, dx$", "answer": "$\\frac{x^4}{4} + C$" },
+                { "question": "Find $\\int \\cos x \\, dx$", "answer": " " },
+                { "question": "Find $\\int e^{2x} \\, dx$", "answer": "$\\frac{e^{2x}}{2} + C$" },
+                { "question": "Extra", "answer": "1" }
+            ]
+        });
+        let variants = parse_variants(&value, 2).unwrap();
+        assert_eq!(variants.len(), 2);
+        assert_eq!(variants[1].answer, "$\\frac{e^{2x}}{2} + C$");
+        assert!(parse_variants(&json!({ "variants": [] }), 3).is_err());
+    }
+
+    #[test]
+    fn keeps_up_to_three_hints() {
+        let value = json!({ "hints": ["Use the chain rule", " ", "Let u = x^2", "Differentiate", "Extra"] });
+        assert_eq!(
+            parse_hints(&value).unwrap(),
+            vec!["Use the chain rule", "Let u = x^2", "Differentiate"]
+        );
+        assert!(parse_hints(&json!({ "hints": [] })).is_err());
+    }
+
+    #[test]
+    fn parses_solution_steps() {
+        let value = json!({
+            "steps": [
+                { "latex": "$2x + 3 = 7$", "justification": " Start from the equation " },
+                { "latex": "", "justification": "" },
+                { "latex": "x = 2", "justification": "Subtract 3, divide by 2" }
+            ]
+        });
+        let steps = parse_solution_steps(&value).unwrap();
+        assert_eq!(steps.len(), 2);
+        assert_eq!(steps[0].latex, "2x + 3 = 7");
+        assert_eq!(steps[0].justification, "Start from the equation");
+        assert!(parse_solution_steps(&json!({ "steps": [] })).is_err());
+    }
+
+    #[test]
+    fn parses_structured_assessment() {
+        let value = json!({
+            "estimated_seconds": 95.4,
+            "difficulty": 3,
+            "topics": ["integration by parts", " "],
+            "prerequisites": ["product rule"]
+        });
+        let assessment = parse_difficulty_assessment(&value).unwrap();
+        assert_eq!(assessment.estimated_seconds, 95);
+        assert_eq!(assessment.difficulty, 3);
+        assert_eq!(assessment.topics, vec!["integration by parts"]);
+        assert_eq!(assessment.prerequisites, vec!["product rule"]);
+    }
+
+    #[test]
+    fn clamps_out_of_range_values() {
+        let value = json!({
+            "estimated_seconds": "9000",
+            "difficulty": 8,
+            "topics": [],
+            "prerequisites": []
+        });
+        let assessment = parse_difficulty_assessment(&value).unwrap();
+        assert_eq!(assessment.estimated_seconds, 3600);
+        assert_eq!(assessment.difficulty, 5);
+    }
+
+    #[test]
+    fn reports_truncated_structured_output() {
+        let anthropic = json!({
+            "stop_reason": "max_tokens",
+            "content": [{ "type": "tool_use", "input": { "steps": [] } }]
+        });
+        let openai = json!({
+            "choices": [{ "finish_reason": "length", "message": { "content": "{\"steps\": [" } }]
+        });
+        for response in [anthropic, openai] {
+            assert!(structured_output(&response).unwrap_err().contains("cut off"));
+        }
+    }
+
+    #[test]
+    fn surfaces_unparseable_assessment() {
+        let value = json!({ "estimated_seconds": "about 120 seconds", "difficulty": 2 });
+        let err = parse_difficulty_assessment(&value).unwrap_err();
+        assert!(err.contains("estimated_seconds"));
+    }
+
+    #[test]
+    fn keeps_every_system_message_for_anthropic() {
+        let messages = json!([
+            { "role": "system", "content": "You are a tutor." },
+            { "role": "system", "content": [{ "type": "text", "text": "Be brief." }] },
+            { "role": "user", "content": "Hi" }
+        ]);
+        let (system, converted) = to_anthropic_messages(&messages);
+        assert_eq!(system, "You are a tutor.\n\nBe brief.");
+        assert_eq!(converted.len(), 1);
+    }
+
+    #[test]
+    fn converts_data_url_images_for_anthropic() {
+        let messages = json!([{
+            "role": "user",
+            "content": [
+                { "type": "text", "text": "What is this?" },
+                { "type": "image_url", "image_url": { "url": "data:image/png;base64,AAAA" } }
+            ]
+        }]);
+        let (_, converted) = to_anthropic_messages(&messages);
+        let image = &converted[0]["content"][1];
+        assert_eq!(image["type"], "image");
+        assert_eq!(image["source"]["media_type"], "image/png");
+        assert_eq!(image["source"]["data"], "AAAA");
+    }
+
+    #[test]
+    fn groups_tool_results_into_one_anthropic_turn() {
+        let messages = json!([
+            { "role": "assistant", "content": null, "tool_calls": [
+                { "id": "a", "type": "function", "function": { "name": "x", "arguments": "{}" } },
+                { "id": "b", "type": "function", "function": { "name": "y", "arguments": "{}" } }
+            ]},
+            { "role": "tool", "tool_call_id": "a", "content": "1" },
+            { "role": "tool", "tool_call_id": "b", "content": [{ "type": "text", "text": "2" }] }
+        ]);
+        let (_, converted) = to_anthropic_messages(&messages);
+        assert_eq!(converted.len(), 2);
+        let results = converted[1]["content"].as_array().unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[1]["content"][0]["text"], "2");
+    }
+
+    #[test]
+    fn converts_anthropic_images_and_tool_images_for_openai() {
+        let messages = json!([
+            { "role": "user", "content": [
+                { "type": "image", "source": { "type": "base64", "media_type": "image/jpeg", "data": "BBBB" } }
+            ]},
+            { "role": "tool", "tool_call_id": "a", "content": [
+                { "type": "text", "text": "card image" },
+                { "type": "image_url", "image_url": { "url": "data:image/png;base64,CCCC" } }
+            ]},
+            { "role": "assistant", "content": "Done" }
+        ]);
+        let converted = to_openai_messages(&messages);
+        assert_eq!(converted[0]["content"][0]["image_url"]["url"], "data:image/jpeg;base64,BBBB");
+        assert_eq!(converted[1]["content"], "card image");
+        assert_eq!(converted[2]["role"], "user");
+        assert_eq!(converted[2]["content"][1]["image_url"]["url"], "data:image/png;base64,CCCC");
+        assert_eq!(converted[3]["role"], "assistant");
+    }
+
+    #[tokio::test]
+    async fn inlines_only_images_under_app_data() {
+        let root = std::env::temp_dir().join(format!("flashmath-{}", uuid::Uuid::new_v4()));
+        let app_data = root.join("app");
+        std::fs::create_dir_all(app_data.join("captures")).unwrap();
+        let inside = app_data.join("captures").join("card.png");
+        std::fs::write(&inside, [1, 2, 3]).unwrap();
+        std::fs::write(app_data.join("notes.txt"), "secret").unwrap();
+        std::fs::write(root.join("outside.png"), [1]).unwrap();
+
+        let url = local_image_data_url(&app_data, inside.to_str().unwrap()).await;
+        assert_eq!(url.unwrap(), "data:image/png;base64,AQID");
+
+        for rejected in [
+            app_data.join("notes.txt"),
+            app_data.join("..").join("outside.png"),
+            app_data.join("captures").join("gone.png"),
+        ] {
+            let path = rejected.to_str().unwrap();
+            let result = local_image_data_url(&app_data, path).await;
+            assert!(result.is_err(), "{}", path);
+        }
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn strips_markdown_code_fence() {
+        assert_eq!(strip_code_fence("```json\n{\"a\": 1}\n```"), "{\"a\": 1}");
+        assert_eq!(strip_code_fence(" {} "), "{}");
+    }
+}
@@ -0,0 +1,208 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::Manager;
+
+use super::llm::LLMConfig;
+
+const DEFAULT_TTL_DAYS: u64 = 30;
+const DEFAULT_MAX_MB: u64 = 50;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    created_at: u64,
+    response: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LLMCacheStats {
+    pub entries: u64,
+    pub total_bytes: u64,
+}
+
+/// Identifies a cached response: provider, model, prompt template version and
+/// a SHA-256 of every input (prompt text, image bytes) fed to the model.
+pub struct CacheKey(String);
+
+impl CacheKey {
+    pub fn new(config: &LLMConfig, template: &str, inputs: &[&[u8]]) -> Self {
+        let mut hasher = Sha256::new();
+        for part in [
+            config.provider.as_bytes(),
+            config.base_url.as_bytes(),
+            config.model.as_bytes(),
+            template.as_bytes(),
+        ] {
+            hasher.update((part.len() as u64).to_le_bytes());
+            hasher.update(part);
+        }
+        for input in inputs {
+            let digest = Sha256::digest(input);
+            hasher.update(digest);
+        }
+        CacheKey(format!("{:x}", hasher.finalize()))
+    }
+}
+
+/// Returns the cached response for `key`, dropping it if it has expired.
+pub async fn get(app: &tauri::AppHandle, config: &LLMConfig, key: &CacheKey) -> Option<String> {
+    let path = get_cache_dir(app)
+        .await
+        .ok()?
+        .join(format!("{}.json", key.0));
+    let contents = tokio::fs::read_to_string(&path).await.ok()?;
+    let entry: CacheEntry = match serde_json::from_str(&contents) {
+        Ok(entry) => entry,
+        Err(_) => {
+            let _ = tokio::fs::remove_file(&path).await;
+            return None;
+        }
+    };
+    if now_secs().saturating_sub(entry.created_at) > ttl_secs(config) {
+        let _ = tokio::fs::remove_file(&path).await;
+        return None;
+    }
+    Some(entry.response)
+}
+
+/// Stores `response` under `key` and trims the cache back under its size limit.
+/// Failures are logged rather than returned: a cache miss is never fatal.
+pub async fn put(app: &tauri::AppHandle, config: &LLMConfig, key: &CacheKey, response: &str) {
+    let dir = match get_cache_dir(app).await {
+        Ok(dir) => dir,
+        Err(e) => {
+            log::warn!("LLM cache unavailable: {}", e);
+            return;
+        }
+    };
+    let entry = CacheEntry {
+        created_at: now_secs(),
+        response: response.to_string(),
+    };
+    let contents = match serde_json::to_string(&entry) {
+        Ok(contents) => contents,
+        Err(e) => {
+            log::warn!("Failed to serialize LLM cache entry: {}", e);
+            return;
+        }
+    };
+    if let Err(e) = tokio::fs::write(dir.join(format!("{}.json", key.0)), contents).await {
+        log::warn!("Failed to write LLM cache entry: {}", e);
+        return;
+    }
+    if let Err(e) = enforce_limits(&dir, config).await {
+        log::warn!("Failed to trim LLM cache: {}", e);
+    }
+}
+
+#[tauri::command]
+pub async fn clear_llm_cache(app: tauri::AppHandle) -> Result<LLMCacheStats, String> {
+    let dir = get_cache_dir(&app).await?;
+    let mut removed = LLMCacheStats {
+        entries: 0,
+        total_bytes: 0,
+    };
+    for (path, size, _) in list_entries(&dir).await? {
+        tokio::fs::remove_file(&path)
+            .await
+            .map_err(|e| format!("Failed to remove cache entry: {}", e))?;
+        removed.entries += 1;
+        removed.total_bytes += size;
+    }
+    Ok(removed)
+}
+
+#[tauri::command]
+pub async fn get_llm_cache_stats(app: tauri::AppHandle) -> Result<LLMCacheStats, String> {
+    let entries = list_entries(&get_cache_dir(&app).await?).await?;
+    Ok(LLMCacheStats {
+        entries: entries.len() as u64,
+        total_bytes: entries.iter().map(|(_, size, _)| size).sum(),
+    })
+}
+
+fn ttl_secs(config: &LLMConfig) -> u64 {
+    config
+        .cache_ttl_days
+        .unwrap_or(DEFAULT_TTL_DAYS)
+        .saturating_mul(24 * 60 * 60)
+}
+
+fn max_bytes(config: &LLMConfig) -> u64 {
+    config
+        .cache_max_mb
+        .unwrap_or(DEFAULT_MAX_MB)
+        .saturating_mul(1024 * 1024)
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Lists cache files as (path, size in bytes, modified time), oldest first.
+async fn list_entries(dir: &Path) -> Result<Vec<(PathBuf, u64, SystemTime)>, String> {
+    let mut read_dir = tokio::fs::read_dir(dir)
+        .await
+        .map_err(|e| format!("Failed to read cache directory: {}", e))?;
+    let mut entries = Vec::new();
+    while let Ok(Some(entry)) = read_dir.next_entry().await {
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("json") {
+            continue;
+        }
+        let Ok(meta) = entry.metadata().await else {
+            continue;
+        };
+        let modified = meta.modified().unwrap_or(UNIX_EPOCH);
+        entries.push((path, meta.len(), modified));
+    }
+    entries.sort_by_key(|(_, _, modified)| *modified);
+    Ok(entries)
+}
+
+async fn enforce_limits(dir: &Path, config: &LLMConfig) -> Result<(), String> {
+    let ttl = std::time::Duration::from_secs(ttl_secs(config));
+    let now = SystemTime::now();
+    let mut total: u64 = 0;
+    let mut live = Vec::new();
+    for (path, size, modified) in list_entries(dir).await? {
+        let expired = now
+            .duration_since(modified)
+            .map(|age| age > ttl)
+            .unwrap_or(false);
+        if expired {
+            let _ = tokio::fs::remove_file(&path).await;
+        } else {
+            total += size;
+            live.push((path, size));
+        }
+    }
+
+    // Evict oldest entries first until the cache fits.
+    let limit = max_bytes(config);
+    for (path, size) in live {
+        if total <= limit {
+            break;
+        }
+        if tokio::fs::remove_file(&path).await.is_ok() {
+            total -= size;
+        }
+    }
+    Ok(())
+}
+
+async fn get_cache_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let app_data = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+    let cache_dir = app_data.join("llm_cache");
+    tokio::fs::create_dir_all(&cache_dir)
+        .await
+        .map_err(|e| format!("Failed to create cache directory: {}", e))?;
+    Ok(cache_dir)
+}
@@ -0,0 +1,316 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::FromRow;
+use std::time::Duration;
+use uuid::Uuid;
+
+use super::database::{get_pool, now_iso};
+use super::llm::LLMConfig;
+
+/// USD per million tokens.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelPrice {
+    pub input_per_million: f64,
+    pub output_per_million: f64,
+}
+
+/// Built-in list prices, matched by model-name prefix (longest prefix wins).
+/// `price_table` in the LLM config overrides or extends these.
+const DEFAULT_PRICES: &[(&str, f64, f64)] = &[
+    ("gpt-4o-mini", 0.15, 0.60),
+    ("gpt-4o", 2.50, 10.00),
+    ("gpt-4.1-mini", 0.40, 1.60),
+    ("gpt-4.1-nano", 0.10, 0.40),
+    ("gpt-4.1", 2.00, 8.00),
+    ("o4-mini", 1.10, 4.40),
+    ("claude-3-5-haiku", 0.80, 4.00),
+    ("claude-3-5-sonnet", 3.00, 15.00),
+    ("claude-3-7-sonnet", 3.00, 15.00),
+    ("claude-sonnet-4", 3.00, 15.00),
+    ("claude-opus-4", 15.00, 75.00),
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct LLMUsageBucket {
+    pub key: String,
+    pub calls: i64,
+    pub failed_calls: i64,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    pub cost_usd: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LLMUsageSummary {
+    pub month_to_date_usd: f64,
+    pub monthly_budget_usd: Option<f64>,
+    pub by_day: Vec<LLMUsageBucket>,
+    pub by_command: Vec<LLMUsageBucket>,
+}
+
+/// Estimated cost of a call in USD, or `None` if the model has no known price.
+/// Local Ollama models are free.
+pub fn estimate_cost(config: &LLMConfig, input_tokens: i64, output_tokens: i64) -> Option<f64> {
+    if config.provider == "ollama" {
+        return Some(0.0);
+    }
+    // OpenRouter models are namespaced, e.g. "openai/gpt-4o".
+    let model = config.model.rsplit('/').next().unwrap_or(&config.model);
+
+    let price = config
+        .price_table
+        .as_ref()
+        .and_then(|table| {
+            table.get(&config.model).cloned().or_else(|| {
+                table
+                    .iter()
+                    .filter(|(prefix, _)| model.starts_with(prefix.as_str()))
+                    .max_by_key(|(prefix, _)| prefix.len())
+                    .map(|(_, price)| price.clone())
+            })
+        })
+        .or_else(|| {
+            DEFAULT_PRICES
+                .iter()
+                .filter(|(prefix, _, _)| model.starts_with(prefix))
+                .max_by_key(|(prefix, _, _)| prefix.len())
+                .map(|(_, input, output)| ModelPrice {
+                    input_per_million: *input,
+                    output_per_million: *output,
+                })
+        })?;
+
+    Some(
+        (input_tokens as f64 * price.input_per_million
+            + output_tokens as f64 * price.output_per_million)
+            / 1_000_000.0,
+    )
+}
+
+/// Reads (input, output) token counts from an Anthropic or OpenAI-style response.
+fn extract_usage(response: &Value) -> (i64, i64) {
+    let usage = &response["usage"];
+    let input = usage["input_tokens"]
+        .as_i64()
+        .or_else(|| usage["prompt_tokens"].as_i64())
+        .unwrap_or(0);
+    let output = usage["output_tokens"]
+        .as_i64()
+        .or_else(|| usage["completion_tokens"].as_i64())
+        .unwrap_or(0);
+    (input, output)
+}
+
+/// Errors if a monthly budget is set and this month's spend has reached it.
+/// Under a budget, models without a known price and unreadable spend are
+/// refused too, since neither can be counted against the cap.
+pub async fn check_budget(app: &tauri::AppHandle, config: &LLMConfig) -> Result<(), String> {
+    let Some(budget) = config.monthly_budget_usd else {
+        return Ok(());
+    };
+    if estimate_cost(config, 0, 0).is_none() {
+        return Err(format!(
+            "No price is known for {}. Set one in Settings to use it under a monthly budget.",
+            config.model
+        ));
+    }
+    let spent = month_to_date(app)
+        .await
+        .map_err(|e| format!("Could not check the monthly LLM budget: {}", e))?;
+    if spent >= budget {
+        return Err(format!(
+            "Monthly LLM budget of ${:.2} reached (${:.2} spent). Raise it in Settings to continue.",
+            budget, spent
+        ));
+    }
+    Ok(())
+}
+
+/// Logs one provider call to `llm_usage`. Logging failures never fail the call.
+pub async fn record(
+    app: &tauri::AppHandle,
+    config: &LLMConfig,
+    command: &str,
+    latency: Duration,
+    result: &Result<Value, String>,
+) {
+    let pool = match get_pool(app).await {
+        Ok(pool) => pool,
+        Err(e) => {
+            log::warn!("Skipping LLM usage log: {}", e);
+            return;
+        }
+    };
+    let (input_tokens, output_tokens) = match result {
+        Ok(response) => extract_usage(response),
+        Err(_) => (0, 0),
+    };
+    let error = result
+        .as_ref()
+        .err()
+        .map(|e| e.chars().take(500).collect::<String>());
+
+    let inserted = sqlx::query(
+        r#"INSERT INTO llm_usage
+            (id, provider, model, command, input_tokens, output_tokens, latency_ms,
+             success, error, cost_usd, created_at)
+           VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"#,
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(&config.provider)
+    .bind(&config.model)
+    .bind(command)
+    .bind(input_tokens)
+    .bind(output_tokens)
+    .bind(latency.as_millis() as i64)
+    .bind(result.is_ok())
+    .bind(error)
+    .bind(estimate_cost(config, input_tokens, output_tokens))
+    .bind(now_iso())
+    .execute(&pool)
+    .await;
+    if let Err(e) = inserted {
+        log::warn!("Failed to log LLM usage: {}", e);
+    }
+}
+
+async fn month_to_date(app: &tauri::AppHandle) -> Result<f64, String> {
+    let pool = get_pool(app).await?;
+    let month = chrono::Utc::now().format("%Y-%m").to_string();
+    let (spent,): (f64,) = sqlx::query_as(
+        "SELECT COALESCE(SUM(cost_usd), 0.0) FROM llm_usage WHERE substr(created_at, 1, 7) = ?",
+    )
+    .bind(month)
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| format!("Failed to load LLM usage: {}", e))?;
+    Ok(spent)
+}
+
+/// Spend over the last `days` days (default 30), grouped by day and by command.
+#[tauri::command]
+pub async fn get_llm_usage_summary(
+    app: tauri::AppHandle,
+    days: Option<i64>,
+) -> Result<LLMUsageSummary, String> {
+    let pool = get_pool(&app).await?;
+    let since = (chrono::Utc::now() - chrono::Duration::days(days.unwrap_or(30).max(1)))
+        .format("%Y-%m-%d")
+        .to_string();
+
+    let bucket_query = |group: &str| {
+        format!(
+            r#"SELECT {} AS key, COUNT(*) AS calls,
+                      COALESCE(SUM(CASE WHEN success = 0 THEN 1 ELSE 0 END), 0) AS failed_calls,
+                      COALESCE(SUM(input_tokens), 0) AS input_tokens,
+                      COALESCE(SUM(output_tokens), 0) AS output_tokens,
+                      COALESCE(SUM(cost_usd), 0.0) AS cost_usd
+               FROM llm_usage WHERE created_at >= ?
+               GROUP BY key ORDER BY {}"#,
+            group,
+            if group == "command" {
+                "cost_usd DESC"
+            } else {
+                "key DESC"
+            }
+        )
+    };
+
+    let by_day = sqlx::query_as::<_, LLMUsageBucket>(&bucket_query("substr(created_at, 1, 10)"))
+        .bind(&since)
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| format!("Failed to load LLM usage: {}", e))?;
+    let by_command = sqlx::query_as::<_, LLMUsageBucket>(&bucket_query("command"))
+        .bind(&since)
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| format!("Failed to load LLM usage: {}", e))?;
+
+    let monthly_budget_usd = super::llm::get_llm_config(app.clone())
+        .await
+        .ok()
+        .and_then(|config| config.monthly_budget_usd);
+
+    Ok(LLMUsageSummary {
+        month_to_date_usd: month_to_date(&app).await?,
+        monthly_budget_usd,
+        by_day,
+        by_command,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    fn config(provider: &str, model: &str) -> LLMConfig {
+        LLMConfig {
+            provider: provider.to_string(),
+            api_key: String::new(),
+            model: model.to_string(),
+            base_url: String::new(),
+            cache_ttl_days: None,
+            cache_max_mb: None,
+            price_table: None,
+            monthly_budget_usd: None,
+        }
+    }
+
+    fn price(input_per_million: f64, output_per_million: f64) -> ModelPrice {
+        ModelPrice {
+            input_per_million,
+            output_per_million,
+        }
+    }
+
+    #[test]
+    fn longest_prefix_wins() {
+        let mini = estimate_cost(&config("openai", "gpt-4o-mini-2024-07-18"), 1_000_000, 0);
+        assert_eq!(mini, Some(0.15));
+        let full = estimate_cost(&config("openai", "gpt-4o-2024-08-06"), 0, 1_000_000);
+        assert_eq!(full, Some(10.0));
+    }
+
+    #[test]
+    fn strips_the_openrouter_vendor() {
+        let cost = estimate_cost(
+            &config("openrouter", "anthropic/claude-sonnet-4"),
+            1_000_000,
+            1_000_000,
+        );
+        assert_eq!(cost, Some(18.0));
+    }
+
+    #[test]
+    fn price_table_overrides_defaults() {
+        let mut openai = config("openai", "gpt-4o-mini");
+        openai.price_table = Some(HashMap::from([("gpt-4o".to_string(), price(1.0, 2.0))]));
+        assert_eq!(estimate_cost(&openai, 1_000_000, 1_000_000), Some(3.0));
+
+        let mut openrouter = config("openrouter", "meta/llama-3-70b");
+        assert_eq!(estimate_cost(&openrouter, 1, 1), None);
+        openrouter.price_table = Some(HashMap::from([(
+            "meta/llama-3-70b".to_string(),
+            price(0.5, 0.5),
+        )]));
+        assert_eq!(estimate_cost(&openrouter, 2_000_000, 0), Some(1.0));
+    }
+
+    #[test]
+    fn ollama_is_free() {
+        let cost = estimate_cost(&config("ollama", "llama3"), 5_000, 5_000);
+        assert_eq!(cost, Some(0.0));
+    }
+
+    #[test]
+    fn reads_usage_from_both_response_shapes() {
+        let anthropic = json!({ "usage": { "input_tokens": 12, "output_tokens": 34 } });
+        assert_eq!(extract_usage(&anthropic), (12, 34));
+        let openai = json!({ "usage": { "prompt_tokens": 56, "completion_tokens": 78 } });
+        assert_eq!(extract_usage(&openai), (56, 78));
+        assert_eq!(extract_usage(&json!({})), (0, 0));
+    }
+}
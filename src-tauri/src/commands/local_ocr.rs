@@ -0,0 +1,330 @@
+//! Offline image-to-LaTeX OCR for users without (or not wanting) a cloud LLM.
+//!
+//! A model directory holds a TrOCR/pix2tex-style encoder-decoder exported to
+//! ONNX (`encoder.onnx`, `decoder.onnx`), its `tokenizer.json` and an optional
+//! `config.json` describing preprocessing and special tokens. The ONNX engine
+//! is only compiled with the `local-ocr` feature.
+
+use serde::{Deserialize, Serialize};
+use tauri::Manager;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OcrConfig {
+    /// "llm" (default) sends images to the configured LLM; "local" runs the
+    /// ONNX model in `model_path` on the CPU.
+    pub provider: String,
+    pub model_path: String,
+    /// Consent to read image cards in the background so their text can be
+    /// searched, used as alt text and compared for duplicates.
+    #[serde(default)]
+    pub background_indexing: bool,
+}
+
+impl Default for OcrConfig {
+    fn default() -> Self {
+        OcrConfig {
+            provider: "llm".to_string(),
+            model_path: String::new(),
+            background_indexing: false,
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn get_ocr_config(app: tauri::AppHandle) -> Result<OcrConfig, String> {
+    load_ocr_config(&app)
+}
+
+#[tauri::command]
+pub async fn set_ocr_config(app: tauri::AppHandle, config: OcrConfig) -> Result<(), String> {
+    if config.provider == "local" {
+        if !cfg!(feature = "local-ocr") {
+            return Err("This build of FlashMath does not include local OCR".to_string());
+        }
+        validate_model_dir(&config.model_path)?;
+    }
+    let app_data = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+    std::fs::create_dir_all(&app_data)
+        .map_err(|e| format!("Failed to create config dir: {}", e))?;
+    let contents =
+        serde_json::to_string_pretty(&config).map_err(|e| format!("Failed to serialize: {}", e))?;
+    std::fs::write(app_data.join("ocr_config.json"), contents)
+        .map_err(|e| format!("Failed to write config: {}", e))?;
+    if config.background_indexing {
+        super::ocr_index::start(&app);
+    }
+    Ok(())
+}
+
+/// Whether this build includes the local OCR engine, so the settings page only
+/// offers it when it can run.
+#[tauri::command]
+pub fn local_ocr_available() -> bool {
+    cfg!(feature = "local-ocr")
+}
+
+/// Loads the model in `model_path` and runs it on `image_path`, so the settings
+/// page can check a model before switching to it.
+#[tauri::command]
+pub async fn test_local_ocr(model_path: String, image_path: String) -> Result<String, String> {
+    validate_model_dir(&model_path)?;
+    run_local_ocr(model_path, image_path).await
+}
+
+/// Runs the local engine if it is the selected OCR provider. Returns `None`
+/// when OCR should go to the LLM instead.
+pub async fn try_local_ocr(
+    app: &tauri::AppHandle,
+    image_path: &str,
+) -> Result<Option<String>, String> {
+    let config = load_ocr_config(app)?;
+    if config.provider != "local" {
+        return Ok(None);
+    }
+    let latex = run_local_ocr(config.model_path, image_path.to_string()).await?;
+    // The model emits bare LaTeX; wrap it the way the LLM prompts ask for.
+    Ok(Some(format!("$${}$$", latex.trim())))
+}
+
+pub fn load_ocr_config(app: &tauri::AppHandle) -> Result<OcrConfig, String> {
+    let app_data = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+    let config_path = app_data.join("ocr_config.json");
+    if !config_path.exists() {
+        return Ok(OcrConfig::default());
+    }
+    let contents = std::fs::read_to_string(&config_path)
+        .map_err(|e| format!("Failed to read OCR config: {}", e))?;
+    serde_json::from_str(&contents).map_err(|e| format!("Failed to parse OCR config: {}", e))
+}
+
+fn validate_model_dir(model_path: &str) -> Result<(), String> {
+    let dir = std::path::Path::new(model_path);
+    for file in ["encoder.onnx", "decoder.onnx", "tokenizer.json"] {
+        if !dir.join(file).exists() {
+            return Err(format!("OCR model folder is missing {}", file));
+        }
+    }
+    Ok(())
+}
+
+async fn run_local_ocr(model_path: String, image_path: String) -> Result<String, String> {
+    tauri::async_runtime::spawn_blocking(move || engine::recognize(&model_path, &image_path))
+        .await
+        .map_err(|e| format!("Local OCR task failed: {}", e))?
+}
+
+#[cfg(not(feature = "local-ocr"))]
+mod engine {
+    pub fn recognize(_model_path: &str, _image_path: &str) -> Result<String, String> {
+        Err("This build of FlashMath does not include local OCR. Rebuild with the `local-ocr` feature or switch OCR back to the LLM.".to_string())
+    }
+}
+
+#[cfg(feature = "local-ocr")]
+mod engine {
+    use crate::commands::capture::load_image_oriented;
+    use image::imageops::FilterType;
+    use ort::session::Session;
+    use ort::value::Tensor;
+    use serde::Deserialize;
+    use std::collections::HashSet;
+    use std::sync::Mutex;
+
+    #[derive(Debug, Clone, Default, Deserialize)]
+    #[serde(default)]
+    struct ModelConfig {
+        /// Input size as [height, width]; images are letterboxed onto a white canvas.
+        image_size: Option<[u32; 2]>,
+        /// 1 for grayscale models (pix2tex), 3 for RGB (TrOCR).
+        channels: Option<u32>,
+        mean: Option<f32>,
+        std: Option<f32>,
+        bos_token_id: Option<i64>,
+        eos_token_id: Option<i64>,
+        max_length: Option<usize>,
+    }
+
+    fn load_model_config(model_path: &str) -> Result<ModelConfig, String> {
+        let path = std::path::Path::new(model_path).join("config.json");
+        if !path.exists() {
+            return Ok(ModelConfig::default());
+        }
+        let contents = std::fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read model config: {}", e))?;
+        let config: ModelConfig = serde_json::from_str(&contents)
+            .map_err(|e| format!("Failed to parse model config: {}", e))?;
+        match config.channels {
+            None | Some(1) | Some(3) => Ok(config),
+            Some(other) => Err(format!(
+                "Model config has {} channels; only 1 (grayscale) or 3 (RGB) are supported",
+                other
+            )),
+        }
+    }
+
+    /// Maps token ids back to text using the `model.vocab` table of a Hugging Face
+    /// `tokenizer.json` (BPE or WordPiece), undoing byte-level and sentencepiece
+    /// space markers. Special tokens listed under `added_tokens` are dropped.
+    struct Vocab {
+        tokens: Vec<String>,
+        special: HashSet<i64>,
+    }
+
+    impl Vocab {
+        fn load(model_path: &str) -> Result<Self, String> {
+            let path = std::path::Path::new(model_path).join("tokenizer.json");
+            let contents = std::fs::read_to_string(&path)
+                .map_err(|e| format!("Failed to read tokenizer: {}", e))?;
+            let json: serde_json::Value = serde_json::from_str(&contents)
+                .map_err(|e| format!("Failed to parse tokenizer: {}", e))?;
+            let vocab = json["model"]["vocab"]
+                .as_object()
+                .ok_or_else(|| "tokenizer.json has no model.vocab table".to_string())?;
+            let size = vocab.values().filter_map(|v| v.as_u64()).max().unwrap_or(0) as usize + 1;
+            let mut tokens = vec![String::new(); size];
+            for (token, id) in vocab {
+                if let Some(id) = id.as_u64() {
+                    tokens[id as usize] = token.clone();
+                }
+            }
+            let special = json["added_tokens"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter(|token| token["special"].as_bool().unwrap_or(false))
+                .filter_map(|token| token["id"].as_i64())
+                .collect();
+            Ok(Vocab { tokens, special })
+        }
+
+        fn decode(&self, ids: &[i64]) -> String {
+            let text: String = ids
+                .iter()
+                .filter(|id| !self.special.contains(id))
+                .filter_map(|id| self.tokens.get(*id as usize))
+                .map(|token| token.replace(['Ġ', '▁'], " "))
+                .collect();
+            text.split_whitespace().collect::<Vec<_>>().join(" ")
+        }
+    }
+
+    struct LoadedModel {
+        path: String,
+        encoder: Session,
+        decoder: Session,
+        vocab: Vocab,
+        config: ModelConfig,
+    }
+
+    // Sessions are expensive to build, so keep the last model loaded.
+    static MODEL: Mutex<Option<LoadedModel>> = Mutex::new(None);
+
+    pub fn recognize(model_path: &str, image_path: &str) -> Result<String, String> {
+        let mut guard = MODEL
+            .lock()
+            .map_err(|_| "Local OCR model lock poisoned".to_string())?;
+        if guard.as_ref().map(|m| m.path != model_path).unwrap_or(true) {
+            *guard = Some(load(model_path)?);
+        }
+        let model = guard.as_mut().expect("model loaded above");
+
+        let pixels = preprocess(image_path, &model.config)?;
+        let encoder_outputs = model
+            .encoder
+            .run(ort::inputs![pixels])
+            .map_err(|e| format!("OCR encoder failed: {}", e))?;
+        let (shape, hidden) = encoder_outputs[0]
+            .try_extract_tensor::<f32>()
+            .map_err(|e| format!("Unexpected encoder output: {}", e))?;
+        let hidden_shape: Vec<usize> = shape.iter().map(|d| *d as usize).collect();
+        let hidden = hidden.to_vec();
+        drop(encoder_outputs);
+
+        let bos = model.config.bos_token_id.unwrap_or(1);
+        let eos = model.config.eos_token_id.unwrap_or(2);
+        let max_length = model.config.max_length.unwrap_or(512);
+        let mut ids = vec![bos];
+
+        while ids.len() < max_length {
+            let input_ids = Tensor::from_array(([1usize, ids.len()], ids.clone()))
+                .map_err(|e| format!("Failed to build decoder input: {}", e))?;
+            let encoder_hidden = Tensor::from_array((hidden_shape.clone(), hidden.clone()))
+                .map_err(|e| format!("Failed to build decoder input: {}", e))?;
+            let outputs = model
+                .decoder
+                .run(ort::inputs![input_ids, encoder_hidden])
+                .map_err(|e| format!("OCR decoder failed: {}", e))?;
+            let (shape, logits) = outputs[0]
+                .try_extract_tensor::<f32>()
+                .map_err(|e| format!("Unexpected decoder output: {}", e))?;
+            let vocab_size = *shape.last().ok_or("Decoder returned no logits")? as usize;
+            let last = &logits[logits.len() - vocab_size..];
+            let next = last
+                .iter()
+                .enumerate()
+                .max_by(|a, b| a.1.total_cmp(b.1))
+                .map(|(i, _)| i as i64)
+                .ok_or("Decoder returned empty logits")?;
+            if next == eos {
+                break;
+            }
+            ids.push(next);
+        }
+
+        Ok(model.vocab.decode(&ids[1..]))
+    }
+
+    fn load(model_path: &str) -> Result<LoadedModel, String> {
+        let dir = std::path::Path::new(model_path);
+        let session = |file: &str| {
+            Session::builder()
+                .and_then(|b| b.commit_from_file(dir.join(file)))
+                .map_err(|e| format!("Failed to load {}: {}", file, e))
+        };
+        Ok(LoadedModel {
+            path: model_path.to_string(),
+            encoder: session("encoder.onnx")?,
+            decoder: session("decoder.onnx")?,
+            vocab: Vocab::load(model_path)?,
+            config: load_model_config(model_path)?,
+        })
+    }
+
+    /// Letterboxes the image onto a white canvas of the model's input size and
+    /// normalizes it into an NCHW float tensor.
+    fn preprocess(image_path: &str, config: &ModelConfig) -> Result<Tensor<f32>, String> {
+        let [height, width] = config.image_size.unwrap_or([384, 384]);
+        let channels = config.channels.unwrap_or(3) as usize;
+        let mean = config.mean.unwrap_or(0.5);
+        let std = config.std.unwrap_or(0.5);
+
+        let img = load_image_oriented(image_path)?
+            .resize(width, height, FilterType::Triangle)
+            .to_rgb8();
+        let (x_off, y_off) = ((width - img.width()) / 2, (height - img.height()) / 2);
+
+        let plane = (height * width) as usize;
+        let mut data = vec![(1.0 - mean) / std; channels * plane];
+        for (x, y, px) in img.enumerate_pixels() {
+            let idx = ((y + y_off) * width + (x + x_off)) as usize;
+            if channels == 1 {
+                let gray =
+                    (0.299 * px[0] as f32 + 0.587 * px[1] as f32 + 0.114 * px[2] as f32) / 255.0;
+                data[idx] = (gray - mean) / std;
+            } else {
+                for c in 0..3 {
+                    data[c * plane + idx] = (px[c] as f32 / 255.0 - mean) / std;
+                }
+            }
+        }
+
+        Tensor::from_array(([1usize, channels, height as usize, width as usize], data))
+            .map_err(|e| format!("Failed to build image tensor: {}", e))
+    }
+}
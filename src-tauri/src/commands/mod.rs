@@ -1,4 +1,20 @@
 pub mod database;
+pub mod embeddings;
+pub mod exam_plan;
 pub mod capture;
+pub mod card_state;
+pub mod chat;
 pub mod llm;
+pub mod llm_cache;
+pub mod llm_usage;
 pub mod files;
+pub mod grading;
+pub mod jobs;
+pub mod leeches;
+pub mod local_ocr;
+pub mod ocr_index;
+pub mod search;
+pub mod sessions;
+pub mod study_queue;
+pub mod templates;
+pub mod verify;
@@ -0,0 +1,221 @@
+//! Stored OCR text for image cards. With the user's consent
+//! (`OcrConfig::background_indexing`) a background task reads every image
+//! side that has no text yet and saves it to `question_ocr`/`answer_ocr`,
+//! which feed search, screen-reader alt text and duplicate detection.
+
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use serde::Serialize;
+use sqlx::{FromRow, Pool, Sqlite};
+use tauri::{Emitter, Manager};
+
+use super::database::get_pool;
+use super::llm::ocr_image;
+use super::local_ocr::load_ocr_config;
+
+const PROGRESS_EVENT: &str = "ocr-index-progress";
+const BATCH_SIZE: i64 = 20;
+const MAX_BATCH_SIZE: i64 = 200;
+
+const PENDING: &str = "(question_type = 'image' AND question_ocr IS NULL)
+    OR (answer_type = 'image' AND answer_content IS NOT NULL AND answer_ocr IS NULL)";
+
+/// Whether the background task is running; at most one runs at a time.
+#[derive(Default)]
+pub struct OcrIndexer(AtomicBool);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OcrIndexStatus {
+    pub enabled: bool,
+    pub running: bool,
+    /// Image cards still waiting for OCR.
+    pub pending: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OcrIndexResult {
+    pub indexed: usize,
+    pub failed: usize,
+    pub remaining: i64,
+}
+
+#[derive(FromRow)]
+struct PendingCard {
+    id: String,
+    question_type: String,
+    question_content: String,
+    answer_type: Option<String>,
+    answer_content: Option<String>,
+    question_ocr: Option<String>,
+    answer_ocr: Option<String>,
+}
+
+#[tauri::command]
+pub async fn get_ocr_index_status(app: tauri::AppHandle) -> Result<OcrIndexStatus, String> {
+    status(&app).await
+}
+
+/// Starts the background indexer if the user has opted in. The frontend
+/// calls this on launch and after cards change; it is a no-op otherwise.
+#[tauri::command]
+pub async fn start_ocr_indexer(app: tauri::AppHandle) -> Result<OcrIndexStatus, String> {
+    if load_ocr_config(&app)?.background_indexing {
+        start(&app);
+    }
+    status(&app).await
+}
+
+/// Reads one batch of image cards right away, for users who haven't turned
+/// on background indexing but asked for it explicitly.
+#[tauri::command]
+pub async fn index_card_ocr(
+    app: tauri::AppHandle,
+    limit: Option<i64>,
+) -> Result<OcrIndexResult, String> {
+    let pool = get_pool(&app).await?;
+    let cards = pending_cards(&pool, limit.unwrap_or(BATCH_SIZE).clamp(1, MAX_BATCH_SIZE)).await?;
+    let mut result = OcrIndexResult {
+        indexed: 0,
+        failed: 0,
+        remaining: 0,
+    };
+    for card in cards {
+        match index_card(&app, &pool, &card).await {
+            Ok(()) => result.indexed += 1,
+            Err(e) => {
+                log::warn!("Failed to OCR card {}: {}", card.id, e);
+                result.failed += 1;
+            }
+        }
+    }
+    result.remaining = pending_count(&pool).await?;
+    Ok(result)
+}
+
+pub fn start(app: &tauri::AppHandle) {
+    if app.state::<OcrIndexer>().0.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = run(&app).await {
+            log::error!("OCR indexer stopped: {}", e);
+        }
+        app.state::<OcrIndexer>().0.store(false, Ordering::SeqCst);
+    });
+}
+
+/// Indexes until nothing is pending or consent is withdrawn. Cards that fail
+/// are skipped for the rest of the run so one bad image can't stall it.
+async fn run(app: &tauri::AppHandle) -> Result<(), String> {
+    let pool = get_pool(app).await?;
+    let mut failed: HashSet<String> = HashSet::new();
+    let mut result = OcrIndexResult {
+        indexed: 0,
+        failed: 0,
+        remaining: 0,
+    };
+    loop {
+        if !load_ocr_config(app)?.background_indexing {
+            return Ok(());
+        }
+        let batch: Vec<PendingCard> = pending_cards(&pool, BATCH_SIZE + failed.len() as i64)
+            .await?
+            .into_iter()
+            .filter(|card| !failed.contains(&card.id))
+            .take(BATCH_SIZE as usize)
+            .collect();
+        if batch.is_empty() {
+            return Ok(());
+        }
+        for card in batch {
+            match index_card(app, &pool, &card).await {
+                Ok(()) => result.indexed += 1,
+                Err(e) => {
+                    log::warn!("Failed to OCR card {}: {}", card.id, e);
+                    failed.insert(card.id);
+                    result.failed += 1;
+                }
+            }
+            result.remaining = pending_count(&pool).await? - failed.len() as i64;
+            let _ = app.emit(PROGRESS_EVENT, result.clone());
+        }
+    }
+}
+
+async fn status(app: &tauri::AppHandle) -> Result<OcrIndexStatus, String> {
+    let pool = get_pool(app).await?;
+    Ok(OcrIndexStatus {
+        enabled: load_ocr_config(app)?.background_indexing,
+        running: app.state::<OcrIndexer>().0.load(Ordering::SeqCst),
+        pending: pending_count(&pool).await?,
+    })
+}
+
+async fn pending_cards(pool: &Pool<Sqlite>, limit: i64) -> Result<Vec<PendingCard>, String> {
+    sqlx::query_as(&format!(
+        "SELECT id, question_type, question_content, answer_type, answer_content,
+                question_ocr, answer_ocr
+         FROM flashcards WHERE {} ORDER BY created_at LIMIT $1",
+        PENDING
+    ))
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load image cards: {}", e))
+}
+
+async fn pending_count(pool: &Pool<Sqlite>) -> Result<i64, String> {
+    sqlx::query_scalar(&format!(
+        "SELECT COUNT(*) FROM flashcards WHERE {}",
+        PENDING
+    ))
+    .fetch_one(pool)
+    .await
+    .map_err(|e| format!("Failed to count image cards: {}", e))
+}
+
+async fn index_card(
+    app: &tauri::AppHandle,
+    pool: &Pool<Sqlite>,
+    card: &PendingCard,
+) -> Result<(), String> {
+    let question_ocr = match (card.question_type.as_str(), &card.question_ocr) {
+        ("image", None) => Some(ocr_images(app, &card.question_content).await?),
+        _ => None,
+    };
+    let answer_ocr = match (
+        card.answer_type.as_deref(),
+        &card.answer_content,
+        &card.answer_ocr,
+    ) {
+        (Some("image"), Some(content), None) => Some(ocr_images(app, content).await?),
+        _ => None,
+    };
+    // Only store the text if the images weren't replaced while reading them.
+    sqlx::query(
+        "UPDATE flashcards SET
+            question_ocr = COALESCE($1, question_ocr),
+            answer_ocr = COALESCE($2, answer_ocr)
+         WHERE id = $3 AND question_content = $4 AND answer_content IS $5",
+    )
+    .bind(question_ocr)
+    .bind(answer_ocr)
+    .bind(&card.id)
+    .bind(&card.question_content)
+    .bind(&card.answer_content)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to save OCR text: {}", e))?;
+    Ok(())
+}
+
+/// OCR text of an image field, which may hold several `|||`-separated pages.
+pub async fn ocr_images(app: &tauri::AppHandle, content: &str) -> Result<String, String> {
+    let mut pages = Vec::new();
+    for path in content.split("|||").filter(|p| !p.is_empty()) {
+        pages.push(ocr_image(app.clone(), path.to_string(), None).await?);
+    }
+    Ok(pages.join("\n"))
+}
@@ -0,0 +1,364 @@
+//! Card search. Matches come from the `cards_fts` full-text index over
+//! titles, text content and the OCR text of image cards; when semantic
+//! ranking is on, they are fused with the nearest card embeddings.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, QueryBuilder, Sqlite};
+
+use super::database::{get_pool, now_iso};
+use super::embeddings::{cosine, decode_vector, normalized, Embedder};
+
+const DEFAULT_LIMIT: i64 = 50;
+const MAX_LIMIT: i64 = 200;
+/// Reciprocal rank fusion constant; dampens the weight of the top ranks.
+const RRF_K: f64 = 60.0;
+const HIGHLIGHT_START: char = '\u{2}';
+const HIGHLIGHT_END: char = '\u{3}';
+
+const CARD_COLUMNS: &str = "f.id, f.folder_id, f.title, f.question_type, f.question_content,
+     f.answer_type, f.answer_content, f.ease_factor, f.repetitions, f.due_date";
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SearchFilters {
+    pub folder_id: Option<String>,
+    /// "due", "upcoming" or "new".
+    pub due: Option<String>,
+    pub question_type: Option<String>,
+    pub ease_min: Option<f64>,
+    pub ease_max: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct SearchCard {
+    pub id: String,
+    pub folder_id: Option<String>,
+    pub title: Option<String>,
+    pub question_type: String,
+    pub question_content: String,
+    pub answer_type: Option<String>,
+    pub answer_content: Option<String>,
+    pub ease_factor: f64,
+    pub repetitions: i32,
+    pub due_date: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SnippetPart {
+    pub text: String,
+    pub highlight: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Snippet {
+    /// "title", "question", "answer" or "ocr".
+    pub field: String,
+    pub parts: Vec<SnippetPart>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchHit {
+    pub card: SearchCard,
+    pub score: f64,
+    /// Only fields containing a text match; empty for semantic-only hits.
+    pub snippets: Vec<Snippet>,
+}
+
+#[derive(FromRow)]
+struct TextMatch {
+    #[sqlx(flatten)]
+    card: SearchCard,
+    title_snippet: Option<String>,
+    question_snippet: Option<String>,
+    answer_snippet: Option<String>,
+    ocr_snippet: Option<String>,
+}
+
+#[derive(FromRow)]
+struct EmbeddedCard {
+    #[sqlx(flatten)]
+    card: SearchCard,
+    vector: Vec<u8>,
+}
+
+/// Searches every card. A blank query lists the cards matching `filters`.
+#[tauri::command]
+pub async fn search_cards(
+    app: tauri::AppHandle,
+    query: String,
+    filters: Option<SearchFilters>,
+    semantic: Option<bool>,
+    limit: Option<i64>,
+) -> Result<Vec<SearchHit>, String> {
+    let pool = get_pool(&app).await?;
+    let filters = filters.unwrap_or_default();
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let now = now_iso();
+
+    let Some(fts) = fts_query(&query) else {
+        let mut builder = QueryBuilder::<Sqlite>::new(format!(
+            "SELECT {} FROM flashcards f WHERE 1 = 1",
+            CARD_COLUMNS
+        ));
+        push_filters(&mut builder, &filters, &now);
+        builder.push(" ORDER BY f.created_at DESC LIMIT ");
+        builder.push_bind(limit);
+        let cards: Vec<SearchCard> = builder
+            .build_query_as()
+            .fetch_all(&pool)
+            .await
+            .map_err(|e| format!("Failed to list flashcards: {}", e))?;
+        return Ok(cards
+            .into_iter()
+            .map(|card| SearchHit {
+                card,
+                score: 0.0,
+                snippets: Vec::new(),
+            })
+            .collect());
+    };
+
+    let snippet =
+        |column: usize| format!("snippet(cards_fts, {}, char(2), char(3), '…', 12)", column);
+    let mut builder = QueryBuilder::<Sqlite>::new(format!(
+        "SELECT {}, {} AS title_snippet, {} AS question_snippet, {} AS answer_snippet,
+                {} AS ocr_snippet
+         FROM cards_fts JOIN flashcards f ON f.id = cards_fts.flashcard_id
+         WHERE cards_fts MATCH ",
+        CARD_COLUMNS,
+        snippet(1),
+        snippet(2),
+        snippet(3),
+        snippet(4)
+    ));
+    builder.push_bind(fts);
+    push_filters(&mut builder, &filters, &now);
+    // Title matches weigh most, OCR text least since it is the noisiest.
+    builder.push(" ORDER BY bm25(cards_fts, 0.0, 8.0, 4.0, 2.0, 1.0) LIMIT ");
+    builder.push_bind(limit);
+    let matches: Vec<TextMatch> = builder
+        .build_query_as()
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| format!("Failed to search flashcards: {}", e))?;
+
+    let mut hits: Vec<SearchHit> = Vec::new();
+    let mut text_ranking = Vec::new();
+    for row in matches {
+        text_ranking.push(row.card.id.clone());
+        let snippets = [
+            ("title", row.title_snippet),
+            ("question", row.question_snippet),
+            ("answer", row.answer_snippet),
+            ("ocr", row.ocr_snippet),
+        ]
+        .into_iter()
+        .filter_map(|(field, text)| {
+            let parts = split_highlights(&text.unwrap_or_default());
+            parts.iter().any(|p| p.highlight).then(|| Snippet {
+                field: field.to_string(),
+                parts,
+            })
+        })
+        .collect();
+        hits.push(SearchHit {
+            card: row.card,
+            score: 0.0,
+            snippets,
+        });
+    }
+    if !semantic.unwrap_or(false) {
+        let scores = fuse_rankings(&[&text_ranking]);
+        return Ok(ranked(hits, &scores, limit));
+    }
+
+    let embedder = Embedder::load(&app)?;
+    let query_vector = normalized(
+        embedder
+            .embed(&app, &[query.trim().to_string()])
+            .await?
+            .pop()
+            .ok_or("Embedding returned no vector")?,
+    );
+    let mut builder = QueryBuilder::<Sqlite>::new(format!(
+        "SELECT {}, e.vector FROM card_embeddings e
+         JOIN flashcards f ON f.id = e.flashcard_id
+         WHERE e.model = ",
+        CARD_COLUMNS
+    ));
+    builder.push_bind(embedder.model().to_string());
+    push_filters(&mut builder, &filters, &now);
+    let embedded: Vec<EmbeddedCard> = builder
+        .build_query_as()
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| format!("Failed to load embeddings: {}", e))?;
+
+    let mut nearest: Vec<(f32, SearchCard)> = embedded
+        .into_iter()
+        .map(|row| (cosine(&query_vector, &decode_vector(&row.vector)), row.card))
+        .collect();
+    nearest.sort_by(|a, b| b.0.total_cmp(&a.0));
+    nearest.truncate(limit as usize);
+    let semantic_ranking: Vec<String> = nearest.iter().map(|(_, c)| c.id.clone()).collect();
+    for (_, card) in nearest {
+        if !text_ranking.contains(&card.id) {
+            hits.push(SearchHit {
+                card,
+                score: 0.0,
+                snippets: Vec::new(),
+            });
+        }
+    }
+    let scores = fuse_rankings(&[&text_ranking, &semantic_ranking]);
+    Ok(ranked(hits, &scores, limit))
+}
+
+fn push_filters(builder: &mut QueryBuilder<'_, Sqlite>, filters: &SearchFilters, now: &str) {
+    if let Some(folder_id) = &filters.folder_id {
+        builder.push(" AND f.folder_id = ");
+        builder.push_bind(folder_id.clone());
+    }
+    match filters.due.as_deref() {
+        Some("due") => {
+            builder.push(" AND f.suspended = 0 AND (f.buried_until IS NULL OR f.buried_until <= ");
+            builder.push_bind(now.to_string());
+            builder.push(") AND (f.due_date IS NULL OR f.due_date <= ");
+            builder.push_bind(now.to_string());
+            builder.push(")");
+        }
+        Some("upcoming") => {
+            builder.push(" AND f.due_date > ");
+            builder.push_bind(now.to_string());
+        }
+        Some("new") => {
+            builder.push(" AND f.repetitions = 0");
+        }
+        _ => {}
+    }
+    if let Some(question_type) = &filters.question_type {
+        builder.push(" AND f.question_type = ");
+        builder.push_bind(question_type.clone());
+    }
+    if let Some(ease_min) = filters.ease_min {
+        builder.push(" AND f.ease_factor >= ");
+        builder.push_bind(ease_min);
+    }
+    if let Some(ease_max) = filters.ease_max {
+        builder.push(" AND f.ease_factor <= ");
+        builder.push_bind(ease_max);
+    }
+}
+
+/// Turns free text into an FTS5 query: every word must appear, as a prefix,
+/// so "deriv sin" finds "derivative of \sin x". Operators and quotes in the
+/// input are treated as plain text.
+fn fts_query(text: &str) -> Option<String> {
+    let terms: Vec<String> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| format!("\"{}\"*", word.to_lowercase()))
+        .collect();
+    (!terms.is_empty()).then(|| terms.join(" "))
+}
+
+/// Splits an FTS5 snippet on the highlight markers it was built with.
+fn split_highlights(snippet: &str) -> Vec<SnippetPart> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut highlight = false;
+    for c in snippet.chars() {
+        if c == HIGHLIGHT_START || c == HIGHLIGHT_END {
+            if !current.is_empty() {
+                parts.push(SnippetPart {
+                    text: std::mem::take(&mut current),
+                    highlight,
+                });
+            }
+            highlight = c == HIGHLIGHT_START;
+        } else {
+            current.push(c);
+        }
+    }
+    if !current.is_empty() {
+        parts.push(SnippetPart {
+            text: current,
+            highlight,
+        });
+    }
+    parts
+}
+
+/// Reciprocal rank fusion: each ranking adds `1 / (k + rank)` to the score of
+/// the ids in it, so items near the top of several rankings come first.
+fn fuse_rankings(rankings: &[&[String]]) -> HashMap<String, f64> {
+    let mut scores = HashMap::new();
+    for ranking in rankings {
+        for (rank, id) in ranking.iter().enumerate() {
+            *scores.entry(id.clone()).or_insert(0.0) += 1.0 / (RRF_K + rank as f64 + 1.0);
+        }
+    }
+    scores
+}
+
+fn ranked(mut hits: Vec<SearchHit>, scores: &HashMap<String, f64>, limit: i64) -> Vec<SearchHit> {
+    for hit in &mut hits {
+        hit.score = scores.get(&hit.card.id).copied().unwrap_or(0.0);
+    }
+    hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+    hits.truncate(limit as usize);
+    hits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_prefix_queries_from_free_text() {
+        assert_eq!(
+            fts_query("deriv  sin"),
+            Some("\"deriv\"* \"sin\"*".to_string())
+        );
+        assert_eq!(
+            fts_query("\"x\" OR NEAR(y)"),
+            Some("\"x\"* \"or\"* \"near\"* \"y\"*".to_string())
+        );
+        assert_eq!(
+            fts_query(r"\frac{1}{2}"),
+            Some("\"frac\"* \"1\"* \"2\"*".to_string())
+        );
+        assert_eq!(fts_query("  $ - "), None);
+    }
+
+    #[test]
+    fn splits_snippet_highlights() {
+        let parts = split_highlights("…find the \u{2}derivative\u{3} of \u{2}sin\u{3}");
+        let texts: Vec<(&str, bool)> = parts
+            .iter()
+            .map(|p| (p.text.as_str(), p.highlight))
+            .collect();
+        assert_eq!(
+            texts,
+            vec![
+                ("…find the ", false),
+                ("derivative", true),
+                (" of ", false),
+                ("sin", true)
+            ]
+        );
+        assert!(split_highlights("").is_empty());
+    }
+
+    #[test]
+    fn fusion_favours_cards_ranked_by_both() {
+        let ids = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+        let text = ids(&["a", "b", "c"]);
+        let semantic = ids(&["c", "d", "b"]);
+        let scores = fuse_rankings(&[&text, &semantic]);
+        assert!(scores["b"] > scores["a"]);
+        assert!(scores["c"] > scores["b"]);
+        assert!(scores["a"] > scores["d"]);
+    }
+}
@@ -0,0 +1,175 @@
+//! Analytics for persisted study sessions. The study screen writes
+//! `study_sessions` rows and tags each review with its `session_id` (see
+//! `startStudySession` in `src/lib/database.ts`); this module summarises them.
+
+use serde::Serialize;
+use sqlx::{FromRow, Pool, Sqlite};
+
+use super::database::get_pool;
+
+const DEFAULT_SESSION_LIMIT: u32 = 20;
+
+#[derive(FromRow)]
+struct SessionRow {
+    id: String,
+    folder_ids: String,
+    mode: String,
+    queue: String,
+    position: i64,
+    started_at: String,
+    ended_at: Option<String>,
+}
+
+#[derive(FromRow)]
+struct ReviewPoint {
+    correct: bool,
+    response_time_seconds: f64,
+    speed_ratio: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionAnalytics {
+    pub id: String,
+    pub folder_ids: Vec<String>,
+    pub mode: String,
+    pub started_at: String,
+    pub ended_at: Option<String>,
+    pub queue_length: usize,
+    pub position: i64,
+    pub reviewed: usize,
+    pub correct: usize,
+    pub accuracy: f64,
+    pub avg_response_seconds: f64,
+    pub avg_speed_ratio: f64,
+    /// Change in speed ratio per card over the session; negative means the
+    /// learner got faster relative to each card's timer.
+    pub speed_trend: f64,
+    /// Speed ratio of each answer, in order.
+    pub speed_ratios: Vec<f64>,
+}
+
+/// Recent sessions, newest first, optionally only those that included
+/// `folder_id` (sessions over every deck always match).
+#[tauri::command]
+pub async fn get_study_sessions(
+    app: tauri::AppHandle,
+    folder_id: Option<String>,
+    limit: Option<u32>,
+) -> Result<Vec<SessionAnalytics>, String> {
+    let pool = get_pool(&app).await?;
+    let rows: Vec<SessionRow> = sqlx::query_as(
+        "SELECT id, folder_ids, mode, queue, position, started_at, ended_at
+         FROM study_sessions s
+         WHERE $1 IS NULL OR s.folder_ids = '[]'
+            OR EXISTS (SELECT 1 FROM json_each(s.folder_ids) WHERE value = $1)
+         ORDER BY started_at DESC
+         LIMIT $2",
+    )
+    .bind(&folder_id)
+    .bind(limit.unwrap_or(DEFAULT_SESSION_LIMIT))
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| format!("Failed to load study sessions: {}", e))?;
+
+    let mut sessions = Vec::with_capacity(rows.len());
+    for row in rows {
+        sessions.push(analyze_session(&pool, row).await?);
+    }
+    Ok(sessions)
+}
+
+#[tauri::command]
+pub async fn get_session_analytics(
+    app: tauri::AppHandle,
+    session_id: String,
+) -> Result<SessionAnalytics, String> {
+    let pool = get_pool(&app).await?;
+    let row: SessionRow = sqlx::query_as(
+        "SELECT id, folder_ids, mode, queue, position, started_at, ended_at
+         FROM study_sessions WHERE id = $1",
+    )
+    .bind(&session_id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| format!("Failed to load study session: {}", e))?
+    .ok_or_else(|| format!("Study session not found: {}", session_id))?;
+    analyze_session(&pool, row).await
+}
+
+async fn analyze_session(pool: &Pool<Sqlite>, row: SessionRow) -> Result<SessionAnalytics, String> {
+    let points: Vec<ReviewPoint> = sqlx::query_as(
+        "SELECT correct != 0 AS correct,
+                COALESCE(response_time_seconds, 0) AS response_time_seconds,
+                COALESCE(speed_ratio, 0) AS speed_ratio
+         FROM reviews
+         WHERE session_id = $1 AND kind = 'review'
+         ORDER BY reviewed_at",
+    )
+    .bind(&row.id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load session reviews: {}", e))?;
+
+    let reviewed = points.len();
+    let correct = points.iter().filter(|p| p.correct).count();
+    let speed_ratios: Vec<f64> = points.iter().map(|p| p.speed_ratio).collect();
+    Ok(SessionAnalytics {
+        id: row.id,
+        folder_ids: serde_json::from_str(&row.folder_ids).unwrap_or_default(),
+        mode: row.mode,
+        started_at: row.started_at,
+        ended_at: row.ended_at,
+        queue_length: serde_json::from_str::<Vec<String>>(&row.queue)
+            .map(|queue| queue.len())
+            .unwrap_or(0),
+        position: row.position,
+        reviewed,
+        correct,
+        accuracy: ratio(correct as f64, reviewed),
+        avg_response_seconds: ratio(
+            points.iter().map(|p| p.response_time_seconds).sum(),
+            reviewed,
+        ),
+        avg_speed_ratio: ratio(speed_ratios.iter().sum(), reviewed),
+        speed_trend: trend(&speed_ratios),
+        speed_ratios,
+    })
+}
+
+fn ratio(total: f64, count: usize) -> f64 {
+    if count == 0 {
+        0.0
+    } else {
+        total / count as f64
+    }
+}
+
+/// Least-squares slope of `values` against their index.
+fn trend(values: &[f64]) -> f64 {
+    let n = values.len() as f64;
+    if values.len() < 2 {
+        return 0.0;
+    }
+    let mean_x = (n - 1.0) / 2.0;
+    let mean_y = values.iter().sum::<f64>() / n;
+    let (mut covariance, mut variance) = (0.0, 0.0);
+    for (i, y) in values.iter().enumerate() {
+        let dx = i as f64 - mean_x;
+        covariance += dx * (y - mean_y);
+        variance += dx * dx;
+    }
+    covariance / variance
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn speed_trend_is_the_least_squares_slope() {
+        assert_eq!(trend(&[]), 0.0);
+        assert_eq!(trend(&[0.8]), 0.0);
+        assert!((trend(&[1.0, 0.8, 0.6, 0.4]) + 0.2).abs() < 1e-9);
+        assert!((trend(&[0.5, 0.7, 0.5, 0.7]) - 0.04).abs() < 1e-9);
+    }
+}
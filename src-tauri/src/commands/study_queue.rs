@@ -0,0 +1,127 @@
+//! Builds study queues with the rules in `review_policy`. A folder's daily
+//! queue puts learning cards first, then reviews up to its review limit with
+//! the day's new cards (up to `new_cards_per_day`, in `new_card_order`) mixed
+//! in evenly. A session interleaves several folders' daily queues. Mirrors
+//! `getScheduledDueCards` and `buildSessionQueue` in
+//! `src/lib/review-policy.ts`, which serve local mode.
+
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use super::database::{get_pool, load_card_states, load_folder_policies, now_iso, today_start_iso};
+use crate::review_policy::{
+    daily_queues_by_folder, interleave_folder_queues, CardState, FolderPolicy, FolderQueue,
+};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DailyQueue {
+    pub card_ids: Vec<String>,
+    pub learning_count: usize,
+    pub review_count: usize,
+    pub new_count: usize,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SessionFolder {
+    pub folder_id: String,
+    #[serde(default = "default_weight")]
+    pub weight: f64,
+}
+
+fn default_weight() -> f64 {
+    1.0
+}
+
+/// Today's queue for one folder, or every card when `folder_id` is `None`.
+/// `card_ids` narrows the candidates (e.g. after rotating variant families);
+/// otherwise every due card is a candidate.
+#[tauri::command]
+pub async fn build_daily_queue(
+    app: tauri::AppHandle,
+    folder_id: Option<String>,
+    card_ids: Option<Vec<String>>,
+) -> Result<DailyQueue, String> {
+    let pool = get_pool(&app).await?;
+    let folders = load_folder_policies(&pool).await?;
+    let cards = load_card_states(&pool, folder_id.as_deref(), true).await?;
+    let queues = folder_queues(&folders, &cards, card_ids.as_deref());
+    Ok(summarize(
+        queues.into_iter().flat_map(|queue| queue.cards).collect(),
+    ))
+}
+
+/// An interleaved session across `folders` with their weights, or across every
+/// folder with due cards when `folders` is `None`. Each folder keeps its own
+/// daily limits; `max_cards` caps the session, which then favours the heavier
+/// and more urgent folders.
+#[tauri::command]
+pub async fn build_session_queue(
+    app: tauri::AppHandle,
+    folders: Option<Vec<SessionFolder>>,
+    card_ids: Option<Vec<String>>,
+    max_cards: Option<usize>,
+) -> Result<DailyQueue, String> {
+    let pool = get_pool(&app).await?;
+    let policies = load_folder_policies(&pool).await?;
+    let cards = load_card_states(&pool, None, true).await?;
+    let mut queues = folder_queues(&policies, &cards, card_ids.as_deref());
+
+    if let Some(selected) = folders {
+        let weights: HashMap<&str, f64> = selected
+            .iter()
+            .map(|folder| (folder.folder_id.as_str(), folder.weight))
+            .collect();
+        queues.retain_mut(|queue| {
+            match queue.folder_id.as_deref().and_then(|id| weights.get(id)) {
+                Some(&weight) => {
+                    queue.weight = weight;
+                    true
+                }
+                None => false,
+            }
+        });
+    }
+
+    let mut session = interleave_folder_queues(queues);
+    if let Some(max_cards) = max_cards {
+        session.truncate(max_cards);
+    }
+    Ok(summarize(session))
+}
+
+fn folder_queues<'a>(
+    folders: &[FolderPolicy],
+    cards: &'a [CardState],
+    card_ids: Option<&[String]>,
+) -> Vec<FolderQueue<'a>> {
+    let now = now_iso();
+    let today = today_start_iso();
+    let candidates: Option<HashSet<&str>> =
+        card_ids.map(|ids| ids.iter().map(String::as_str).collect());
+    let due: Vec<&CardState> = cards
+        .iter()
+        .filter(|card| {
+            card.is_due(&now)
+                && match &candidates {
+                    Some(ids) => ids.contains(card.id.as_str()),
+                    None => true,
+                }
+        })
+        .collect();
+    daily_queues_by_folder(&due, folders, cards, &now, &today)
+}
+
+fn summarize(queue: Vec<&CardState>) -> DailyQueue {
+    let learning_count = queue.iter().filter(|card| card.is_learning()).count();
+    let new_count = queue
+        .iter()
+        .filter(|card| !card.is_learning() && card.is_new())
+        .count();
+    DailyQueue {
+        review_count: queue.len() - learning_count - new_count,
+        learning_count,
+        new_count,
+        card_ids: queue.into_iter().map(|card| card.id.clone()).collect(),
+    }
+}
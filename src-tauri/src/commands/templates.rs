@@ -0,0 +1,71 @@
+//! Parametric cards. Values are drawn locally from a seed, so a review can be
+//! reproduced from the seed stored with it.
+
+use serde::Serialize;
+
+use crate::math::template::{self, Instance};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TemplateValue {
+    pub name: String,
+    pub value: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TemplateInstance {
+    pub seed: u64,
+    pub question: String,
+    /// The computed answer, formatted for display.
+    pub answer: String,
+    pub values: Vec<TemplateValue>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TemplateCheck {
+    pub correct: bool,
+    pub expected: String,
+}
+
+impl From<Instance> for TemplateInstance {
+    fn from(instance: Instance) -> Self {
+        TemplateInstance {
+            seed: instance.seed,
+            question: instance.question,
+            answer: template::format_answer(instance.answer),
+            values: instance
+                .values
+                .into_iter()
+                .map(|(name, value)| TemplateValue { name, value })
+                .collect(),
+        }
+    }
+}
+
+/// Fills a template card's placeholders. Without a seed a fresh one is drawn.
+#[tauri::command]
+pub fn instantiate_template(
+    question_content: String,
+    answer_content: String,
+    template_params: String,
+    seed: Option<u64>,
+) -> Result<TemplateInstance, String> {
+    let params = template::parse_params(&template_params)?;
+    let seed = seed.unwrap_or_else(template::fresh_seed);
+    template::instantiate(&question_content, &answer_content, &params, seed).map(Into::into)
+}
+
+/// Grades a typed answer against the instance drawn from `seed`.
+#[tauri::command]
+pub fn check_template_answer(
+    answer_content: String,
+    template_params: String,
+    seed: u64,
+    typed_answer: String,
+) -> Result<TemplateCheck, String> {
+    let params = template::parse_params(&template_params)?;
+    let instance = template::instantiate("", &answer_content, &params, seed)?;
+    Ok(TemplateCheck {
+        correct: template::check_typed(&typed_answer, instance.answer)?,
+        expected: template::format_answer(instance.answer),
+    })
+}
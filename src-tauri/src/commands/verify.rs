@@ -0,0 +1,376 @@
+//! Answer verification. The model restates a card's answer as a claim the math
+//! engine can test (an identity, solutions of an equation, a derivative, ...),
+//! and the claim is then checked numerically at random points.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use super::database::get_pool;
+use super::llm::{call_llm_structured, command_name, load_llm_config, ocr_image, SolutionStep};
+use super::llm_cache::{self, CacheKey};
+use crate::math::check::{self, Outcome};
+use crate::math::parse::{math_segments, parse_list};
+use crate::math::rng::Rng;
+use crate::math::{parse_latex, parse_relation, Expr};
+
+const VERIFY_ANSWER_TEMPLATE: &str = "verify_answer/v1";
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AnswerVerification {
+    /// `verified`, `unverified` (nothing could be checked) or `disagreement`
+    /// (the check found the answer wrong).
+    pub status: String,
+    pub explanation: String,
+    /// The claim that was checked, in the engine's normalized notation.
+    pub checked_form: Option<String>,
+}
+
+impl AnswerVerification {
+    fn unverified(explanation: impl Into<String>) -> Self {
+        AnswerVerification {
+            status: "unverified".to_string(),
+            explanation: explanation.into(),
+            checked_form: None,
+        }
+    }
+}
+
+/// What the model says the answer claims, as LaTeX strings.
+#[derive(Debug, Clone, Deserialize)]
+struct Claim {
+    kind: String,
+    lhs: String,
+    rhs: String,
+    variable: String,
+    solutions: Vec<String>,
+    lower: String,
+    upper: String,
+    reason: String,
+}
+
+#[tauri::command]
+pub async fn verify_answer(
+    app: tauri::AppHandle,
+    question_content: String,
+    question_type: String,
+    answer_content: String,
+    regenerate: Option<bool>,
+) -> Result<AnswerVerification, String> {
+    let question = if question_type == "image" {
+        ocr_image(app.clone(), question_content, None).await?
+    } else {
+        question_content
+    };
+    let claim = extract_claim(
+        &app,
+        &question,
+        &answer_content,
+        regenerate.unwrap_or(false),
+    )
+    .await?;
+    Ok(check_claim(
+        &claim,
+        &mut Rng::from_str_seed(&answer_content),
+    ))
+}
+
+/// Verifies a saved card and stores the result on it.
+#[tauri::command]
+pub async fn verify_flashcard(
+    app: tauri::AppHandle,
+    flashcard_id: String,
+) -> Result<AnswerVerification, String> {
+    let pool = get_pool(&app).await?;
+    let (question_type, question_content, answer_type, answer_content): (
+        String,
+        String,
+        Option<String>,
+        Option<String>,
+    ) = sqlx::query_as(
+        "SELECT question_type, question_content, answer_type, answer_content FROM flashcards WHERE id = $1",
+    )
+    .bind(&flashcard_id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| format!("Failed to load flashcard: {}", e))?
+    .ok_or_else(|| format!("Flashcard not found: {}", flashcard_id))?;
+
+    let verification = match answer_content.filter(|a| !a.trim().is_empty()) {
+        None => AnswerVerification::unverified("The card has no answer to check"),
+        Some(_) if answer_type.as_deref() == Some("image") => {
+            AnswerVerification::unverified("Image answers cannot be checked")
+        }
+        Some(answer) if answer_type.as_deref() == Some("solution") => {
+            let answer = solution_text(&answer)?;
+            verify_answer(app.clone(), question_content, question_type, answer, None).await?
+        }
+        Some(answer) => {
+            verify_answer(app.clone(), question_content, question_type, answer, None).await?
+        }
+    };
+
+    sqlx::query(
+        "UPDATE flashcards SET verification_status = $1, verification_note = $2 WHERE id = $3",
+    )
+    .bind(&verification.status)
+    .bind(&verification.explanation)
+    .bind(&flashcard_id)
+    .execute(&pool)
+    .await
+    .map_err(|e| format!("Failed to save verification: {}", e))?;
+    Ok(verification)
+}
+
+async fn extract_claim(
+    app: &tauri::AppHandle,
+    question: &str,
+    answer: &str,
+    regenerate: bool,
+) -> Result<Claim, String> {
+    let config = load_llm_config(app)?;
+
+    // Give the model the parsed form of the answer's final expression so its
+    // restatement stays faithful to what was actually written.
+    let parsed = math_segments(answer)
+        .iter()
+        .rev()
+        .find_map(|segment| parse_latex(segment).ok());
+    let parsed_hint = parsed
+        .map(|expr| format!("\n\nThe answer's final expression parses as: {}", expr))
+        .unwrap_or_default();
+
+    let prompt = format!(
+        "Restate this flashcard's answer as a claim that can be checked by evaluating \
+         expressions numerically. Do NOT solve the problem yourself and do NOT correct \
+         the answer: take the problem side from the question and the result side \
+         verbatim from the answer.\n\n\
+         kinds:\n\
+         - identity: lhs (from the question) equals rhs (the answer) for all values, \
+         e.g. simplifications, expansions, or a computed value\n\
+         - solutions: the equation lhs = rhs from the question is solved for `variable`; \
+         put each solution from the answer in `solutions`\n\
+         - derivative: lhs is the function, rhs the claimed derivative with respect to `variable`\n\
+         - antiderivative: lhs is the integrand, rhs the claimed antiderivative \
+         (without + C) with respect to `variable`\n\
+         - definite_integral: lhs is the integrand, rhs the claimed value, `lower`/`upper` the bounds\n\
+         - not_checkable: proofs, definitions, limits, word answers, anything else\n\n\
+         Write every expression in plain LaTeX without dollar signs. Use empty strings \
+         and an empty list for fields that do not apply. Explain briefly in `reason`.\n\n\
+         Question: {}\n\nAnswer: {}{}",
+        question, answer, parsed_hint
+    );
+    let text = json!({ "type": "string" });
+    let schema = json!({
+        "type": "object",
+        "properties": {
+            "kind": {
+                "type": "string",
+                "enum": ["identity", "solutions", "derivative", "antiderivative", "definite_integral", "not_checkable"]
+            },
+            "lhs": text,
+            "rhs": text,
+            "variable": text,
+            "solutions": { "type": "array", "items": text },
+            "lower": text,
+            "upper": text,
+            "reason": text
+        },
+        "required": ["kind", "lhs", "rhs", "variable", "solutions", "lower", "upper", "reason"],
+        "additionalProperties": false
+    });
+
+    let key = CacheKey::new(
+        &config,
+        VERIFY_ANSWER_TEMPLATE,
+        &[question.as_bytes(), answer.as_bytes()],
+    );
+    if !regenerate {
+        let cached = llm_cache::get(app, &config, &key)
+            .await
+            .and_then(|c| serde_json::from_str::<Value>(&c).ok())
+            .and_then(|v| serde_json::from_value::<Claim>(v).ok());
+        if let Some(claim) = cached {
+            return Ok(claim);
+        }
+    }
+
+    let value = call_llm_structured(
+        app,
+        &config,
+        command_name(VERIFY_ANSWER_TEMPLATE),
+        &prompt,
+        "checkable_claim",
+        &schema,
+    )
+    .await?;
+    let claim: Claim = serde_json::from_value(value.clone())
+        .map_err(|e| format!("Model returned an invalid claim ({}): {}", e, value))?;
+    llm_cache::put(app, &config, &key, &value.to_string()).await;
+    Ok(claim)
+}
+
+/// Flattens a stored solution into answer text, one step per line, so the
+/// final step is the last math segment.
+pub fn solution_text(content: &str) -> Result<String, String> {
+    let steps: Vec<SolutionStep> = serde_json::from_str(content)
+        .map_err(|e| format!("Failed to parse solution steps: {}", e))?;
+    Ok(steps
+        .iter()
+        .map(|step| format!("{}: $${}$$", step.justification, step.latex))
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+fn check_claim(claim: &Claim, rng: &mut Rng) -> AnswerVerification {
+    match run_check(claim, rng) {
+        Ok((form, Outcome::Agree)) => AnswerVerification {
+            status: "verified".to_string(),
+            explanation: format!("Checked numerically: {}", claim.reason.trim()),
+            checked_form: Some(form),
+        },
+        Ok((form, Outcome::Disagree(detail))) => AnswerVerification {
+            status: "disagreement".to_string(),
+            explanation: detail,
+            checked_form: Some(form),
+        },
+        Ok((form, Outcome::Inconclusive(detail))) => AnswerVerification {
+            checked_form: Some(form),
+            ..AnswerVerification::unverified(detail)
+        },
+        Err(reason) => AnswerVerification::unverified(reason),
+    }
+}
+
+fn parse_side(latex: &str, side: &str) -> Result<Expr, String> {
+    parse_latex(latex).map_err(|e| format!("Could not read the {} `{}`: {}", side, latex, e))
+}
+
+fn run_check(claim: &Claim, rng: &mut Rng) -> Result<(String, Outcome), String> {
+    let var = if claim.variable.trim().is_empty() {
+        "x"
+    } else {
+        claim.variable.trim()
+    };
+    match claim.kind.as_str() {
+        "identity" => {
+            let lhs = parse_side(&claim.lhs, "problem")?;
+            let rhs = parse_side(&claim.rhs, "answer")?;
+            Ok((
+                format!("{} = {}", lhs, rhs),
+                check::identity(&lhs, &rhs, rng),
+            ))
+        }
+        "solutions" => {
+            let (lhs, rhs) = parse_relation(&format!("{} = {}", claim.lhs, claim.rhs))
+                .map_err(|e| format!("Could not read the equation: {}", e))?;
+            let solutions = claim
+                .solutions
+                .iter()
+                .map(|s| {
+                    parse_list(s).map_err(|e| format!("Could not read the solution `{}`: {}", s, e))
+                })
+                .collect::<Result<Vec<_>, _>>()?
+                .concat();
+            let listed = solutions
+                .iter()
+                .map(|s| s.to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            Ok((
+                format!("{} = {} at {} = {}", lhs, rhs, var, listed),
+                check::solutions(&lhs, &rhs, var, &solutions, rng),
+            ))
+        }
+        "derivative" => {
+            let f = parse_side(&claim.lhs, "function")?;
+            let df = parse_side(&claim.rhs, "answer")?;
+            Ok((
+                format!("d/d{} {} = {}", var, f, df),
+                check::derivative(&f, &df, var, rng),
+            ))
+        }
+        "antiderivative" => {
+            let f = parse_side(&claim.lhs, "integrand")?;
+            let antiderivative = parse_side(&claim.rhs, "answer")?;
+            Ok((
+                format!("∫ {} d{} = {} + C", f, var, antiderivative),
+                check::antiderivative(&f, &antiderivative, var, rng),
+            ))
+        }
+        "definite_integral" => {
+            let f = parse_side(&claim.lhs, "integrand")?;
+            let value = parse_side(&claim.rhs, "answer")?;
+            let lower = parse_side(&claim.lower, "lower bound")?;
+            let upper = parse_side(&claim.upper, "upper bound")?;
+            Ok((
+                format!("∫[{}, {}] {} d{} = {}", lower, upper, f, var, value),
+                check::definite_integral(&f, var, &lower, &upper, &value, rng),
+            ))
+        }
+        _ => Err(if claim.reason.trim().is_empty() {
+            "The answer cannot be checked automatically".to_string()
+        } else {
+            claim.reason.trim().to_string()
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn claim(kind: &str, lhs: &str, rhs: &str, solutions: &[&str]) -> Claim {
+        Claim {
+            kind: kind.to_string(),
+            lhs: lhs.to_string(),
+            rhs: rhs.to_string(),
+            variable: "x".to_string(),
+            solutions: solutions.iter().map(|s| s.to_string()).collect(),
+            lower: String::new(),
+            upper: String::new(),
+            reason: "test".to_string(),
+        }
+    }
+
+    fn status(claim: &Claim) -> String {
+        check_claim(claim, &mut Rng::new(1)).status
+    }
+
+    #[test]
+    fn flattens_solutions_with_the_final_step_last() {
+        let content = r#"[{"latex":"2x = 4","justification":"Subtract 3"},{"latex":"x = 2","justification":"Divide by 2"}]"#;
+        let text = solution_text(content).unwrap();
+        assert_eq!(math_segments(&text).last(), Some(&"x = 2"));
+    }
+
+    #[test]
+    fn maps_outcomes_to_statuses() {
+        assert_eq!(
+            status(&claim("identity", "(x+1)^2", "x^2 + 2x + 1", &[])),
+            "verified"
+        );
+        assert_eq!(
+            status(&claim("identity", "(x+1)^2", "x^2 + 1", &[])),
+            "disagreement"
+        );
+        assert_eq!(
+            status(&claim("solutions", "x^2 - 4", "0", &["x = 2", "x = -2"])),
+            "verified"
+        );
+        assert_eq!(
+            status(&claim("derivative", r"\sin x", r"-\cos x", &[])),
+            "disagreement"
+        );
+    }
+
+    #[test]
+    fn unreadable_or_uncheckable_claims_stay_unverified() {
+        let result = check_claim(
+            &claim("identity", r"\lim_{x \to 0} x", "0", &[]),
+            &mut Rng::new(1),
+        );
+        assert_eq!(result.status, "unverified");
+        assert!(result.explanation.starts_with("Could not read"));
+        assert_eq!(status(&claim("not_checkable", "", "", &[])), "unverified");
+    }
+}
@@ -1,7 +1,9 @@
 mod commands;
+mod math;
+mod review_policy;
 mod srs;
 
-use commands::database::get_migrations;
+use commands::database::{get_migrations, DB_URL};
 use tauri::Emitter;
 use tauri_plugin_global_shortcut::{Code, GlobalShortcutExt, Modifiers, ShortcutState};
 
@@ -10,9 +12,11 @@ pub fn run() {
     tauri::Builder::default()
         .plugin(
             tauri_plugin_sql::Builder::default()
-                .add_migrations("sqlite:flashmath.db", get_migrations())
+                .add_migrations(DB_URL, get_migrations())
                 .build(),
         )
+        .manage(commands::jobs::JobRegistry::default())
+        .manage(commands::ocr_index::OcrIndexer::default())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
         .plugin(
@@ -65,14 +69,61 @@ pub fn run() {
             commands::llm::generate_image_title,
             commands::llm::generate_latex_title,
             commands::llm::generate_answer,
+            commands::llm::generate_solution,
+            commands::llm::generate_hints,
+            commands::llm::generate_variants,
             commands::llm::generate_question,
             commands::llm::convert_image_to_text,
             commands::llm::get_llm_config,
             commands::llm::set_llm_config,
             commands::llm::test_llm_connection,
-            commands::llm::chat_completion,
+            commands::chat::chat_completion,
+            commands::verify::verify_answer,
+            commands::verify::verify_flashcard,
+            commands::templates::instantiate_template,
+            commands::templates::check_template_answer,
+            commands::grading::grade_typed_answer,
+            commands::grading::grade_handwritten_work,
+            commands::embeddings::get_embedding_config,
+            commands::embeddings::set_embedding_config,
+            commands::embeddings::embed_cards,
+            commands::embeddings::find_duplicate_cards,
+            commands::embeddings::merge_duplicate_cards,
+            commands::search::search_cards,
+            commands::ocr_index::get_ocr_index_status,
+            commands::ocr_index::start_ocr_indexer,
+            commands::ocr_index::index_card_ocr,
+            commands::leeches::get_leeches,
+            commands::leeches::suggest_prerequisite_cards,
+            commands::card_state::set_cards_suspended,
+            commands::card_state::bury_cards,
+            commands::card_state::unbury_cards,
+            commands::card_state::reschedule_cards,
+            commands::card_state::reset_cards,
+            commands::card_state::forget_cards,
+            commands::study_queue::build_daily_queue,
+            commands::study_queue::build_session_queue,
+            commands::sessions::get_study_sessions,
+            commands::sessions::get_session_analytics,
+            commands::exam_plan::plan_exam,
+            commands::exam_plan::get_exam_plan,
+            commands::exam_plan::replan_exams,
+            commands::llm_cache::clear_llm_cache,
+            commands::llm_cache::get_llm_cache_stats,
+            commands::llm_usage::get_llm_usage_summary,
             commands::files::get_image_as_data_url,
             commands::files::copy_image_to_app_data,
+            commands::local_ocr::get_ocr_config,
+            commands::local_ocr::set_ocr_config,
+            commands::local_ocr::test_local_ocr,
+            commands::local_ocr::local_ocr_available,
+            commands::jobs::create_generation_job,
+            commands::jobs::list_generation_jobs,
+            commands::jobs::get_generation_job_items,
+            commands::jobs::pause_generation_job,
+            commands::jobs::resume_generation_job,
+            commands::jobs::cancel_generation_job,
+            commands::jobs::accept_generation_job_items,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,401 @@
+//! Numeric checks of symbolic claims by evaluating both sides at random points.
+
+use super::rng::Rng;
+use super::{Env, Expr};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    Agree,
+    /// The claim is false; the message names a point where it fails.
+    Disagree(String),
+    /// Too few points where both sides are defined to decide.
+    Inconclusive(String),
+}
+
+const SAMPLES: usize = 12;
+const MAX_ATTEMPTS: usize = SAMPLES * 8;
+const TOLERANCE: f64 = 1e-6;
+/// Looser tolerance for checks that go through numeric differentiation or
+/// integration.
+const NUMERIC_TOLERANCE: f64 = 1e-4;
+
+pub fn approx_eq(a: f64, b: f64, tolerance: f64) -> bool {
+    (a - b).abs() <= tolerance * 1f64.max(a.abs()).max(b.abs())
+}
+
+fn union(a: &Expr, b: &Expr) -> Vec<String> {
+    let mut vars = a.variables();
+    vars.extend(b.variables());
+    vars.sort();
+    vars.dedup();
+    vars
+}
+
+fn describe(env: &Env) -> String {
+    let mut pairs: Vec<_> = env.iter().collect();
+    pairs.sort_by(|a, b| a.0.cmp(b.0));
+    pairs
+        .iter()
+        .map(|(name, value)| format!("{} = {:.4}", name, value))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Compares `f` and `g` at random values of `vars`, skipping points where
+/// either side is undefined.
+fn compare(
+    vars: &[String],
+    rng: &mut Rng,
+    tolerance: f64,
+    f: impl Fn(&Env) -> f64,
+    g: impl Fn(&Env) -> f64,
+) -> Outcome {
+    let samples = if vars.is_empty() { 1 } else { SAMPLES };
+    let mut valid = 0;
+    for _ in 0..MAX_ATTEMPTS {
+        let env: Env = vars
+            .iter()
+            .map(|var| (var.clone(), rng.range_f64(-3.0, 3.0)))
+            .collect();
+        let (a, b) = (f(&env), g(&env));
+        if !a.is_finite() || !b.is_finite() {
+            continue;
+        }
+        if !approx_eq(a, b, tolerance) {
+            let at = if env.is_empty() {
+                String::new()
+            } else {
+                format!("At {}: ", describe(&env))
+            };
+            return Outcome::Disagree(format!("{}left side = {:.6}, right side = {:.6}", at, a, b));
+        }
+        valid += 1;
+        if valid >= samples {
+            return Outcome::Agree;
+        }
+    }
+    if valid >= 3 {
+        Outcome::Agree
+    } else {
+        Outcome::Inconclusive("Both sides are undefined at almost every sampled point".to_string())
+    }
+}
+
+/// `lhs = rhs` for every value of the free variables.
+pub fn identity(lhs: &Expr, rhs: &Expr, rng: &mut Rng) -> Outcome {
+    compare(
+        &union(lhs, rhs),
+        rng,
+        TOLERANCE,
+        |env| lhs.eval(env),
+        |env| rhs.eval(env),
+    )
+}
+
+/// Each solution, substituted for `var`, satisfies `lhs = rhs`, and no real
+/// root in `[-SCAN_RANGE, SCAN_RANGE]` is missing from the list. Equations
+/// with other free variables are only checked for the listed roots.
+pub fn solutions(lhs: &Expr, rhs: &Expr, var: &str, solutions: &[Expr], rng: &mut Rng) -> Outcome {
+    if solutions.is_empty() {
+        return Outcome::Inconclusive("No solutions to check".to_string());
+    }
+    for solution in solutions {
+        let mut params = union(lhs, rhs);
+        params.extend(solution.variables());
+        params.retain(|v| v != var);
+        params.sort();
+        params.dedup();
+        let substitute = |env: &Env| {
+            let mut env = env.clone();
+            env.insert(var.to_string(), solution.eval(&env));
+            env
+        };
+        match compare(
+            &params,
+            rng,
+            TOLERANCE,
+            |env| lhs.eval(&substitute(env)),
+            |env| rhs.eval(&substitute(env)),
+        ) {
+            Outcome::Agree => {}
+            Outcome::Disagree(detail) => {
+                return Outcome::Disagree(format!(
+                    "{} = {} does not satisfy the equation. {}",
+                    var, solution, detail
+                ))
+            }
+            inconclusive => return inconclusive,
+        }
+    }
+
+    let mut params = union(lhs, rhs);
+    params.retain(|v| v != var);
+    let listed: Vec<f64> = solutions
+        .iter()
+        .map(|solution| solution.eval(&Env::new()))
+        .collect();
+    if !params.is_empty() || listed.iter().any(|root| !root.is_finite()) {
+        return Outcome::Inconclusive(
+            "Only checked that the listed roots satisfy the equation".to_string(),
+        );
+    }
+    let residual = |x: f64| {
+        let env: Env = [(var.to_string(), x)].into_iter().collect();
+        lhs.eval(&env) - rhs.eval(&env)
+    };
+    match unlisted_root(residual, &listed) {
+        Scan::Complete => Outcome::Agree,
+        Scan::Missing(root) => Outcome::Disagree(format!(
+            "{} = {:.4} also satisfies the equation but is not listed",
+            var, root
+        )),
+        Scan::Degenerate => Outcome::Inconclusive(
+            "The equation holds for almost every value, so its roots can't be listed".to_string(),
+        ),
+    }
+}
+
+/// Half-width of the interval searched for roots the answer left out.
+const SCAN_RANGE: f64 = 100.0;
+const SCAN_STEPS: usize = 40_000;
+
+enum Scan {
+    Complete,
+    Missing(f64),
+    Degenerate,
+}
+
+/// Looks for a root of `f` that is not in `listed`, from sign changes and
+/// from local minima of `|f|` that touch zero (double roots).
+fn unlisted_root(f: impl Fn(f64) -> f64, listed: &[f64]) -> Scan {
+    let step = 2.0 * SCAN_RANGE / SCAN_STEPS as f64;
+    let xs: Vec<f64> = (0..=SCAN_STEPS).map(|i| -SCAN_RANGE + i as f64 * step).collect();
+    let ys: Vec<f64> = xs.iter().map(|&x| f(x)).collect();
+    if ys.iter().filter(|y| y.abs() <= TOLERANCE).count() * 2 > ys.len() {
+        return Scan::Degenerate;
+    }
+    let unlisted = |x: f64| !listed.iter().any(|&root| approx_eq(root, x, NUMERIC_TOLERANCE));
+
+    for i in 0..SCAN_STEPS {
+        let (a, b) = (ys[i], ys[i + 1]);
+        if !a.is_finite() || !b.is_finite() {
+            continue;
+        }
+        let root = if a == 0.0 {
+            Some(xs[i])
+        } else if a.signum() != b.signum() && b != 0.0 {
+            // A sign change across a pole blows up instead of shrinking
+            let x = bisect(&f, xs[i], xs[i + 1]);
+            (f(x).abs() <= a.abs() + b.abs()).then_some(x)
+        } else if i > 0 && ys[i - 1].is_finite() && a.abs() < ys[i - 1].abs() && a.abs() <= b.abs()
+        {
+            let x = minimize_abs(&f, xs[i - 1], xs[i + 1]);
+            (f(x).abs() <= TOLERANCE).then_some(x)
+        } else {
+            None
+        };
+        if let Some(x) = root.filter(|&x| unlisted(x)) {
+            return Scan::Missing(x);
+        }
+    }
+    Scan::Complete
+}
+
+fn bisect(f: &impl Fn(f64) -> f64, mut low: f64, mut high: f64) -> f64 {
+    let low_sign = f(low).signum();
+    for _ in 0..80 {
+        let mid = 0.5 * (low + high);
+        if f(mid).signum() == low_sign {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    0.5 * (low + high)
+}
+
+/// Golden-section search for the minimum of `|f|` on `[low, high]`.
+fn minimize_abs(f: &impl Fn(f64) -> f64, mut low: f64, mut high: f64) -> f64 {
+    let ratio = (5f64.sqrt() - 1.0) / 2.0;
+    for _ in 0..80 {
+        let left = high - ratio * (high - low);
+        let right = low + ratio * (high - low);
+        if f(left).abs() < f(right).abs() {
+            high = right;
+        } else {
+            low = left;
+        }
+    }
+    0.5 * (low + high)
+}
+
+fn numeric_derivative(f: &Expr, var: &str, env: &Env) -> f64 {
+    let x = env.get(var).copied().unwrap_or(0.0);
+    let h = 1e-5 * 1f64.max(x.abs());
+    let mut env = env.clone();
+    env.insert(var.to_string(), x + h);
+    let ahead = f.eval(&env);
+    env.insert(var.to_string(), x - h);
+    let behind = f.eval(&env);
+    (ahead - behind) / (2.0 * h)
+}
+
+/// `claimed` is the derivative of `function` with respect to `var`.
+pub fn derivative(function: &Expr, claimed: &Expr, var: &str, rng: &mut Rng) -> Outcome {
+    let mut vars = union(function, claimed);
+    vars.push(var.to_string());
+    vars.sort();
+    vars.dedup();
+    compare(
+        &vars,
+        rng,
+        NUMERIC_TOLERANCE,
+        |env| numeric_derivative(function, var, env),
+        |env| claimed.eval(env),
+    )
+}
+
+/// `claimed` is an antiderivative of `integrand` (up to a constant).
+pub fn antiderivative(integrand: &Expr, claimed: &Expr, var: &str, rng: &mut Rng) -> Outcome {
+    derivative(claimed, integrand, var, rng)
+}
+
+/// Composite Simpson's rule.
+fn integrate(integrand: &Expr, var: &str, lower: f64, upper: f64, env: &Env) -> f64 {
+    const STEPS: usize = 2000;
+    let h = (upper - lower) / STEPS as f64;
+    let mut env = env.clone();
+    let mut at = |x: f64| {
+        env.insert(var.to_string(), x);
+        integrand.eval(&env)
+    };
+    let mut sum = at(lower) + at(upper);
+    for i in 1..STEPS {
+        let weight = if i % 2 == 1 { 4.0 } else { 2.0 };
+        sum += weight * at(lower + i as f64 * h);
+    }
+    sum * h / 3.0
+}
+
+/// The integral of `integrand` from `lower` to `upper` equals `claimed`.
+pub fn definite_integral(
+    integrand: &Expr,
+    var: &str,
+    lower: &Expr,
+    upper: &Expr,
+    claimed: &Expr,
+    rng: &mut Rng,
+) -> Outcome {
+    let mut params = union(integrand, claimed);
+    params.extend(lower.variables());
+    params.extend(upper.variables());
+    params.retain(|v| v != var);
+    params.sort();
+    params.dedup();
+    compare(
+        &params,
+        rng,
+        NUMERIC_TOLERANCE,
+        |env| integrate(integrand, var, lower.eval(env), upper.eval(env), env),
+        |env| claimed.eval(env),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::parse::{parse_latex, parse_list, parse_relation};
+
+    fn rng() -> Rng {
+        Rng::new(7)
+    }
+
+    #[test]
+    fn accepts_true_identities() {
+        let lhs = parse_latex(r"\sin^2 x + \cos^2 x").unwrap();
+        let rhs = parse_latex("1").unwrap();
+        assert_eq!(identity(&lhs, &rhs, &mut rng()), Outcome::Agree);
+
+        let lhs = parse_latex(r"\frac{x^2 - 1}{x - 1}").unwrap();
+        let rhs = parse_latex("x + 1").unwrap();
+        assert_eq!(identity(&lhs, &rhs, &mut rng()), Outcome::Agree);
+    }
+
+    #[test]
+    fn rejects_false_identities() {
+        let lhs = parse_latex("(x + 1)^2").unwrap();
+        let rhs = parse_latex("x^2 + 1").unwrap();
+        assert!(matches!(
+            identity(&lhs, &rhs, &mut rng()),
+            Outcome::Disagree(_)
+        ));
+    }
+
+    #[test]
+    fn substitutes_solutions() {
+        let (lhs, rhs) = parse_relation("x^2 - 5x + 6 = 0").unwrap();
+        let good = parse_list("x = 2, x = 3").unwrap();
+        assert_eq!(
+            solutions(&lhs, &rhs, "x", &good, &mut rng()),
+            Outcome::Agree
+        );
+        let bad = parse_list("2, -3").unwrap();
+        assert!(matches!(
+            solutions(&lhs, &rhs, "x", &bad, &mut rng()),
+            Outcome::Disagree(_)
+        ));
+    }
+
+    #[test]
+    fn rejects_missing_solutions() {
+        let (lhs, rhs) = parse_relation("x^2 - 5x + 6 = 0").unwrap();
+        let partial = parse_list("x = 2").unwrap();
+        assert!(matches!(
+            solutions(&lhs, &rhs, "x", &partial, &mut rng()),
+            Outcome::Disagree(detail) if detail.contains("x = 3.0000")
+        ));
+
+        // A double root has no sign change but still counts
+        let (lhs, rhs) = parse_relation("(x - 1)^2 (x - 2) = 0").unwrap();
+        let partial = parse_list("x = 2").unwrap();
+        assert!(matches!(
+            solutions(&lhs, &rhs, "x", &partial, &mut rng()),
+            Outcome::Disagree(_)
+        ));
+        let full = parse_list("x = 1, x = 2").unwrap();
+        assert_eq!(
+            solutions(&lhs, &rhs, "x", &full, &mut rng()),
+            Outcome::Agree
+        );
+
+        // Poles are not roots
+        let (lhs, rhs) = parse_relation(r"\frac{1}{x} + 1 = 0").unwrap();
+        let only = parse_list("x = -1").unwrap();
+        assert_eq!(
+            solutions(&lhs, &rhs, "x", &only, &mut rng()),
+            Outcome::Agree
+        );
+    }
+
+    #[test]
+    fn checks_derivatives_and_integrals() {
+        let f = parse_latex(r"x^3 \ln x").unwrap();
+        let df = parse_latex(r"3x^2 \ln x + x^2").unwrap();
+        assert_eq!(derivative(&f, &df, "x", &mut rng()), Outcome::Agree);
+
+        let integrand = parse_latex("2x").unwrap();
+        let claimed = parse_latex("x^2 + 5").unwrap();
+        assert_eq!(
+            antiderivative(&integrand, &claimed, "x", &mut rng()),
+            Outcome::Agree
+        );
+
+        let value = parse_latex(r"\frac{1}{3}").unwrap();
+        let integrand = parse_latex("x^2").unwrap();
+        let (lower, upper) = (parse_latex("0").unwrap(), parse_latex("1").unwrap());
+        assert_eq!(
+            definite_integral(&integrand, "x", &lower, &upper, &value, &mut rng()),
+            Outcome::Agree
+        );
+    }
+}
@@ -0,0 +1,189 @@
+//! Grading a typed answer against a card's expected answer. Both are parsed
+//! and compared numerically, so `1/2`, `0.5` and `\frac{2}{4}` are equal and
+//! `2(x+1)` matches `2x + 2`.
+
+use serde::Serialize;
+
+use super::check::{self, Outcome};
+use super::parse::{math_segments, parse_list};
+use super::rng::Rng;
+use super::{Expr, Func};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Grade {
+    pub correct: bool,
+    /// `numeric`, `symbolic`, or `text` when the expected answer isn't math
+    /// the engine can read and the answers are compared as normalized text.
+    pub method: String,
+    pub detail: Option<String>,
+}
+
+impl Grade {
+    fn new(correct: bool, method: &str, detail: Option<String>) -> Self {
+        Grade {
+            correct,
+            method: method.to_string(),
+            detail,
+        }
+    }
+}
+
+/// The final math in an answer: every `$...$` segment on the line of its
+/// last one, joined as a list (so `$x = 2$ or $x = -3$` reads as two values),
+/// or the whole text when it has none.
+fn final_math(text: &str) -> String {
+    let segments = math_segments(text);
+    let offset = |segment: &str| segment.as_ptr() as usize - text.as_ptr() as usize;
+    let Some(last) = segments.last() else {
+        return text.trim().to_string();
+    };
+    let line_start = text[..offset(last)].rfind('\n').map_or(0, |i| i + 1);
+    segments
+        .iter()
+        .filter(|segment| offset(segment) >= line_start)
+        .copied()
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+pub fn grade(expected: &str, typed: &str) -> Grade {
+    let typed = final_math(typed);
+    let typed = typed.as_str();
+    if typed.is_empty() {
+        return Grade::new(false, "text", Some("No answer given".to_string()));
+    }
+    let expected_math = final_math(expected);
+    let expected_math = expected_math.as_str();
+    if is_prose(expected_math) {
+        return grade_text(expected, typed);
+    }
+    let Ok(expected_items) = parse_list(expected_math) else {
+        return grade_text(expected, typed);
+    };
+    let method = if expected_items.iter().all(is_constant) {
+        "numeric"
+    } else {
+        "symbolic"
+    };
+    let typed_items = match parse_list(typed) {
+        Ok(items) => items,
+        Err(e) => {
+            return Grade::new(
+                false,
+                method,
+                Some(format!("Couldn't read the answer: {}", e)),
+            )
+        }
+    };
+    if typed_items.len() != expected_items.len() {
+        return Grade::new(
+            false,
+            method,
+            Some(format!(
+                "Expected {} value(s), got {}",
+                expected_items.len(),
+                typed_items.len()
+            )),
+        );
+    }
+
+    // Lists match in any order.
+    let mut rng = Rng::from_str_seed(expected_math);
+    let mut unmatched = typed_items;
+    for item in &expected_items {
+        let found = unmatched
+            .iter()
+            .position(|candidate| check::identity(item, candidate, &mut rng) == Outcome::Agree);
+        match found {
+            Some(index) => {
+                unmatched.remove(index);
+            }
+            None => {
+                return Grade::new(false, method, Some(format!("Expected {}", expected_math)));
+            }
+        }
+    }
+    Grade::new(true, method, None)
+}
+
+/// The parser reads `abc` as a product of variables, so a word that isn't a
+/// function name means the answer is prose rather than an expression.
+fn is_prose(text: &str) -> bool {
+    let mut rest = text;
+    while let Some(start) = rest.find(|c: char| c.is_ascii_alphabetic()) {
+        let escaped = rest[..start].ends_with('\\');
+        let word_len = rest[start..]
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(rest.len() - start);
+        let word = &rest[start..start + word_len];
+        if !escaped && word.len() >= 4 && Func::from_name(word).is_none() {
+            return true;
+        }
+        rest = &rest[start + word_len..];
+    }
+    false
+}
+
+fn is_constant(expr: &Expr) -> bool {
+    expr.variables().iter().all(|v| v == "pi" || v == "e")
+}
+
+fn grade_text(expected: &str, typed: &str) -> Grade {
+    let normalize = |text: &str| {
+        text.chars()
+            .filter(|c| !c.is_whitespace() && *c != '$')
+            .flat_map(char::to_lowercase)
+            .collect::<String>()
+    };
+    let correct = normalize(expected) == normalize(typed);
+    Grade::new(
+        correct,
+        "text",
+        (!correct).then(|| format!("Expected {}", expected.trim())),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn correct(expected: &str, typed: &str) -> bool {
+        grade(expected, typed).correct
+    }
+
+    #[test]
+    fn equal_numbers_in_any_notation() {
+        for typed in ["1/2", "0.5", r"\frac{2}{4}", "$\\frac{1}{2}$", "2^{-1}"] {
+            assert!(correct(r"$\frac{1}{2}$", typed), "{}", typed);
+        }
+        assert!(!correct(r"$\frac{1}{2}$", "0.51"));
+        assert!(correct(r"$\sqrt{2}$", "sqrt(2)"));
+        assert_eq!(grade("$3$", "3").method, "numeric");
+    }
+
+    #[test]
+    fn symbolic_answers_and_lists() {
+        assert!(correct("$y = 2x + 2$", "2(x+1)"));
+        assert!(!correct("$2x + 2$", "2x + 1"));
+        assert_eq!(grade("$2x + 2$", "2x+2").method, "symbolic");
+        assert!(correct("The roots are $x = 2, x = -3$", "-3, 2"));
+        assert!(!correct("$x = 2, x = -3$", "2"));
+    }
+
+    #[test]
+    fn collects_every_segment_on_the_final_line() {
+        let expected = "Factor to $(x - 2)(x + 3) = 0$.\nSo $x = 2$ or $x = -3$";
+        assert!(correct(expected, "2, -3"));
+        assert!(correct(expected, "$x = -3$ or $x = 2$"));
+        assert!(!correct(expected, "2"));
+    }
+
+    #[test]
+    fn falls_back_to_text() {
+        let grade_result = grade("Converges absolutely", " converges  Absolutely");
+        assert!(grade_result.correct);
+        assert_eq!(grade_result.method, "text");
+        assert!(!correct("Diverges", "converges"));
+        assert!(!correct("", ""));
+    }
+}
@@ -0,0 +1,229 @@
+//! A small expression engine for checking math answers: a LaTeX parser, a
+//! numeric evaluator and random-point comparison helpers.
+
+pub mod check;
+pub mod grade;
+pub mod parse;
+pub mod rng;
+pub mod template;
+
+use std::collections::HashMap;
+use std::fmt;
+
+pub use parse::{parse_latex, parse_relation};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Func {
+    Sin,
+    Cos,
+    Tan,
+    Sec,
+    Csc,
+    Cot,
+    Asin,
+    Acos,
+    Atan,
+    Sinh,
+    Cosh,
+    Tanh,
+    Ln,
+    Log10,
+    Exp,
+    Sqrt,
+    Abs,
+    Floor,
+    Ceil,
+}
+
+impl Func {
+    pub fn from_name(name: &str) -> Option<Func> {
+        Some(match name {
+            "sin" => Func::Sin,
+            "cos" => Func::Cos,
+            "tan" => Func::Tan,
+            "sec" => Func::Sec,
+            "csc" => Func::Csc,
+            "cot" => Func::Cot,
+            "arcsin" | "asin" => Func::Asin,
+            "arccos" | "acos" => Func::Acos,
+            "arctan" | "atan" => Func::Atan,
+            "sinh" => Func::Sinh,
+            "cosh" => Func::Cosh,
+            "tanh" => Func::Tanh,
+            "ln" => Func::Ln,
+            "log" => Func::Log10,
+            "exp" => Func::Exp,
+            "sqrt" => Func::Sqrt,
+            "abs" => Func::Abs,
+            "floor" => Func::Floor,
+            "ceil" => Func::Ceil,
+            _ => return None,
+        })
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Func::Sin => "sin",
+            Func::Cos => "cos",
+            Func::Tan => "tan",
+            Func::Sec => "sec",
+            Func::Csc => "csc",
+            Func::Cot => "cot",
+            Func::Asin => "arcsin",
+            Func::Acos => "arccos",
+            Func::Atan => "arctan",
+            Func::Sinh => "sinh",
+            Func::Cosh => "cosh",
+            Func::Tanh => "tanh",
+            Func::Ln => "ln",
+            Func::Log10 => "log",
+            Func::Exp => "exp",
+            Func::Sqrt => "sqrt",
+            Func::Abs => "abs",
+            Func::Floor => "floor",
+            Func::Ceil => "ceil",
+        }
+    }
+
+    /// The inverse function, for `\sin^{-1}`-style notation.
+    pub fn inverse(self) -> Option<Func> {
+        match self {
+            Func::Sin => Some(Func::Asin),
+            Func::Cos => Some(Func::Acos),
+            Func::Tan => Some(Func::Atan),
+            _ => None,
+        }
+    }
+
+    fn apply(self, x: f64) -> f64 {
+        match self {
+            Func::Sin => x.sin(),
+            Func::Cos => x.cos(),
+            Func::Tan => x.tan(),
+            Func::Sec => 1.0 / x.cos(),
+            Func::Csc => 1.0 / x.sin(),
+            Func::Cot => 1.0 / x.tan(),
+            Func::Asin => x.asin(),
+            Func::Acos => x.acos(),
+            Func::Atan => x.atan(),
+            Func::Sinh => x.sinh(),
+            Func::Cosh => x.cosh(),
+            Func::Tanh => x.tanh(),
+            Func::Ln => x.ln(),
+            Func::Log10 => x.log10(),
+            Func::Exp => x.exp(),
+            Func::Sqrt => x.sqrt(),
+            Func::Abs => x.abs(),
+            Func::Floor => x.floor(),
+            Func::Ceil => x.ceil(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Num(f64),
+    Var(String),
+    Neg(Box<Expr>),
+    Add(Box<Expr>, Box<Expr>),
+    Sub(Box<Expr>, Box<Expr>),
+    Mul(Box<Expr>, Box<Expr>),
+    Div(Box<Expr>, Box<Expr>),
+    Pow(Box<Expr>, Box<Expr>),
+    Call(Func, Box<Expr>),
+    /// Logarithm with an explicit base: `Log(base, x)`.
+    Log(Box<Expr>, Box<Expr>),
+    Factorial(Box<Expr>),
+}
+
+pub type Env = HashMap<String, f64>;
+
+impl Expr {
+    /// Evaluates with `pi` and `e` predefined (unless `env` overrides them).
+    /// Undefined variables evaluate to NaN.
+    pub fn eval(&self, env: &Env) -> f64 {
+        match self {
+            Expr::Num(n) => *n,
+            Expr::Var(name) => env.get(name).copied().unwrap_or(match name.as_str() {
+                "pi" => std::f64::consts::PI,
+                "e" => std::f64::consts::E,
+                _ => f64::NAN,
+            }),
+            Expr::Neg(a) => -a.eval(env),
+            Expr::Add(a, b) => a.eval(env) + b.eval(env),
+            Expr::Sub(a, b) => a.eval(env) - b.eval(env),
+            Expr::Mul(a, b) => a.eval(env) * b.eval(env),
+            Expr::Div(a, b) => a.eval(env) / b.eval(env),
+            Expr::Pow(a, b) => pow(a.eval(env), b.eval(env)),
+            Expr::Call(f, a) => f.apply(a.eval(env)),
+            Expr::Log(base, a) => a.eval(env).ln() / base.eval(env).ln(),
+            Expr::Factorial(a) => factorial(a.eval(env)),
+        }
+    }
+
+    /// Free variables, excluding the constants `pi` and `e`.
+    pub fn variables(&self) -> Vec<String> {
+        let mut vars = Vec::new();
+        self.collect_variables(&mut vars);
+        vars.sort();
+        vars.dedup();
+        vars
+    }
+
+    fn collect_variables(&self, vars: &mut Vec<String>) {
+        match self {
+            Expr::Num(_) => {}
+            Expr::Var(name) => {
+                if name != "pi" && name != "e" {
+                    vars.push(name.clone());
+                }
+            }
+            Expr::Neg(a) | Expr::Call(_, a) | Expr::Factorial(a) => a.collect_variables(vars),
+            Expr::Add(a, b)
+            | Expr::Sub(a, b)
+            | Expr::Mul(a, b)
+            | Expr::Div(a, b)
+            | Expr::Pow(a, b)
+            | Expr::Log(a, b) => {
+                a.collect_variables(vars);
+                b.collect_variables(vars);
+            }
+        }
+    }
+}
+
+/// Real power, taking odd roots of negative numbers (so `(-8)^(1/3) = -2`).
+fn pow(base: f64, exponent: f64) -> f64 {
+    if base < 0.0 && exponent.fract() != 0.0 {
+        let inverse = 1.0 / exponent;
+        if (inverse.round() - inverse).abs() < 1e-9 && inverse.round() as i64 % 2 != 0 {
+            return -(-base).powf(exponent);
+        }
+    }
+    base.powf(exponent)
+}
+
+fn factorial(n: f64) -> f64 {
+    if n < 0.0 || n.fract() != 0.0 || n > 170.0 {
+        return f64::NAN;
+    }
+    (1..=n as u64).fold(1.0, |acc, k| acc * k as f64)
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Num(n) => write!(f, "{}", n),
+            Expr::Var(name) => write!(f, "{}", name),
+            Expr::Neg(a) => write!(f, "-({})", a),
+            Expr::Add(a, b) => write!(f, "({} + {})", a, b),
+            Expr::Sub(a, b) => write!(f, "({} - {})", a, b),
+            Expr::Mul(a, b) => write!(f, "({} * {})", a, b),
+            Expr::Div(a, b) => write!(f, "({} / {})", a, b),
+            Expr::Pow(a, b) => write!(f, "({})^({})", a, b),
+            Expr::Call(func, a) => write!(f, "{}({})", func.name(), a),
+            Expr::Log(base, a) => write!(f, "log_({})({})", base, a),
+            Expr::Factorial(a) => write!(f, "({})!", a),
+        }
+    }
+}
@@ -0,0 +1,563 @@
+//! Parses the LaTeX subset used for answers (fractions, roots, powers,
+//! functions, implicit multiplication) and plain typed math like `2x^2 + sin(x)`.
+
+use super::{Expr, Func};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Tok {
+    Num(f64),
+    Ident(String),
+    Func(Func),
+    Frac,
+    Sqrt,
+    Op(char),
+    Open(char),
+    Close(char),
+}
+
+/// Plain-text function names, longest first so `sinh` wins over `sin`.
+const WORD_FUNCTIONS: &[&str] = &[
+    "arcsin", "arccos", "arctan", "sinh", "cosh", "tanh", "sqrt", "asin", "acos", "atan", "sin",
+    "cos", "tan", "sec", "csc", "cot", "exp", "log", "ln", "abs",
+];
+
+const GREEK: &[&str] = &[
+    "alpha",
+    "beta",
+    "gamma",
+    "delta",
+    "epsilon",
+    "varepsilon",
+    "zeta",
+    "eta",
+    "theta",
+    "vartheta",
+    "iota",
+    "kappa",
+    "lambda",
+    "mu",
+    "nu",
+    "xi",
+    "rho",
+    "sigma",
+    "tau",
+    "upsilon",
+    "phi",
+    "varphi",
+    "chi",
+    "psi",
+    "omega",
+    "Gamma",
+    "Delta",
+    "Theta",
+    "Lambda",
+    "Xi",
+    "Sigma",
+    "Phi",
+    "Psi",
+    "Omega",
+];
+
+/// Commands that only affect layout.
+const IGNORED: &[&str] = &[
+    "left",
+    "right",
+    "displaystyle",
+    "textstyle",
+    "quad",
+    "qquad",
+    "big",
+    "Big",
+    "bigg",
+    "Bigg",
+    "bigl",
+    "bigr",
+    "Bigl",
+    "Bigr",
+];
+
+/// Parses a single expression.
+pub fn parse_latex(input: &str) -> Result<Expr, String> {
+    let mut parser = Parser::new(input)?;
+    let expr = parser.expr()?;
+    parser.finish()?;
+    Ok(expr)
+}
+
+/// Parses `lhs = rhs`.
+pub fn parse_relation(input: &str) -> Result<(Expr, Expr), String> {
+    let mut parser = Parser::new(input)?;
+    let lhs = parser.expr()?;
+    if !parser.eat(&Tok::Op('=')) {
+        return Err(format!("Expected an equation with '=': {}", input));
+    }
+    let rhs = parser.expr()?;
+    parser.finish()?;
+    Ok((lhs, rhs))
+}
+
+/// Parses a comma-separated list such as `x = 2, x = -3` or `2, -3`. For
+/// items written as `var = value`, the value is returned.
+pub fn parse_list(input: &str) -> Result<Vec<Expr>, String> {
+    let mut parser = Parser::new(input)?;
+    let mut items = Vec::new();
+    loop {
+        let mut item = parser.expr()?;
+        if parser.eat(&Tok::Op('=')) {
+            item = parser.expr()?;
+        }
+        items.push(item);
+        if !parser.eat(&Tok::Op(',')) {
+            break;
+        }
+    }
+    parser.finish()?;
+    Ok(items)
+}
+
+/// The contents of `$...$` and `$$...$$` segments, in order.
+pub fn math_segments(text: &str) -> Vec<&str> {
+    let mut segments = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('$') {
+        let delimiter = if rest[start..].starts_with("$$") {
+            "$$"
+        } else {
+            "$"
+        };
+        let body = &rest[start + delimiter.len()..];
+        let Some(end) = body.find(delimiter) else {
+            break;
+        };
+        let segment = body[..end].trim();
+        if !segment.is_empty() {
+            segments.push(segment);
+        }
+        rest = &body[end + delimiter.len()..];
+    }
+    segments
+}
+
+struct Parser {
+    toks: Vec<Tok>,
+    pos: usize,
+    abs_depth: usize,
+}
+
+impl Parser {
+    fn new(input: &str) -> Result<Self, String> {
+        Ok(Parser {
+            toks: tokenize(input)?,
+            pos: 0,
+            abs_depth: 0,
+        })
+    }
+
+    fn peek(&self) -> Option<&Tok> {
+        self.toks.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Tok> {
+        let tok = self.toks.get(self.pos).cloned();
+        self.pos += 1;
+        tok
+    }
+
+    fn eat(&mut self, tok: &Tok) -> bool {
+        if self.peek() == Some(tok) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn finish(&self) -> Result<(), String> {
+        match self.peek() {
+            None => Ok(()),
+            Some(tok) => Err(format!("Unexpected {:?}", tok)),
+        }
+    }
+
+    fn expr(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.term()?;
+        loop {
+            if self.eat(&Tok::Op('+')) {
+                lhs = Expr::Add(Box::new(lhs), Box::new(self.term()?));
+            } else if self.eat(&Tok::Op('-')) {
+                lhs = Expr::Sub(Box::new(lhs), Box::new(self.term()?));
+            } else {
+                return Ok(lhs);
+            }
+        }
+    }
+
+    fn term(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.unary()?;
+        loop {
+            if self.eat(&Tok::Op('*')) {
+                lhs = Expr::Mul(Box::new(lhs), Box::new(self.unary()?));
+            } else if self.eat(&Tok::Op('/')) {
+                lhs = Expr::Div(Box::new(lhs), Box::new(self.unary()?));
+            } else if self.starts_atom() {
+                lhs = Expr::Mul(Box::new(lhs), Box::new(self.power()?));
+            } else {
+                return Ok(lhs);
+            }
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.eat(&Tok::Op('-')) {
+            return Ok(Expr::Neg(Box::new(self.unary()?)));
+        }
+        if self.eat(&Tok::Op('+')) {
+            return self.unary();
+        }
+        self.power()
+    }
+
+    fn power(&mut self) -> Result<Expr, String> {
+        let base = self.postfix()?;
+        if self.eat(&Tok::Op('^')) {
+            let exponent = self.exponent()?;
+            return Ok(Expr::Pow(Box::new(base), Box::new(exponent)));
+        }
+        Ok(base)
+    }
+
+    /// The operand of `^`: a group, or a single (optionally negated) atom.
+    fn exponent(&mut self) -> Result<Expr, String> {
+        if self.eat(&Tok::Op('-')) {
+            return Ok(Expr::Neg(Box::new(self.exponent()?)));
+        }
+        let atom = self.postfix()?;
+        // Right-associative: 2^3^2 = 2^(3^2).
+        if self.eat(&Tok::Op('^')) {
+            return Ok(Expr::Pow(Box::new(atom), Box::new(self.exponent()?)));
+        }
+        Ok(atom)
+    }
+
+    fn postfix(&mut self) -> Result<Expr, String> {
+        let mut expr = self.atom()?;
+        while self.eat(&Tok::Op('!')) {
+            expr = Expr::Factorial(Box::new(expr));
+        }
+        Ok(expr)
+    }
+
+    fn starts_atom(&self) -> bool {
+        match self.peek() {
+            Some(
+                Tok::Num(_) | Tok::Ident(_) | Tok::Func(_) | Tok::Frac | Tok::Sqrt | Tok::Open(_),
+            ) => true,
+            Some(Tok::Op('|')) => self.abs_depth == 0,
+            _ => false,
+        }
+    }
+
+    fn atom(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Some(Tok::Num(n)) => Ok(Expr::Num(n)),
+            Some(Tok::Ident(name)) => Ok(Expr::Var(name)),
+            Some(Tok::Open(_)) => {
+                let inner = self.expr()?;
+                self.close()?;
+                Ok(inner)
+            }
+            Some(Tok::Op('|')) => {
+                self.abs_depth += 1;
+                let inner = self.expr()?;
+                self.abs_depth -= 1;
+                if !self.eat(&Tok::Op('|')) {
+                    return Err("Unclosed absolute value".to_string());
+                }
+                Ok(Expr::Call(Func::Abs, Box::new(inner)))
+            }
+            Some(Tok::Frac) => {
+                let numerator = self.group_or_atom()?;
+                let denominator = self.group_or_atom()?;
+                Ok(Expr::Div(Box::new(numerator), Box::new(denominator)))
+            }
+            Some(Tok::Sqrt) => {
+                if self.eat(&Tok::Open('[')) {
+                    let index = self.expr()?;
+                    self.close()?;
+                    let radicand = self.group_or_atom()?;
+                    return Ok(Expr::Pow(
+                        Box::new(radicand),
+                        Box::new(Expr::Div(Box::new(Expr::Num(1.0)), Box::new(index))),
+                    ));
+                }
+                Ok(Expr::Call(Func::Sqrt, Box::new(self.group_or_atom()?)))
+            }
+            Some(Tok::Func(func)) => self.function(func),
+            Some(tok) => Err(format!("Unexpected {:?}", tok)),
+            None => Err("Unexpected end of expression".to_string()),
+        }
+    }
+
+    fn close(&mut self) -> Result<(), String> {
+        match self.next() {
+            Some(Tok::Close(_)) => Ok(()),
+            _ => Err("Unbalanced brackets".to_string()),
+        }
+    }
+
+    fn group_or_atom(&mut self) -> Result<Expr, String> {
+        if matches!(self.peek(), Some(Tok::Open(_))) {
+            return self.atom();
+        }
+        self.postfix()
+    }
+
+    /// `\sin x`, `\sin(2x)`, `\sin^2 x`, `\sin^{-1} x`, `\log_2 8`.
+    fn function(&mut self, func: Func) -> Result<Expr, String> {
+        let mut base = None;
+        if func == Func::Log10 && self.eat(&Tok::Op('_')) {
+            base = Some(self.group_or_atom()?);
+        }
+        let mut func = func;
+        let mut exponent = None;
+        if self.eat(&Tok::Op('^')) {
+            let e = self.exponent()?;
+            match (&e, func.inverse()) {
+                (Expr::Neg(inner), Some(inverse)) if **inner == Expr::Num(1.0) => func = inverse,
+                _ => exponent = Some(e),
+            }
+        }
+
+        let arg = if matches!(self.peek(), Some(Tok::Open(_))) {
+            self.atom()?
+        } else {
+            // `\sin 2x` means sin(2x), but `\sin x \cos x` is a product.
+            let mut arg = self.power()?;
+            while self.starts_atom() && !matches!(self.peek(), Some(Tok::Func(_))) {
+                arg = Expr::Mul(Box::new(arg), Box::new(self.power()?));
+            }
+            arg
+        };
+
+        let call = match base {
+            Some(base) => Expr::Log(Box::new(base), Box::new(arg)),
+            None => Expr::Call(func, Box::new(arg)),
+        };
+        Ok(match exponent {
+            Some(e) => Expr::Pow(Box::new(call), Box::new(e)),
+            None => call,
+        })
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<Tok>, String> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut toks = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            ' ' | '\t' | '\n' | '\r' | '$' | '&' => i += 1,
+            '0'..='9' | '.' => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                    i += 1;
+                }
+                let text: String = chars[start..i].iter().collect();
+                let n = text
+                    .parse::<f64>()
+                    .map_err(|_| format!("Invalid number: {}", text))?;
+                toks.push(Tok::Num(n));
+            }
+            '\\' => {
+                i += 1;
+                let start = i;
+                while i < chars.len() && chars[i].is_ascii_alphabetic() {
+                    i += 1;
+                }
+                if start == i {
+                    // Spacing (`\,` `\;` `\!` `\ `) and escaped braces.
+                    match chars.get(i) {
+                        Some('{') => toks.push(Tok::Open('{')),
+                        Some('}') => toks.push(Tok::Close('}')),
+                        _ => {}
+                    }
+                    i += 1;
+                    continue;
+                }
+                let name: String = chars[start..i].iter().collect();
+                match name.as_str() {
+                    "frac" | "dfrac" | "tfrac" => toks.push(Tok::Frac),
+                    "sqrt" => toks.push(Tok::Sqrt),
+                    "cdot" | "times" | "ast" => toks.push(Tok::Op('*')),
+                    "div" => toks.push(Tok::Op('/')),
+                    "vert" | "lvert" | "rvert" | "mid" => toks.push(Tok::Op('|')),
+                    "pi" => toks.push(Tok::Ident("pi".to_string())),
+                    "operatorname" | "mathrm" | "text" => {
+                        let (word, next) = read_braced(&chars, i)?;
+                        i = next;
+                        match Func::from_name(word.trim()) {
+                            Some(func) => toks.push(Tok::Func(func)),
+                            None if word.trim().chars().all(|c| c.is_ascii_alphabetic()) => {
+                                toks.push(Tok::Ident(word.trim().to_string()))
+                            }
+                            None => return Err(format!("Unsupported text: {}", word)),
+                        }
+                    }
+                    name if IGNORED.contains(&name) => {}
+                    name if GREEK.contains(&name) => {
+                        let (ident, next) = with_subscript(name.to_string(), &chars, i)?;
+                        i = next;
+                        toks.push(Tok::Ident(ident));
+                    }
+                    name => match Func::from_name(name) {
+                        Some(func) => toks.push(Tok::Func(func)),
+                        None => return Err(format!("Unsupported LaTeX command \\{}", name)),
+                    },
+                }
+            }
+            'a'..='z' | 'A'..='Z' => {
+                let rest: String = chars[i..].iter().take(6).collect();
+                if let Some(word) = WORD_FUNCTIONS.iter().find(|w| rest.starts_with(*w)) {
+                    i += word.len();
+                    toks.push(match *word {
+                        "sqrt" => Tok::Sqrt,
+                        w => Tok::Func(Func::from_name(w).expect("known function")),
+                    });
+                } else if rest.starts_with("pi") {
+                    i += 2;
+                    toks.push(Tok::Ident("pi".to_string()));
+                } else {
+                    let (ident, next) = with_subscript(c.to_string(), &chars, i + 1)?;
+                    i = next;
+                    toks.push(Tok::Ident(ident));
+                }
+            }
+            '{' | '(' | '[' => {
+                toks.push(Tok::Open(c));
+                i += 1;
+            }
+            '}' | ')' | ']' => {
+                toks.push(Tok::Close(c));
+                i += 1;
+            }
+            '+' | '-' | '*' | '/' | '^' | '_' | '!' | '=' | ',' | '|' => {
+                toks.push(Tok::Op(c));
+                i += 1;
+            }
+            '×' | '·' => {
+                toks.push(Tok::Op('*'));
+                i += 1;
+            }
+            '÷' => {
+                toks.push(Tok::Op('/'));
+                i += 1;
+            }
+            '−' => {
+                toks.push(Tok::Op('-'));
+                i += 1;
+            }
+            'π' => {
+                toks.push(Tok::Ident("pi".to_string()));
+                i += 1;
+            }
+            _ => return Err(format!("Unsupported character '{}'", c)),
+        }
+    }
+    Ok(toks)
+}
+
+/// Appends a `_1` / `_{12}` subscript to an identifier, if one follows.
+fn with_subscript(name: String, chars: &[char], i: usize) -> Result<(String, usize), String> {
+    if chars.get(i) != Some(&'_') {
+        return Ok((name, i));
+    }
+    match chars.get(i + 1) {
+        Some('{') => {
+            let (sub, next) = read_braced(chars, i + 1)?;
+            Ok((format!("{}_{}", name, sub.trim()), next))
+        }
+        Some(c) if c.is_ascii_alphanumeric() => Ok((format!("{}_{}", name, c), i + 2)),
+        _ => Ok((name, i)),
+    }
+}
+
+/// Reads `{...}` starting at `i` (skipping spaces); returns the inner text and
+/// the index after the closing brace.
+fn read_braced(chars: &[char], mut i: usize) -> Result<(String, usize), String> {
+    while chars.get(i) == Some(&' ') {
+        i += 1;
+    }
+    if chars.get(i) != Some(&'{') {
+        return Err("Expected '{'".to_string());
+    }
+    let mut depth = 0;
+    let start = i + 1;
+    while i < chars.len() {
+        match chars[i] {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Ok((chars[start..i].iter().collect(), i + 1));
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    Err("Unclosed '{'".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::Env;
+
+    fn eval(input: &str, x: f64) -> f64 {
+        let env: Env = [("x".to_string(), x)].into_iter().collect();
+        parse_latex(input).unwrap().eval(&env)
+    }
+
+    #[test]
+    fn finds_math_segments() {
+        assert_eq!(
+            math_segments(r"So $x = 2$ and $$y = \frac{1}{2}$$ then $ $"),
+            vec!["x = 2", r"y = \frac{1}{2}"]
+        );
+        assert!(math_segments("no math").is_empty());
+    }
+
+    #[test]
+    fn parses_latex_structures() {
+        assert_eq!(eval(r"\frac{x^2}{2}", 4.0), 8.0);
+        assert_eq!(eval(r"\sqrt[3]{x}", -8.0), -2.0);
+        assert_eq!(eval(r"2x^2 + 3x - 1", 2.0), 13.0);
+        assert_eq!(eval(r"\left| x - 5 \right|", 2.0), 3.0);
+        assert_eq!(eval(r"\log_2 x", 8.0), 3.0);
+        assert!((eval(r"\sin^{-1} x", 1.0) - std::f64::consts::FRAC_PI_2).abs() < 1e-12);
+        assert!((eval(r"2\pi x", 1.0) - 2.0 * std::f64::consts::PI).abs() < 1e-12);
+    }
+
+    #[test]
+    fn parses_typed_math() {
+        assert_eq!(eval("x^2/2 + 1", 2.0), 3.0);
+        assert_eq!(eval("sqrt(x) * 3", 4.0), 6.0);
+        assert_eq!(eval("-x^2", 3.0), -9.0);
+        assert_eq!(eval("x!", 4.0), 24.0);
+    }
+
+    #[test]
+    fn function_arguments_follow_convention() {
+        // \sin 2x = sin(2x), \sin x \cos x = sin(x) cos(x)
+        assert_eq!(eval(r"\sin 2x", 0.3), (0.6f64).sin());
+        assert_eq!(eval(r"\sin x \cos x", 0.3), (0.3f64).sin() * (0.3f64).cos());
+    }
+
+    #[test]
+    fn reports_unsupported_input() {
+        assert!(parse_latex(r"\int_0^1 x \, dx").is_err());
+        assert!(parse_latex("(x + 1").is_err());
+    }
+}
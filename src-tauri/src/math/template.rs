@@ -0,0 +1,305 @@
+//! Parametric cards: `{name}` placeholders in the question are filled with
+//! seeded random values and the answer is an expression over the same
+//! placeholders, e.g. `{a} \cdot {b}`.
+
+use serde::{Deserialize, Serialize};
+
+use super::check::approx_eq;
+use super::rng::Rng;
+use super::{parse_latex, Env};
+
+/// A declared placeholder and the range its values are drawn from.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParamSpec {
+    pub name: String,
+    pub min: f64,
+    pub max: f64,
+    /// Decimal places; 0 draws integers.
+    #[serde(default)]
+    pub decimals: u32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Instance {
+    pub seed: u64,
+    pub values: Vec<(String, f64)>,
+    pub question: String,
+    pub answer: f64,
+}
+
+const MAX_DECIMALS: u32 = 6;
+/// Largest magnitude a parameter bound may have, so scaled bounds stay exact
+/// in an `i64`.
+const MAX_MAGNITUDE: f64 = 1e9;
+/// Redraws allowed when the answer is undefined for the drawn values
+/// (e.g. a zero denominator).
+const MAX_DRAWS: usize = 50;
+const TOLERANCE: f64 = 1e-6;
+
+/// Parses and validates the `template_params` JSON stored on a card.
+pub fn parse_params(json: &str) -> Result<Vec<ParamSpec>, String> {
+    let params: Vec<ParamSpec> = serde_json::from_str(json)
+        .map_err(|e| format!("Failed to parse template params: {}", e))?;
+    if params.is_empty() {
+        return Err("A template card needs at least one parameter".to_string());
+    }
+    for (i, param) in params.iter().enumerate() {
+        let mut chars = param.name.chars();
+        let valid = chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+            && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid {
+            return Err(format!("Invalid parameter name: {:?}", param.name));
+        }
+        if params[..i].iter().any(|p| p.name == param.name) {
+            return Err(format!("Duplicate parameter: {}", param.name));
+        }
+        if !param.min.is_finite() || !param.max.is_finite() || param.min > param.max {
+            return Err(format!("Invalid range for {}", param.name));
+        }
+        if param.min.abs() > MAX_MAGNITUDE || param.max.abs() > MAX_MAGNITUDE {
+            return Err(format!(
+                "{} must stay between -{} and {}",
+                param.name, MAX_MAGNITUDE, MAX_MAGNITUDE
+            ));
+        }
+        if param.decimals > MAX_DECIMALS {
+            return Err(format!(
+                "{} can have at most {} decimals",
+                param.name, MAX_DECIMALS
+            ));
+        }
+        let (low, high) = bounds(param);
+        if low > high {
+            return Err(format!(
+                "No value between {} and {} has {} decimal(s) for {}",
+                param.min, param.max, param.decimals, param.name
+            ));
+        }
+    }
+    Ok(params)
+}
+
+/// A new seed small enough to survive a round trip through a JS number.
+pub fn fresh_seed() -> u64 {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or_default();
+    Rng::new(nanos).next_u64() >> 11
+}
+
+/// Draws values for `params` from `seed` and evaluates the answer. The same
+/// seed always produces the same instance.
+pub fn instantiate(
+    question: &str,
+    answer: &str,
+    params: &[ParamSpec],
+    seed: u64,
+) -> Result<Instance, String> {
+    let mut rng = Rng::new(seed);
+    for _ in 0..MAX_DRAWS {
+        let values: Vec<(String, f64)> = params
+            .iter()
+            .map(|p| (p.name.clone(), draw(p, &mut rng)))
+            .collect();
+        let value = evaluate(answer, params, &values)?;
+        if value.is_finite() {
+            return Ok(Instance {
+                seed,
+                question: substitute(question, params, &values, false),
+                values,
+                answer: value,
+            });
+        }
+    }
+    Err("The answer is undefined for every drawn value; check the parameter ranges".to_string())
+}
+
+/// The range in units of the last decimal place. Empty (`low > high`) when no
+/// value with that many decimals fits.
+fn bounds(param: &ParamSpec) -> (i64, i64) {
+    let scale = 10f64.powi(param.decimals as i32);
+    // Absorbs float error, so 0.3 with one decimal is 3 and not 4
+    let low = (param.min * scale - 1e-9).ceil() as i64;
+    let high = (param.max * scale + 1e-9).floor() as i64;
+    (low, high)
+}
+
+fn draw(param: &ParamSpec, rng: &mut Rng) -> f64 {
+    let (low, high) = bounds(param);
+    rng.range_i64(low, high) as f64 / 10f64.powi(param.decimals as i32)
+}
+
+fn evaluate(answer: &str, params: &[ParamSpec], values: &[(String, f64)]) -> Result<f64, String> {
+    let expr = parse_latex(&substitute(answer, params, values, true))
+        .map_err(|e| format!("Failed to parse answer expression: {}", e))?;
+    let unknown: Vec<String> = expr
+        .variables()
+        .into_iter()
+        .filter(|v| v != "pi" && v != "e")
+        .collect();
+    if !unknown.is_empty() {
+        return Err(format!(
+            "Answer expression uses undeclared names: {}",
+            unknown.join(", ")
+        ));
+    }
+    Ok(expr.eval(&Env::new()))
+}
+
+/// Replaces each `{name}` of a declared parameter. Values are parenthesized
+/// inside expressions so `-{a}^2` keeps its meaning.
+fn substitute(
+    text: &str,
+    params: &[ParamSpec],
+    values: &[(String, f64)],
+    parenthesize: bool,
+) -> String {
+    let mut out = text.to_string();
+    for (param, (name, value)) in params.iter().zip(values) {
+        let formatted = format_number(*value, param.decimals);
+        let replacement = if parenthesize {
+            format!("({})", formatted)
+        } else {
+            formatted
+        };
+        out = out.replace(&format!("{{{}}}", name), &replacement);
+    }
+    out
+}
+
+/// Formats with at most `decimals` places, dropping trailing zeros.
+pub fn format_number(value: f64, decimals: u32) -> String {
+    let text = format!("{:.*}", decimals as usize, value);
+    let text = if text.contains('.') {
+        text.trim_end_matches('0').trim_end_matches('.').to_string()
+    } else {
+        text
+    };
+    if text == "-0" {
+        "0".to_string()
+    } else {
+        text
+    }
+}
+
+/// Formats a computed answer for display.
+pub fn format_answer(value: f64) -> String {
+    if approx_eq(value, value.round(), 1e-9) {
+        return format_number(value.round(), 0);
+    }
+    format_number(value, MAX_DECIMALS)
+}
+
+/// Whether a typed answer matches. Expressions like `3/4` are evaluated; a
+/// plain decimal with at least two places is also accepted when it is
+/// `expected` correctly rounded.
+pub fn check_typed(typed: &str, expected: f64) -> Result<bool, String> {
+    let typed = typed.trim();
+    if typed.is_empty() {
+        return Ok(false);
+    }
+    let expr = parse_latex(typed).map_err(|e| format!("Failed to read answer: {}", e))?;
+    if expr.variables().iter().any(|v| v != "pi" && v != "e") {
+        return Err("Enter a number, not an expression with variables".to_string());
+    }
+    let value = expr.eval(&Env::new());
+    if !value.is_finite() {
+        return Ok(false);
+    }
+    if approx_eq(value, expected, TOLERANCE) {
+        return Ok(true);
+    }
+    let places = typed
+        .split_once('.')
+        .filter(|(_, frac)| frac.chars().all(|c| c.is_ascii_digit()))
+        .map(|(_, frac)| frac.len() as i32);
+    Ok(match places {
+        Some(places) if places >= 2 => {
+            (value - expected).abs() <= 0.5 * 10f64.powi(-places) + 1e-12
+        }
+        _ => false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(json: &str) -> Vec<ParamSpec> {
+        parse_params(json).unwrap()
+    }
+
+    #[test]
+    fn same_seed_same_instance() {
+        let p = params(r#"[{"name":"a","min":2,"max":9},{"name":"b","min":2,"max":9}]"#);
+        let first = instantiate("What is ${a} \\times {b}$?", "{a} \\cdot {b}", &p, 42).unwrap();
+        let again = instantiate("What is ${a} \\times {b}$?", "{a} \\cdot {b}", &p, 42).unwrap();
+        assert_eq!(first, again);
+
+        let a = first.values[0].1;
+        let b = first.values[1].1;
+        assert!((2.0..=9.0).contains(&a) && a.fract() == 0.0);
+        assert_eq!(first.answer, a * b);
+        assert_eq!(first.question, format!("What is ${} \\times {}$?", a, b));
+    }
+
+    #[test]
+    fn respects_decimals_and_parenthesizes() {
+        let p = params(r#"[{"name":"x","min":-5,"max":-1,"decimals":1}]"#);
+        for seed in 0..20 {
+            let instance = instantiate("{x}", "-{x}^2", &p, seed).unwrap();
+            let x = instance.values[0].1;
+            assert!((-5.0..=-1.0).contains(&x));
+            assert_eq!(format_number(x, 1).parse::<f64>().unwrap(), x);
+            assert!(approx_eq(instance.answer, -(x * x), 1e-9));
+        }
+    }
+
+    #[test]
+    fn redraws_undefined_answers() {
+        let p = params(r#"[{"name":"a","min":1,"max":3},{"name":"b","min":0,"max":1}]"#);
+        for seed in 0..20 {
+            let instance = instantiate("", "\\frac{{a}}{{b}}", &p, seed).unwrap();
+            assert_eq!(instance.values[1].1, 1.0);
+        }
+        let zero = params(r#"[{"name":"b","min":0,"max":0}]"#);
+        assert!(instantiate("", "1/{b}", &zero, 1).is_err());
+    }
+
+    #[test]
+    fn rejects_bad_params() {
+        assert!(parse_params("[]").is_err());
+        assert!(parse_params(r#"[{"name":"1a","min":0,"max":1}]"#).is_err());
+        assert!(parse_params(r#"[{"name":"a","min":2,"max":1}]"#).is_err());
+        assert!(
+            parse_params(r#"[{"name":"a","min":0,"max":1},{"name":"a","min":0,"max":1}]"#).is_err()
+        );
+        // Nothing between 0.11 and 0.19 has one decimal
+        assert!(parse_params(r#"[{"name":"a","min":0.11,"max":0.19,"decimals":1}]"#).is_err());
+        let p = params(r#"[{"name":"a","min":0.3,"max":0.3,"decimals":1}]"#);
+        assert_eq!(instantiate("", "{a}", &p, 0).unwrap().answer, 0.3);
+        let p = params(r#"[{"name":"a","min":0,"max":1}]"#);
+        assert!(instantiate("", "{a} + y", &p, 0).is_err());
+        // Bounds whose span would overflow when drawing
+        assert!(parse_params(r#"[{"name":"a","min":-1e18,"max":1e18}]"#).is_err());
+        assert!(parse_params(r#"[{"name":"a","min":0,"max":1e10}]"#).is_err());
+        let p = params(r#"[{"name":"a","min":-1e9,"max":1e9,"decimals":6}]"#);
+        let a = instantiate("", "{a}", &p, 7).unwrap().values[0].1;
+        assert!((-1e9..=1e9).contains(&a));
+    }
+
+    #[test]
+    fn checks_typed_answers() {
+        assert!(check_typed("12", 12.0).unwrap());
+        assert!(check_typed(" 3/4 ", 0.75).unwrap());
+        assert!(check_typed("\\frac{1}{3}", 1.0 / 3.0).unwrap());
+        assert!(check_typed("0.33", 1.0 / 3.0).unwrap());
+        assert!(!check_typed("0.3", 1.0 / 3.0).unwrap());
+        assert!(!check_typed("13", 12.0).unwrap());
+        assert!(!check_typed("", 12.0).unwrap());
+        assert!(check_typed("x", 1.0).is_err());
+        assert_eq!(format_answer(12.0000000001), "12");
+        assert_eq!(format_answer(0.125), "0.125");
+    }
+}
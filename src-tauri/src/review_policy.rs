@@ -0,0 +1,604 @@
+//! Daily review limits per folder. Mirrors `src/lib/review-policy.ts` so backend
+//! commands count due cards the same way the study screen does.
+
+use chrono::{Local, NaiveDate, NaiveTime, TimeZone};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+pub const DEFAULT_REVIEW_CARDS_PER_DAY: i64 = 20;
+pub const DEFAULT_AUTO_TARGET_REPS: i64 = 3;
+pub const DEFAULT_NEW_CARDS_PER_DAY: i64 = 20;
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct FolderPolicy {
+    pub id: String,
+    pub deadline: Option<String>,
+    pub review_cards_per_day: i64,
+    pub review_target_mode: String,
+    pub auto_target_reps: i64,
+    pub new_cards_per_day: i64,
+    /// `created`, `random` or `difficulty`.
+    pub new_card_order: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct CardState {
+    pub id: String,
+    pub folder_id: Option<String>,
+    pub repetitions: i64,
+    pub interval_days: f64,
+    pub due_date: Option<String>,
+    pub last_reviewed: Option<String>,
+    /// Suspended cards (e.g. leeches) are never due.
+    #[serde(default)]
+    pub suspended: bool,
+    /// Buried cards are skipped until this time has passed.
+    #[serde(default)]
+    pub buried_until: Option<String>,
+    #[serde(default = "default_learning_state")]
+    pub learning_state: String,
+    /// When the card was first reviewed; new cards have none.
+    #[serde(default)]
+    pub introduced_at: Option<String>,
+    /// The assessed answer time, which doubles as a difficulty estimate.
+    #[serde(default)]
+    pub timer_seconds: i64,
+    #[serde(default)]
+    pub created_at: Option<String>,
+}
+
+fn default_learning_state() -> String {
+    "review".to_string()
+}
+
+impl CardState {
+    pub fn is_new(&self) -> bool {
+        self.introduced_at.is_none()
+    }
+
+    /// Learning and relearning cards step through intra-day delays.
+    pub fn is_learning(&self) -> bool {
+        matches!(self.learning_state.as_str(), "learning" | "relearning")
+    }
+
+    pub fn is_due(&self, now: &str) -> bool {
+        !self.suspended
+            && self
+                .buried_until
+                .as_deref()
+                .map(|until| until <= now)
+                .unwrap_or(true)
+            && self
+                .due_date
+                .as_deref()
+                .map(|due| due <= now)
+                .unwrap_or(true)
+    }
+}
+
+pub fn sanitize_review_cards_per_day(value: i64) -> i64 {
+    value.max(1)
+}
+
+pub fn sanitize_auto_target_reps(value: i64) -> i64 {
+    value.clamp(1, 10)
+}
+
+pub fn sanitize_new_cards_per_day(value: i64) -> i64 {
+    value.max(0)
+}
+
+/// Whole days until the end of the deadline day (at least 1), or `None`
+/// without a deadline.
+pub fn days_remaining(deadline: Option<&str>) -> Option<i64> {
+    let date = NaiveDate::parse_from_str(deadline?, "%Y-%m-%d").ok()?;
+    let end_of_day = date.and_time(NaiveTime::from_hms_opt(23, 59, 59)?);
+    let target = Local.from_local_datetime(&end_of_day).earliest()?;
+    let diff_ms = (target - Local::now()).num_milliseconds();
+    if diff_ms <= 0 {
+        return Some(1);
+    }
+    Some(
+        ((diff_ms as f64) / (1000.0 * 60.0 * 60.0 * 24.0))
+            .ceil()
+            .max(1.0) as i64,
+    )
+}
+
+/// An extra deadline within a folder, e.g. a quiz before the final.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Milestone {
+    pub folder_id: String,
+    /// Local date, `YYYY-MM-DD`.
+    pub date: String,
+    /// The cards it covers; `None` covers the whole folder.
+    pub card_ids: Option<Vec<String>>,
+}
+
+impl Milestone {
+    pub fn covers(&self, card_id: &str) -> bool {
+        self.card_ids
+            .as_ref()
+            .map(|ids| ids.iter().any(|id| id == card_id))
+            .unwrap_or(true)
+    }
+}
+
+/// The earliest of the folder's `deadline` and its milestones that falls on
+/// or after `today`, counting only milestones covering `card_id` when given.
+/// A milestone counts through the end of its own day, then the next one takes
+/// over; with nothing upcoming this is just `deadline`.
+pub fn nearest_deadline<'a>(
+    deadline: Option<&'a str>,
+    milestones: &'a [Milestone],
+    card_id: Option<&str>,
+    today: NaiveDate,
+) -> Option<&'a str> {
+    let upcoming = |date: &&str| {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map(|date| date >= today)
+            .unwrap_or(false)
+    };
+    deadline
+        .into_iter()
+        .chain(
+            milestones
+                .iter()
+                .filter(|m| card_id.map(|id| m.covers(id)).unwrap_or(true))
+                .map(|m| m.date.as_str()),
+        )
+        .filter(upcoming)
+        .min()
+        .or(deadline)
+}
+
+fn reviews_needed_for_mastery(card: &CardState, target_reps: i64) -> i64 {
+    if card.repetitions >= target_reps || card.interval_days >= 7.0 {
+        return 0;
+    }
+    (target_reps - card.repetitions).max(0)
+}
+
+fn dynamic_daily_review_target(
+    folder: Option<&FolderPolicy>,
+    cards: &[CardState],
+    now: &str,
+) -> i64 {
+    let fallback = sanitize_review_cards_per_day(
+        folder
+            .map(|f| f.review_cards_per_day)
+            .unwrap_or(DEFAULT_REVIEW_CARDS_PER_DAY),
+    );
+    let Some(days) = days_remaining(folder.and_then(|f| f.deadline.as_deref())) else {
+        return fallback;
+    };
+    let target_reps = sanitize_auto_target_reps(
+        folder
+            .map(|f| f.auto_target_reps)
+            .unwrap_or(DEFAULT_AUTO_TARGET_REPS),
+    );
+
+    let remaining_reviews: i64 = cards
+        .iter()
+        .map(|card| reviews_needed_for_mastery(card, target_reps))
+        .sum();
+    let due_backlog = cards.iter().filter(|card| card.is_due(now)).count() as i64;
+    let backlog_target = if due_backlog == 0 {
+        0
+    } else {
+        ((due_backlog as f64) / (days.min(7) as f64))
+            .ceil()
+            .max(1.0) as i64
+    };
+    if remaining_reviews == 0 {
+        return backlog_target;
+    }
+
+    let base_target = ((remaining_reviews as f64) / (days as f64)).ceil().max(1.0) as i64;
+    base_target.max(backlog_target)
+}
+
+/// How many cards from `folder` may be reviewed today.
+pub fn effective_daily_review_limit(
+    folder: Option<&FolderPolicy>,
+    cards: &[CardState],
+    now: &str,
+) -> i64 {
+    if folder
+        .map(|f| f.review_target_mode == "dynamic")
+        .unwrap_or(false)
+    {
+        return dynamic_daily_review_target(folder, cards, now);
+    }
+    sanitize_review_cards_per_day(
+        folder
+            .map(|f| f.review_cards_per_day)
+            .unwrap_or(DEFAULT_REVIEW_CARDS_PER_DAY),
+    )
+}
+
+/// Number of `due` cards that fit under each folder's daily limits.
+pub fn scheduled_due_count(
+    due: &[&CardState],
+    folders: &[FolderPolicy],
+    all_cards: &[CardState],
+    now: &str,
+    today: &str,
+) -> i64 {
+    build_daily_queue(due, folders, all_cards, now, today).len() as i64
+}
+
+/// One folder's share of a study session.
+#[derive(Debug, Clone)]
+pub struct FolderQueue<'a> {
+    pub folder_id: Option<String>,
+    /// Relative share of the session; 1.0 unless the learner weights folders.
+    pub weight: f64,
+    pub days_remaining: Option<i64>,
+    pub cards: Vec<&'a CardState>,
+}
+
+/// Today's study queue from the `due` cards, folder by folder in `folders`
+/// order with unfiled cards last. See `daily_queues_by_folder`.
+pub fn build_daily_queue<'a>(
+    due: &[&'a CardState],
+    folders: &[FolderPolicy],
+    all_cards: &[CardState],
+    now: &str,
+    today: &str,
+) -> Vec<&'a CardState> {
+    daily_queues_by_folder(due, folders, all_cards, now, today)
+        .into_iter()
+        .flat_map(|queue| queue.cards)
+        .collect()
+}
+
+/// Each folder's queue for today. A folder contributes every learning card,
+/// then reviews up to its review limit with up to `new_cards_per_day` new
+/// cards (less those already introduced today) spread evenly between them.
+pub fn daily_queues_by_folder<'a>(
+    due: &[&'a CardState],
+    folders: &[FolderPolicy],
+    all_cards: &[CardState],
+    now: &str,
+    today: &str,
+) -> Vec<FolderQueue<'a>> {
+    let mut folder_ids: Vec<Option<&str>> = folders.iter().map(|f| Some(f.id.as_str())).collect();
+    folder_ids.push(None);
+
+    let mut queues = Vec::new();
+    for folder_id in folder_ids {
+        let folder_due: Vec<&CardState> = due
+            .iter()
+            .copied()
+            .filter(|card| card.folder_id.as_deref() == folder_id)
+            .collect();
+        if folder_due.is_empty() {
+            continue;
+        }
+        let folder = folder_id.and_then(|id| folders.iter().find(|f| f.id == id));
+        let folder_cards: Vec<CardState> = all_cards
+            .iter()
+            .filter(|card| card.folder_id.as_deref() == folder_id)
+            .cloned()
+            .collect();
+        queues.push(FolderQueue {
+            folder_id: folder_id.map(str::to_string),
+            weight: 1.0,
+            days_remaining: folder.and_then(|f| days_remaining(f.deadline.as_deref())),
+            cards: folder_queue(folder_due, folder, &folder_cards, now, today),
+        });
+    }
+    queues
+}
+
+/// Merges folder queues into one session, keeping each folder's own order.
+/// Folders get slots in proportion to their weight, boosted as their deadline
+/// nears (a folder due in a week counts double), with ties going to the
+/// nearest deadline. Slots are spaced evenly, and on a tie a different folder
+/// from the previous card goes first, so topics alternate rather than bunch.
+pub fn interleave_folder_queues<'a>(mut queues: Vec<FolderQueue<'a>>) -> Vec<&'a CardState> {
+    queues.retain(|queue| queue.weight > 0.0 && !queue.cards.is_empty());
+    queues.sort_by_key(|queue| queue.days_remaining.unwrap_or(i64::MAX));
+
+    let shares: Vec<f64> = queues
+        .iter()
+        .map(|queue| {
+            let urgency = queue
+                .days_remaining
+                .map(|days| 1.0 + 7.0 / days.max(1) as f64)
+                .unwrap_or(1.0);
+            queue.weight * urgency
+        })
+        .collect();
+    // Stride scheduling: each folder's next card is due at `pass`.
+    let mut passes: Vec<f64> = shares.iter().map(|share| 0.5 / share).collect();
+    let mut cursors = vec![0; queues.len()];
+    let total: usize = queues.iter().map(|queue| queue.cards.len()).sum();
+
+    let mut session = Vec::with_capacity(total);
+    let mut previous: Option<usize> = None;
+    while session.len() < total {
+        let next = (0..queues.len())
+            .filter(|&i| cursors[i] < queues[i].cards.len())
+            .min_by(|&a, &b| {
+                passes[a]
+                    .total_cmp(&passes[b])
+                    .then_with(|| (Some(a) == previous).cmp(&(Some(b) == previous)))
+            });
+        let Some(next) = next else {
+            break;
+        };
+        session.push(queues[next].cards[cursors[next]]);
+        cursors[next] += 1;
+        passes[next] += 1.0 / shares[next];
+        previous = Some(next);
+    }
+    session
+}
+
+fn folder_queue<'a>(
+    due: Vec<&'a CardState>,
+    folder: Option<&FolderPolicy>,
+    folder_cards: &[CardState],
+    now: &str,
+    today: &str,
+) -> Vec<&'a CardState> {
+    let (mut queue, rest): (Vec<&CardState>, Vec<&CardState>) =
+        due.into_iter().partition(|card| card.is_learning());
+    let (mut new, mut reviews): (Vec<&CardState>, Vec<&CardState>) =
+        rest.into_iter().partition(|card| card.is_new());
+
+    // Each repetition halves a review's expected score, favouring weaker cards.
+    let day = &today[..today.len().min(10)];
+    reviews.sort_by(|a, b| {
+        let score =
+            |card: &CardState| daily_random(&card.id, day) * 0.5f64.powi(card.repetitions as i32);
+        score(b).total_cmp(&score(a))
+    });
+    reviews.truncate(effective_daily_review_limit(folder, folder_cards, now).max(0) as usize);
+
+    match folder.map(|f| f.new_card_order.as_str()) {
+        Some("random") => {
+            new.sort_by(|a, b| daily_random(&a.id, day).total_cmp(&daily_random(&b.id, day)))
+        }
+        Some("difficulty") => new.sort_by(|a, b| {
+            a.timer_seconds
+                .cmp(&b.timer_seconds)
+                .then_with(|| a.created_at.cmp(&b.created_at))
+        }),
+        _ => new.sort_by(|a, b| a.created_at.cmp(&b.created_at)),
+    }
+    let introduced_today = folder_cards
+        .iter()
+        .filter(|card| {
+            card.introduced_at
+                .as_deref()
+                .map(|at| at >= today)
+                .unwrap_or(false)
+        })
+        .count() as i64;
+    let new_per_day = sanitize_new_cards_per_day(
+        folder
+            .map(|f| f.new_cards_per_day)
+            .unwrap_or(DEFAULT_NEW_CARDS_PER_DAY),
+    );
+    new.truncate((new_per_day - introduced_today).max(0) as usize);
+
+    queue.extend(spread(reviews, new));
+    queue
+}
+
+/// Interleaves `extra` evenly through `base`.
+fn spread<T>(base: Vec<T>, extra: Vec<T>) -> Vec<T> {
+    let total = base.len() + extra.len();
+    let extra_len = extra.len();
+    let mut base = base.into_iter();
+    let mut extra = extra.into_iter();
+    let mut placed = 0;
+    let mut out = Vec::with_capacity(total);
+    for slot in 0..total {
+        // Aim each extra item at the middle of its share of the slots.
+        let take_extra = (2 * placed + 1) * total <= 2 * (slot + 1) * extra_len;
+        let next = if take_extra {
+            extra.next().or_else(|| base.next())
+        } else {
+            base.next().or_else(|| extra.next())
+        };
+        if take_extra {
+            placed += 1;
+        }
+        out.extend(next);
+    }
+    out
+}
+
+/// A number in [0, 1) that is stable for a card within one day, so the queue
+/// doesn't reshuffle every time it is rebuilt.
+fn daily_random(id: &str, day: &str) -> f64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in day.bytes().chain(id.bytes()) {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    (hash >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn card(
+        id: &str,
+        repetitions: i64,
+        introduced_at: Option<&str>,
+        learning_state: &str,
+    ) -> CardState {
+        CardState {
+            id: id.to_string(),
+            folder_id: Some("f".to_string()),
+            repetitions,
+            interval_days: 1.0,
+            due_date: None,
+            last_reviewed: None,
+            suspended: false,
+            buried_until: None,
+            learning_state: learning_state.to_string(),
+            introduced_at: introduced_at.map(str::to_string),
+            timer_seconds: 300,
+            created_at: Some(format!("2026-01-01T00:00:0{}Z", id.len() % 10)),
+        }
+    }
+
+    fn folder(review_cards_per_day: i64, new_cards_per_day: i64) -> FolderPolicy {
+        FolderPolicy {
+            id: "f".to_string(),
+            deadline: None,
+            review_cards_per_day,
+            review_target_mode: "fixed".to_string(),
+            auto_target_reps: 3,
+            new_cards_per_day,
+            new_card_order: "created".to_string(),
+        }
+    }
+
+    #[test]
+    fn limits_new_cards_separately() {
+        let today = "2026-03-01T00:00:00.000Z";
+        let now = "2026-03-01T12:00:00.000Z";
+        let mut cards: Vec<CardState> = (0..200)
+            .map(|i| card(&format!("new{}", i), 0, None, "new"))
+            .collect();
+        cards.extend((0..5).map(|i| {
+            card(
+                &format!("rev{}", i),
+                2,
+                Some("2026-02-01T00:00:00.000Z"),
+                "review",
+            )
+        }));
+        cards.push(card(
+            "learn",
+            0,
+            Some("2026-03-01T11:00:00.000Z"),
+            "learning",
+        ));
+        let due: Vec<&CardState> = cards.iter().collect();
+
+        let queue = build_daily_queue(&due, &[folder(3, 10)], &cards, now, today);
+        assert_eq!(queue[0].id, "learn");
+        assert_eq!(queue.iter().filter(|c| c.is_learning()).count(), 1);
+        assert_eq!(queue.iter().filter(|c| c.id.starts_with("rev")).count(), 3);
+        // One new card was already introduced today.
+        assert_eq!(queue.iter().filter(|c| c.is_new()).count(), 9);
+        assert_eq!(
+            scheduled_due_count(&due, &[folder(3, 0)], &cards, now, today),
+            4
+        );
+    }
+
+    #[test]
+    fn interleaves_folders_by_weight_and_deadline() {
+        let cards: Vec<CardState> = (0..12)
+            .map(|i| {
+                let mut c = card(
+                    &format!("{}{}", if i < 6 { "a" } else { "b" }, i),
+                    2,
+                    Some("2026-01-01"),
+                    "review",
+                );
+                c.folder_id = Some(if i < 6 { "a" } else { "b" }.to_string());
+                c
+            })
+            .collect();
+        let queue = |folder: &str, weight: f64, days: Option<i64>| FolderQueue {
+            folder_id: Some(folder.to_string()),
+            weight,
+            days_remaining: days,
+            cards: cards
+                .iter()
+                .filter(|c| c.folder_id.as_deref() == Some(folder))
+                .collect(),
+        };
+        let folder_of = |session: &[&CardState]| -> String {
+            session
+                .iter()
+                .map(|c| c.folder_id.clone().unwrap())
+                .collect::<Vec<_>>()
+                .concat()
+        };
+
+        let even = interleave_folder_queues(vec![queue("a", 1.0, None), queue("b", 1.0, None)]);
+        assert_eq!(folder_of(&even), "abababababab");
+
+        // The nearer deadline leads and takes more of the early slots.
+        let urgent =
+            interleave_folder_queues(vec![queue("a", 1.0, None), queue("b", 1.0, Some(7))]);
+        assert_eq!(&folder_of(&urgent)[..4], "babb");
+        assert_eq!(urgent.len(), 12);
+
+        let weighted = interleave_folder_queues(vec![queue("a", 3.0, None), queue("b", 1.0, None)]);
+        assert_eq!(&folder_of(&weighted)[..4], "abaa");
+        assert_eq!(folder_of(&weighted[..8]).matches('a').count(), 6);
+        assert_eq!(
+            interleave_folder_queues(vec![queue("a", 0.0, None)]).len(),
+            0
+        );
+    }
+
+    #[test]
+    fn spreads_new_cards_between_reviews() {
+        let mixed = spread(vec!["r1", "r2", "r3", "r4"], vec!["n1", "n2"]);
+        assert_eq!(mixed, vec!["r1", "n1", "r2", "r3", "n2", "r4"]);
+        assert_eq!(spread(Vec::<&str>::new(), vec!["n1"]), vec!["n1"]);
+    }
+
+    #[test]
+    fn targets_the_nearest_milestone_covering_each_card() {
+        let milestone = |date: &str, card_ids: Option<&[&str]>| Milestone {
+            folder_id: "f".to_string(),
+            date: date.to_string(),
+            card_ids: card_ids.map(|ids| ids.iter().map(|id| id.to_string()).collect()),
+        };
+        let milestones = vec![
+            milestone("2026-03-08", Some(&["quiz"])),
+            milestone("2026-04-01", None),
+        ];
+        let day = |d: u32| NaiveDate::from_ymd_opt(2026, 3, d).unwrap();
+        let final_exam = Some("2026-05-01");
+
+        assert_eq!(
+            nearest_deadline(final_exam, &milestones, Some("quiz"), day(1)),
+            Some("2026-03-08")
+        );
+        assert_eq!(
+            nearest_deadline(final_exam, &milestones, Some("other"), day(1)),
+            Some("2026-04-01")
+        );
+        // The folder as a whole answers to its nearest milestone of any kind.
+        assert_eq!(
+            nearest_deadline(final_exam, &milestones, None, day(1)),
+            Some("2026-03-08")
+        );
+        // The quiz still counts on its own day; the day after, its cards move
+        // on to the next deadline.
+        assert_eq!(
+            nearest_deadline(final_exam, &milestones, Some("quiz"), day(8)),
+            Some("2026-03-08")
+        );
+        assert_eq!(
+            nearest_deadline(final_exam, &milestones, Some("quiz"), day(9)),
+            Some("2026-04-01")
+        );
+        // With everything past, only the folder's own deadline remains.
+        let after_all = NaiveDate::from_ymd_opt(2026, 6, 1).unwrap();
+        assert_eq!(
+            nearest_deadline(None, &milestones, Some("quiz"), after_all),
+            None
+        );
+        assert_eq!(
+            nearest_deadline(final_exam, &milestones, Some("quiz"), after_all),
+            final_exam
+        );
+    }
+}
@@ -9,8 +9,12 @@ mod tests {
     pub struct SrsCard {
         pub ease_factor: f64,
         pub interval_days: f64,
+        /// Interval before deadline compression, while it is compressed.
+        pub base_interval_days: Option<f64>,
         pub repetitions: i32,
         pub due_date: Option<String>,
+        pub learning_state: String,
+        pub learning_step: i32,
     }
 
     #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -18,19 +22,38 @@ mod tests {
         pub correct: bool,
         pub response_time_seconds: f64,
         pub timer_limit_seconds: f64,
+        /// Hints revealed before answering.
+        pub hints_used: i32,
+    }
+
+    /// A folder's learning and relearning steps, in minutes.
+    #[derive(Debug, Clone)]
+    pub struct LearningSteps {
+        pub learning: Vec<f64>,
+        pub relearning: Vec<f64>,
     }
 
     #[derive(Debug, Clone, Serialize, Deserialize)]
     pub struct SrsResult {
         pub ease_factor: f64,
         pub interval_days: f64,
+        pub base_interval_days: Option<f64>,
         pub repetitions: i32,
         pub due_date: String,
         pub quality: i32,
         pub speed_ratio: f64,
+        pub learning_state: String,
+        pub learning_step: i32,
+        /// Whether this answer adds to the card's lapse count.
+        pub lapsed: bool,
     }
 
-    pub fn calculate_srs(card: &SrsCard, review: &ReviewInput, deadline: Option<&str>) -> SrsResult {
+    pub fn calculate_srs(
+        card: &SrsCard,
+        review: &ReviewInput,
+        steps: &LearningSteps,
+        deadline: Option<&str>,
+    ) -> SrsResult {
         let speed_ratio = if review.timer_limit_seconds > 0.0 {
             review.response_time_seconds / review.timer_limit_seconds
         } else {
@@ -40,33 +63,95 @@ mod tests {
         let mut ease_factor = card.ease_factor;
         let mut interval_days: f64;
         let mut repetitions = card.repetitions;
-        let quality: i32;
-
-        if !review.correct {
-            quality = 0;
-            ease_factor = (ease_factor - 0.20).max(MIN_EASE);
-            repetitions = 0;
-            interval_days = 1.0;
-        } else if speed_ratio <= 0.6 {
-            quality = 5;
-            ease_factor += 0.15;
-            repetitions += 1;
-            interval_days = next_interval(repetitions, card.interval_days, ease_factor, 1.3);
-        } else if speed_ratio <= 1.0 {
-            quality = 4;
-            ease_factor += 0.05;
-            repetitions += 1;
-            interval_days = next_interval(repetitions, card.interval_days, ease_factor, 1.0);
+        let mut learning_state = card.learning_state.clone();
+        let mut learning_step = card.learning_step;
+        // Intervals grow from the uncompressed one, so they re-expand once
+        // the deadline that compressed them has passed.
+        let previous_interval = card.base_interval_days.unwrap_or(card.interval_days);
+        // Minutes until the next learning step, when the card is still learning.
+        let mut step_minutes: Option<f64> = None;
+        let quality = hinted_quality(review.correct, speed_ratio, review.hints_used);
+        // Forgetting a card that had graduated to review is a lapse.
+        let lapsed = learning_state == "review" && quality == 0;
+
+        if learning_state == "review" {
+            match quality {
+                0 => {
+                    ease_factor = (ease_factor - 0.20).max(MIN_EASE);
+                    repetitions = 0;
+                    interval_days = 1.0;
+                    if let Some(&first) = steps.relearning.first() {
+                        learning_state = "relearning".to_string();
+                        learning_step = 0;
+                        step_minutes = Some(first);
+                    }
+                }
+                5 => {
+                    ease_factor += 0.15;
+                    repetitions += 1;
+                    interval_days =
+                        next_interval(repetitions, previous_interval, ease_factor, 1.3);
+                }
+                4 => {
+                    ease_factor += 0.05;
+                    repetitions += 1;
+                    interval_days =
+                        next_interval(repetitions, previous_interval, ease_factor, 1.0);
+                }
+                3 => {
+                    ease_factor = (ease_factor - 0.10).max(MIN_EASE);
+                    repetitions += 1;
+                    interval_days =
+                        next_interval(repetitions, previous_interval, ease_factor, 0.8);
+                }
+                _ => {
+                    // Solved only with heavy help: not a lapse, but not progress either.
+                    ease_factor = (ease_factor - 0.15).max(MIN_EASE);
+                    interval_days = 1.0;
+                }
+            }
         } else {
-            quality = 3;
-            ease_factor = (ease_factor - 0.10).max(MIN_EASE);
-            repetitions += 1;
-            interval_days = next_interval(repetitions, card.interval_days, ease_factor, 0.8);
+            let relearning = learning_state == "relearning";
+            let card_steps = if relearning {
+                &steps.relearning
+            } else {
+                &steps.learning
+            };
+            if !relearning {
+                learning_state = "learning".to_string();
+            }
+            let next_step = match quality {
+                0 => Some(0),
+                // Easy skips the remaining steps.
+                5 => None,
+                // Heavily hinted repeats the current step.
+                2 => Some(learning_step),
+                _ => Some(learning_step + 1),
+            };
+            match next_step.and_then(|step| card_steps.get(step as usize).map(|m| (step, *m))) {
+                Some((step, minutes)) => {
+                    learning_step = step;
+                    step_minutes = Some(minutes);
+                    interval_days = previous_interval;
+                }
+                None if quality == 0 => {
+                    // No steps configured: a failed card comes back tomorrow.
+                    learning_step = 0;
+                    interval_days = 1.0;
+                }
+                None => {
+                    learning_state = "review".to_string();
+                    learning_step = 0;
+                    repetitions += 1;
+                    interval_days = if quality == 5 && !relearning { 3.0 } else { 1.0 };
+                }
+            }
         }
 
         ease_factor = ease_factor.max(MIN_EASE);
+        let natural_interval = interval_days;
 
-        if let Some(deadline_str) = deadline {
+        if let (None, Some(deadline_str)) = (step_minutes, deadline) {
             if let Ok(deadline_date) = NaiveDateTime::parse_from_str(
                 &format!("{} 00:00:00", deadline_str),
                 "%Y-%m-%d %H:%M:%S",
@@ -82,20 +167,64 @@ mod tests {
             }
         }
 
-        let now = Utc::now();
-        let due = now + chrono::Duration::days(interval_days.round() as i64);
+        let base_interval_days = (interval_days < natural_interval).then_some(natural_interval);
+
+        let minutes = step_minutes.unwrap_or(interval_days * 1440.0);
+        let due = Utc::now() + chrono::Duration::minutes(minutes.round() as i64);
         let due_date = due.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string();
 
         SrsResult {
             ease_factor,
             interval_days,
+            base_interval_days,
             repetitions,
             due_date,
             quality,
             speed_ratio,
+            learning_state,
+            learning_step,
+            lapsed,
         }
     }
 
+    /// Parses steps such as `"1m 10m 1h"` into minutes. A bare number is in
+    /// minutes; tokens that don't parse are skipped.
+    pub fn parse_steps(value: &str) -> Vec<f64> {
+        value
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter_map(|token| {
+                let token = token.trim().to_lowercase();
+                let (number, unit) = match token.char_indices().last()? {
+                    (i, 'm') => (&token[..i], 1.0),
+                    (i, 'h') => (&token[..i], 60.0),
+                    (i, 'd') => (&token[..i], 1440.0),
+                    _ => (token.as_str(), 1.0),
+                };
+                number
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|n| *n > 0.0)
+                    .map(|n| n * unit)
+            })
+            .collect()
+    }
+
+    /// Quality from speed, lowered by one per hint for a correct answer (never
+    /// below 2, which still counts as correct).
+    fn hinted_quality(correct: bool, speed_ratio: f64, hints_used: i32) -> i32 {
+        if !correct {
+            return 0;
+        }
+        let unaided = if speed_ratio <= 0.6 {
+            5
+        } else if speed_ratio <= 1.0 {
+            4
+        } else {
+            3
+        };
+        (unaided - hints_used.max(0)).max(2)
+    }
+
     fn next_interval(reps: i32, prev_interval: f64, ease: f64, speed_multiplier: f64) -> f64 {
         match reps {
             r if r <= 1 => 1.0,
@@ -104,23 +233,41 @@ mod tests {
         }
     }
 
+    fn steps() -> LearningSteps {
+        LearningSteps {
+            learning: parse_steps("1m 10m 1h"),
+            relearning: parse_steps("10m"),
+        }
+    }
+
+    fn minutes_until(due_date: &str) -> i64 {
+        let due = chrono::DateTime::parse_from_rfc3339(due_date).unwrap();
+        ((due.with_timezone(&Utc) - Utc::now()).num_milliseconds() as f64 / 60_000.0).round() as i64
+    }
+
     #[test]
     fn test_incorrect_resets() {
         let card = SrsCard {
             ease_factor: 2.5,
             interval_days: 10.0,
+            base_interval_days: None,
             repetitions: 3,
             due_date: None,
+            learning_state: "review".to_string(),
+            learning_step: 0,
         };
         let review = ReviewInput {
             correct: false,
             response_time_seconds: 30.0,
             timer_limit_seconds: 60.0,
+            hints_used: 0,
         };
-        let result = calculate_srs(&card, &review, None);
+        let result = calculate_srs(&card, &review, &steps(), None);
         assert_eq!(result.repetitions, 0);
         assert_eq!(result.interval_days, 1.0);
         assert_eq!(result.quality, 0);
+        assert_eq!(result.learning_state, "relearning");
+        assert_eq!(result.learning_step, 0);
         assert!((result.ease_factor - 2.3).abs() < 0.001);
     }
 
@@ -129,15 +276,19 @@ mod tests {
         let card = SrsCard {
             ease_factor: 2.5,
             interval_days: 0.0,
+            base_interval_days: None,
             repetitions: 0,
             due_date: None,
+            learning_state: "review".to_string(),
+            learning_step: 0,
         };
         let review = ReviewInput {
             correct: true,
             response_time_seconds: 20.0,
             timer_limit_seconds: 60.0,
+            hints_used: 0,
         };
-        let result = calculate_srs(&card, &review, None);
+        let result = calculate_srs(&card, &review, &steps(), None);
         assert_eq!(result.repetitions, 1);
         assert_eq!(result.interval_days, 1.0);
         assert_eq!(result.quality, 5);
@@ -149,15 +300,19 @@ mod tests {
         let card = SrsCard {
             ease_factor: 2.5,
             interval_days: 3.0,
+            base_interval_days: None,
             repetitions: 2,
             due_date: None,
+            learning_state: "review".to_string(),
+            learning_step: 0,
         };
         let review = ReviewInput {
             correct: true,
             response_time_seconds: 45.0,
             timer_limit_seconds: 60.0,
+            hints_used: 0,
         };
-        let result = calculate_srs(&card, &review, None);
+        let result = calculate_srs(&card, &review, &steps(), None);
         assert_eq!(result.repetitions, 3);
         assert!((result.interval_days - 7.65).abs() < 0.1);
         assert_eq!(result.quality, 4);
@@ -168,15 +323,19 @@ mod tests {
         let card = SrsCard {
             ease_factor: 2.5,
             interval_days: 3.0,
+            base_interval_days: None,
             repetitions: 2,
             due_date: None,
+            learning_state: "review".to_string(),
+            learning_step: 0,
         };
         let review = ReviewInput {
             correct: true,
             response_time_seconds: 90.0,
             timer_limit_seconds: 60.0,
+            hints_used: 0,
         };
-        let result = calculate_srs(&card, &review, None);
+        let result = calculate_srs(&card, &review, &steps(), None);
         assert_eq!(result.quality, 3);
         assert!((result.ease_factor - 2.4).abs() < 0.001);
     }
@@ -186,15 +345,19 @@ mod tests {
         let card = SrsCard {
             ease_factor: 1.3,
             interval_days: 1.0,
+            base_interval_days: None,
             repetitions: 0,
             due_date: None,
+            learning_state: "review".to_string(),
+            learning_step: 0,
         };
         let review = ReviewInput {
             correct: false,
             response_time_seconds: 30.0,
             timer_limit_seconds: 60.0,
+            hints_used: 0,
         };
-        let result = calculate_srs(&card, &review, None);
+        let result = calculate_srs(&card, &review, &steps(), None);
         assert!((result.ease_factor - MIN_EASE).abs() < 0.001);
     }
 
@@ -203,18 +366,286 @@ mod tests {
         let card = SrsCard {
             ease_factor: 2.5,
             interval_days: 10.0,
+            base_interval_days: None,
             repetitions: 3,
             due_date: None,
+            learning_state: "review".to_string(),
+            learning_step: 0,
         };
         let review = ReviewInput {
             correct: true,
             response_time_seconds: 30.0,
             timer_limit_seconds: 60.0,
+            hints_used: 0,
         };
         let deadline = (Utc::now() + chrono::Duration::days(5))
             .format("%Y-%m-%d")
             .to_string();
-        let result = calculate_srs(&card, &review, Some(&deadline));
+        let result = calculate_srs(&card, &review, &steps(), Some(&deadline));
         assert!(result.interval_days <= 3.0);
     }
+
+    #[test]
+    fn test_intervals_reexpand_after_deadline() {
+        let card = SrsCard {
+            ease_factor: 2.5,
+            interval_days: 10.0,
+            base_interval_days: None,
+            repetitions: 3,
+            due_date: None,
+            learning_state: "review".to_string(),
+            learning_step: 0,
+        };
+        let review = ReviewInput {
+            correct: true,
+            response_time_seconds: 30.0,
+            timer_limit_seconds: 60.0,
+            hints_used: 0,
+        };
+        let quiz = (Utc::now() + chrono::Duration::days(5))
+            .format("%Y-%m-%d")
+            .to_string();
+        let compressed = calculate_srs(&card, &review, &steps(), Some(&quiz));
+        assert!(compressed.interval_days <= 3.0);
+        let base = compressed.base_interval_days.unwrap();
+        assert!(base > 10.0);
+
+        // Once the quiz has passed, the next interval grows from the
+        // uncompressed one rather than the squeezed one.
+        let after = calculate_srs(
+            &SrsCard {
+                interval_days: compressed.interval_days,
+                base_interval_days: compressed.base_interval_days,
+                repetitions: compressed.repetitions,
+                ..card.clone()
+            },
+            &review,
+            &steps(),
+            None,
+        );
+        assert_eq!(after.base_interval_days, None);
+        assert!(after.interval_days > base);
+    }
+
+    #[test]
+    fn test_hints_lower_quality() {
+        let card = SrsCard {
+            ease_factor: 2.5,
+            interval_days: 3.0,
+            base_interval_days: None,
+            repetitions: 2,
+            due_date: None,
+            learning_state: "review".to_string(),
+            learning_step: 0,
+        };
+        let unaided = ReviewInput {
+            correct: true,
+            response_time_seconds: 20.0,
+            timer_limit_seconds: 60.0,
+            hints_used: 0,
+        };
+        let one_hint = ReviewInput {
+            hints_used: 1,
+            ..unaided.clone()
+        };
+        let fast = calculate_srs(&card, &unaided, &steps(), None);
+        let hinted = calculate_srs(&card, &one_hint, &steps(), None);
+        assert_eq!(fast.quality, 5);
+        assert_eq!(hinted.quality, 4);
+        assert!(hinted.interval_days < fast.interval_days);
+        assert!(hinted.ease_factor < fast.ease_factor);
+    }
+
+    #[test]
+    fn test_heavily_hinted_keeps_repetitions() {
+        let card = SrsCard {
+            ease_factor: 2.5,
+            interval_days: 3.0,
+            base_interval_days: None,
+            repetitions: 2,
+            due_date: None,
+            learning_state: "review".to_string(),
+            learning_step: 0,
+        };
+        let review = ReviewInput {
+            correct: true,
+            response_time_seconds: 50.0,
+            timer_limit_seconds: 60.0,
+            hints_used: 3,
+        };
+        let result = calculate_srs(&card, &review, &steps(), None);
+        assert_eq!(result.quality, 2);
+        assert_eq!(result.repetitions, 2);
+        assert_eq!(result.interval_days, 1.0);
+        assert!((result.ease_factor - 2.35).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_parse_steps() {
+        assert_eq!(parse_steps("1m 10m 1h"), vec![1.0, 10.0, 60.0]);
+        assert_eq!(parse_steps("5, 2d, x, 0m"), vec![5.0, 2880.0]);
+        assert!(parse_steps("").is_empty());
+    }
+
+    #[test]
+    fn test_learning_steps() {
+        let card = SrsCard {
+            ease_factor: 2.5,
+            interval_days: 0.0,
+            base_interval_days: None,
+            repetitions: 0,
+            due_date: None,
+            learning_state: "new".to_string(),
+            learning_step: 0,
+        };
+        let good = ReviewInput {
+            correct: true,
+            response_time_seconds: 45.0,
+            timer_limit_seconds: 60.0,
+            hints_used: 0,
+        };
+        let failed = ReviewInput {
+            correct: false,
+            ..good.clone()
+        };
+
+        let first = calculate_srs(&card, &good, &steps(), None);
+        assert_eq!(first.learning_state, "learning");
+        assert_eq!(first.learning_step, 1);
+        assert_eq!(minutes_until(&first.due_date), 10);
+        assert_eq!(first.repetitions, 0);
+        assert!((first.ease_factor - 2.5).abs() < 0.001);
+
+        let again = calculate_srs(
+            &SrsCard {
+                learning_state: first.learning_state.clone(),
+                learning_step: first.learning_step,
+                ..card.clone()
+            },
+            &failed,
+            &steps(),
+            None,
+        );
+        assert_eq!(again.learning_step, 0);
+        assert_eq!(minutes_until(&again.due_date), 1);
+
+        let graduated = calculate_srs(
+            &SrsCard {
+                learning_state: "learning".to_string(),
+                learning_step: 2,
+                ..card.clone()
+            },
+            &good,
+            &steps(),
+            None,
+        );
+        assert_eq!(graduated.learning_state, "review");
+        assert_eq!(graduated.repetitions, 1);
+        assert_eq!(graduated.interval_days, 1.0);
+    }
+
+    #[test]
+    fn test_relearning_after_lapse() {
+        let card = SrsCard {
+            ease_factor: 2.5,
+            interval_days: 10.0,
+            base_interval_days: None,
+            repetitions: 3,
+            due_date: None,
+            learning_state: "review".to_string(),
+            learning_step: 0,
+        };
+        let failed = ReviewInput {
+            correct: false,
+            response_time_seconds: 30.0,
+            timer_limit_seconds: 60.0,
+            hints_used: 0,
+        };
+        let lapsed = calculate_srs(&card, &failed, &steps(), None);
+        assert_eq!(minutes_until(&lapsed.due_date), 10);
+
+        let no_steps = LearningSteps {
+            learning: Vec::new(),
+            relearning: Vec::new(),
+        };
+        let lapsed = calculate_srs(&card, &failed, &no_steps, None);
+        assert_eq!(lapsed.learning_state, "review");
+        assert_eq!(minutes_until(&lapsed.due_date), 1440);
+
+        let relearned = calculate_srs(
+            &SrsCard {
+                learning_state: "relearning".to_string(),
+                repetitions: 0,
+                interval_days: 1.0,
+                ..card.clone()
+            },
+            &ReviewInput {
+                correct: true,
+                ..failed.clone()
+            },
+            &steps(),
+            None,
+        );
+        assert_eq!(relearned.learning_state, "review");
+        assert_eq!(relearned.repetitions, 1);
+        assert_eq!(relearned.interval_days, 1.0);
+    }
+
+    #[test]
+    fn test_only_review_failures_count_as_lapses() {
+        let review_card = SrsCard {
+            ease_factor: 2.5,
+            interval_days: 10.0,
+            base_interval_days: None,
+            repetitions: 3,
+            due_date: None,
+            learning_state: "review".to_string(),
+            learning_step: 0,
+        };
+        let failed = ReviewInput {
+            correct: false,
+            response_time_seconds: 30.0,
+            timer_limit_seconds: 60.0,
+            hints_used: 0,
+        };
+        let fail_repeatedly = |steps: &LearningSteps, times: usize| {
+            let mut card = review_card.clone();
+            let mut lapses = 0;
+            for _ in 0..times {
+                let result = calculate_srs(&card, &failed, steps, None);
+                lapses += result.lapsed as usize;
+                card = SrsCard {
+                    learning_state: result.learning_state,
+                    learning_step: result.learning_step,
+                    repetitions: result.repetitions,
+                    ease_factor: result.ease_factor,
+                    ..card
+                };
+            }
+            lapses
+        };
+
+        // The first failure moves the card to relearning; failing again there
+        // is not another lapse.
+        assert_eq!(fail_repeatedly(&steps(), 4), 1);
+        // Without relearning steps the card stays in review, so every failure
+        // is a lapse.
+        let no_steps = LearningSteps {
+            learning: Vec::new(),
+            relearning: Vec::new(),
+        };
+        assert_eq!(fail_repeatedly(&no_steps, 4), 4);
+
+        let learning = calculate_srs(
+            &SrsCard {
+                learning_state: "learning".to_string(),
+                repetitions: 0,
+                ..review_card.clone()
+            },
+            &failed,
+            &steps(),
+            None,
+        );
+        assert!(!learning.lapsed);
+    }
 }
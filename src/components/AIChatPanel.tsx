@@ -1,4 +1,12 @@
-import { useState, useRef, useEffect, useCallback, useMemo, type KeyboardEvent } from "react";
+import {
+  useState,
+  useRef,
+  useEffect,
+  useCallback,
+  useMemo,
+  type ClipboardEvent,
+  type KeyboardEvent,
+} from "react";
 import { cn } from "@/lib/utils";
 import { sendChat, setOnProposedCards, type ChatMessage } from "@/lib/ai-chat";
 import { useAppStore } from "@/stores/app-store";
@@ -146,12 +154,14 @@ interface DisplayMessage {
   id: string;
   role: "user" | "assistant";
   content: string;
+  images?: string[];
 }
 
 export function AIChatPanel({ open, onClose }: AIChatPanelProps) {
   const [messages, setMessages] = useState<DisplayMessage[]>([]);
   const [chatHistory, setChatHistory] = useState<ChatMessage[]>([]);
   const [input, setInput] = useState("");
+  const [attachments, setAttachments] = useState<string[]>([]);
   const [loading, setLoading] = useState(false);
   const [toolStatus, setToolStatus] = useState<string | null>(null);
   const scrollRef = useRef<HTMLDivElement>(null);
@@ -228,21 +238,28 @@ export function AIChatPanel({ open, onClose }: AIChatPanelProps) {
 
   const send = useCallback(
     async (text: string) => {
-      if (!text.trim() || loading) return;
+      if ((!text.trim() && attachments.length === 0) || loading) return;
 
+      const images = attachments;
       const userMsg: DisplayMessage = {
         id: crypto.randomUUID(),
         role: "user",
         content: text.trim(),
+        images,
       };
       setMessages((prev) => [...prev, userMsg]);
 
-      const newHistory: ChatMessage[] = [
-        ...chatHistory,
-        { role: "user", content: text.trim() },
-      ];
+      const content: ChatMessage["content"] =
+        images.length === 0
+          ? text.trim()
+          : [
+              ...(text.trim() ? [{ type: "text" as const, text: text.trim() }] : []),
+              ...images.map((url) => ({ type: "image_url" as const, image_url: { url } })),
+            ];
+      const newHistory: ChatMessage[] = [...chatHistory, { role: "user", content }];
       setChatHistory(newHistory);
       setInput("");
+      setAttachments([]);
       setLoading(true);
       setToolStatus(null);
 
@@ -273,9 +290,27 @@ export function AIChatPanel({ open, onClose }: AIChatPanelProps) {
         setToolStatus(null);
       }
     },
-    [chatHistory, loading]
+    [attachments, chatHistory, loading]
   );
 
+  const handlePaste = (e: ClipboardEvent<HTMLTextAreaElement>) => {
+    const files = Array.from(e.clipboardData.items)
+      .filter((item) => item.type.startsWith("image/"))
+      .map((item) => item.getAsFile())
+      .filter((file): file is File => file !== null);
+    if (files.length === 0) return;
+    e.preventDefault();
+    for (const file of files) {
+      const reader = new FileReader();
+      reader.onload = () => {
+        if (typeof reader.result === "string") {
+          setAttachments((prev) => [...prev, reader.result as string]);
+        }
+      };
+      reader.readAsDataURL(file);
+    }
+  };
+
   const handleKeyDown = (e: KeyboardEvent<HTMLTextAreaElement>) => {
     if (e.key === "Enter" && !e.shiftKey) {
       e.preventDefault();
@@ -286,6 +321,7 @@ export function AIChatPanel({ open, onClose }: AIChatPanelProps) {
   const clearChat = () => {
     setMessages([]);
     setChatHistory([]);
+    setAttachments([]);
   };
 
   if (!open) return null;
@@ -358,6 +394,26 @@ export function AIChatPanel({ open, onClose }: AIChatPanelProps) {
 
       {/* ── Input ──────────────────────────────────────── */}
       <div className="border-t border-border/50 px-4 py-3">
+        {attachments.length > 0 && (
+          <div className="flex flex-wrap gap-2 mb-2">
+            {attachments.map((url, i) => (
+              <div key={i} className="relative">
+                <img
+                  src={url}
+                  alt="Pasted image"
+                  className="h-14 w-14 rounded-lg border border-border/60 object-cover"
+                />
+                <button
+                  onClick={() => setAttachments((prev) => prev.filter((_, j) => j !== i))}
+                  className="absolute -top-1.5 -right-1.5 rounded-full bg-background border border-border p-0.5 text-muted-foreground hover:text-destructive"
+                  title="Remove image"
+                >
+                  <X className="h-3 w-3" />
+                </button>
+              </div>
+            ))}
+          </div>
+        )}
         <div
           className={cn(
             "flex items-end gap-2 rounded-2xl border border-border/70 bg-background px-4 py-2.5",
@@ -370,7 +426,8 @@ export function AIChatPanel({ open, onClose }: AIChatPanelProps) {
             value={input}
             onChange={(e) => setInput(e.target.value)}
             onKeyDown={handleKeyDown}
-            placeholder="Ask about your flashcards..."
+            onPaste={handlePaste}
+            placeholder="Ask about your flashcards or paste an image..."
             rows={1}
             className={cn(
               "flex-1 resize-none bg-transparent text-[13px] placeholder:text-muted-foreground/50",
@@ -382,10 +439,10 @@ export function AIChatPanel({ open, onClose }: AIChatPanelProps) {
           />
           <button
             onClick={() => send(input)}
-            disabled={loading || !input.trim()}
+            disabled={loading || (!input.trim() && attachments.length === 0)}
             className={cn(
               "flex h-8 w-8 shrink-0 items-center justify-center rounded-xl transition-all duration-200",
-              input.trim() && !loading
+              (input.trim() || attachments.length > 0) && !loading
                 ? "bg-primary text-white shadow-sm hover:opacity-90 scale-100"
                 : "bg-muted text-muted-foreground/40 scale-95"
             )}
@@ -475,8 +532,17 @@ function MessageBubble({
             : "bg-muted/70 text-foreground rounded-bl-md border border-border/40"
         )}
       >
+        {message.images && message.images.length > 0 && (
+          <div className="flex flex-wrap gap-1.5 mb-1.5">
+            {message.images.map((url, i) => (
+              <img key={i} src={url} alt="Attached image" className="max-h-40 rounded-lg" />
+            ))}
+          </div>
+        )}
         {isUser ? (
-          <p className="text-[13px] leading-relaxed whitespace-pre-wrap">{message.content}</p>
+          message.content && (
+            <p className="text-[13px] leading-relaxed whitespace-pre-wrap">{message.content}</p>
+          )
         ) : (
           <div className="space-y-1 [&_.katex]:text-[13px]">{renderMarkdown(message.content)}</div>
         )}
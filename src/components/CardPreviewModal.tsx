@@ -3,9 +3,10 @@ import { createPortal } from "react-dom";
 import type { Flashcard } from "@/lib/types";
 import { LaTeXRenderer } from "./LaTeXRenderer";
 import { ZoomableImage } from "./ZoomableImage";
+import { SolutionSteps } from "./SolutionSteps";
 import { Button } from "@/components/ui/button";
 import { Badge } from "@/components/ui/badge";
-import { formatDate } from "@/lib/utils";
+import { formatDate, parseSolutionSteps } from "@/lib/utils";
 import { X, ChevronLeft, ChevronRight, Eye, EyeOff, Pencil } from "lucide-react";
 import { Link } from "react-router-dom";
 
@@ -16,11 +17,22 @@ interface CardPreviewModalProps {
   onNavigate: (index: number) => void;
 }
 
-function FlashcardContent({ type, content }: { type: string; content: string }) {
+function FlashcardContent({
+  type,
+  content,
+  altText,
+}: {
+  type: string;
+  content: string;
+  altText?: string | null;
+}) {
   if (type === "latex") {
     return <LaTeXRenderer content={content} />;
   }
-  return <ZoomableImage src={content} alt="Flashcard content" />;
+  if (type === "solution") {
+    return <SolutionSteps steps={parseSolutionSteps(content)} />;
+  }
+  return <ZoomableImage src={content} alt={altText || "Flashcard content"} />;
 }
 
 export function CardPreviewModal({
@@ -131,6 +143,7 @@ export function CardPreviewModal({
             <FlashcardContent
               type={card.question_type}
               content={card.question_content}
+              altText={card.question_ocr}
             />
           </div>
         </div>
@@ -147,6 +160,7 @@ export function CardPreviewModal({
                   <FlashcardContent
                     type={card.answer_type || "latex"}
                     content={card.answer_content}
+                    altText={card.answer_ocr}
                   />
                 </div>
               ) : (
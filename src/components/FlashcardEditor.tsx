@@ -1,20 +1,30 @@
 import { useState, useRef, useEffect, useCallback } from "react";
 import { useNavigate } from "react-router-dom";
 import { TIMER_PRESETS } from "@/lib/constants";
-import type { CreateFlashcardInput } from "@/lib/types";
+import type {
+  CreateFlashcardInput,
+  DifficultyAssessment,
+  SolutionStep,
+  TemplateParam,
+  VerificationStatus,
+} from "@/lib/types";
 import { Button } from "@/components/ui/button";
 import { Textarea } from "@/components/ui/textarea";
 import { Card, CardContent } from "@/components/ui/card";
 import { LaTeXRenderer } from "@/components/LaTeXRenderer";
 import { ImageDisplay, isImagePath } from "@/components/ImageDisplay";
-import { cn } from "@/lib/utils";
+import { VerificationBadge } from "@/components/VerificationBadge";
+import { TemplateParamsEditor } from "@/components/TemplateParamsEditor";
+import { cn, parseSolutionSteps, solutionToText } from "@/lib/utils";
 import {
   Clock, Image, Type, Camera, FileUp, ImagePlus, X,
-  Sparkles, Loader2, ArrowRightLeft, Wand2,
+  Sparkles, Loader2, ArrowRightLeft, Wand2, ShieldCheck, ListOrdered, Plus, Dices,
 } from "lucide-react";
 import * as commands from "@/lib/commands";
 import { useAppStore } from "@/stores/app-store";
 
+type AnswerType = "latex" | "image" | "solution";
+
 interface FlashcardEditorProps {
   initialData?: Partial<CreateFlashcardInput>;
   onSave: (data: CreateFlashcardInput) => void;
@@ -39,8 +49,8 @@ export function FlashcardEditor({
   const [questionContent, setQuestionContent] = useState(
     initialData?.question_content || ""
   );
-  const [answerType, setAnswerType] = useState<"latex" | "image">(
-    (initialData?.answer_type as "latex" | "image") || "latex"
+  const [answerType, setAnswerType] = useState<AnswerType>(
+    (initialData?.answer_type as AnswerType) || "latex"
   );
   const [answerContent, setAnswerContent] = useState(
     initialData?.answer_content || ""
@@ -63,6 +73,13 @@ export function FlashcardEditor({
   const fileInputRef = useRef<HTMLInputElement>(null);
   const answerFileInputRef = useRef<HTMLInputElement>(null);
   const questionTextareaRef = useRef<HTMLTextAreaElement>(null);
+  const [templateParams, setTemplateParams] = useState<TemplateParam[]>(() => {
+    try {
+      return initialData?.template_params ? JSON.parse(initialData.template_params) : [];
+    } catch {
+      return [];
+    }
+  });
   const answerTextareaRef = useRef<HTMLTextAreaElement>(null);
 
   // LLM generation states
@@ -70,7 +87,25 @@ export function FlashcardEditor({
   const [generatingQuestion, setGeneratingQuestion] = useState(false);
   const [convertingQuestion, setConvertingQuestion] = useState(false);
   const [convertingAnswer, setConvertingAnswer] = useState(false);
-  const [assessedTime, setAssessedTime] = useState<number | null>(null);
+  const [assessment, setAssessment] = useState<DifficultyAssessment | null>(null);
+  const [assessError, setAssessError] = useState<string | null>(null);
+  // The last verification result and the question/answer it was made for; it
+  // no longer applies once either is edited.
+  const [verification, setVerification] = useState<{
+    status: VerificationStatus;
+    note: string | null;
+    checkedFor: string;
+  } | null>(() =>
+    initialData?.verification_status
+      ? {
+          status: initialData.verification_status,
+          note: initialData.verification_note ?? null,
+          checkedFor: `${initialData.question_content ?? ""}\u0000${initialData.answer_content ?? ""}`,
+        }
+      : null
+  );
+  const [verifying, setVerifying] = useState(false);
+  const assessedTime = assessment?.estimated_seconds ?? null;
   const [assessingTime, setAssessingTime] = useState(false);
 
   // AI inline card generation
@@ -146,22 +181,23 @@ export function FlashcardEditor({
 
   // Auto-assess difficulty when timer mode is "llm" and question has content
   useEffect(() => {
+    setAssessError(null);
     if (timerMode !== "llm" || !questionContent.trim()) {
-      setAssessedTime(null);
+      setAssessment(null);
       return;
     }
     // Only assess for latex questions (image assessment would need a different approach)
     if (questionType !== "latex") {
-      setAssessedTime(null);
+      setAssessment(null);
       return;
     }
     const timeout = setTimeout(async () => {
       setAssessingTime(true);
       try {
-        const seconds = await commands.assessDifficulty(questionContent);
-        setAssessedTime(seconds);
-      } catch {
-        setAssessedTime(null);
+        setAssessment(await commands.assessDifficulty(questionContent));
+      } catch (err) {
+        setAssessment(null);
+        setAssessError(String(err));
       } finally {
         setAssessingTime(false);
       }
@@ -178,7 +214,24 @@ export function FlashcardEditor({
 
   // Determine if content has been entered (for type locking)
   const questionHasContent = questionContent.trim().length > 0;
-  const answerHasContent = answerContent.trim().length > 0;
+  const solutionSteps =
+    answerType === "solution" ? parseSolutionSteps(answerContent) : [];
+  const answerHasContent =
+    answerType === "solution"
+      ? solutionSteps.some((step) => step.latex.trim() || step.justification.trim())
+      : answerContent.trim().length > 0;
+  // The answer as plain LaTeX text, for commands that take a text answer
+  const answerText =
+    answerType === "solution" ? solutionToText(solutionSteps) : answerContent;
+
+  // Only LaTeX cards can carry placeholders
+  const isTemplate =
+    templateParams.length > 0 && questionType === "latex" && answerType === "latex";
+
+  const currentVerification =
+    verification && verification.checkedFor === `${questionContent}\u0000${answerContent}`
+      ? verification
+      : null;
 
   const IMAGE_DELIMITER = "|||";
   const getAnswerImages = (content: string) => content.split(IMAGE_DELIMITER).filter(Boolean);
@@ -194,6 +247,15 @@ export function FlashcardEditor({
     setAnswerType("latex");
   };
 
+  const setSolutionSteps = (steps: SolutionStep[]) => {
+    setAnswerContent(JSON.stringify(steps));
+  };
+
+  const updateSolutionStep = (index: number, patch: Partial<SolutionStep>) => {
+    const steps = solutionSteps.length > 0 ? solutionSteps : [{ latex: "", justification: "" }];
+    setSolutionSteps(steps.map((step, i) => (i === index ? { ...step, ...patch } : step)));
+  };
+
   const handleRemoveAnswerImage = (index: number) => {
     const images = getAnswerImages(answerContent);
     const newImages = images.filter((_, i) => i !== index);
@@ -207,9 +269,9 @@ export function FlashcardEditor({
 
   const handleSubmit = (e: React.FormEvent) => {
     e.preventDefault();
-    if (!questionContent.trim() && !answerContent.trim()) return;
+    if (!questionContent.trim() && !answerHasContent) return;
 
-    const aHas = answerContent.trim().length > 0;
+    const aHas = answerHasContent;
     const timerSeconds =
       timerMode === "llm"
         ? (assessedTime || 300)
@@ -222,9 +284,18 @@ export function FlashcardEditor({
       question_type: questionType,
       question_content: questionContent,
       answer_type: aHas ? answerType : undefined,
-      answer_content: aHas ? answerContent : undefined,
+      answer_content: !aHas
+        ? undefined
+        : answerType === "solution"
+        ? JSON.stringify(
+            solutionSteps.filter((step) => step.latex.trim() || step.justification.trim())
+          )
+        : answerContent,
       timer_mode: timerMode,
       timer_seconds: timerSeconds,
+      verification_status: currentVerification?.status ?? null,
+      verification_note: currentVerification?.note ?? null,
+      template_params: isTemplate && aHas ? JSON.stringify(templateParams) : null,
     });
   };
 
@@ -299,7 +370,13 @@ export function FlashcardEditor({
         content = await commands.saveImageFromDataUrl(content);
         setQuestionContent(content);
       }
-      const result = await commands.generateAnswer(content, questionType);
+      if (answerType === "solution") {
+        const steps = await commands.generateSolution(content, questionType, answerHasContent);
+        setSolutionSteps(steps);
+        return;
+      }
+      // Asking again with an answer already present means "regenerate", so skip the cache
+      const result = await commands.generateAnswer(content, questionType, answerHasContent);
       if (result) {
         setAnswerType("latex");
         setAnswerContent(result.trim());
@@ -311,6 +388,35 @@ export function FlashcardEditor({
     }
   };
 
+  const handleVerifyAnswer = async () => {
+    if (!questionHasContent || !answerHasContent || verifying) return;
+    setVerifying(true);
+    try {
+      let content = questionContent;
+      if (questionType === "image" && content.startsWith("data:")) {
+        content = await commands.saveImageFromDataUrl(content);
+        setQuestionContent(content);
+      }
+      const result = await commands.verifyAnswer(content, questionType, answerText);
+      setVerification({
+        status: result.status,
+        note: result.checked_form
+          ? `${result.explanation}\nChecked: ${result.checked_form}`
+          : result.explanation,
+        checkedFor: `${content}\u0000${answerContent}`,
+      });
+    } catch (err) {
+      console.error("Failed to verify answer:", err);
+      setVerification({
+        status: "unverified",
+        note: String(err),
+        checkedFor: `${questionContent}\u0000${answerContent}`,
+      });
+    } finally {
+      setVerifying(false);
+    }
+  };
+
   const handleGenerateQuestion = async () => {
     if (!answerHasContent || generatingQuestion) return;
     setGeneratingQuestion(true);
@@ -320,7 +426,10 @@ export function FlashcardEditor({
         content = await commands.saveImageFromDataUrl(content);
         setAnswerContent(content);
       }
-      const result = await commands.generateQuestion(content, answerType);
+      const result =
+        answerType === "solution"
+          ? await commands.generateQuestion(answerText, "latex", questionHasContent)
+          : await commands.generateQuestion(content, answerType, questionHasContent);
       if (result) {
         setQuestionType("latex");
         setQuestionContent(result.trim());
@@ -386,25 +495,9 @@ No other text, just the JSON.`,
         },
         { role: "user", content: prompt },
       ];
-      const raw = await commands.chatCompletion(messages);
-      // Parse response
-      const choices = (raw as Record<string, unknown>).choices as Array<{
-        message: { content: string };
-      }> | undefined;
-      const contentBlocks = (raw as Record<string, unknown>).content as Array<{
-        type: string;
-        text?: string;
-      }> | undefined;
-
-      let text = "";
-      if (choices?.[0]?.message?.content) {
-        text = choices[0].message.content;
-      } else if (contentBlocks) {
-        text = contentBlocks
-          .filter((b) => b.type === "text")
-          .map((b) => b.text || "")
-          .join("");
-      }
+      // No tool rounds: this is a single plain completion
+      const response = await commands.chatCompletion(messages, [], 0);
+      const text = response.content || "";
 
       // Extract JSON from response (handle markdown code blocks)
       const jsonMatch = text.match(/\{[\s\S]*\}/);
@@ -676,12 +769,37 @@ No other text, just the JSON.`,
                   )}
                 </button>
               )}
+              {questionHasContent && answerHasContent && answerType !== "image" && !isTemplate && (
+                <button
+                  type="button"
+                  onClick={handleVerifyAnswer}
+                  disabled={verifying}
+                  className="rounded-md p-1 text-muted-foreground hover:text-primary hover:bg-primary/10 transition-colors"
+                  title="Check the answer with the math engine"
+                >
+                  {verifying ? (
+                    <Loader2 className="h-3.5 w-3.5 animate-spin text-primary" />
+                  ) : (
+                    <ShieldCheck className="h-3.5 w-3.5" />
+                  )}
+                </button>
+              )}
+              {currentVerification && (
+                <VerificationBadge
+                  status={currentVerification.status}
+                  note={currentVerification.note}
+                />
+              )}
             </div>
             <div className="flex items-center gap-1.5">
               <TypeToggle
                 value={answerType}
-                onChange={(t) => setAnswerType(t)}
+                onChange={(t) => {
+                  setAnswerType(t);
+                  setAnswerContent("");
+                }}
                 lockedTo={answerHasContent ? answerType : undefined}
+                allowSolution
               />
               {answerHasContent && (
                 <button
@@ -696,7 +814,67 @@ No other text, just the JSON.`,
             </div>
           </div>
 
-          {answerType === "latex" ? (
+          {answerType === "solution" ? (
+            <div className="space-y-3">
+              {(solutionSteps.length > 0
+                ? solutionSteps
+                : [{ latex: "", justification: "" }]
+              ).map((step, index, steps) => (
+                <div key={index} className="flex gap-3">
+                  <span className="mt-2 flex h-5 w-5 shrink-0 items-center justify-center rounded-full bg-primary/10 text-[10px] font-bold text-primary">
+                    {index + 1}
+                  </span>
+                  <div className="min-w-0 flex-1 space-y-1.5">
+                    <Textarea
+                      value={step.latex}
+                      onChange={(e) => updateSolutionStep(index, { latex: e.target.value })}
+                      placeholder="Expression, e.g. 2x + 3 = 7"
+                      className="min-h-[44px] font-mono text-sm"
+                    />
+                    <input
+                      value={step.justification}
+                      onChange={(e) =>
+                        updateSolutionStep(index, { justification: e.target.value })
+                      }
+                      placeholder="Why this step follows"
+                      className="w-full rounded-lg border border-input bg-background px-3 py-1.5 text-xs outline-none focus:border-primary/40 focus:ring-2 focus:ring-primary/10"
+                    />
+                    {step.latex.trim() && (
+                      <div className="rounded-lg bg-muted/30 px-3 py-2">
+                        <LaTeXRenderer content={`$$${step.latex}$$`} />
+                      </div>
+                    )}
+                  </div>
+                  {steps.length > 1 && (
+                    <button
+                      type="button"
+                      onClick={() => setSolutionSteps(steps.filter((_, i) => i !== index))}
+                      className="mt-2 h-fit rounded-md p-1 text-muted-foreground hover:text-destructive hover:bg-destructive/10 transition-colors"
+                      title="Remove step"
+                    >
+                      <X className="h-3.5 w-3.5" />
+                    </button>
+                  )}
+                </div>
+              ))}
+              <Button
+                type="button"
+                variant="ghost"
+                size="sm"
+                onClick={() =>
+                  setSolutionSteps([
+                    ...(solutionSteps.length > 0
+                      ? solutionSteps
+                      : [{ latex: "", justification: "" }]),
+                    { latex: "", justification: "" },
+                  ])
+                }
+              >
+                <Plus className="h-3.5 w-3.5 mr-1" />
+                Add step
+              </Button>
+            </div>
+          ) : answerType === "latex" ? (
             <div className="space-y-2">
               <Textarea
                 ref={answerTextareaRef}
@@ -815,6 +993,22 @@ No other text, just the JSON.`,
 
       </section>
 
+      {/* Parameters */}
+      {questionType === "latex" && answerType === "latex" && (
+        <section className="space-y-3">
+          <div className="flex items-center gap-2">
+            <Dices className="h-4 w-4 text-muted-foreground" />
+            <h3 className="text-sm font-bold">Random values</h3>
+          </div>
+          <TemplateParamsEditor
+            params={templateParams}
+            onChange={setTemplateParams}
+            questionContent={questionContent}
+            answerContent={answerContent}
+          />
+        </section>
+      )}
+
       {/* Timer */}
       <section className="space-y-3">
         <div className="flex items-center gap-2">
@@ -882,6 +1076,25 @@ No other text, just the JSON.`,
                     ? `${Math.floor(assessedTime / 60)}m ${assessedTime % 60}s`
                     : `${assessedTime}s`}
                 </span>
+                {assessment && (
+                  <>
+                    {" · "}Difficulty{" "}
+                    <span className="font-bold text-foreground">
+                      {assessment.difficulty}/5
+                    </span>
+                    {assessment.topics.length > 0 &&
+                      ` · ${assessment.topics.join(", ")}`}
+                  </>
+                )}
+                {assessment && assessment.prerequisites.length > 0 && (
+                  <span className="block">
+                    Requires: {assessment.prerequisites.join(", ")}
+                  </span>
+                )}
+              </p>
+            ) : assessError ? (
+              <p className="text-xs text-destructive">
+                Could not estimate time: {assessError}
               </p>
             ) : (
               <p className="text-xs text-muted-foreground">
@@ -896,7 +1109,7 @@ No other text, just the JSON.`,
       <div className="flex gap-3 pt-2">
         <Button
           type="submit"
-          disabled={saving || (!questionContent.trim() && !answerContent.trim())}
+          disabled={saving || (!questionContent.trim() && !answerHasContent)}
         >
           {saving ? "Saving..." : "Save Card"}
         </Button>
@@ -933,47 +1146,48 @@ No other text, just the JSON.`,
   );
 }
 
-function TypeToggle({
+function TypeToggle<T extends AnswerType>({
   value,
   onChange,
   lockedTo,
+  allowSolution = false,
 }: {
-  value: "latex" | "image";
-  onChange: (val: "latex" | "image") => void;
-  lockedTo?: "latex" | "image";
+  value: T;
+  onChange: (val: T) => void;
+  lockedTo?: T;
+  allowSolution?: boolean;
 }) {
+  const options: { type: AnswerType; label: string; icon: typeof Type }[] = [
+    { type: "latex", label: "LaTeX", icon: Type },
+    { type: "image", label: "Image", icon: Image },
+  ];
+  if (allowSolution) {
+    options.push({ type: "solution", label: "Steps", icon: ListOrdered });
+  }
+
   return (
     <div className="flex rounded-lg border border-input overflow-hidden">
-      <button
-        type="button"
-        onClick={() => !lockedTo && onChange("latex")}
-        disabled={lockedTo === "image"}
-        className={cn(
-          "flex items-center gap-1.5 px-3 py-1.5 text-xs font-medium transition-colors",
-          value === "latex"
-            ? "bg-primary text-primary-foreground"
-            : "text-muted-foreground hover:text-foreground hover:bg-accent",
-          lockedTo === "image" && "opacity-40 cursor-not-allowed hover:bg-transparent hover:text-muted-foreground"
-        )}
-      >
-        <Type className="h-3 w-3" />
-        LaTeX
-      </button>
-      <button
-        type="button"
-        onClick={() => !lockedTo && onChange("image")}
-        disabled={lockedTo === "latex"}
-        className={cn(
-          "flex items-center gap-1.5 px-3 py-1.5 text-xs font-medium transition-colors",
-          value === "image"
-            ? "bg-primary text-primary-foreground"
-            : "text-muted-foreground hover:text-foreground hover:bg-accent",
-          lockedTo === "latex" && "opacity-40 cursor-not-allowed hover:bg-transparent hover:text-muted-foreground"
-        )}
-      >
-        <Image className="h-3 w-3" />
-        Image
-      </button>
+      {options.map(({ type, label, icon: Icon }) => {
+        const locked = lockedTo !== undefined && lockedTo !== type;
+        return (
+          <button
+            key={type}
+            type="button"
+            onClick={() => !lockedTo && onChange(type as T)}
+            disabled={locked}
+            className={cn(
+              "flex items-center gap-1.5 px-3 py-1.5 text-xs font-medium transition-colors",
+              value === type
+                ? "bg-primary text-primary-foreground"
+                : "text-muted-foreground hover:text-foreground hover:bg-accent",
+              locked && "opacity-40 cursor-not-allowed hover:bg-transparent hover:text-muted-foreground"
+            )}
+          >
+            <Icon className="h-3 w-3" />
+            {label}
+          </button>
+        );
+      })}
     </div>
   );
 }
@@ -44,6 +44,19 @@ export function Layout() {
     return () => mediaQuery.removeEventListener("change", applyTheme);
   }, [theme]);
 
+  // Resume reading image cards in the background if the user opted in, and
+  // pick up new image cards as they are added.
+  useEffect(() => {
+    const startIndexer = () => {
+      commands.startOcrIndexer().catch(() => {});
+    };
+    startIndexer();
+    window.addEventListener("flashmath:data-changed", startIndexer);
+    return () => {
+      window.removeEventListener("flashmath:data-changed", startIndexer);
+    };
+  }, []);
+
   useEffect(() => {
     let unlisten: (() => void) | undefined;
 
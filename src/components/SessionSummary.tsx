@@ -1,5 +1,5 @@
 import type { Flashcard, ReviewResult } from "@/lib/types";
-import { formatTime } from "@/lib/utils";
+import { describeSpeedTrend, formatTime, trendSlope } from "@/lib/utils";
 import { cn } from "@/lib/utils";
 import { Button } from "@/components/ui/button";
 import { Card, CardContent } from "@/components/ui/card";
@@ -48,6 +48,10 @@ export function SessionSummary({
   const accuracy = Math.round((correct / completed.length) * 100);
   const avgTime =
     completed.reduce((acc, c) => acc + c.responseTime, 0) / completed.length;
+  const pace = describeSpeedTrend(
+    trendSlope(completed.map((c) => c.result.speed_ratio)),
+    completed.length
+  );
 
   return (
     <div className="max-w-2xl mx-auto space-y-6 animate-fade-up">
@@ -61,11 +65,12 @@ export function SessionSummary({
       </div>
 
       {/* Stats */}
-      <div className="grid grid-cols-2 gap-3 sm:grid-cols-4 stagger-children">
+      <div className="grid grid-cols-2 gap-3 sm:grid-cols-5 stagger-children">
         <MiniStat label="Reviewed" value={completed.length.toString()} />
         <MiniStat label="Accuracy" value={`${accuracy}%`} accent="text-primary" />
         <MiniStat label="Correct" value={correct.toString()} accent="text-success" />
         <MiniStat label="Avg Time" value={formatTime(avgTime)} />
+        <MiniStat label="Pace" value={pace} />
       </div>
 
       {/* Results */}
@@ -95,6 +100,11 @@ export function SessionSummary({
                   ? item.card.question_content.slice(0, 60)
                   : "[Image]"}
               </span>
+              {item.result.leech && (
+                <Badge variant="destructive" className="text-[10px]">
+                  {item.result.suspended ? "Leech · Suspended" : "Leech"}
+                </Badge>
+              )}
               <span className="text-xs text-muted-foreground tabular-nums">
                 {formatTime(item.responseTime)}
               </span>
@@ -15,6 +15,9 @@ import {
   Zap,
   Library,
   Images,
+  Copy,
+  Bug,
+  Inbox,
 } from "lucide-react";
 import { Button } from "@/components/ui/button";
 import { Input } from "@/components/ui/input";
@@ -174,6 +177,24 @@ export function Sidebar({
           icon={<Images className="h-4 w-4" />}
           active={pathname === "/imports"}
         />
+        <NavItem
+          to="/duplicates"
+          label="Duplicates"
+          icon={<Copy className="h-4 w-4" />}
+          active={pathname === "/duplicates"}
+        />
+        <NavItem
+          to="/leeches"
+          label="Leeches"
+          icon={<Bug className="h-4 w-4" />}
+          active={pathname === "/leeches"}
+        />
+        <NavItem
+          to="/drafts"
+          label="Drafts"
+          icon={<Inbox className="h-4 w-4" />}
+          active={pathname === "/drafts"}
+        />
         <NavItem
           to="/settings"
           label="Settings"
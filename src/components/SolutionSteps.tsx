@@ -0,0 +1,33 @@
+import type { SolutionStep } from "@/lib/types";
+import { LaTeXRenderer } from "./LaTeXRenderer";
+import { cn } from "@/lib/utils";
+
+interface SolutionStepsProps {
+  steps: SolutionStep[];
+  /** How many steps to show; all of them when omitted. */
+  revealed?: number;
+  className?: string;
+}
+
+export function SolutionSteps({ steps, revealed, className }: SolutionStepsProps) {
+  const visible = steps.slice(0, revealed ?? steps.length);
+  return (
+    <ol className={cn("space-y-3", className)}>
+      {visible.map((step, index) => (
+        <li key={index} className="flex gap-3 animate-fade-up">
+          <span className="flex h-5 w-5 shrink-0 items-center justify-center rounded-full bg-primary/10 text-[10px] font-bold text-primary">
+            {index + 1}
+          </span>
+          <div className="min-w-0 flex-1 space-y-1">
+            {step.latex && <LaTeXRenderer content={`$$${step.latex}$$`} />}
+            {step.justification && (
+              <div className="text-xs text-muted-foreground">
+                <LaTeXRenderer content={step.justification} />
+              </div>
+            )}
+          </div>
+        </li>
+      ))}
+    </ol>
+  );
+}
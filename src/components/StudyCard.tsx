@@ -1,15 +1,34 @@
-import type { Flashcard } from "@/lib/types";
+import { useEffect, useState } from "react";
+import type { Flashcard, WorkGrade } from "@/lib/types";
 import { LaTeXRenderer } from "./LaTeXRenderer";
 import { ZoomableImage } from "./ZoomableImage";
+import { VerificationBadge } from "./VerificationBadge";
+import { SolutionSteps } from "./SolutionSteps";
+import { parseSolutionSteps } from "@/lib/utils";
 import { Button } from "@/components/ui/button";
 import { Card, CardContent, CardFooter } from "@/components/ui/card";
-import { Check, X } from "lucide-react";
+import { Input } from "@/components/ui/input";
+import { ArrowRight, Check, ChevronDown, Lightbulb, Loader2, X } from "lucide-react";
 
 interface StudyCardProps {
   card: Flashcard;
   showAnswer: boolean;
   onReveal: () => void;
   onRate: (correct: boolean) => void;
+  /** Hints revealed so far. */
+  hints?: string[];
+  canHint?: boolean;
+  loadingHint?: boolean;
+  onHint?: () => void;
+  /** Grades a typed answer; shown for parametric cards and in typed-answer mode. */
+  onCheckAnswer?: (typed: string) => Promise<void>;
+  /** Result of the typed answer, which then decides the rating. */
+  typedCheck?: { correct: boolean; detail: string | null } | null;
+  /**
+   * Grade of submitted written work. A correct or incorrect verdict becomes
+   * the rating on Continue, with the opposite rating offered as an override.
+   */
+  workGrade?: WorkGrade | null;
 }
 
 export function StudyCard({
@@ -17,7 +36,36 @@ export function StudyCard({
   showAnswer,
   onReveal,
   onRate,
+  hints = [],
+  canHint = false,
+  loadingHint = false,
+  onHint,
+  onCheckAnswer,
+  typedCheck = null,
+  workGrade = null,
 }: StudyCardProps) {
+  const steps =
+    card.answer_type === "solution" ? parseSolutionSteps(card.answer_content) : [];
+  // Solutions are revealed one step at a time, starting with the first.
+  const [revealedSteps, setRevealedSteps] = useState(1);
+  const [typed, setTyped] = useState("");
+  const [checking, setChecking] = useState(false);
+
+  useEffect(() => {
+    setRevealedSteps(1);
+    setTyped("");
+  }, [card.id]);
+
+  const handleCheck = async () => {
+    if (!onCheckAnswer || !typed.trim() || checking) return;
+    setChecking(true);
+    try {
+      await onCheckAnswer(typed);
+    } finally {
+      setChecking(false);
+    }
+  };
+
   return (
     <Card className="overflow-hidden w-full">
       {/* Question */}
@@ -29,21 +77,126 @@ export function StudyCard({
           <FlashcardContent
             type={card.question_type}
             content={card.question_content}
+            altText={card.question_ocr}
           />
         </div>
+        {hints.length > 0 && (
+          <ol className="mt-4 space-y-2">
+            {hints.map((hint, index) => (
+              <li
+                key={index}
+                className="flex gap-2 rounded-lg bg-warning/10 px-3 py-2 text-sm animate-fade-up"
+              >
+                <Lightbulb className="h-4 w-4 mt-0.5 shrink-0 text-warning" />
+                <div className="min-w-0">
+                  <span className="text-[10px] font-bold uppercase tracking-wider text-muted-foreground">
+                    Hint {index + 1}
+                  </span>
+                  <LaTeXRenderer content={hint} />
+                </div>
+              </li>
+            ))}
+          </ol>
+        )}
+        {onCheckAnswer && !showAnswer && (
+          <form
+            className="mt-4 flex gap-2"
+            onSubmit={(e) => {
+              e.preventDefault();
+              handleCheck();
+            }}
+          >
+            <Input
+              value={typed}
+              onChange={(e) => setTyped(e.target.value)}
+              placeholder="Your answer, e.g. 3/4 or 2(x+1)"
+              className="font-mono"
+              autoFocus
+            />
+            <Button type="submit" variant="secondary" disabled={!typed.trim() || checking}>
+              {checking ? <Loader2 className="h-4 w-4 animate-spin" /> : "Check"}
+            </Button>
+          </form>
+        )}
       </CardContent>
 
       {/* Answer */}
       {showAnswer && (
         <CardContent className="p-6 pt-0">
-          <p className="text-[10px] font-bold uppercase tracking-wider text-muted-foreground mb-3">
-            Answer
-          </p>
-          {card.answer_content ? (
+          <div className="flex items-center gap-2 mb-3">
+            <p className="text-[10px] font-bold uppercase tracking-wider text-muted-foreground">
+              Answer
+            </p>
+            {card.verification_status && card.verification_status !== "unverified" && (
+              <VerificationBadge
+                status={card.verification_status}
+                note={card.verification_note}
+              />
+            )}
+          </div>
+          {typedCheck && (
+            <div
+              className={`mb-3 flex items-center gap-2 rounded-lg px-3 py-2 text-sm ${
+                typedCheck.correct
+                  ? "bg-success/10 text-success"
+                  : "bg-destructive/10 text-destructive"
+              }`}
+            >
+              {typedCheck.correct ? <Check className="h-4 w-4" /> : <X className="h-4 w-4" />}
+              <span className="font-mono">{typed}</span>
+              <span>{typedCheck.correct ? "is correct" : "is incorrect"}</span>
+              {typedCheck.detail && (
+                <span className="text-xs text-muted-foreground">· {typedCheck.detail}</span>
+              )}
+            </div>
+          )}
+          {workGrade && (
+            <div
+              className={`mb-3 space-y-1 rounded-lg px-3 py-2 text-sm ${WORK_VERDICT_STYLES[workGrade.verdict]}`}
+            >
+              <p className="font-medium">{WORK_VERDICT_LABELS[workGrade.verdict]}</p>
+              {workGrade.first_wrong_step && (
+                <div className="text-xs">
+                  <span className="text-muted-foreground">First wrong step: </span>
+                  <LaTeXRenderer content={`$${workGrade.first_wrong_step}$`} />
+                </div>
+              )}
+              {workGrade.feedback && (
+                <div className="text-xs text-foreground/80">
+                  <LaTeXRenderer content={workGrade.feedback} />
+                </div>
+              )}
+            </div>
+          )}
+          {steps.length > 0 ? (
+            <div className="rounded-xl bg-success/5 border border-success/20 p-5 space-y-4">
+              <SolutionSteps steps={steps} revealed={revealedSteps} />
+              {revealedSteps < steps.length && (
+                <div className="flex gap-2">
+                  <Button
+                    variant="secondary"
+                    size="sm"
+                    onClick={() => setRevealedSteps((n) => n + 1)}
+                  >
+                    <ChevronDown className="h-4 w-4 mr-1" />
+                    Next step ({revealedSteps}/{steps.length})
+                  </Button>
+                  <Button
+                    variant="ghost"
+                    size="sm"
+                    onClick={() => setRevealedSteps(steps.length)}
+                  >
+                    Show all
+                  </Button>
+                </div>
+              )}
+            </div>
+          ) : card.answer_content ? (
             <div className="rounded-xl bg-success/5 border border-success/20 p-5">
               <FlashcardContent
                 type={card.answer_type || "latex"}
                 content={card.answer_content}
+                altText={card.answer_ocr}
               />
             </div>
           ) : (
@@ -57,9 +210,56 @@ export function StudyCard({
       {/* Actions */}
       <CardFooter className="justify-center gap-3 py-5 border-t border-border">
         {!showAnswer ? (
-          <Button size="lg" onClick={onReveal}>
-            Reveal Answer
+          <>
+            {onHint && canHint && (
+              <Button
+                variant="outline"
+                size="lg"
+                onClick={onHint}
+                disabled={loadingHint}
+                title="Each hint counts as a weaker answer when scheduling"
+              >
+                {loadingHint ? (
+                  <Loader2 className="h-4 w-4 mr-1.5 animate-spin" />
+                ) : (
+                  <Lightbulb className="h-4 w-4 mr-1.5" />
+                )}
+                {hints.length > 0 ? "Next Hint" : "Hint"}
+              </Button>
+            )}
+            <Button size="lg" onClick={onReveal}>
+              Reveal Answer
+            </Button>
+          </>
+        ) : typedCheck ? (
+          <Button
+            size="lg"
+            onClick={() => onRate(typedCheck.correct)}
+            className="min-w-[130px]"
+          >
+            Continue
+            <ArrowRight className="h-4 w-4 ml-1.5" />
           </Button>
+        ) : workGrade && workGrade.verdict !== "partial" ? (
+          <>
+            <Button
+              variant="ghost"
+              size="lg"
+              onClick={() => onRate(workGrade.verdict !== "correct")}
+            >
+              {workGrade.verdict === "correct" ? "Mark Incorrect" : "Mark Correct"}
+            </Button>
+            <Button
+              variant={workGrade.verdict === "correct" ? "success" : "destructive"}
+              size="lg"
+              onClick={() => onRate(workGrade.verdict === "correct")}
+              className="min-w-[130px]"
+              autoFocus
+            >
+              Continue
+              <ArrowRight className="h-4 w-4 ml-1.5" />
+            </Button>
+          </>
         ) : (
           <>
             <Button
@@ -87,12 +287,27 @@ export function StudyCard({
   );
 }
 
+const WORK_VERDICT_LABELS: Record<WorkGrade["verdict"], string> = {
+  correct: "Your work looks correct — rated correct unless you override it",
+  partial: "Partially correct — rate it yourself",
+  incorrect: "Your work has a mistake — rated incorrect unless you override it",
+};
+
+const WORK_VERDICT_STYLES: Record<WorkGrade["verdict"], string> = {
+  correct: "bg-success/10 text-success",
+  partial: "bg-warning/10 text-warning",
+  incorrect: "bg-destructive/10 text-destructive",
+};
+
 function FlashcardContent({
   type,
   content,
+  altText,
 }: {
   type: string;
   content: string;
+  /** OCR text of the image, read out by screen readers. */
+  altText?: string | null;
 }) {
   if (type === "latex") {
     return <LaTeXRenderer content={content} />;
@@ -103,11 +318,15 @@ function FlashcardContent({
     return (
       <div className="flex flex-col gap-4">
         {images.map((src, i) => (
-          <ZoomableImage key={i} src={src} alt={`Flashcard content ${i + 1}`} />
+          <ZoomableImage
+            key={i}
+            src={src}
+            alt={i === 0 && altText ? altText : `Flashcard content ${i + 1}`}
+          />
         ))}
       </div>
     );
   }
 
-  return <ZoomableImage src={content} alt="Flashcard content" />;
+  return <ZoomableImage src={content} alt={altText || "Flashcard content"} />;
 }
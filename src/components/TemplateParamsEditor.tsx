@@ -0,0 +1,151 @@
+import { useState } from "react";
+import type { TemplateInstance, TemplateParam } from "@/lib/types";
+import { Button } from "@/components/ui/button";
+import { LaTeXRenderer } from "./LaTeXRenderer";
+import { Dices, Loader2, Plus, X } from "lucide-react";
+import * as commands from "@/lib/commands";
+
+interface TemplateParamsEditorProps {
+  params: TemplateParam[];
+  onChange: (params: TemplateParam[]) => void;
+  questionContent: string;
+  answerContent: string;
+}
+
+const FIELD_CLASS =
+  "w-full rounded-lg border border-input bg-background px-2 py-1.5 text-xs outline-none focus:border-primary/40 focus:ring-2 focus:ring-primary/10";
+
+function nextName(params: TemplateParam[]): string {
+  const used = new Set(params.map((p) => p.name));
+  return "abcdfghkmnpqrstuvw".split("").find((c) => !used.has(c)) ?? `p${params.length + 1}`;
+}
+
+/** Declares the `{name}` placeholders of a parametric card and previews a draw. */
+export function TemplateParamsEditor({
+  params,
+  onChange,
+  questionContent,
+  answerContent,
+}: TemplateParamsEditorProps) {
+  const [preview, setPreview] = useState<TemplateInstance | null>(null);
+  const [error, setError] = useState<string | null>(null);
+  const [loading, setLoading] = useState(false);
+
+  const update = (index: number, patch: Partial<TemplateParam>) => {
+    onChange(params.map((p, i) => (i === index ? { ...p, ...patch } : p)));
+    setPreview(null);
+  };
+
+  const handlePreview = async () => {
+    setLoading(true);
+    setError(null);
+    try {
+      setPreview(
+        await commands.instantiateTemplate(
+          questionContent,
+          answerContent,
+          JSON.stringify(params)
+        )
+      );
+    } catch (err) {
+      setPreview(null);
+      setError(String(err));
+    } finally {
+      setLoading(false);
+    }
+  };
+
+  return (
+    <div className="space-y-3">
+      <p className="text-xs text-muted-foreground">
+        Write <code className="font-mono">{"{a}"}</code> in the question and an expression such
+        as <code className="font-mono">{"{a} \\cdot {b}"}</code> as the answer. Each review draws
+        new values and checks the typed result.
+      </p>
+      {params.map((param, index) => (
+        <div key={index} className="grid grid-cols-[1fr_1fr_1fr_1fr_auto] items-center gap-2">
+          <input
+            value={param.name}
+            onChange={(e) => update(index, { name: e.target.value.trim() })}
+            placeholder="name"
+            className={`${FIELD_CLASS} font-mono`}
+          />
+          <input
+            type="number"
+            value={param.min}
+            onChange={(e) => update(index, { min: Number(e.target.value) })}
+            title="Minimum"
+            className={FIELD_CLASS}
+          />
+          <input
+            type="number"
+            value={param.max}
+            onChange={(e) => update(index, { max: Number(e.target.value) })}
+            title="Maximum"
+            className={FIELD_CLASS}
+          />
+          <input
+            type="number"
+            min={0}
+            max={6}
+            value={param.decimals}
+            onChange={(e) =>
+              update(index, { decimals: Math.max(0, Math.min(6, Number(e.target.value) || 0)) })
+            }
+            title="Decimal places (0 for integers)"
+            className={FIELD_CLASS}
+          />
+          <button
+            type="button"
+            onClick={() => {
+              onChange(params.filter((_, i) => i !== index));
+              setPreview(null);
+            }}
+            className="rounded-md p-1 text-muted-foreground hover:text-destructive hover:bg-destructive/10 transition-colors"
+            title="Remove parameter"
+          >
+            <X className="h-3.5 w-3.5" />
+          </button>
+        </div>
+      ))}
+      <div className="flex items-center gap-2">
+        <Button
+          type="button"
+          variant="ghost"
+          size="sm"
+          onClick={() =>
+            onChange([...params, { name: nextName(params), min: 1, max: 10, decimals: 0 }])
+          }
+        >
+          <Plus className="h-3.5 w-3.5 mr-1" />
+          Add parameter
+        </Button>
+        {params.length > 0 && (
+          <Button
+            type="button"
+            variant="outline"
+            size="sm"
+            onClick={handlePreview}
+            disabled={loading || !answerContent.trim()}
+          >
+            {loading ? (
+              <Loader2 className="h-3.5 w-3.5 mr-1 animate-spin" />
+            ) : (
+              <Dices className="h-3.5 w-3.5 mr-1" />
+            )}
+            Preview
+          </Button>
+        )}
+      </div>
+      {error && <p className="text-xs text-destructive">{error}</p>}
+      {preview && (
+        <div className="rounded-lg bg-muted/30 px-3 py-2 space-y-1 text-sm">
+          <LaTeXRenderer content={preview.question} />
+          <div className="text-muted-foreground">
+            <LaTeXRenderer content={`Answer: $${preview.answer}$`} />
+          </div>
+        </div>
+      )}
+    </div>
+  );
+}
@@ -0,0 +1,30 @@
+import { Badge } from "@/components/ui/badge";
+import type { VerificationStatus } from "@/lib/types";
+
+const LABELS: Record<VerificationStatus, string> = {
+  verified: "Verified",
+  unverified: "Unverified",
+  disagreement: "Check failed",
+};
+
+const VARIANTS = {
+  verified: "success",
+  unverified: "secondary",
+  disagreement: "destructive",
+} as const;
+
+export function VerificationBadge({
+  status,
+  note,
+  className,
+}: {
+  status: VerificationStatus;
+  note?: string | null;
+  className?: string;
+}) {
+  return (
+    <Badge variant={VARIANTS[status]} title={note ?? undefined} className={className}>
+      {LABELS[status]}
+    </Badge>
+  );
+}
@@ -0,0 +1,130 @@
+import { useEffect, useRef, useState } from "react";
+import type { WorkGrade } from "@/lib/types";
+import { AnnotationCanvas, type Region } from "./AnnotationCanvas";
+import { Button } from "@/components/ui/button";
+import { Camera, Loader2, PenLine, X } from "lucide-react";
+import * as commands from "@/lib/commands";
+
+interface WorkSubmissionProps {
+  flashcardId: string;
+  onGraded: (grade: WorkGrade, imagePath: string) => void;
+}
+
+/**
+ * Lets the student submit a photo or drawing of their written work, optionally
+ * boxed to the relevant part, and has it graded against the card's answer.
+ */
+export function WorkSubmission({ flashcardId, onGraded }: WorkSubmissionProps) {
+  const fileInputRef = useRef<HTMLInputElement>(null);
+  const [dataUrl, setDataUrl] = useState<string | null>(null);
+  const [region, setRegion] = useState<Region | null>(null);
+  const [grading, setGrading] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  useEffect(() => {
+    setDataUrl(null);
+    setRegion(null);
+    setError(null);
+  }, [flashcardId]);
+
+  const readFile = (file: File) => {
+    const reader = new FileReader();
+    reader.onload = () => {
+      setDataUrl(reader.result as string);
+      setRegion(null);
+      setError(null);
+    };
+    reader.readAsDataURL(file);
+  };
+
+  const handlePaste = (e: React.ClipboardEvent) => {
+    const file = Array.from(e.clipboardData.files).find((f) => f.type.startsWith("image/"));
+    if (file) readFile(file);
+  };
+
+  const handleGrade = async () => {
+    if (!dataUrl || grading) return;
+    setGrading(true);
+    setError(null);
+    try {
+      let imagePath = await commands.saveImageFromDataUrl(dataUrl);
+      if (region) {
+        imagePath = await commands.cropRegion(
+          imagePath,
+          Math.round(region.x),
+          Math.round(region.y),
+          Math.round(region.width),
+          Math.round(region.height)
+        );
+      }
+      const grade = await commands.gradeHandwrittenWork(flashcardId, imagePath);
+      onGraded(grade, imagePath);
+    } catch (err) {
+      setError(String(err));
+    } finally {
+      setGrading(false);
+    }
+  };
+
+  return (
+    <div className="space-y-3" onPaste={handlePaste}>
+      <input
+        ref={fileInputRef}
+        type="file"
+        accept="image/*"
+        className="hidden"
+        onChange={(e) => {
+          const file = e.target.files?.[0];
+          if (file) readFile(file);
+          e.target.value = "";
+        }}
+      />
+      {!dataUrl ? (
+        <Button
+          type="button"
+          variant="outline"
+          className="w-full border-dashed"
+          onClick={() => fileInputRef.current?.click()}
+        >
+          <Camera className="h-4 w-4 mr-1.5" />
+          Submit written work (choose or paste a photo)
+        </Button>
+      ) : (
+        <div className="space-y-3 rounded-xl border border-border p-3">
+          <div className="flex items-center justify-between">
+            <p className="text-xs text-muted-foreground">
+              Drag a box around your work to grade just that part.
+            </p>
+            <button
+              type="button"
+              onClick={() => setDataUrl(null)}
+              className="rounded-md p-1 text-muted-foreground hover:text-destructive hover:bg-destructive/10 transition-colors"
+              title="Discard photo"
+            >
+              <X className="h-4 w-4" />
+            </button>
+          </div>
+          <AnnotationCanvas
+            imageUrl={dataUrl}
+            pageIndex={0}
+            regions={region ? [region] : []}
+            regionsByType={region ? [region] : []}
+            activeMode="answer"
+            onRegionAdded={setRegion}
+            onRegionChange={setRegion}
+            onRegionDeleted={() => setRegion(null)}
+          />
+          {error && <p className="text-xs text-destructive">{error}</p>}
+          <Button type="button" onClick={handleGrade} disabled={grading} className="w-full">
+            {grading ? (
+              <Loader2 className="h-4 w-4 mr-1.5 animate-spin" />
+            ) : (
+              <PenLine className="h-4 w-4 mr-1.5" />
+            )}
+            {grading ? "Grading..." : "Grade my work"}
+          </Button>
+        </div>
+      )}
+    </div>
+  );
+}
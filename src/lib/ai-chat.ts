@@ -2,30 +2,31 @@
  * AI Chat service with tool-calling support.
  *
  * Uses OpenAI-compatible message format internally — the Rust backend
- * translates to Anthropic format when needed.  The frontend executes
- * tools locally (it has direct DB access) and loops back to the LLM
- * until no more tool calls are requested.
+ * translates to Anthropic format when needed.  The backend owns the tool
+ * loop and runs data tools (folders, cards, stats) itself; editor tools
+ * are sent back here because only the UI can touch the open card editor.
  */
 
 import * as commands from "./commands";
 import { useAppStore } from "@/stores/app-store";
-import type { Flashcard, Folder, StudyStats, CreateFlashcardInput } from "./types";
+import type { ChatToolCall } from "./types";
 
 // ── Types ──────────────────────────────────────────────────────────
 
+export type ChatContentPart =
+  | { type: "text"; text: string }
+  | { type: "image_url"; image_url: { url: string } };
+
 export interface ChatMessage {
   role: "system" | "user" | "assistant" | "tool";
-  content: string | null;
+  // Parts let a user message carry images (data URLs or image file paths)
+  content: string | ChatContentPart[] | null;
   tool_calls?: ToolCall[];
   tool_call_id?: string;
   name?: string;
 }
 
-interface ToolCall {
-  id: string;
-  type: "function";
-  function: { name: string; arguments: string };
-}
+type ToolCall = ChatToolCall;
 
 interface ToolDef {
   type: "function";
@@ -38,134 +39,6 @@ interface ToolDef {
 
 // ── Tool definitions (OpenAI format) ───────────────────────────────
 
-const TOOLS: ToolDef[] = [
-  {
-    type: "function",
-    function: {
-      name: "get_all_folders",
-      description: "List every deck/folder the user has, including card counts and deadlines.",
-      parameters: { type: "object", properties: {}, required: [] },
-    },
-  },
-  {
-    type: "function",
-    function: {
-      name: "get_flashcards",
-      description:
-        "Get all flashcards in a specific folder. Returns card content, SRS stats (ease, interval, reps, due date), and timer settings.",
-      parameters: {
-        type: "object",
-        properties: {
-          folder_id: { type: "string", description: "The folder ID to fetch cards from" },
-        },
-        required: ["folder_id"],
-      },
-    },
-  },
-  {
-    type: "function",
-    function: {
-      name: "get_due_flashcards",
-      description: "Get flashcards that are currently due for review. Optionally filter by folder.",
-      parameters: {
-        type: "object",
-        properties: {
-          folder_id: { type: "string", description: "Optional folder ID to filter by" },
-        },
-        required: [],
-      },
-    },
-  },
-  {
-    type: "function",
-    function: {
-      name: "get_study_stats",
-      description:
-        "Get study statistics: total cards, due today, overdue, reviewed today, accuracy today. Optionally scoped to a folder.",
-      parameters: {
-        type: "object",
-        properties: {
-          folder_id: { type: "string", description: "Optional folder ID" },
-        },
-        required: [],
-      },
-    },
-  },
-  {
-    type: "function",
-    function: {
-      name: "get_flashcard_detail",
-      description:
-        "Get full details of a single flashcard by ID, including its question content (LaTeX or image path), answer, SRS parameters, and review history.",
-      parameters: {
-        type: "object",
-        properties: {
-          flashcard_id: { type: "string", description: "The flashcard ID" },
-        },
-        required: ["flashcard_id"],
-      },
-    },
-  },
-  {
-    type: "function",
-    function: {
-      name: "get_review_history",
-      description: "Get the review history for a flashcard — timestamps, correct/incorrect, speed ratios, ease changes.",
-      parameters: {
-        type: "object",
-        properties: {
-          flashcard_id: { type: "string", description: "The flashcard ID" },
-        },
-        required: ["flashcard_id"],
-      },
-    },
-  },
-  {
-    type: "function",
-    function: {
-      name: "create_flashcard",
-      description:
-        "Create a single flashcard in a specific folder. Use $...$ for inline math and $$...$$ for display math in question/answer text. The card is saved immediately.",
-      parameters: {
-        type: "object",
-        properties: {
-          folder_id: { type: "string", description: "The folder ID to create the card in" },
-          question: { type: "string", description: "The question text (use $...$ for math)" },
-          answer: { type: "string", description: "The answer text (use $...$ for math)" },
-        },
-        required: ["folder_id", "question"],
-      },
-    },
-  },
-  {
-    type: "function",
-    function: {
-      name: "propose_flashcards",
-      description:
-        "Propose multiple flashcards for the user to review before creating them. Returns a preview. The user must confirm before cards are saved. Use this when the user asks to create many cards at once or when bulk-generating cards on a topic.",
-      parameters: {
-        type: "object",
-        properties: {
-          folder_id: { type: "string", description: "The folder ID to create cards in" },
-          cards: {
-            type: "array",
-            description: "Array of proposed flashcards",
-            items: {
-              type: "object",
-              properties: {
-                question: { type: "string", description: "The question text" },
-                answer: { type: "string", description: "The answer text" },
-              },
-              required: ["question", "answer"],
-            },
-          },
-        },
-        required: ["folder_id", "cards"],
-      },
-    },
-  },
-];
-
 // Editor tools — only included when user is in the card editor
 const EDITOR_TOOLS: ToolDef[] = [
   {
@@ -250,136 +123,9 @@ export function setOnProposedCards(
   _onProposedCards = cb;
 }
 
-async function executeTool(name: string, args: Record<string, unknown>): Promise<string> {
+async function executeEditorTool(name: string, args: Record<string, unknown>): Promise<string> {
   try {
     switch (name) {
-      case "get_all_folders": {
-        const folders: Folder[] = await commands.getFolders();
-        const results = [];
-        for (const f of folders) {
-          const stats: StudyStats = await commands.getStudyStats(f.id);
-          results.push({
-            id: f.id,
-            name: f.name,
-            emoji: f.emoji,
-            deadline: f.deadline,
-            total_cards: stats.total_cards,
-            due_today: stats.due_today,
-            reviewed_today: stats.reviewed_today,
-            accuracy: Math.round(stats.accuracy_today * 100) + "%",
-          });
-        }
-        return JSON.stringify(results, null, 2);
-      }
-
-      case "get_flashcards": {
-        const folderId = args.folder_id as string;
-        const cards: Flashcard[] = await commands.getFlashcards(folderId);
-        const summary = cards.map((c) => ({
-          id: c.id,
-          title: c.title,
-          question_type: c.question_type,
-          question_preview:
-            c.question_type === "latex"
-              ? c.question_content.slice(0, 120)
-              : "[image]",
-          has_answer: Boolean(c.answer_content),
-          timer_mode: c.timer_mode,
-          ease_factor: c.ease_factor,
-          interval_days: Math.round(c.interval_days * 10) / 10,
-          repetitions: c.repetitions,
-          due_date: c.due_date,
-          last_reviewed: c.last_reviewed,
-        }));
-        return JSON.stringify(summary, null, 2);
-      }
-
-      case "get_due_flashcards": {
-        const fId = args.folder_id as string | undefined;
-        const due: Flashcard[] = await commands.getDueFlashcards(fId);
-        const summary = due.map((c) => ({
-          id: c.id,
-          title: c.title,
-          question_preview:
-            c.question_type === "latex"
-              ? c.question_content.slice(0, 120)
-              : "[image]",
-          ease_factor: c.ease_factor,
-          interval_days: Math.round(c.interval_days * 10) / 10,
-          repetitions: c.repetitions,
-          due_date: c.due_date,
-        }));
-        return JSON.stringify(summary, null, 2);
-      }
-
-      case "get_study_stats": {
-        const sid = args.folder_id as string | undefined;
-        const stats: StudyStats = await commands.getStudyStats(sid);
-        return JSON.stringify(stats, null, 2);
-      }
-
-      case "get_flashcard_detail": {
-        const card: Flashcard = await commands.getFlashcard(args.flashcard_id as string);
-        return JSON.stringify(
-          {
-            ...card,
-            question_content:
-              card.question_type === "latex"
-                ? card.question_content
-                : "[image at " + card.question_content + "]",
-            answer_content:
-              card.answer_type === "latex"
-                ? card.answer_content
-                : card.answer_content
-                ? "[image]"
-                : null,
-          },
-          null,
-          2
-        );
-      }
-
-      case "get_review_history": {
-        const reviews = await commands.getReviewHistory(args.flashcard_id as string);
-        return JSON.stringify(reviews.slice(0, 20), null, 2);
-      }
-
-      case "create_flashcard": {
-        const input: CreateFlashcardInput = {
-          folder_id: (args.folder_id as string) || null,
-          question_type: "latex",
-          question_content: args.question as string,
-          answer_type: args.answer ? "latex" : undefined,
-          answer_content: args.answer as string | undefined,
-        };
-        const card = await commands.createFlashcard(input);
-        return JSON.stringify({
-          success: true,
-          card_id: card.id,
-          message: "Flashcard created successfully",
-        });
-      }
-
-      case "propose_flashcards": {
-        const proposedCards = (args.cards as Array<{ question: string; answer: string }>).map(
-          (c) => ({
-            question: c.question,
-            answer: c.answer,
-            folderId: (args.folder_id as string) || null,
-          })
-        );
-        // Notify the UI about proposed cards
-        if (_onProposedCards) {
-          _onProposedCards(proposedCards);
-        }
-        return JSON.stringify({
-          success: true,
-          count: proposedCards.length,
-          message: `Proposed ${proposedCards.length} cards. The user will review and confirm them in the UI.`,
-        });
-      }
-
-      // ── Editor tools ──
       case "set_editor_question": {
         const cbs = useAppStore.getState().editorCallbacks;
         if (!cbs) return JSON.stringify({ error: "No card editor is open" });
@@ -432,48 +178,6 @@ async function executeTool(name: string, args: Record<string, unknown>): Promise
   }
 }
 
-// ── Normalise LLM response ────────────────────────────────────────
-
-interface NormalisedResponse {
-  content: string | null;
-  toolCalls: ToolCall[];
-}
-
-function normaliseResponse(raw: Record<string, unknown>): NormalisedResponse {
-  // OpenAI format
-  const choices = raw.choices as Array<{ message: Record<string, unknown> }> | undefined;
-  if (choices?.[0]?.message) {
-    const msg = choices[0].message;
-    return {
-      content: (msg.content as string) ?? null,
-      toolCalls: (msg.tool_calls as ToolCall[]) ?? [],
-    };
-  }
-
-  // Anthropic format
-  const contentBlocks = raw.content as Array<Record<string, unknown>> | undefined;
-  if (contentBlocks) {
-    let text = "";
-    const toolCalls: ToolCall[] = [];
-    for (const block of contentBlocks) {
-      if (block.type === "text") text += block.text;
-      if (block.type === "tool_use") {
-        toolCalls.push({
-          id: block.id as string,
-          type: "function",
-          function: {
-            name: block.name as string,
-            arguments: JSON.stringify(block.input),
-          },
-        });
-      }
-    }
-    return { content: text || null, toolCalls };
-  }
-
-  return { content: null, toolCalls: [] };
-}
-
 // ── Main chat function ─────────────────────────────────────────────
 
 const SYSTEM_PROMPT = `You are FlashMath AI, a helpful study assistant embedded in a flashcard app. You have access to tools that let you look up the user's decks, flashcards, review history, and study statistics. You can also CREATE flashcards.
@@ -503,7 +207,6 @@ export async function sendChat(
 ): Promise<ChatMessage> {
   const editorOpen = useAppStore.getState().editorCallbacks !== null;
   const editorCtx = useAppStore.getState().aiEditorContext;
-  const tools = editorOpen ? [...TOOLS, ...EDITOR_TOOLS] : TOOLS;
 
   let systemPrompt = SYSTEM_PROMPT;
   if (editorOpen) {
@@ -516,39 +219,58 @@ The user currently has a card editor open${editorCtx?.folderName ? ` for deck "$
 Use these tools when the user asks you to help write, edit, or fill in their card content.`;
   }
 
-  const messages: ChatMessage[] = [
+  let messages: ChatMessage[] = [
     { role: "system", content: systemPrompt },
     ...history,
   ];
 
-  for (let round = 0; round < MAX_TOOL_ROUNDS; round++) {
-    const raw = await commands.chatCompletion(messages, tools);
-    const { content, toolCalls } = normaliseResponse(raw);
+  const { listen } = await import("@tauri-apps/api/event");
+  const unlisten = await listen<string>("chat-tool-call", (event) =>
+    onToolCall?.(event.payload)
+  );
 
-    if (toolCalls.length === 0) {
-      // Done — return the assistant's final message
-      return { role: "assistant", content: content || "(No response)" };
-    }
+  try {
+    let dataChanged = false;
+    for (let round = 0; round < MAX_TOOL_ROUNDS; round++) {
+      const response = await commands.chatCompletion(
+        messages,
+        editorOpen ? EDITOR_TOOLS : [],
+        MAX_TOOL_ROUNDS
+      );
+      dataChanged = dataChanged || response.data_changed;
+      if (response.proposed_cards.length > 0 && _onProposedCards) {
+        _onProposedCards(
+          response.proposed_cards.map((c) => ({
+            question: c.question,
+            answer: c.answer,
+            folderId: c.folder_id,
+          }))
+        );
+      }
+
+      messages = response.messages as ChatMessage[];
+      if (response.pending_tool_calls.length === 0) {
+        if (dataChanged) {
+          window.dispatchEvent(new CustomEvent("flashmath:data-changed"));
+        }
+        return { role: "assistant", content: response.content || "(No response)" };
+      }
 
-    // The assistant wants to call tools — add its message with tool_calls
-    messages.push({ role: "assistant", content, tool_calls: toolCalls });
-
-    // Execute each tool and add results
-    for (const tc of toolCalls) {
-      onToolCall?.(tc.function.name);
-      const args = JSON.parse(tc.function.arguments || "{}");
-      const result = await executeTool(tc.function.name, args);
-      messages.push({
-        role: "tool",
-        content: result,
-        tool_call_id: tc.id,
-        name: tc.function.name,
-      });
+      // Editor tools run here, then the backend picks the loop back up
+      for (const tc of response.pending_tool_calls) {
+        onToolCall?.(tc.function.name);
+        const args = JSON.parse(tc.function.arguments || "{}");
+        const result = await executeEditorTool(tc.function.name, args);
+        messages.push({
+          role: "tool",
+          content: result,
+          tool_call_id: tc.id,
+          name: tc.function.name,
+        });
+      }
     }
+    return { role: "assistant", content: "(No response)" };
+  } finally {
+    unlisten();
   }
-
-  // Exhausted rounds — ask the LLM for a final answer without tools
-  const finalRaw = await commands.chatCompletion(messages, undefined);
-  const { content } = normaliseResponse(finalRaw);
-  return { role: "assistant", content: content || "(No response)" };
 }
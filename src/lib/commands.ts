@@ -1,6 +1,35 @@
 import type {
+  AnswerGrade,
+  AnswerVerification,
+  ChatResponse,
+  CreateGenerationJobInput,
+  DailyQueue,
+  DifficultyAssessment,
+  DuplicateCluster,
+  EmbeddingConfig,
+  EmbeddingProgress,
+  EmbeddingRunResult,
+  ExamPlan,
+  GenerationJob,
+  GenerationJobItem,
+  GenerationJobProgress,
+  LeechCard,
+  LLMCacheStats,
   LLMConfig,
+  LLMUsageSummary,
+  OcrConfig,
+  OcrIndexResult,
+  OcrIndexStatus,
+  PrerequisiteCard,
   ReviewInput,
+  SearchFilters,
+  SearchHit,
+  SessionAnalytics,
+  SessionFolder,
+  SolutionStep,
+  TemplateCheck,
+  TemplateInstance,
+  WorkGrade,
 } from "./types";
 import * as db from "./database";
 
@@ -13,13 +42,26 @@ export {
   setFolderEmoji,
   setFolderDeadline,
   setFolderReviewSettings,
+  setFolderLeechSettings,
+  setFolderLearningSteps,
+  setFolderNewCardSettings,
+  setFolderTargetRecall,
+  getFolderMilestones,
+  createFolderMilestone,
+  deleteFolderMilestone,
   getFlashcards,
   getFlashcard,
   createFlashcard,
   updateFlashcard,
   deleteFlashcard,
   moveFlashcard,
+  setFlashcardSuspended,
+  startStudySession,
+  saveStudySessionProgress,
+  endStudySession,
+  getUnfinishedStudySession,
   getDueFlashcards,
+  getCardFamilies,
   getReviewHistory,
   getStudyStats,
 } from "./database";
@@ -29,7 +71,13 @@ export async function submitReview(data: ReviewInput) {
   return db.submitReview(
     data.flashcard_id,
     data.correct,
-    data.response_time_seconds
+    data.response_time_seconds,
+    data.hints_used ?? 0,
+    data.template_seed ?? null,
+    data.typed_answer ?? null,
+    data.work_image ?? null,
+    data.work_verdict ?? null,
+    data.session_id ?? null
   );
 }
 
@@ -56,26 +104,239 @@ export const cropRegion = (
   height: number
 ) => invoke<string>("crop_region", { imagePath, x, y, width, height });
 
-// LLM
-export const ocrImage = (imagePath: string) =>
-  invoke<string>("ocr_image", { imagePath });
-export const assessDifficulty = (latex: string) =>
-  invoke<number>("assess_difficulty", { latex });
-export const generateImageTitle = (imagePath: string) =>
-  invoke<string>("generate_image_title", { imagePath });
-export const generateLatexTitle = (latex: string) =>
-  invoke<string>("generate_latex_title", { latex });
-export const generateAnswer = (questionContent: string, questionType: string) =>
-  invoke<string>("generate_answer", { questionContent, questionType });
-export const generateQuestion = (answerContent: string, answerType: string) =>
-  invoke<string>("generate_question", { answerContent, answerType });
-export const convertImageToText = (imagePath: string, role: string) =>
-  invoke<string>("convert_image_to_text", { imagePath, role });
+// LLM — pass `regenerate` to skip the on-disk response cache
+export const ocrImage = (imagePath: string, regenerate = false) =>
+  invoke<string>("ocr_image", { imagePath, regenerate });
+export const assessDifficulty = (latex: string, regenerate = false) =>
+  invoke<DifficultyAssessment>("assess_difficulty", { latex, regenerate });
+export const generateImageTitle = (imagePath: string, regenerate = false) =>
+  invoke<string>("generate_image_title", { imagePath, regenerate });
+export const generateLatexTitle = (latex: string, regenerate = false) =>
+  invoke<string>("generate_latex_title", { latex, regenerate });
+export const generateAnswer = (
+  questionContent: string,
+  questionType: string,
+  regenerate = false
+) => invoke<string>("generate_answer", { questionContent, questionType, regenerate });
+export const generateSolution = (
+  questionContent: string,
+  questionType: string,
+  regenerate = false
+) =>
+  invoke<SolutionStep[]>("generate_solution", { questionContent, questionType, regenerate });
+export const generateVariants = (flashcardId: string, count = 3) =>
+  invoke<string[]>("generate_variants", { flashcardId, count });
+export const generateHints = (flashcardId: string, regenerate = false) =>
+  invoke<string[]>("generate_hints", { flashcardId, regenerate });
+export const verifyAnswer = (
+  questionContent: string,
+  questionType: string,
+  answerContent: string,
+  regenerate = false
+) =>
+  invoke<AnswerVerification>("verify_answer", {
+    questionContent,
+    questionType,
+    answerContent,
+    regenerate,
+  });
+export const verifyFlashcard = (flashcardId: string) =>
+  invoke<AnswerVerification>("verify_flashcard", { flashcardId });
 
+// Parametric cards — values are drawn locally from a seed
+export const instantiateTemplate = (
+  questionContent: string,
+  answerContent: string,
+  templateParams: string,
+  seed: number | null = null
+) =>
+  invoke<TemplateInstance>("instantiate_template", {
+    questionContent,
+    answerContent,
+    templateParams,
+    seed,
+  });
+export const checkTemplateAnswer = (
+  answerContent: string,
+  templateParams: string,
+  seed: number,
+  typedAnswer: string
+) =>
+  invoke<TemplateCheck>("check_template_answer", {
+    answerContent,
+    templateParams,
+    seed,
+    typedAnswer,
+  });
+export const gradeTypedAnswer = (
+  answerType: string | null,
+  answerContent: string,
+  typedAnswer: string
+) => invoke<AnswerGrade>("grade_typed_answer", { answerType, answerContent, typedAnswer });
+export const gradeHandwrittenWork = (flashcardId: string, imagePath: string, regenerate = false) =>
+  invoke<WorkGrade>("grade_handwritten_work", { flashcardId, imagePath, regenerate });
+
+export const generateQuestion = (
+  answerContent: string,
+  answerType: string,
+  regenerate = false
+) => invoke<string>("generate_question", { answerContent, answerType, regenerate });
+export const convertImageToText = (imagePath: string, role: string, regenerate = false) =>
+  invoke<string>("convert_image_to_text", { imagePath, role, regenerate });
+export const clearLLMCache = () => invoke<LLMCacheStats>("clear_llm_cache");
+export const getLLMCacheStats = () => invoke<LLMCacheStats>("get_llm_cache_stats");
+export const getOcrConfig = () => invoke<OcrConfig>("get_ocr_config");
+export const setOcrConfig = (config: OcrConfig) =>
+  invoke<void>("set_ocr_config", { config });
+export const localOcrAvailable = () => invoke<boolean>("local_ocr_available");
+export const testLocalOcr = (modelPath: string, imagePath: string) =>
+  invoke<string>("test_local_ocr", { modelPath, imagePath });
+export const getLLMUsageSummary = (days?: number) =>
+  invoke<LLMUsageSummary>("get_llm_usage_summary", { days: days ?? null });
+
+// Runs the chat tool loop in the backend. `clientTools` are UI-only tools the
+// backend hands back in `pending_tool_calls` instead of running itself.
 // eslint-disable-next-line @typescript-eslint/no-explicit-any
-export const chatCompletion = (messages: any[], tools?: any[]) =>
-  // eslint-disable-next-line @typescript-eslint/no-explicit-any
-  invoke<any>("chat_completion", { messages, tools: tools ?? null });
+export const chatCompletion = (messages: any[], clientTools: any[] = [], maxIterations?: number) =>
+  invoke<ChatResponse>("chat_completion", {
+    messages,
+    clientTools,
+    maxIterations: maxIterations ?? null,
+  });
+
+// Batch generation jobs
+export const createGenerationJob = (input: CreateGenerationJobInput) =>
+  invoke<GenerationJob>("create_generation_job", { input });
+export const listGenerationJobs = () =>
+  invoke<GenerationJob[]>("list_generation_jobs");
+export const getGenerationJobItems = (jobId: string) =>
+  invoke<GenerationJobItem[]>("get_generation_job_items", { jobId });
+export const pauseGenerationJob = (jobId: string) =>
+  invoke<void>("pause_generation_job", { jobId });
+export const resumeGenerationJob = (jobId: string) =>
+  invoke<void>("resume_generation_job", { jobId });
+export const cancelGenerationJob = (jobId: string) =>
+  invoke<void>("cancel_generation_job", { jobId });
+export const acceptGenerationJobItems = async (jobId: string, itemIds: string[]) => {
+  const created = await invoke<string[]>("accept_generation_job_items", { jobId, itemIds });
+  window.dispatchEvent(new CustomEvent("flashmath:data-changed"));
+  return created;
+};
+
+export async function onGenerationJobProgress(
+  handler: (progress: GenerationJobProgress) => void
+) {
+  const { listen } = await import("@tauri-apps/api/event");
+  return listen<GenerationJobProgress>("generation-job-progress", (event) =>
+    handler(event.payload)
+  );
+}
+
+// Card embeddings and duplicate detection
+export const getEmbeddingConfig = () => invoke<EmbeddingConfig>("get_embedding_config");
+export const setEmbeddingConfig = (config: EmbeddingConfig) =>
+  invoke<void>("set_embedding_config", { config });
+export const embedCards = () => invoke<EmbeddingRunResult>("embed_cards");
+export const findDuplicateCards = (threshold?: number) =>
+  invoke<DuplicateCluster[]>("find_duplicate_cards", { threshold: threshold ?? null });
+export const mergeDuplicateCards = async (keepId: string, removeIds: string[]) => {
+  await invoke<void>("merge_duplicate_cards", { keepId, removeIds });
+  window.dispatchEvent(new CustomEvent("flashmath:data-changed"));
+};
+
+export async function onEmbeddingProgress(handler: (progress: EmbeddingProgress) => void) {
+  const { listen } = await import("@tauri-apps/api/event");
+  return listen<EmbeddingProgress>("embedding-progress", (event) => handler(event.payload));
+}
+
+// Search
+export const searchCards = (
+  query: string,
+  filters: SearchFilters = {},
+  semantic = false,
+  limit?: number
+) => invoke<SearchHit[]>("search_cards", { query, filters, semantic, limit: limit ?? null });
+export const indexCardOcr = (limit?: number) =>
+  invoke<OcrIndexResult>("index_card_ocr", { limit: limit ?? null });
+export const getOcrIndexStatus = () => invoke<OcrIndexStatus>("get_ocr_index_status");
+export const startOcrIndexer = () => invoke<OcrIndexStatus>("start_ocr_indexer");
+
+export async function onOcrIndexProgress(handler: (progress: OcrIndexResult) => void) {
+  const { listen } = await import("@tauri-apps/api/event");
+  return listen<OcrIndexResult>("ocr-index-progress", (event) => handler(event.payload));
+}
+
+// Leeches
+export const getLeeches = (folderId?: string | null) =>
+  invoke<LeechCard[]>("get_leeches", { folderId: folderId ?? null });
+export const suggestPrerequisiteCards = (flashcardId: string, count?: number) =>
+  invoke<PrerequisiteCard[]>("suggest_prerequisite_cards", {
+    flashcardId,
+    count: count ?? null,
+  });
+
+/** Today's queue under the folders' review and new-card limits. */
+export const buildDailyQueue = (folderId?: string | null, cardIds?: string[] | null) =>
+  invoke<DailyQueue>("build_daily_queue", {
+    folderId: folderId ?? null,
+    cardIds: cardIds ?? null,
+  });
+/** Interleaves the given decks (or every deck with due cards) into one session. */
+export const buildSessionQueue = (
+  folders?: SessionFolder[] | null,
+  cardIds?: string[] | null,
+  maxCards?: number | null
+) =>
+  invoke<DailyQueue>("build_session_queue", {
+    folders: folders ?? null,
+    cardIds: cardIds ?? null,
+    maxCards: maxCards ?? null,
+  });
+
+// Exam planning. Planning moves the planned cards' due dates.
+export const planExam = async (folderId: string) => {
+  const plan = await invoke<ExamPlan>("plan_exam", { folderId });
+  window.dispatchEvent(new CustomEvent("flashmath:data-changed"));
+  return plan;
+};
+export const getExamPlan = (folderId: string) =>
+  invoke<ExamPlan>("get_exam_plan", { folderId });
+/** Re-plans every deck with a deadline, e.g. after a study session. */
+export const replanExams = async () => {
+  const planned = await invoke<number>("replan_exams");
+  if (planned > 0) window.dispatchEvent(new CustomEvent("flashmath:data-changed"));
+  return planned;
+};
+
+// Study session analytics
+export const getStudySessions = (folderId?: string | null, limit?: number) =>
+  invoke<SessionAnalytics[]>("get_study_sessions", {
+    folderId: folderId ?? null,
+    limit: limit ?? null,
+  });
+export const getSessionAnalytics = (sessionId: string) =>
+  invoke<SessionAnalytics>("get_session_analytics", { sessionId });
+
+// Card states and manual scheduling. Each returns the number of cards changed.
+async function changeCards(cmd: string, args: Record<string, unknown>) {
+  const changed = await invoke<number>(cmd, args);
+  window.dispatchEvent(new CustomEvent("flashmath:data-changed"));
+  return changed;
+}
+
+export const setCardsSuspended = (flashcardIds: string[], suspended: boolean) =>
+  changeCards("set_cards_suspended", { flashcardIds, suspended });
+/** Hides cards until `until` (YYYY-MM-DD or ISO timestamp), or tomorrow. */
+export const buryCards = (flashcardIds: string[], until?: string | null) =>
+  changeCards("bury_cards", { flashcardIds, until: until ?? null });
+export const unburyCards = (flashcardIds: string[]) =>
+  changeCards("unbury_cards", { flashcardIds });
+export const rescheduleCards = (flashcardIds: string[], dueDate: string) =>
+  changeCards("reschedule_cards", { flashcardIds, dueDate });
+export const resetCards = (flashcardIds: string[]) =>
+  changeCards("reset_cards", { flashcardIds });
+export const forgetCards = (flashcardIds: string[]) =>
+  changeCards("forget_cards", { flashcardIds });
 
 // Files
 export const saveImageFromDataUrl = (dataUrl: string) =>
@@ -1,19 +1,39 @@
 import type {
   Folder,
+  FolderMilestone,
   Flashcard,
   CreateFlashcardInput,
   UpdateFlashcardInput,
+  LearningState,
+  NewCardOrder,
   Review,
+  SessionAnswer,
+  StudySession,
   StudyStats,
 } from "./types";
 import {
   DEFAULT_AUTO_TARGET_REPS,
+  DEFAULT_LEARNING_STEPS,
+  DEFAULT_LEECH_THRESHOLD,
+  DEFAULT_NEW_CARDS_PER_DAY,
+  DEFAULT_RELEARNING_STEPS,
   DEFAULT_REVIEW_CARDS_PER_DAY,
+  DEFAULT_TARGET_RECALL,
+  getCardState,
   getFolderReviewMode,
   getScheduledDueCards,
+  isLearning,
+  nearestDeadline,
+  parseLearningSteps,
   sanitizeAutoTargetReps,
+  sanitizeLearningSteps,
+  sanitizeLeechThreshold,
+  sanitizeNewCardOrder,
+  sanitizeNewCardsPerDay,
   sanitizeReviewCardsPerDay,
+  sanitizeTargetRecall,
 } from "./review-policy";
+import { toDateKey } from "./utils";
 
 import type Database from "@tauri-apps/plugin-sql";
 
@@ -23,8 +43,10 @@ const LOCAL_STORAGE_KEY = "flashmath_local_db_v1";
 
 interface LocalDb {
   folders: Folder[];
+  folder_milestones: FolderMilestone[];
   flashcards: Flashcard[];
   reviews: Review[];
+  study_sessions: StudySession[];
   settings: Record<string, string>;
 }
 
@@ -42,22 +64,45 @@ function isTauriRuntime(): boolean {
 
 function getLocalDb(): LocalDb {
   if (!isBrowser()) {
-    return { folders: [], flashcards: [], reviews: [], settings: {} };
+    return {
+      folders: [],
+      folder_milestones: [],
+      flashcards: [],
+      reviews: [],
+      study_sessions: [],
+      settings: {},
+    };
   }
   const raw = window.localStorage.getItem(LOCAL_STORAGE_KEY);
   if (!raw) {
-    return { folders: [], flashcards: [], reviews: [], settings: {} };
+    return {
+      folders: [],
+      folder_milestones: [],
+      flashcards: [],
+      reviews: [],
+      study_sessions: [],
+      settings: {},
+    };
   }
   try {
     const parsed = JSON.parse(raw) as LocalDb;
     return {
       folders: parsed.folders ?? [],
+      folder_milestones: parsed.folder_milestones ?? [],
       flashcards: parsed.flashcards ?? [],
       reviews: parsed.reviews ?? [],
+      study_sessions: parsed.study_sessions ?? [],
       settings: parsed.settings ?? {},
     };
   } catch {
-    return { folders: [], flashcards: [], reviews: [], settings: {} };
+    return {
+      folders: [],
+      folder_milestones: [],
+      flashcards: [],
+      reviews: [],
+      study_sessions: [],
+      settings: {},
+    };
   }
 }
 
@@ -113,6 +158,13 @@ function normalizeFolder(folder: Folder): Folder {
     review_cards_per_day: sanitizeReviewCardsPerDay(folder.review_cards_per_day),
     review_target_mode: getFolderReviewMode(folder),
     auto_target_reps: sanitizeAutoTargetReps(folder.auto_target_reps),
+    leech_threshold: sanitizeLeechThreshold(folder.leech_threshold),
+    leech_action: folder.leech_action === "tag" ? "tag" : "suspend",
+    learning_steps: sanitizeLearningSteps(folder.learning_steps, DEFAULT_LEARNING_STEPS),
+    relearning_steps: sanitizeLearningSteps(folder.relearning_steps, DEFAULT_RELEARNING_STEPS),
+    new_cards_per_day: sanitizeNewCardsPerDay(folder.new_cards_per_day),
+    new_card_order: sanitizeNewCardOrder(folder.new_card_order),
+    target_recall: sanitizeTargetRecall(folder.target_recall),
   };
 }
 
@@ -147,6 +199,13 @@ export async function createFolder(name: string): Promise<Folder> {
       review_cards_per_day: DEFAULT_REVIEW_CARDS_PER_DAY,
       review_target_mode: "fixed",
       auto_target_reps: DEFAULT_AUTO_TARGET_REPS,
+      leech_threshold: DEFAULT_LEECH_THRESHOLD,
+      leech_action: "suspend",
+      learning_steps: DEFAULT_LEARNING_STEPS,
+      relearning_steps: DEFAULT_RELEARNING_STEPS,
+      new_cards_per_day: DEFAULT_NEW_CARDS_PER_DAY,
+      new_card_order: "created",
+      target_recall: DEFAULT_TARGET_RECALL,
       created_at: now,
       updated_at: now,
     };
@@ -194,6 +253,9 @@ export async function deleteFolder(id: string): Promise<void> {
   if (await useLocalMode()) {
     const localDb = getLocalDb();
     localDb.folders = localDb.folders.filter((folder) => folder.id !== id);
+    localDb.folder_milestones = localDb.folder_milestones.filter(
+      (milestone) => milestone.folder_id !== id
+    );
     localDb.flashcards = localDb.flashcards.map((card) =>
       card.folder_id === id ? { ...card, folder_id: null } : card
     );
@@ -292,6 +354,195 @@ export async function setFolderReviewSettings(
   emitDataChanged();
 }
 
+export async function setFolderLeechSettings(
+  id: string,
+  leechThreshold: number,
+  leechAction: Folder["leech_action"]
+): Promise<void> {
+  const nextThreshold = sanitizeLeechThreshold(leechThreshold);
+  const nextAction = leechAction === "tag" ? "tag" : "suspend";
+
+  if (await useLocalMode()) {
+    const localDb = getLocalDb();
+    localDb.folders = localDb.folders.map((folder) =>
+      folder.id === id
+        ? {
+            ...folder,
+            leech_threshold: nextThreshold,
+            leech_action: nextAction,
+            updated_at: nowISO(),
+          }
+        : folder
+    );
+    saveLocalDb(localDb);
+    emitDataChanged();
+    return;
+  }
+
+  const db = await getDb();
+  await db.execute(
+    `UPDATE folders SET leech_threshold = $1, leech_action = $2, updated_at = $3
+     WHERE id = $4`,
+    [nextThreshold, nextAction, nowISO(), id]
+  );
+  emitDataChanged();
+}
+
+export async function setFolderLearningSteps(
+  id: string,
+  learningSteps: string,
+  relearningSteps: string
+): Promise<void> {
+  const nextLearningSteps = sanitizeLearningSteps(learningSteps, DEFAULT_LEARNING_STEPS);
+  const nextRelearningSteps = sanitizeLearningSteps(relearningSteps, DEFAULT_RELEARNING_STEPS);
+
+  if (await useLocalMode()) {
+    const localDb = getLocalDb();
+    localDb.folders = localDb.folders.map((folder) =>
+      folder.id === id
+        ? {
+            ...folder,
+            learning_steps: nextLearningSteps,
+            relearning_steps: nextRelearningSteps,
+            updated_at: nowISO(),
+          }
+        : folder
+    );
+    saveLocalDb(localDb);
+    emitDataChanged();
+    return;
+  }
+
+  const db = await getDb();
+  await db.execute(
+    `UPDATE folders SET learning_steps = $1, relearning_steps = $2, updated_at = $3
+     WHERE id = $4`,
+    [nextLearningSteps, nextRelearningSteps, nowISO(), id]
+  );
+  emitDataChanged();
+}
+
+export async function setFolderNewCardSettings(
+  id: string,
+  newCardsPerDay: number,
+  newCardOrder: NewCardOrder
+): Promise<void> {
+  const nextNewCardsPerDay = sanitizeNewCardsPerDay(newCardsPerDay);
+  const nextNewCardOrder = sanitizeNewCardOrder(newCardOrder);
+
+  if (await useLocalMode()) {
+    const localDb = getLocalDb();
+    localDb.folders = localDb.folders.map((folder) =>
+      folder.id === id
+        ? {
+            ...folder,
+            new_cards_per_day: nextNewCardsPerDay,
+            new_card_order: nextNewCardOrder,
+            updated_at: nowISO(),
+          }
+        : folder
+    );
+    saveLocalDb(localDb);
+    emitDataChanged();
+    return;
+  }
+
+  const db = await getDb();
+  await db.execute(
+    `UPDATE folders SET new_cards_per_day = $1, new_card_order = $2, updated_at = $3
+     WHERE id = $4`,
+    [nextNewCardsPerDay, nextNewCardOrder, nowISO(), id]
+  );
+  emitDataChanged();
+}
+
+export async function setFolderTargetRecall(id: string, targetRecall: number): Promise<void> {
+  const nextTargetRecall = sanitizeTargetRecall(targetRecall);
+
+  if (await useLocalMode()) {
+    const localDb = getLocalDb();
+    localDb.folders = localDb.folders.map((folder) =>
+      folder.id === id
+        ? { ...folder, target_recall: nextTargetRecall, updated_at: nowISO() }
+        : folder
+    );
+    saveLocalDb(localDb);
+    emitDataChanged();
+    return;
+  }
+
+  const db = await getDb();
+  await db.execute(
+    "UPDATE folders SET target_recall = $1, updated_at = $2 WHERE id = $3",
+    [nextTargetRecall, nowISO(), id]
+  );
+  emitDataChanged();
+}
+
+// --- Milestones ---
+
+export async function getFolderMilestones(folderId: string): Promise<FolderMilestone[]> {
+  if (await useLocalMode()) {
+    const localDb = getLocalDb();
+    return localDb.folder_milestones
+      .filter((milestone) => milestone.folder_id === folderId)
+      .sort((a, b) => a.date.localeCompare(b.date));
+  }
+  const db = await getDb();
+  return db.select<FolderMilestone[]>(
+    "SELECT * FROM folder_milestones WHERE folder_id = $1 ORDER BY date ASC, created_at ASC",
+    [folderId]
+  );
+}
+
+export async function createFolderMilestone(
+  folderId: string,
+  name: string,
+  date: string,
+  cardIds: string[] | null
+): Promise<FolderMilestone> {
+  const now = nowISO();
+  const milestone: FolderMilestone = {
+    id: generateId(),
+    folder_id: folderId,
+    name,
+    date,
+    card_ids: cardIds ? JSON.stringify(cardIds) : null,
+    created_at: now,
+    updated_at: now,
+  };
+  if (await useLocalMode()) {
+    const localDb = getLocalDb();
+    localDb.folder_milestones.push(milestone);
+    saveLocalDb(localDb);
+    emitDataChanged();
+    return milestone;
+  }
+  const db = await getDb();
+  await db.execute(
+    `INSERT INTO folder_milestones (id, folder_id, name, date, card_ids, created_at, updated_at)
+     VALUES ($1, $2, $3, $4, $5, $6, $7)`,
+    [milestone.id, folderId, name, date, milestone.card_ids, now, now]
+  );
+  emitDataChanged();
+  return milestone;
+}
+
+export async function deleteFolderMilestone(id: string): Promise<void> {
+  if (await useLocalMode()) {
+    const localDb = getLocalDb();
+    localDb.folder_milestones = localDb.folder_milestones.filter(
+      (milestone) => milestone.id !== id
+    );
+    saveLocalDb(localDb);
+    emitDataChanged();
+    return;
+  }
+  const db = await getDb();
+  await db.execute("DELETE FROM folder_milestones WHERE id = $1", [id]);
+  emitDataChanged();
+}
+
 // --- Flashcards ---
 
 export async function getFlashcards(folderId?: string): Promise<Flashcard[]> {
@@ -355,9 +606,22 @@ export async function createFlashcard(
       timer_seconds: timerSeconds,
       ease_factor: 2.5,
       interval_days: 0,
+      base_interval_days: null,
       repetitions: 0,
       due_date: now,
       last_reviewed: null,
+      verification_status: data.verification_status ?? null,
+      verification_note: data.verification_note ?? null,
+      template_params: data.template_params ?? null,
+      question_ocr: data.question_ocr ?? null,
+      answer_ocr: data.answer_ocr ?? null,
+      lapses: 0,
+      leech: false,
+      suspended: false,
+      buried_until: null,
+      learning_state: "new",
+      learning_step: 0,
+      introduced_at: null,
       created_at: now,
       updated_at: now,
     };
@@ -371,8 +635,9 @@ export async function createFlashcard(
     `INSERT INTO flashcards
       (id, folder_id, title, question_type, question_content, answer_type, answer_content,
        timer_mode, timer_seconds, ease_factor, interval_days, repetitions, due_date,
+       verification_status, verification_note, template_params, question_ocr, answer_ocr,
        created_at, updated_at)
-     VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, 2.5, 0, 0, $10, $11, $12)`,
+     VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, 2.5, 0, 0, $10, $11, $12, $13, $14, $15, $16, $17)`,
     [
       id,
       data.folder_id || null,
@@ -384,6 +649,11 @@ export async function createFlashcard(
       timerMode,
       timerSeconds,
       now, // due_date = now (immediately due)
+      data.verification_status ?? null,
+      data.verification_note ?? null,
+      data.template_params ?? null,
+      data.question_ocr ?? null,
+      data.answer_ocr ?? null,
       now,
       now,
     ]
@@ -416,6 +686,21 @@ export async function updateFlashcard(
             data.answer_content !== undefined
               ? data.answer_content
               : card.answer_content,
+          // Replaced images lose their OCR text, as the SQL trigger does.
+          question_ocr:
+            data.question_ocr !== undefined
+              ? data.question_ocr
+              : data.question_content !== undefined &&
+                  data.question_content !== card.question_content
+                ? null
+                : card.question_ocr,
+          answer_ocr:
+            data.answer_ocr !== undefined
+              ? data.answer_ocr
+              : data.answer_content !== undefined &&
+                  data.answer_content !== card.answer_content
+                ? null
+                : card.answer_ocr,
           updated_at: nowISO(),
         }
         : card
@@ -461,6 +746,26 @@ export async function updateFlashcard(
     fields.push(`timer_seconds = $${paramIdx++}`);
     values.push(data.timer_seconds);
   }
+  if (data.verification_status !== undefined) {
+    fields.push(`verification_status = $${paramIdx++}`);
+    values.push(data.verification_status);
+  }
+  if (data.verification_note !== undefined) {
+    fields.push(`verification_note = $${paramIdx++}`);
+    values.push(data.verification_note);
+  }
+  if (data.template_params !== undefined) {
+    fields.push(`template_params = $${paramIdx++}`);
+    values.push(data.template_params);
+  }
+  if (data.question_ocr !== undefined) {
+    fields.push(`question_ocr = $${paramIdx++}`);
+    values.push(data.question_ocr);
+  }
+  if (data.answer_ocr !== undefined) {
+    fields.push(`answer_ocr = $${paramIdx++}`);
+    values.push(data.answer_ocr);
+  }
 
   if (fields.length === 0) return;
 
@@ -514,6 +819,27 @@ export async function moveFlashcard(
   emitDataChanged();
 }
 
+export async function setFlashcardSuspended(
+  id: string,
+  suspended: boolean
+): Promise<void> {
+  if (await useLocalMode()) {
+    const localDb = getLocalDb();
+    localDb.flashcards = localDb.flashcards.map((card) =>
+      card.id === id ? { ...card, suspended, updated_at: nowISO() } : card
+    );
+    saveLocalDb(localDb);
+    emitDataChanged();
+    return;
+  }
+  const db = await getDb();
+  await db.execute(
+    "UPDATE flashcards SET suspended = $1, updated_at = $2 WHERE id = $3",
+    [suspended ? 1 : 0, nowISO(), id]
+  );
+  emitDataChanged();
+}
+
 export async function getDueFlashcards(
   folderId?: string
 ): Promise<Flashcard[]> {
@@ -524,7 +850,10 @@ export async function getDueFlashcards(
       ? localDb.flashcards.filter((card) => card.folder_id === folderId)
       : localDb.flashcards;
     return cards
-      .filter((card) => !card.due_date || card.due_date <= now)
+      .filter(
+        (card) =>
+          getCardState(card, now) === "active" && (!card.due_date || card.due_date <= now)
+      )
       .sort((a, b) => {
         const dueA = a.due_date ? new Date(a.due_date).getTime() : 0;
         const dueB = b.due_date ? new Date(b.due_date).getTime() : 0;
@@ -535,43 +864,86 @@ export async function getDueFlashcards(
   if (folderId) {
     return db.select<Flashcard[]>(
       `SELECT * FROM flashcards
-       WHERE folder_id = $1 AND (due_date IS NULL OR due_date <= $2)
+       WHERE folder_id = $1 AND suspended = 0
+         AND (buried_until IS NULL OR buried_until <= $2)
+         AND (due_date IS NULL OR due_date <= $2)
        ORDER BY due_date ASC`,
       [folderId, now]
     );
   }
   return db.select<Flashcard[]>(
     `SELECT * FROM flashcards
-     WHERE due_date IS NULL OR due_date <= $1
+     WHERE suspended = 0
+       AND (buried_until IS NULL OR buried_until <= $1)
+       AND (due_date IS NULL OR due_date <= $1)
      ORDER BY due_date ASC`,
     [now]
   );
 }
 
+/**
+ * Maps each card that belongs to a variant family to its family id (the id of
+ * the card the variants were generated from).
+ */
+export async function getCardFamilies(): Promise<Record<string, string>> {
+  if (await useLocalMode()) {
+    return {};
+  }
+  const db = await getDb();
+  const rows = await db.select<{ flashcard_id: string; family_id: string }[]>(
+    "SELECT flashcard_id, family_id FROM card_family"
+  );
+  return Object.fromEntries(rows.map((row) => [row.flashcard_id, row.family_id]));
+}
+
 // --- Reviews ---
 
 export async function submitReview(
   flashcardId: string,
   correct: boolean,
-  responseTimeSeconds: number
+  responseTimeSeconds: number,
+  hintsUsed = 0,
+  templateSeed: number | null = null,
+  typedAnswer: string | null = null,
+  workImage: string | null = null,
+  workVerdict: Review["work_verdict"] = null,
+  sessionId: string | null = null
 ): Promise<{
   quality: number;
   speed_ratio: number;
   ease_factor: number;
   interval_days: number;
   due_date: string;
+  lapses: number;
+  learning_state: LearningState;
+  leech: boolean;
+  suspended: boolean;
 }> {
   let card: Flashcard;
   let deadline: string | null = null;
+  let milestones: FolderMilestone[] = [];
+  let leechThreshold = DEFAULT_LEECH_THRESHOLD;
+  let leechAction: Folder["leech_action"] = "suspend";
+  let learningSteps = parseLearningSteps(DEFAULT_LEARNING_STEPS);
+  let relearningSteps = parseLearningSteps(DEFAULT_RELEARNING_STEPS);
   if (await useLocalMode()) {
     const localDb = getLocalDb();
     const found = localDb.flashcards.find((item) => item.id === flashcardId);
     if (!found) throw new Error("Flashcard not found");
     card = found;
-    if (card.folder_id) {
-      deadline =
-        localDb.folders.find((folder) => folder.id === card.folder_id)
-          ?.deadline || null;
+    const folder = card.folder_id
+      ? localDb.folders.find((item) => item.id === card.folder_id)
+      : undefined;
+    if (folder) {
+      const normalized = normalizeFolder(folder);
+      deadline = normalized.deadline || null;
+      milestones = localDb.folder_milestones.filter(
+        (milestone) => milestone.folder_id === folder.id
+      );
+      leechThreshold = normalized.leech_threshold;
+      leechAction = normalized.leech_action;
+      learningSteps = parseLearningSteps(normalized.learning_steps);
+      relearningSteps = parseLearningSteps(normalized.relearning_steps);
     }
   } else {
     const db = await getDb();
@@ -583,10 +955,22 @@ export async function submitReview(
     card = cards[0];
     if (card.folder_id) {
       const folders = await db.select<Folder[]>(
-        "SELECT deadline FROM folders WHERE id = $1",
+        `SELECT deadline, leech_threshold, leech_action, learning_steps, relearning_steps
+         FROM folders WHERE id = $1`,
         [card.folder_id]
       );
-      if (folders.length > 0) deadline = folders[0].deadline;
+      if (folders.length > 0) {
+        const folder = normalizeFolder(folders[0]);
+        deadline = folder.deadline;
+        milestones = await db.select<FolderMilestone[]>(
+          "SELECT * FROM folder_milestones WHERE folder_id = $1",
+          [card.folder_id]
+        );
+        leechThreshold = folder.leech_threshold;
+        leechAction = folder.leech_action;
+        learningSteps = parseLearningSteps(folder.learning_steps);
+        relearningSteps = parseLearningSteps(folder.relearning_steps);
+      }
     }
   }
 
@@ -597,38 +981,96 @@ export async function submitReview(
   let easeFactor = card.ease_factor;
   let intervalDays: number;
   let repetitions = card.repetitions;
-  let quality: number;
+  let learningState: LearningState =
+    card.learning_state ?? (card.repetitions > 0 ? "review" : "new");
+  let learningStep = card.learning_step ?? 0;
+  // Intervals grow from the uncompressed one, so they re-expand once the
+  // deadline that compressed them has passed
+  const previousInterval = card.base_interval_days ?? card.interval_days;
+  // Minutes until the next learning step, while the card is still learning
+  let stepMinutes: number | null = null;
   const MIN_EASE = 1.3;
+  // Each hint lowers a correct answer's quality by one, never below 2
+  const unaidedQuality = speedRatio <= 0.6 ? 5 : speedRatio <= 1.0 ? 4 : 3;
+  const quality = correct
+    ? Math.max(2, unaidedQuality - Math.max(0, hintsUsed))
+    : 0;
+  // Forgetting a card that had graduated to review is a lapse
+  const lapsed = learningState === "review" && quality === 0;
 
-  if (!correct) {
-    quality = 0;
+  if (learningState !== "review") {
+    // New and learning cards step through the folder's learning steps, and
+    // forgotten cards through its relearning steps, before day intervals
+    const relearning = learningState === "relearning";
+    const steps = relearning ? relearningSteps : learningSteps;
+    if (!relearning) learningState = "learning";
+    // Easy skips the remaining steps; heavily hinted repeats the current one
+    const nextStep =
+      quality === 0
+        ? 0
+        : quality === 5
+        ? null
+        : quality === 2
+        ? learningStep
+        : learningStep + 1;
+    if (nextStep !== null && nextStep < steps.length) {
+      learningStep = nextStep;
+      stepMinutes = steps[nextStep];
+      intervalDays = previousInterval;
+    } else if (quality === 0) {
+      // No steps configured: a failed card comes back tomorrow
+      learningStep = 0;
+      intervalDays = 1;
+    } else {
+      learningState = "review";
+      learningStep = 0;
+      repetitions += 1;
+      intervalDays = quality === 5 && !relearning ? 3 : 1;
+    }
+  } else if (quality === 0) {
     easeFactor = Math.max(MIN_EASE, easeFactor - 0.2);
     repetitions = 0;
-    intervalDays = 0;
-  } else if (speedRatio <= 0.6) {
-    quality = 5;
+    intervalDays = 1;
+    if (relearningSteps.length > 0) {
+      learningState = "relearning";
+      learningStep = 0;
+      stepMinutes = relearningSteps[0];
+    }
+  } else if (quality === 5) {
     easeFactor += 0.15;
     repetitions += 1;
-    intervalDays = nextInterval(repetitions, card.interval_days, easeFactor, 1.3);
-  } else if (speedRatio <= 1.0) {
-    quality = 4;
+    intervalDays = nextInterval(repetitions, previousInterval, easeFactor, 1.3);
+  } else if (quality === 4) {
     easeFactor += 0.05;
     repetitions += 1;
-    intervalDays = nextInterval(repetitions, card.interval_days, easeFactor, 1.0);
-  } else {
-    quality = 3;
+    intervalDays = nextInterval(repetitions, previousInterval, easeFactor, 1.0);
+  } else if (quality === 3) {
     easeFactor = Math.max(MIN_EASE, easeFactor - 0.1);
     repetitions += 1;
-    intervalDays = nextInterval(repetitions, card.interval_days, easeFactor, 0.8);
+    intervalDays = nextInterval(repetitions, previousInterval, easeFactor, 0.8);
+  } else {
+    // Solved only with heavy help: not a lapse, but not progress either
+    easeFactor = Math.max(MIN_EASE, easeFactor - 0.15);
+    intervalDays = 1;
   }
 
   easeFactor = Math.max(MIN_EASE, easeFactor);
 
-  // Apply deadline compression
-  if (deadline) {
+  // Reaching the folder's lapse threshold makes a leech
+  const lapses = (card.lapses || 0) + (lapsed ? 1 : 0);
+  const becameLeech = !card.leech && lapsed && lapses >= leechThreshold;
+  const leech = Boolean(card.leech) || becameLeech;
+  const suspended =
+    Boolean(card.suspended) || (becameLeech && leechAction === "suspend");
+
+  // Apply deadline compression to day intervals, toward the nearest
+  // upcoming milestone covering this card
+  const naturalInterval = intervalDays;
+  const cardDeadline = nearestDeadline(deadline, milestones, card.id, toDateKey());
+  if (cardDeadline && stepMinutes === null) {
     const daysRemaining = Math.max(
       0,
-      (new Date(deadline).getTime() - Date.now()) / (1000 * 60 * 60 * 24)
+      (new Date(cardDeadline).getTime() - Date.now()) / (1000 * 60 * 60 * 24)
     );
     if (daysRemaining > 0) {
       const reviewsStillNeeded = Math.max(1, 6 - repetitions);
@@ -636,10 +1078,10 @@ export async function submitReview(
       intervalDays = Math.max(1, Math.min(intervalDays, maxInterval));
     }
   }
+  const baseIntervalDays = intervalDays < naturalInterval ? naturalInterval : null;
 
-  const dueDate = new Date(
-    Date.now() + Math.round(intervalDays) * 24 * 60 * 60 * 1000
-  ).toISOString();
+  const dueMinutes = stepMinutes ?? intervalDays * 24 * 60;
+  const dueDate = new Date(Date.now() + Math.round(dueMinutes) * 60 * 1000).toISOString();
   const now = nowISO();
 
   if (await useLocalMode()) {
@@ -650,9 +1092,17 @@ export async function submitReview(
           ...item,
           ease_factor: easeFactor,
           interval_days: intervalDays,
+          base_interval_days: baseIntervalDays,
           repetitions,
           due_date: dueDate,
           last_reviewed: now,
+          lapses,
+          leech,
+          suspended,
+          buried_until: null,
+          learning_state: learningState,
+          learning_step: learningStep,
+          introduced_at: item.introduced_at ?? now,
           updated_at: now,
         }
         : item
@@ -669,6 +1119,13 @@ export async function submitReview(
       ease_after: easeFactor,
       interval_before: card.interval_days,
       interval_after: intervalDays,
+      hints_used: hintsUsed,
+      template_seed: templateSeed,
+      typed_answer: typedAnswer,
+      work_image: workImage,
+      work_verdict: workVerdict,
+      kind: "review",
+      session_id: sessionId,
       reviewed_at: now,
     });
     saveLocalDb(localDb);
@@ -677,16 +1134,58 @@ export async function submitReview(
     await db.execute(
       `UPDATE flashcards SET
         ease_factor = $1, interval_days = $2, repetitions = $3,
-        due_date = $4, last_reviewed = $5, updated_at = $6
-       WHERE id = $7`,
-      [easeFactor, intervalDays, repetitions, dueDate, now, now, flashcardId]
+        due_date = $4, last_reviewed = $5, updated_at = $6,
+        lapses = $7, leech = $8, suspended = $9, buried_until = NULL,
+        learning_state = $10, learning_step = $11,
+        introduced_at = COALESCE(introduced_at, $5), base_interval_days = $13
+       WHERE id = $12`,
+      [
+        easeFactor,
+        intervalDays,
+        repetitions,
+        dueDate,
+        now,
+        now,
+        lapses,
+        leech ? 1 : 0,
+        suspended ? 1 : 0,
+        learningState,
+        learningStep,
+        flashcardId,
+        baseIntervalDays,
+      ]
+    );
+    // Variants share one schedule, so the rest of the family moves with this card
+    await db.execute(
+      `UPDATE flashcards SET
+        ease_factor = $1, interval_days = $2, repetitions = $3, due_date = $4, updated_at = $5,
+        learning_state = $7, learning_step = $8, introduced_at = COALESCE(introduced_at, $5),
+        base_interval_days = $9
+       WHERE id != $6 AND id IN (
+         SELECT flashcard_id FROM card_family WHERE family_id = (
+           SELECT family_id FROM card_family WHERE flashcard_id = $6
+         )
+       )`,
+      [
+        easeFactor,
+        intervalDays,
+        repetitions,
+        dueDate,
+        now,
+        flashcardId,
+        learningState,
+        learningStep,
+        baseIntervalDays,
+      ]
     );
     const reviewId = generateId();
     await db.execute(
       `INSERT INTO reviews
         (id, flashcard_id, correct, response_time_seconds, timer_limit_seconds,
-         speed_ratio, quality, ease_before, ease_after, interval_before, interval_after, reviewed_at)
-       VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)`,
+         speed_ratio, quality, ease_before, ease_after, interval_before, interval_after,
+         hints_used, template_seed, typed_answer, work_image, work_verdict, reviewed_at,
+         session_id)
+       VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18)`,
       [
         reviewId,
         flashcardId,
@@ -699,7 +1198,13 @@ export async function submitReview(
         easeFactor,
         card.interval_days,
         intervalDays,
+        hintsUsed,
+        templateSeed,
+        typedAnswer,
+        workImage,
+        workVerdict,
         now,
+        sessionId,
       ]
     );
   }
@@ -711,6 +1216,10 @@ export async function submitReview(
     ease_factor: easeFactor,
     interval_days: intervalDays,
     due_date: dueDate,
+    lapses,
+    learning_state: learningState,
+    leech: becameLeech,
+    suspended,
   };
 }
 
@@ -744,6 +1253,170 @@ export async function getReviewHistory(
   );
 }
 
+// --- Study sessions ---
+
+interface StudySessionRow {
+  id: string;
+  folder_ids: string;
+  mode: StudySession["mode"];
+  queue: string;
+  position: number;
+  answers: string;
+  started_at: string;
+  updated_at: string;
+  ended_at: string | null;
+}
+
+function parseStudySession(row: StudySessionRow): StudySession {
+  const parse = <T>(value: string): T[] => {
+    try {
+      const parsed = JSON.parse(value);
+      return Array.isArray(parsed) ? parsed : [];
+    } catch {
+      return [];
+    }
+  };
+  return {
+    ...row,
+    folder_ids: parse<string>(row.folder_ids),
+    queue: parse<string>(row.queue),
+    answers: parse<SessionAnswer>(row.answers),
+  };
+}
+
+/** Folder ids in a stable order, so the same scope always matches. */
+function sessionScope(folderIds: string[]): string {
+  return JSON.stringify([...folderIds].sort());
+}
+
+/**
+ * Starts a session over `folderIds` (empty for every deck). Unfinished
+ * sessions over the same scope are closed at their last activity.
+ */
+export async function startStudySession(
+  folderIds: string[],
+  mode: StudySession["mode"],
+  queue: string[]
+): Promise<StudySession> {
+  const now = nowISO();
+  const scope = sessionScope(folderIds);
+  const session: StudySession = {
+    id: generateId(),
+    folder_ids: JSON.parse(scope),
+    mode,
+    queue,
+    position: 0,
+    answers: [],
+    started_at: now,
+    updated_at: now,
+    ended_at: null,
+  };
+
+  if (await useLocalMode()) {
+    const localDb = getLocalDb();
+    localDb.study_sessions = localDb.study_sessions.map((item) =>
+      !item.ended_at && item.mode === mode && sessionScope(item.folder_ids) === scope
+        ? { ...item, ended_at: item.updated_at }
+        : item
+    );
+    localDb.study_sessions.push(session);
+    saveLocalDb(localDb);
+    return session;
+  }
+
+  const db = await getDb();
+  await db.execute(
+    `UPDATE study_sessions SET ended_at = updated_at
+     WHERE ended_at IS NULL AND folder_ids = $1 AND mode = $2`,
+    [scope, mode]
+  );
+  await db.execute(
+    `INSERT INTO study_sessions
+      (id, folder_ids, mode, queue, position, answers, started_at, updated_at)
+     VALUES ($1, $2, $3, $4, 0, '[]', $5, $5)`,
+    [session.id, scope, mode, JSON.stringify(queue), now]
+  );
+  return session;
+}
+
+/** Saves the queue (which grows as learning cards are requeued), position and answers. */
+export async function saveStudySessionProgress(
+  id: string,
+  queue: string[],
+  position: number,
+  answers: SessionAnswer[]
+): Promise<void> {
+  const now = nowISO();
+  if (await useLocalMode()) {
+    const localDb = getLocalDb();
+    localDb.study_sessions = localDb.study_sessions.map((item) =>
+      item.id === id ? { ...item, queue, position, answers, updated_at: now } : item
+    );
+    saveLocalDb(localDb);
+    return;
+  }
+  const db = await getDb();
+  await db.execute(
+    `UPDATE study_sessions SET queue = $1, position = $2, answers = $3, updated_at = $4
+     WHERE id = $5`,
+    [JSON.stringify(queue), position, JSON.stringify(answers), now, id]
+  );
+}
+
+export async function endStudySession(id: string): Promise<void> {
+  const now = nowISO();
+  if (await useLocalMode()) {
+    const localDb = getLocalDb();
+    localDb.study_sessions = localDb.study_sessions.map((item) =>
+      item.id === id ? { ...item, ended_at: now, updated_at: now } : item
+    );
+    saveLocalDb(localDb);
+    return;
+  }
+  const db = await getDb();
+  await db.execute(
+    "UPDATE study_sessions SET ended_at = $1, updated_at = $1 WHERE id = $2",
+    [now, id]
+  );
+}
+
+/**
+ * The latest session over this scope that was left unfinished today, e.g. by a
+ * crash or closing the laptop. Older ones are not resumed.
+ */
+export async function getUnfinishedStudySession(
+  folderIds: string[],
+  mode: StudySession["mode"]
+): Promise<StudySession | null> {
+  const scope = sessionScope(folderIds);
+  const todayStart = new Date();
+  todayStart.setHours(0, 0, 0, 0);
+  const todayISO = todayStart.toISOString();
+
+  if (await useLocalMode()) {
+    const localDb = getLocalDb();
+    return (
+      localDb.study_sessions
+        .filter(
+          (item) =>
+            !item.ended_at &&
+            item.mode === mode &&
+            item.updated_at >= todayISO &&
+            sessionScope(item.folder_ids) === scope
+        )
+        .sort((a, b) => b.started_at.localeCompare(a.started_at))[0] ?? null
+    );
+  }
+  const db = await getDb();
+  const rows = await db.select<StudySessionRow[]>(
+    `SELECT * FROM study_sessions
+     WHERE ended_at IS NULL AND folder_ids = $1 AND mode = $2 AND updated_at >= $3
+     ORDER BY started_at DESC LIMIT 1`,
+    [scope, mode, todayISO]
+  );
+  return rows.length > 0 ? parseStudySession(rows[0]) : null;
+}
+
 export async function getStudyStats(folderId?: string): Promise<StudyStats> {
   const now = nowISO();
   const todayStart = new Date();
@@ -757,10 +1430,15 @@ export async function getStudyStats(folderId?: string): Promise<StudyStats> {
       ? localDb.flashcards.filter((card) => card.folder_id === folderId)
       : localDb.flashcards.filter((card) => card.folder_id !== null);
     const totalCards = cards.length;
-    const dueCards = cards.filter((card) => !card.due_date || card.due_date <= now);
-    // Cards reviewed correctly today: reviewed today AND now scheduled for the future
+    const dueCards = cards.filter(
+      (card) =>
+        getCardState(card, now) === "active" && (!card.due_date || card.due_date <= now)
+    );
+    // Cards reviewed correctly today: reviewed today AND now scheduled for the
+    // future, excluding cards still stepping through (re)learning today
     const reviewedCorrectlyToday = cards.filter(
       (card) =>
+        !isLearning(card) &&
         card.last_reviewed && card.last_reviewed >= todayISO &&
         card.due_date && card.due_date > now
     );
@@ -801,10 +1479,15 @@ export async function getStudyStats(folderId?: string): Promise<StudyStats> {
         "SELECT * FROM flashcards WHERE folder_id IS NOT NULL"
       );
   const totalCards = cards.length;
-  const dueCards = cards.filter((card) => !card.due_date || card.due_date <= now);
-  // Cards reviewed correctly today: reviewed today AND now scheduled for the future
+  const dueCards = cards.filter(
+    (card) =>
+      getCardState(card, now) === "active" && (!card.due_date || card.due_date <= now)
+  );
+  // Cards reviewed correctly today: reviewed today AND now scheduled for the
+  // future, excluding cards still stepping through (re)learning today
   const reviewedCorrectlyToday = cards.filter(
     (card) =>
+      !isLearning(card) &&
       card.last_reviewed && card.last_reviewed >= todayISO &&
       card.due_date && card.due_date > now
   );
@@ -820,7 +1503,7 @@ export async function getStudyStats(folderId?: string): Promise<StudyStats> {
       `SELECT COUNT(*) as count
        FROM reviews r
        JOIN flashcards f ON f.id = r.flashcard_id
-       WHERE r.reviewed_at >= $1 AND f.folder_id = $2`,
+       WHERE r.reviewed_at >= $1 AND r.kind = 'review' AND f.folder_id = $2`,
       [todayISO, folderId]
     );
     reviewedToday = reviewedRows[0]?.count || 0;
@@ -829,7 +1512,7 @@ export async function getStudyStats(folderId?: string): Promise<StudyStats> {
       `SELECT COUNT(*) as count
        FROM reviews r
        JOIN flashcards f ON f.id = r.flashcard_id
-       WHERE r.reviewed_at >= $1 AND r.correct = 1 AND f.folder_id = $2`,
+       WHERE r.reviewed_at >= $1 AND r.kind = 'review' AND r.correct = 1 AND f.folder_id = $2`,
       [todayISO, folderId]
     );
     correctToday = correctRows[0]?.count || 0;
@@ -838,7 +1521,7 @@ export async function getStudyStats(folderId?: string): Promise<StudyStats> {
       `SELECT COUNT(*) as count
        FROM reviews r
        JOIN flashcards f ON f.id = r.flashcard_id
-       WHERE r.reviewed_at >= $1 AND f.folder_id IS NOT NULL`,
+       WHERE r.reviewed_at >= $1 AND r.kind = 'review' AND f.folder_id IS NOT NULL`,
       [todayISO]
     );
     reviewedToday = reviewedRows[0]?.count || 0;
@@ -847,7 +1530,8 @@ export async function getStudyStats(folderId?: string): Promise<StudyStats> {
       `SELECT COUNT(*) as count
        FROM reviews r
        JOIN flashcards f ON f.id = r.flashcard_id
-       WHERE r.reviewed_at >= $1 AND r.correct = 1 AND f.folder_id IS NOT NULL`,
+       WHERE r.reviewed_at >= $1 AND r.kind = 'review' AND r.correct = 1
+         AND f.folder_id IS NOT NULL`,
       [todayISO]
     );
     correctToday = correctRows[0]?.count || 0;
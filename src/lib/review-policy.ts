@@ -1,7 +1,12 @@
-import type { Flashcard, Folder } from "./types";
+import type { Flashcard, Folder, FolderMilestone, NewCardOrder } from "./types";
 
 export const DEFAULT_REVIEW_CARDS_PER_DAY = 20;
 export const DEFAULT_AUTO_TARGET_REPS = 3;
+export const DEFAULT_LEECH_THRESHOLD = 8;
+export const DEFAULT_LEARNING_STEPS = "1m 10m 1h";
+export const DEFAULT_RELEARNING_STEPS = "10m";
+export const DEFAULT_NEW_CARDS_PER_DAY = 20;
+export const DEFAULT_TARGET_RECALL = 0.9;
 
 export function sanitizeReviewCardsPerDay(value: number | null | undefined): number {
   if (!Number.isFinite(value)) {
@@ -17,6 +22,87 @@ export function sanitizeAutoTargetReps(value: number | null | undefined): number
   return Math.min(10, Math.max(1, Math.round(value as number)));
 }
 
+export function sanitizeLeechThreshold(value: number | null | undefined): number {
+  if (!Number.isFinite(value)) {
+    return DEFAULT_LEECH_THRESHOLD;
+  }
+  return Math.min(99, Math.max(1, Math.round(value as number)));
+}
+
+export function sanitizeNewCardsPerDay(value: number | null | undefined): number {
+  if (!Number.isFinite(value)) {
+    return DEFAULT_NEW_CARDS_PER_DAY;
+  }
+  // Zero pauses new cards while reviews continue
+  return Math.max(0, Math.round(value as number));
+}
+
+export function sanitizeTargetRecall(value: number | null | undefined): number {
+  if (!Number.isFinite(value)) {
+    return DEFAULT_TARGET_RECALL;
+  }
+  return Math.min(0.99, Math.max(0.7, value as number));
+}
+
+export function sanitizeNewCardOrder(value: string | null | undefined): NewCardOrder {
+  return value === "random" || value === "difficulty" ? value : "created";
+}
+
+/**
+ * Parses steps such as "1m 10m 1h" into minutes. A bare number is in minutes;
+ * tokens that don't parse are skipped.
+ */
+export function parseLearningSteps(value: string | null | undefined): number[] {
+  return (value ?? "")
+    .split(/[\s,]+/)
+    .map((token) => {
+      const match = /^(\d+(?:\.\d+)?)([mhd]?)$/i.exec(token.trim());
+      if (!match) return 0;
+      const unit = match[2].toLowerCase();
+      return Number(match[1]) * (unit === "h" ? 60 : unit === "d" ? 1440 : 1);
+    })
+    .filter((minutes) => minutes > 0);
+}
+
+export function sanitizeLearningSteps(
+  value: string | null | undefined,
+  fallback: string
+): string {
+  if (value === null || value === undefined) return fallback;
+  // An empty string is a valid choice: no steps
+  return value
+    .split(/[\s,]+/)
+    .filter((token) => parseLearningSteps(token).length > 0)
+    .join(" ");
+}
+
+export function formatStepDelay(minutes: number): string {
+  if (minutes >= 1440 && minutes % 1440 === 0) return `${minutes / 1440}d`;
+  if (minutes >= 60 && minutes % 60 === 0) return `${minutes / 60}h`;
+  return `${Math.round(minutes)}m`;
+}
+
+/** Learning and relearning cards come back within the day. */
+export function isLearning(card: Pick<Flashcard, "learning_state">): boolean {
+  return card.learning_state === "learning" || card.learning_state === "relearning";
+}
+
+/** Cards never reviewed; they count against `new_cards_per_day`, not the review limit. */
+export function isNewCard(card: Pick<Flashcard, "introduced_at" | "last_reviewed">): boolean {
+  return !(card.introduced_at ?? card.last_reviewed);
+}
+
+export type CardState = "active" | "suspended" | "buried";
+
+export function getCardState(
+  card: Pick<Flashcard, "suspended" | "buried_until">,
+  now = new Date().toISOString()
+): CardState {
+  if (card.suspended) return "suspended";
+  if (card.buried_until && card.buried_until > now) return "buried";
+  return "active";
+}
+
 export function getFolderReviewMode(
   folder: Pick<Folder, "review_target_mode"> | null | undefined
 ): Folder["review_target_mode"] {
@@ -31,6 +117,40 @@ export function getDaysRemaining(deadline: string | null | undefined): number |
   return Math.max(1, Math.ceil(diffMs / (1000 * 60 * 60 * 24)));
 }
 
+export function milestoneCardIds(milestone: Pick<FolderMilestone, "card_ids">): string[] | null {
+  if (!milestone.card_ids) return null;
+  try {
+    const ids = JSON.parse(milestone.card_ids);
+    return Array.isArray(ids) ? ids.map(String) : null;
+  } catch {
+    return null;
+  }
+}
+
+/**
+ * The earliest of the deck's deadline and the milestones covering `cardId`
+ * that falls on or after `today` (YYYY-MM-DD). A milestone counts through the
+ * end of its own day, then the next one takes over; with nothing upcoming this
+ * is just the deadline.
+ */
+export function nearestDeadline(
+  deadline: string | null,
+  milestones: Pick<FolderMilestone, "date" | "card_ids">[],
+  cardId: string | null,
+  today: string
+): string | null {
+  const upcoming = [
+    deadline,
+    ...milestones
+      .filter((milestone) => {
+        const ids = milestoneCardIds(milestone);
+        return !cardId || !ids || ids.includes(cardId);
+      })
+      .map((milestone) => milestone.date),
+  ].filter((date): date is string => Boolean(date) && date! >= today);
+  return upcoming.length > 0 ? upcoming.sort()[0] : deadline;
+}
+
 function getReviewsNeededForMastery(card: Flashcard, targetReps: number): number {
   if (card.repetitions >= targetReps || card.interval_days >= 7) {
     return 0;
@@ -91,11 +211,35 @@ export function getEffectiveDailyReviewLimit(
   return sanitizeReviewCardsPerDay(folder?.review_cards_per_day);
 }
 
+/** One folder's share of a study session. */
+export interface FolderQueue {
+  folderId: string | null;
+  /** Relative share of the session; 1 unless the learner weights folders. */
+  weight: number;
+  daysRemaining: number | null;
+  cards: Flashcard[];
+}
+
+/** Today's queue from the due cards, folder by folder. See `getFolderQueues`. */
 export function getScheduledDueCards(
   dueCards: Flashcard[],
   folders: Folder[],
   allCards: Flashcard[] = dueCards
 ): Flashcard[] {
+  return getFolderQueues(dueCards, folders, allCards).flatMap((queue) => queue.cards);
+}
+
+/**
+ * Each folder's queue for today. A folder contributes every learning card,
+ * then reviews up to its review limit with up to `new_cards_per_day` new cards
+ * (less those introduced today) spread evenly between them. Mirrors
+ * `daily_queues_by_folder` in `src-tauri/src/review_policy.rs`.
+ */
+export function getFolderQueues(
+  dueCards: Flashcard[],
+  folders: Folder[],
+  allCards: Flashcard[] = dueCards
+): FolderQueue[] {
   const folderMap = new Map(folders.map((folder) => [folder.id, folder]));
   const cardsByFolder = new Map<string, Flashcard[]>();
   const allCardsByFolder = new Map<string, Flashcard[]>();
@@ -120,21 +264,186 @@ export function getScheduledDueCards(
     }
   }
 
-  const scheduled: Flashcard[] = [];
+  const todayStart = new Date();
+  todayStart.setHours(0, 0, 0, 0);
+  const today = todayStart.toISOString();
+
+  const queues: FolderQueue[] = [];
   for (const [key, cards] of cardsByFolder.entries()) {
     const folder = key === "__unassigned__" ? null : folderMap.get(key) ?? null;
-    const limit = getEffectiveDailyReviewLimit(
-      folder,
-      allCardsByFolder.get(key) ?? cards
-    );
+    const folderCards = allCardsByFolder.get(key) ?? cards;
+    const learning = cards.filter((card) => isLearning(card));
+    const newCards = cards.filter((card) => !isLearning(card) && isNewCard(card));
+    const reviews = cards.filter((card) => !isLearning(card) && !isNewCard(card));
+
+    const limit = getEffectiveDailyReviewLimit(folder, folderCards);
     // Score each card: score = random * 0.5^reps, so each rep halves the expected score.
-    // New cards (rep=0) draw from [0,1], rep=1 from [0,0.5], rep=2 from [0,0.25], etc.
-    const ordered = [...cards]
+    const orderedReviews = reviews
       .map((card) => ({ card, score: Math.random() * Math.pow(0.5, card.repetitions) }))
       .sort((a, b) => b.score - a.score)
+      .map(({ card }) => card)
+      .slice(0, limit);
+
+    const introducedToday = folderCards.filter(
+      (card) => card.introduced_at && card.introduced_at >= today
+    ).length;
+    const newLimit = Math.max(
+      0,
+      sanitizeNewCardsPerDay(folder?.new_cards_per_day) - introducedToday
+    );
+    const orderedNew = orderNewCards(newCards, sanitizeNewCardOrder(folder?.new_card_order))
+      .slice(0, newLimit);
+
+    queues.push({
+      folderId: folder?.id ?? null,
+      weight: 1,
+      daysRemaining: getDaysRemaining(folder?.deadline),
+      cards: [...learning, ...spreadEvenly(orderedReviews, orderedNew)],
+    });
+  }
+
+  return queues;
+}
+
+/**
+ * A session across the folders in `weights` (folder id to weight), or every
+ * folder with due cards when `weights` is omitted, interleaved by
+ * `interleaveFolderQueues` and capped at `maxCards`.
+ */
+export function buildSessionQueue(
+  dueCards: Flashcard[],
+  folders: Folder[],
+  allCards: Flashcard[] = dueCards,
+  weights?: Record<string, number>,
+  maxCards?: number
+): Flashcard[] {
+  let queues = getFolderQueues(dueCards, folders, allCards);
+  if (weights) {
+    queues = queues
+      .filter((queue) => queue.folderId !== null && queue.folderId in weights)
+      .map((queue) => ({ ...queue, weight: weights[queue.folderId as string] }));
+  }
+  const session = interleaveFolderQueues(queues);
+  return maxCards === undefined ? session : session.slice(0, maxCards);
+}
+
+/**
+ * Merges folder queues, keeping each folder's order. Folders get slots in
+ * proportion to their weight, boosted as their deadline nears (a folder due in
+ * a week counts double), with ties going to the nearest deadline and then to
+ * a different folder than the previous card. Mirrors
+ * `interleave_folder_queues` in `src-tauri/src/review_policy.rs`.
+ */
+export function interleaveFolderQueues(queues: FolderQueue[]): Flashcard[] {
+  const active = queues
+    .filter((queue) => queue.weight > 0 && queue.cards.length > 0)
+    .sort(
+      (a, b) =>
+        (a.daysRemaining ?? Number.MAX_SAFE_INTEGER) - (b.daysRemaining ?? Number.MAX_SAFE_INTEGER)
+    );
+  const shares = active.map(
+    (queue) => queue.weight * (queue.daysRemaining ? 1 + 7 / Math.max(1, queue.daysRemaining) : 1)
+  );
+  // Stride scheduling: each folder's next card is due at its pass
+  const passes = shares.map((share) => 0.5 / share);
+  const cursors = active.map(() => 0);
+  const total = active.reduce((sum, queue) => sum + queue.cards.length, 0);
+
+  const session: Flashcard[] = [];
+  let previous = -1;
+  while (session.length < total) {
+    let next = -1;
+    for (let i = 0; i < active.length; i++) {
+      if (cursors[i] >= active[i].cards.length) continue;
+      if (
+        next === -1 ||
+        passes[i] < passes[next] ||
+        (passes[i] === passes[next] && next === previous && i !== previous)
+      ) {
+        next = i;
+      }
+    }
+    if (next === -1) break;
+    session.push(active[next].cards[cursors[next]++]);
+    passes[next] += 1 / shares[next];
+    previous = next;
+  }
+  return session;
+}
+
+function orderNewCards(cards: Flashcard[], order: NewCardOrder): Flashcard[] {
+  if (order === "random") {
+    return cards
+      .map((card) => ({ card, score: Math.random() }))
+      .sort((a, b) => a.score - b.score)
       .map(({ card }) => card);
-    scheduled.push(...ordered.slice(0, limit));
+  }
+  return [...cards].sort((a, b) => {
+    if (order === "difficulty" && a.timer_seconds !== b.timer_seconds) {
+      return a.timer_seconds - b.timer_seconds;
+    }
+    return a.created_at < b.created_at ? -1 : a.created_at > b.created_at ? 1 : 0;
+  });
+}
+
+/** Interleaves `extra` evenly through `base`, aiming each at the middle of its share. */
+function spreadEvenly<T>(base: T[], extra: T[]): T[] {
+  const total = base.length + extra.length;
+  const out: T[] = [];
+  let baseIndex = 0;
+  let extraIndex = 0;
+  for (let slot = 0; slot < total; slot++) {
+    const takeExtra = (2 * extraIndex + 1) * total <= 2 * (slot + 1) * extra.length;
+    if ((takeExtra && extraIndex < extra.length) || baseIndex >= base.length) {
+      out.push(extra[extraIndex++]);
+    } else {
+      out.push(base[baseIndex++]);
+    }
+  }
+  return out;
+}
+
+/**
+ * Keeps one card per variant family, swapping in the member reviewed longest
+ * ago (never-reviewed first) so each review shows a fresh instance. Family
+ * members share a schedule, so they all come due together. Suspended and
+ * buried members are never swapped in.
+ */
+export function rotateVariants(
+  dueCards: Flashcard[],
+  families: Record<string, string>,
+  allCards: Flashcard[] = dueCards,
+  now = new Date().toISOString()
+): Flashcard[] {
+  const membersByFamily = new Map<string, Flashcard[]>();
+  for (const card of allCards) {
+    const familyId = families[card.id];
+    if (!familyId || getCardState(card, now) !== "active") continue;
+    const members = membersByFamily.get(familyId);
+    if (members) {
+      members.push(card);
+    } else {
+      membersByFamily.set(familyId, [card]);
+    }
   }
 
-  return scheduled;
+  const seen = new Set<string>();
+  const rotated: Flashcard[] = [];
+  for (const card of dueCards) {
+    const familyId = families[card.id];
+    if (!familyId) {
+      rotated.push(card);
+      continue;
+    }
+    if (seen.has(familyId)) continue;
+    seen.add(familyId);
+    const members = membersByFamily.get(familyId) ?? [card];
+    const leastRecent = members.reduce((best, member) => {
+      if (!member.last_reviewed) return best.last_reviewed ? member : best;
+      if (!best.last_reviewed) return best;
+      return member.last_reviewed < best.last_reviewed ? member : best;
+    }, card);
+    rotated.push(leastRecent);
+  }
+  return rotated;
 }
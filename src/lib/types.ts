@@ -7,6 +7,31 @@ export interface Folder {
   review_cards_per_day: number;
   review_target_mode: "fixed" | "dynamic";
   auto_target_reps: number;
+  /** Lapses after which a card is tagged as a leech. */
+  leech_threshold: number;
+  leech_action: "suspend" | "tag";
+  /** Intra-day delays such as "1m 10m 1h" before a new card graduates. */
+  learning_steps: string;
+  /** Delays a forgotten card steps through before returning to review. */
+  relearning_steps: string;
+  /** Cap on never-studied cards introduced per day, separate from reviews. */
+  new_cards_per_day: number;
+  new_card_order: NewCardOrder;
+  /** Recall the exam planner aims for on the deadline, e.g. 0.9. */
+  target_recall: number;
+  created_at: string;
+  updated_at: string;
+}
+
+/** An extra deadline within a deck, such as a quiz or midterm before the final. */
+export interface FolderMilestone {
+  id: string;
+  folder_id: string;
+  name: string;
+  /** Local date, YYYY-MM-DD. */
+  date: string;
+  /** JSON array of the card ids it covers; null covers the whole deck. */
+  card_ids: string | null;
   created_at: string;
   updated_at: string;
 }
@@ -17,19 +42,45 @@ export interface Flashcard {
   title: string | null;
   question_type: "image" | "latex";
   question_content: string;
-  answer_type: "image" | "latex" | null;
+  answer_type: "image" | "latex" | "solution" | null;
   answer_content: string | null;
   timer_mode: "1min" | "5min" | "10min" | "llm" | "custom";
   timer_seconds: number;
   ease_factor: number;
   interval_days: number;
+  /** Interval before deadline compression, while it is compressed. */
+  base_interval_days: number | null;
   repetitions: number;
   due_date: string | null;
   last_reviewed: string | null;
+  verification_status: VerificationStatus | null;
+  verification_note: string | null;
+  /** JSON array of `TemplateParam`; set on parametric cards. */
+  template_params: string | null;
+  /** Text read from an image question/answer; null until OCR has run. */
+  question_ocr: string | null;
+  answer_ocr: string | null;
+  /** Times the card was forgotten after being learned. */
+  lapses: number;
+  leech: boolean;
+  /** Suspended cards are never due until unsuspended. */
+  suspended: boolean;
+  /** Buried cards are skipped until this time. */
+  buried_until: string | null;
+  learning_state: LearningState;
+  /** Index into the folder's (re)learning steps while learning. */
+  learning_step: number;
+  /** First review; cards without one are new. */
+  introduced_at: string | null;
   created_at: string;
   updated_at: string;
 }
 
+/** `difficulty` introduces cards with the shortest assessed timer first. */
+export type NewCardOrder = "created" | "random" | "difficulty";
+
+export type LearningState = "new" | "learning" | "review" | "relearning";
+
 export interface Review {
   id: string;
   flashcard_id: string;
@@ -42,6 +93,21 @@ export interface Review {
   ease_after: number;
   interval_before: number;
   interval_after: number;
+  hints_used: number;
+  /** Seed a parametric card was instantiated with for this review. */
+  template_seed: number | null;
+  /** The answer typed in typed-answer mode, graded automatically. */
+  typed_answer: string | null;
+  /** Photo of written work graded for this review, and the grader's verdict. */
+  work_image: string | null;
+  work_verdict: WorkGrade["verdict"] | null;
+  /**
+   * `review` for an answered card; otherwise a manual schedule change
+   * recorded for the history, which study stats ignore.
+   */
+  kind: "review" | "reschedule" | "reset" | "forget";
+  /** The study session the review was answered in, if any. */
+  session_id: string | null;
   reviewed_at: string;
 }
 
@@ -50,10 +116,15 @@ export interface CreateFlashcardInput {
   title?: string | null;
   question_type: "image" | "latex";
   question_content: string;
-  answer_type?: "image" | "latex" | null;
+  answer_type?: "image" | "latex" | "solution" | null;
   answer_content?: string | null;
   timer_mode?: "1min" | "5min" | "10min" | "llm" | "custom";
   timer_seconds?: number;
+  verification_status?: VerificationStatus | null;
+  verification_note?: string | null;
+  template_params?: string | null;
+  question_ocr?: string | null;
+  answer_ocr?: string | null;
 }
 
 export interface UpdateFlashcardInput {
@@ -61,16 +132,28 @@ export interface UpdateFlashcardInput {
   title?: string | null;
   question_type?: "image" | "latex";
   question_content?: string;
-  answer_type?: "image" | "latex" | null;
+  answer_type?: "image" | "latex" | "solution" | null;
   answer_content?: string | null;
   timer_mode?: "1min" | "5min" | "10min" | "llm" | "custom";
   timer_seconds?: number;
+  verification_status?: VerificationStatus | null;
+  verification_note?: string | null;
+  template_params?: string | null;
+  question_ocr?: string | null;
+  answer_ocr?: string | null;
 }
 
 export interface ReviewInput {
   flashcard_id: string;
   correct: boolean;
   response_time_seconds: number;
+  /** Hints revealed before answering; each one lowers the review quality. */
+  hints_used?: number;
+  template_seed?: number | null;
+  typed_answer?: string | null;
+  work_image?: string | null;
+  work_verdict?: WorkGrade["verdict"] | null;
+  session_id?: string | null;
 }
 
 export interface ReviewResult {
@@ -79,6 +162,103 @@ export interface ReviewResult {
   ease_factor: number;
   interval_days: number;
   due_date: string;
+  lapses: number;
+  learning_state: LearningState;
+  /** True when this review made the card a leech. */
+  leech: boolean;
+  suspended: boolean;
+}
+
+export interface LeechCard {
+  id: string;
+  folder_id: string | null;
+  title: string | null;
+  question_type: "image" | "latex";
+  question_content: string;
+  lapses: number;
+  suspended: boolean;
+  ease_factor: number;
+  last_reviewed: string | null;
+}
+
+/**
+ * A study session as persisted for resuming: the card queue, how far through
+ * it the learner is, and each answer so far.
+ */
+export interface StudySession {
+  id: string;
+  /** Decks in the session; empty means every deck. */
+  folder_ids: string[];
+  mode: "due" | "all";
+  queue: string[];
+  position: number;
+  answers: SessionAnswer[];
+  started_at: string;
+  updated_at: string;
+  ended_at: string | null;
+}
+
+export interface SessionAnswer {
+  flashcard_id: string;
+  response_time_seconds: number;
+  result: ReviewResult;
+}
+
+export interface SessionAnalytics {
+  id: string;
+  folder_ids: string[];
+  mode: StudySession["mode"];
+  started_at: string;
+  ended_at: string | null;
+  queue_length: number;
+  position: number;
+  reviewed: number;
+  correct: number;
+  accuracy: number;
+  avg_response_seconds: number;
+  avg_speed_ratio: number;
+  /** Change in speed ratio per card; negative means getting faster. */
+  speed_trend: number;
+  speed_ratios: number[];
+}
+
+/** Reviews planned per day up to a deck's deadline; see `plan_exam`. */
+export interface ExamPlan {
+  folder_id: string;
+  deadline: string | null;
+  target_recall: number;
+  daily_cap: number;
+  days: PlanDay[];
+  /** Days planned above the cap because the cards didn't fit otherwise. */
+  overloaded_days: number;
+}
+
+export interface PlanDay {
+  /** Local date, YYYY-MM-DD. */
+  date: string;
+  card_ids: string[];
+  /** Cards whose last review before the exam falls on this day. */
+  final_reviews: number;
+}
+
+/** A deck in a mixed session and its relative share of the cards. */
+export interface SessionFolder {
+  folder_id: string;
+  weight: number;
+}
+
+/** Today's study queue, in order, from `build_daily_queue` or `build_session_queue`. */
+export interface DailyQueue {
+  card_ids: string[];
+  learning_count: number;
+  review_count: number;
+  new_count: number;
+}
+
+export interface PrerequisiteCard {
+  question: string;
+  answer: string;
+  skill: string;
 }
 
 export interface StudyStats {
@@ -94,9 +274,266 @@ export interface LLMConfig {
   api_key: string;
   model: string;
   base_url: string;
+  cache_ttl_days?: number | null;
+  cache_max_mb?: number | null;
+  price_table?: Record<string, ModelPrice> | null;
+  monthly_budget_usd?: number | null;
+}
+
+/** USD per million tokens */
+export interface ModelPrice {
+  input_per_million: number;
+  output_per_million: number;
+}
+
+export interface LLMUsageBucket {
+  key: string;
+  calls: number;
+  failed_calls: number;
+  input_tokens: number;
+  output_tokens: number;
+  cost_usd: number;
+}
+
+export interface LLMUsageSummary {
+  month_to_date_usd: number;
+  monthly_budget_usd: number | null;
+  by_day: LLMUsageBucket[];
+  by_command: LLMUsageBucket[];
+}
+
+export interface DifficultyAssessment {
+  estimated_seconds: number;
+  difficulty: number;
+  topics: string[];
+  prerequisites: string[];
+}
+
+/** One step of a `solution` answer, stored as a JSON array in `answer_content`. */
+export interface SolutionStep {
+  latex: string;
+  justification: string;
+}
+
+export type VerificationStatus = "verified" | "unverified" | "disagreement";
+
+export interface AnswerVerification {
+  status: VerificationStatus;
+  explanation: string;
+  checked_form: string | null;
+}
+
+/** A `{name}` placeholder of a parametric card; `decimals: 0` draws integers. */
+export interface TemplateParam {
+  name: string;
+  min: number;
+  max: number;
+  decimals: number;
+}
+
+export interface TemplateInstance {
+  seed: number;
+  question: string;
+  answer: string;
+  values: { name: string; value: number }[];
+}
+
+export interface TemplateCheck {
+  correct: boolean;
+  expected: string;
+}
+
+/** Result of grading a typed answer against the card's answer. */
+export interface AnswerGrade {
+  correct: boolean;
+  method: "numeric" | "symbolic" | "text";
+  detail: string | null;
+}
+
+/** A vision model's grade of handwritten work; the student may override it. */
+export interface WorkGrade {
+  verdict: "correct" | "partial" | "incorrect";
+  first_wrong_step: string | null;
+  feedback: string;
+}
+
+export interface OcrConfig {
+  provider: "llm" | "local";
+  model_path: string;
+  /** Consent to OCR image cards in the background. */
+  background_indexing: boolean;
+}
+
+export interface OcrIndexStatus {
+  enabled: boolean;
+  running: boolean;
+  pending: number;
+}
+
+export interface EmbeddingConfig {
+  provider: "local" | "llm";
+  model: string;
+}
+
+export interface EmbeddingProgress {
+  done: number;
+  total: number;
+}
+
+export interface EmbeddingRunResult {
+  model: string;
+  embedded: number;
+  unchanged: number;
+  failed: number;
+  unindexed: number;
+}
+
+export interface DuplicateCard {
+  id: string;
+  folder_id: string | null;
+  title: string | null;
+  question_type: "image" | "latex";
+  question_content: string;
+  review_count: number;
+  repetitions: number;
+  created_at: string;
+}
+
+export interface DuplicateCluster {
+  cards: DuplicateCard[];
+  keep_id: string;
+  similarity: number;
+}
+
+export interface SearchFilters {
+  folder_id?: string | null;
+  due?: "due" | "upcoming" | "new" | null;
+  question_type?: "image" | "latex" | null;
+  ease_min?: number | null;
+  ease_max?: number | null;
+}
+
+export interface SearchSnippet {
+  field: "title" | "question" | "answer" | "ocr";
+  parts: { text: string; highlight: boolean }[];
+}
+
+export interface SearchHit {
+  card: Pick<
+    Flashcard,
+    | "id"
+    | "folder_id"
+    | "title"
+    | "question_type"
+    | "question_content"
+    | "answer_type"
+    | "answer_content"
+    | "ease_factor"
+    | "repetitions"
+    | "due_date"
+  >;
+  score: number;
+  snippets: SearchSnippet[];
+}
+
+export interface OcrIndexResult {
+  indexed: number;
+  failed: number;
+  remaining: number;
+}
+
+export interface LLMCacheStats {
+  entries: number;
+  total_bytes: number;
 }
 
 export interface CaptureResult {
   image_path: string;
   data_url: string;
 }
+
+export type GenerationJobStatus =
+  | "queued"
+  | "running"
+  | "paused"
+  | "completed"
+  | "cancelled";
+
+export interface GenerationJob {
+  id: string;
+  folder_id: string | null;
+  status: GenerationJobStatus;
+  concurrency: number;
+  ocr: boolean;
+  generate_answer: boolean;
+  generate_title: boolean;
+  assess_difficulty: boolean;
+  total_items: number;
+  done_items: number;
+  failed_items: number;
+  created_at: string;
+  updated_at: string;
+}
+
+export interface GenerationJobItem {
+  id: string;
+  job_id: string;
+  position: number;
+  image_path: string;
+  crop_x: number | null;
+  crop_y: number | null;
+  crop_width: number | null;
+  crop_height: number | null;
+  answer_crop_x: number | null;
+  answer_crop_y: number | null;
+  answer_crop_width: number | null;
+  answer_crop_height: number | null;
+  status: "pending" | "running" | "done" | "failed" | "cancelled" | "accepted";
+  title: string | null;
+  question_type: "image" | "latex" | null;
+  question_content: string | null;
+  answer_type: "image" | "latex" | null;
+  answer_content: string | null;
+  timer_seconds: number | null;
+  error: string | null;
+  flashcard_id: string | null;
+}
+
+export interface CreateGenerationJobInput {
+  folder_id: string | null;
+  items: Array<{
+    image_path: string;
+    region?: { x: number; y: number; width: number; height: number } | null;
+    answer_region?: { x: number; y: number; width: number; height: number } | null;
+  }>;
+  concurrency?: number;
+  ocr?: boolean;
+  generate_answer?: boolean;
+  generate_title?: boolean;
+  assess_difficulty?: boolean;
+}
+
+export interface GenerationJobProgress {
+  job_id: string;
+  item_id: string | null;
+  item_status: string | null;
+  status: GenerationJobStatus;
+  total_items: number;
+  done_items: number;
+  failed_items: number;
+}
+
+export interface ChatToolCall {
+  id: string;
+  type: "function";
+  function: { name: string; arguments: string };
+}
+
+export interface ChatResponse {
+  content: string | null;
+  // eslint-disable-next-line @typescript-eslint/no-explicit-any
+  messages: any[];
+  pending_tool_calls: ChatToolCall[];
+  proposed_cards: Array<{ question: string; answer: string; folder_id: string | null }>;
+  data_changed: boolean;
+}
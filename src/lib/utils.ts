@@ -1,5 +1,6 @@
 import { clsx, type ClassValue } from "clsx";
 import { twMerge } from "tailwind-merge";
+import type { SolutionStep } from "./types";
 
 export function cn(...inputs: ClassValue[]) {
   return twMerge(clsx(inputs));
@@ -22,6 +23,38 @@ export function daysUntil(dateString: string): number {
   return Math.ceil(diff / (1000 * 60 * 60 * 24));
 }
 
+/** Local calendar date as YYYY-MM-DD, the format deadlines are stored in. */
+export function toDateKey(date: Date = new Date()): string {
+  const month = (date.getMonth() + 1).toString().padStart(2, "0");
+  const day = date.getDate().toString().padStart(2, "0");
+  return `${date.getFullYear()}-${month}-${day}`;
+}
+
+/** Least-squares slope of `values` against their index. */
+export function trendSlope(values: number[]): number {
+  if (values.length < 2) return 0;
+  const meanX = (values.length - 1) / 2;
+  const meanY = values.reduce((sum, v) => sum + v, 0) / values.length;
+  let covariance = 0;
+  let variance = 0;
+  values.forEach((y, x) => {
+    covariance += (x - meanX) * (y - meanY);
+    variance += (x - meanX) ** 2;
+  });
+  return covariance / variance;
+}
+
+/**
+ * Pace over a session from the speed-ratio slope (time used over the timer,
+ * per card). A shift under 10% of the timer across the session is steady.
+ */
+export function describeSpeedTrend(slope: number, cards: number): string {
+  const change = slope * Math.max(0, cards - 1);
+  if (change <= -0.1) return "Faster";
+  if (change >= 0.1) return "Slower";
+  return "Steady";
+}
+
 export function generateId(): string {
   if (typeof crypto !== "undefined" && "randomUUID" in crypto) {
     return crypto.randomUUID();
@@ -37,3 +70,21 @@ export function shuffleArray<T>(array: T[]): T[] {
   }
   return arr;
 }
+
+/** Reads the steps of a `solution` answer, or an empty list if malformed. */
+export function parseSolutionSteps(content: string | null): SolutionStep[] {
+  if (!content) return [];
+  try {
+    const steps = JSON.parse(content);
+    return Array.isArray(steps) ? steps : [];
+  } catch {
+    return [];
+  }
+}
+
+/** Flattens solution steps into LaTeX text, one step per line. */
+export function solutionToText(steps: SolutionStep[]): string {
+  return steps
+    .map((step) => `${step.justification}: $$${step.latex}$$`)
+    .join("\n");
+}
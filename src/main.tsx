@@ -13,6 +13,9 @@ import SettingsPage from "./pages/SettingsPage";
 import ImportPdfPage from "./pages/ImportPdfPage";
 import ImportImagePage from "./pages/ImportImagePage";
 import ImportLibraryPage from "./pages/ImportLibraryPage";
+import DuplicatesPage from "./pages/DuplicatesPage";
+import LeechesPage from "./pages/LeechesPage";
+import DraftsPage from "./pages/DraftsPage";
 
 ReactDOM.createRoot(document.getElementById("root")!).render(
   <React.StrictMode>
@@ -26,6 +29,9 @@ ReactDOM.createRoot(document.getElementById("root")!).render(
           <Route path="/study" element={<StudyPage />} />
           <Route path="/browse" element={<BrowsePage />} />
           <Route path="/imports" element={<ImportLibraryPage />} />
+          <Route path="/duplicates" element={<DuplicatesPage />} />
+          <Route path="/leeches" element={<LeechesPage />} />
+          <Route path="/drafts" element={<DraftsPage />} />
           <Route path="/settings" element={<SettingsPage />} />
           <Route path="/import/pdf" element={<ImportPdfPage />} />
           <Route path="/import/image" element={<ImportImagePage />} />
@@ -2,18 +2,35 @@ import { useState, useEffect, useMemo } from "react";
 import { Link } from "react-router-dom";
 import { useAppStore } from "@/stores/app-store";
 import * as commands from "@/lib/commands";
-import type { Flashcard, Folder } from "@/lib/types";
-import { cn, formatDate } from "@/lib/utils";
+import type { Flashcard, Folder, SearchFilters, SearchHit, SearchSnippet } from "@/lib/types";
+import { cn, formatDate, parseSolutionSteps } from "@/lib/utils";
 import { ImageDisplay } from "@/components/ImageDisplay";
 import { LaTeXRenderer } from "@/components/LaTeXRenderer";
+import { SolutionSteps } from "@/components/SolutionSteps";
 import { Card, CardContent } from "@/components/ui/card";
 import { Button } from "@/components/ui/button";
 import { Input } from "@/components/ui/input";
 import { Badge } from "@/components/ui/badge";
-import { Search, Pencil, Trash2, FolderOpen, Clock } from "lucide-react";
+import {
+  Search,
+  Pencil,
+  Trash2,
+  FolderOpen,
+  Clock,
+  Sparkles,
+  ScanText,
+  Loader2,
+  Pause,
+  Play,
+  EyeOff,
+  CalendarClock,
+  RotateCcw,
+  Eraser,
+} from "lucide-react";
 import { CardPreviewModal } from "@/components/CardPreviewModal";
 import { unlinkFlashcardFromImports } from "@/lib/import-library";
 import { confirmDestructive } from "@/lib/dialogs";
+import { getCardState } from "@/lib/review-policy";
 
 type SortMode = "newest" | "oldest" | "due-first" | "ease-asc" | "ease-desc";
 type FilterMode = "all" | "due" | "not-due" | "new";
@@ -28,6 +45,14 @@ export default function BrowsePage() {
   const [filterMode, setFilterMode] = useState<FilterMode>("all");
   const [previewIndex, setPreviewIndex] = useState<number | null>(null);
   const [selectedIds, setSelectedIds] = useState<Set<string>>(new Set());
+  // Ranked results from the search index; null when not searching or when the
+  // index is unavailable and the plain-text filter below is used instead.
+  const [searchHits, setSearchHits] = useState<SearchHit[] | null>(null);
+  const [semantic, setSemantic] = useState(false);
+  const [indexingImages, setIndexingImages] = useState(false);
+  const [indexMessage, setIndexMessage] = useState<string | null>(null);
+  const [rescheduleDate, setRescheduleDate] = useState("");
+  const [bulkBusy, setBulkBusy] = useState(false);
 
   useEffect(() => {
     let active = true;
@@ -46,6 +71,64 @@ export default function BrowsePage() {
     };
   }, [setFolders]);
 
+  useEffect(() => {
+    const query = search.trim();
+    if (!query) {
+      setSearchHits(null);
+      return;
+    }
+    const filters: SearchFilters = {
+      folder_id: selectedFolder === "all" ? null : selectedFolder,
+      due:
+        filterMode === "all" ? null : filterMode === "not-due" ? "upcoming" : filterMode,
+    };
+    let active = true;
+    const timeout = window.setTimeout(() => {
+      commands
+        .searchCards(query, filters, semantic)
+        .then((hits) => {
+          if (active) setSearchHits(hits);
+        })
+        .catch(() => {
+          if (active) setSearchHits(null);
+        });
+    }, 200);
+    return () => {
+      active = false;
+      window.clearTimeout(timeout);
+    };
+  }, [search, selectedFolder, filterMode, semantic]);
+
+  const snippetsById = useMemo(() => {
+    const map = new Map<string, SearchSnippet[]>();
+    for (const hit of searchHits ?? []) map.set(hit.card.id, hit.snippets);
+    return map;
+  }, [searchHits]);
+
+  const handleIndexImages = async () => {
+    setIndexingImages(true);
+    setIndexMessage(null);
+    try {
+      let indexed = 0;
+      let failed = 0;
+      for (;;) {
+        const result = await commands.indexCardOcr();
+        indexed += result.indexed;
+        failed += result.failed;
+        setIndexMessage(`Read ${indexed} image card${indexed !== 1 ? "s" : ""}...`);
+        if (result.remaining === 0 || result.indexed === 0) break;
+      }
+      setIndexMessage(
+        `Image text indexed for ${indexed} card${indexed !== 1 ? "s" : ""}` +
+          (failed > 0 ? `, ${failed} unreadable.` : ".")
+      );
+    } catch (err) {
+      setIndexMessage(`Error: ${err}`);
+    } finally {
+      setIndexingImages(false);
+    }
+  };
+
   const folderMap = useMemo(() => {
     const map = new Map<string, Folder>();
     for (const f of folders) map.set(f.id, f);
@@ -53,6 +136,13 @@ export default function BrowsePage() {
   }, [folders]);
 
   const filteredCards = useMemo(() => {
+    if (searchHits) {
+      const byId = new Map(allCards.map((c) => [c.id, c]));
+      return searchHits
+        .map((hit) => byId.get(hit.card.id))
+        .filter((c): c is Flashcard => c !== undefined);
+    }
+
     let cards = [...allCards];
 
     if (selectedFolder !== "all") {
@@ -61,7 +151,10 @@ export default function BrowsePage() {
 
     const now = new Date();
     if (filterMode === "due") {
-      cards = cards.filter((c) => !c.due_date || new Date(c.due_date) <= now);
+      cards = cards.filter(
+        (c) =>
+          getCardState(c) === "active" && (!c.due_date || new Date(c.due_date) <= now)
+      );
     } else if (filterMode === "not-due") {
       cards = cards.filter((c) => c.due_date && new Date(c.due_date) > now);
     } else if (filterMode === "new") {
@@ -102,7 +195,7 @@ export default function BrowsePage() {
     });
 
     return cards;
-  }, [allCards, selectedFolder, filterMode, search, sortMode]);
+  }, [allCards, selectedFolder, filterMode, search, sortMode, searchHits]);
 
   const handleDelete = async (id: string) => {
     const confirmed = await confirmDestructive(
@@ -146,6 +239,26 @@ export default function BrowsePage() {
     }
   };
 
+  const handleBulkChange = async (
+    change: (ids: string[]) => Promise<number>,
+    confirmation?: [string, string]
+  ) => {
+    const ids = Array.from(selectedIds);
+    if (ids.length === 0) return;
+    if (confirmation && !(await confirmDestructive(confirmation[0], confirmation[1]))) {
+      return;
+    }
+    setBulkBusy(true);
+    try {
+      await change(ids);
+      setAllCards(await commands.getFlashcards());
+    } catch (err) {
+      setIndexMessage(`Error: ${err}`);
+    } finally {
+      setBulkBusy(false);
+    }
+  };
+
   return (
     <div className="space-y-6 animate-fade-up">
       <div>
@@ -208,6 +321,8 @@ export default function BrowsePage() {
         <select
           value={sortMode}
           onChange={(e) => setSortMode(e.target.value as SortMode)}
+          disabled={searchHits !== null}
+          title={searchHits !== null ? "Search results are ranked by relevance" : undefined}
           className="h-9 rounded-lg border border-input bg-background px-3 text-sm"
         >
           <option value="newest">Newest first</option>
@@ -216,8 +331,43 @@ export default function BrowsePage() {
           <option value="ease-asc">Hardest first</option>
           <option value="ease-desc">Easiest first</option>
         </select>
+
+        <Button
+          size="sm"
+          variant={semantic ? "default" : "outline"}
+          onClick={() => setSemantic((prev) => !prev)}
+          title="Also rank cards by meaning, using their embeddings"
+        >
+          <Sparkles className="h-3.5 w-3.5 mr-1.5" />
+          Semantic
+        </Button>
+        <Button
+          size="sm"
+          variant="outline"
+          onClick={handleIndexImages}
+          disabled={indexingImages}
+          title="Read the text in image cards so search can find them"
+        >
+          {indexingImages ? (
+            <Loader2 className="h-3.5 w-3.5 mr-1.5 animate-spin" />
+          ) : (
+            <ScanText className="h-3.5 w-3.5 mr-1.5" />
+          )}
+          Index Images
+        </Button>
       </div>
 
+      {indexMessage && (
+        <p
+          className={cn(
+            "text-xs",
+            indexMessage.startsWith("Error") ? "text-destructive" : "text-muted-foreground"
+          )}
+        >
+          {indexMessage}
+        </p>
+      )}
+
       <div className="flex flex-wrap items-center justify-between gap-3">
         <p className="text-sm text-muted-foreground">
           {filteredCards.length} card{filteredCards.length !== 1 ? "s" : ""}
@@ -257,6 +407,91 @@ export default function BrowsePage() {
         )}
       </div>
 
+      {selectedIds.size > 0 && (
+        <div className="flex flex-wrap items-center gap-2 rounded-xl border border-border bg-muted/30 px-3 py-2">
+          <Button
+            size="sm"
+            variant="outline"
+            disabled={bulkBusy}
+            onClick={() => handleBulkChange((ids) => commands.setCardsSuspended(ids, true))}
+          >
+            <Pause className="h-3.5 w-3.5 mr-1.5" />
+            Suspend
+          </Button>
+          <Button
+            size="sm"
+            variant="outline"
+            disabled={bulkBusy}
+            onClick={() =>
+              handleBulkChange(async (ids) => {
+                await commands.unburyCards(ids);
+                return commands.setCardsSuspended(ids, false);
+              })
+            }
+          >
+            <Play className="h-3.5 w-3.5 mr-1.5" />
+            Restore
+          </Button>
+          <Button
+            size="sm"
+            variant="outline"
+            disabled={bulkBusy}
+            onClick={() => handleBulkChange((ids) => commands.buryCards(ids))}
+            title="Hide until tomorrow"
+          >
+            <EyeOff className="h-3.5 w-3.5 mr-1.5" />
+            Bury
+          </Button>
+          <div className="flex items-center gap-1.5">
+            <Input
+              type="date"
+              value={rescheduleDate}
+              onChange={(e) => setRescheduleDate(e.target.value)}
+              className="h-8 w-40"
+            />
+            <Button
+              size="sm"
+              variant="outline"
+              disabled={bulkBusy || !rescheduleDate}
+              onClick={() =>
+                handleBulkChange((ids) => commands.rescheduleCards(ids, rescheduleDate))
+              }
+            >
+              <CalendarClock className="h-3.5 w-3.5 mr-1.5" />
+              Reschedule
+            </Button>
+          </div>
+          <Button
+            size="sm"
+            variant="outline"
+            disabled={bulkBusy}
+            onClick={() =>
+              handleBulkChange(commands.resetCards, [
+                `Reset progress on ${selectedIds.size} card${selectedIds.size !== 1 ? "s" : ""}? They start over as due now.`,
+                "Reset Progress",
+              ])
+            }
+          >
+            <RotateCcw className="h-3.5 w-3.5 mr-1.5" />
+            Reset
+          </Button>
+          <Button
+            size="sm"
+            variant="outline"
+            disabled={bulkBusy}
+            onClick={() =>
+              handleBulkChange(commands.forgetCards, [
+                `Forget ${selectedIds.size} card${selectedIds.size !== 1 ? "s" : ""}? Progress, lapses and leech tags are cleared; the review history is kept.`,
+                "Forget Cards",
+              ])
+            }
+          >
+            <Eraser className="h-3.5 w-3.5 mr-1.5" />
+            Forget
+          </Button>
+        </div>
+      )}
+
       {loading ? (
         <p className="text-muted-foreground py-8 text-center">
           Loading cards...
@@ -273,18 +508,28 @@ export default function BrowsePage() {
       ) : (
         <div className="space-y-2">
           {filteredCards.map((card, index) => {
+            const state = getCardState(card);
             const isDue =
-              !card.due_date || new Date(card.due_date) <= new Date();
+              state === "active" &&
+              (!card.due_date || new Date(card.due_date) <= new Date());
             const folderName = card.folder_id
               ? folderMap.get(card.folder_id)?.name
               : null;
             const hasAnswer = Boolean(card.answer_content);
-            const statusLabel = isDue
+            const statusLabel =
+              state === "suspended"
+              ? "Suspended"
+              : state === "buried"
+              ? `Buried until ${formatDate(card.buried_until!)}`
+              : isDue
               ? "Due"
               : card.repetitions === 0
               ? "New"
               : formatDate(card.due_date!);
-            const statusVariant = isDue
+            const statusVariant =
+              state !== "active"
+              ? "secondary"
+              : isDue
               ? "default"
               : card.repetitions === 0
               ? "warning"
@@ -320,6 +565,9 @@ export default function BrowsePage() {
                         <span>EF {card.ease_factor.toFixed(1)}</span>
                         <span>Rep {card.repetitions}</span>
                       </div>
+                      {snippetsById.get(card.id)?.map((snippet) => (
+                        <SnippetLine key={snippet.field} snippet={snippet} />
+                      ))}
                     </div>
                     <div
                       className="flex items-center gap-1.5 shrink-0"
@@ -405,6 +653,30 @@ export default function BrowsePage() {
   );
 }
 
+const SNIPPET_LABELS: Record<SearchSnippet["field"], string> = {
+  title: "Title",
+  question: "Question",
+  answer: "Answer",
+  ocr: "Image text",
+};
+
+function SnippetLine({ snippet }: { snippet: SearchSnippet }) {
+  return (
+    <p className="truncate text-xs text-muted-foreground">
+      <span className="font-medium text-foreground/70">{SNIPPET_LABELS[snippet.field]}: </span>
+      {snippet.parts.map((part, index) =>
+        part.highlight ? (
+          <mark key={index} className="rounded bg-primary/15 px-0.5 text-foreground">
+            {part.text}
+          </mark>
+        ) : (
+          <span key={index}>{part.text}</span>
+        )
+      )}
+    </p>
+  );
+}
+
 function PreviewColumn({
   label,
   type,
@@ -459,7 +731,11 @@ function PreviewColumn({
       ) : (
         <div className="overflow-hidden rounded-lg border border-border/60 bg-background p-3.5">
           <div className="max-h-32 overflow-hidden text-sm leading-6">
-            <LaTeXRenderer content={content} />
+            {type === "solution" ? (
+              <SolutionSteps steps={parseSolutionSteps(content)} />
+            ) : (
+              <LaTeXRenderer content={content} />
+            )}
           </div>
         </div>
       )}
@@ -151,6 +151,9 @@ export default function CardPage() {
                     answer_content: card.answer_content,
                     timer_mode: card.timer_mode,
                     timer_seconds: card.timer_seconds,
+                    verification_status: card.verification_status,
+                    verification_note: card.verification_note,
+                    template_params: card.template_params,
                   }
                 : folderId
                 ? { folder_id: folderId }
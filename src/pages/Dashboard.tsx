@@ -2,8 +2,8 @@ import { useEffect, useState, useRef } from "react";
 import { Link } from "react-router-dom";
 import { useAppStore } from "@/stores/app-store";
 import * as commands from "@/lib/commands";
-import type { Flashcard, StudyStats } from "@/lib/types";
-import { cn, daysUntil } from "@/lib/utils";
+import type { Flashcard, Folder, SessionAnalytics, StudyStats } from "@/lib/types";
+import { cn, daysUntil, describeSpeedTrend, formatDate, formatTime } from "@/lib/utils";
 import { Card, CardContent } from "@/components/ui/card";
 import { Button } from "@/components/ui/button";
 import { Badge } from "@/components/ui/badge";
@@ -15,6 +15,7 @@ import {
   Clock,
   FolderOpen,
   RotateCcw,
+  Shuffle,
 } from "lucide-react";
 
 const DECK_EMOJIS = [
@@ -38,6 +39,12 @@ export default function Dashboard() {
   );
   const [loading, setLoading] = useState(true);
   const [emojiPickerFolderId, setEmojiPickerFolderId] = useState<string | null>(null);
+  const [sessions, setSessions] = useState<SessionAnalytics[]>([]);
+
+  useEffect(() => {
+    // Session analytics come from the native backend; hidden without it
+    commands.getStudySessions(null, 5).then(setSessions).catch(() => {});
+  }, []);
 
   useEffect(() => {
     let active = true;
@@ -150,6 +157,13 @@ export default function Dashboard() {
         />
       </div>
 
+      {folders.filter((f) => (folderMetrics.get(f.id)?.stats.due_today ?? 0) > 0).length > 1 && (
+        <MixedSession
+          folders={folders.filter((f) => (folderMetrics.get(f.id)?.stats.due_today ?? 0) > 0)}
+          dueByFolder={folderMetrics}
+        />
+      )}
+
       <div>
         <div className="flex items-center justify-between mb-4">
           <h2 className="text-lg font-bold">Your Decks</h2>
@@ -262,10 +276,140 @@ export default function Dashboard() {
           </div>
         )}
       </div>
+
+      {sessions.length > 0 && <RecentSessions sessions={sessions} folders={folders} />}
+    </div>
+  );
+}
+
+function RecentSessions({
+  sessions,
+  folders,
+}: {
+  sessions: SessionAnalytics[];
+  folders: Folder[];
+}) {
+  const folderName = (session: SessionAnalytics) =>
+    session.folder_ids.length === 0
+      ? "All decks"
+      : session.folder_ids
+          .map((id) => folders.find((f) => f.id === id)?.name ?? "Deleted deck")
+          .join(", ");
+
+  return (
+    <div>
+      <h2 className="text-lg font-bold mb-4">Recent Sessions</h2>
+      <Card>
+        <CardContent className="p-4 space-y-1.5">
+          {sessions.map((session) => (
+            <div
+              key={session.id}
+              className="flex flex-wrap items-center gap-x-4 gap-y-1 rounded-xl bg-muted/30 px-4 py-2.5 text-sm"
+            >
+              <span className="min-w-0 flex-1 truncate font-medium">{folderName(session)}</span>
+              {!session.ended_at && (
+                <Badge variant="secondary" className="text-[10px]">
+                  Unfinished
+                </Badge>
+              )}
+              <span className="text-xs text-muted-foreground">{formatDate(session.started_at)}</span>
+              <span className="text-xs tabular-nums">
+                {session.reviewed}/{session.queue_length} cards
+              </span>
+              {session.reviewed > 0 && (
+                <>
+                  <span className="text-xs tabular-nums text-primary">
+                    {Math.round(session.accuracy * 100)}%
+                  </span>
+                  <span className="text-xs tabular-nums text-muted-foreground">
+                    {formatTime(session.avg_response_seconds)}/card
+                  </span>
+                  <span className="text-xs text-muted-foreground">
+                    {describeSpeedTrend(session.speed_trend, session.reviewed)}
+                  </span>
+                </>
+              )}
+            </div>
+          ))}
+        </CardContent>
+      </Card>
     </div>
   );
 }
 
+const SESSION_WEIGHTS = [1, 2, 3];
+
+/** Picks decks and their weights for one interleaved study session. */
+function MixedSession({
+  folders,
+  dueByFolder,
+}: {
+  folders: Folder[];
+  dueByFolder: Map<string, FolderMetrics>;
+}) {
+  const [weights, setWeights] = useState<Record<string, number>>({});
+  const selected = folders.filter((f) => (weights[f.id] ?? 0) > 0);
+  const sessionParam = selected.map((f) => `${f.id}:${weights[f.id]}`).join(",");
+
+  return (
+    <Card>
+      <CardContent className="p-5 space-y-3">
+        <div className="flex items-start justify-between gap-4">
+          <div>
+            <h2 className="text-sm font-bold">Mixed session</h2>
+            <p className="text-xs text-muted-foreground mt-0.5">
+              Interleave decks in one session. Weight a deck to see more of it; decks with
+              near deadlines come up more often.
+            </p>
+          </div>
+          {selected.length > 0 ? (
+            <Button size="sm" asChild>
+              <Link to={`/study?folders=${encodeURIComponent(sessionParam)}`}>
+                <Shuffle className="h-3.5 w-3.5 mr-1.5" />
+                Start
+              </Link>
+            </Button>
+          ) : (
+            <Button size="sm" disabled>
+              <Shuffle className="h-3.5 w-3.5 mr-1.5" />
+              Start
+            </Button>
+          )}
+        </div>
+        <div className="flex flex-wrap gap-2">
+          {folders.map((folder) => {
+            const weight = weights[folder.id] ?? 0;
+            return (
+              <button
+                key={folder.id}
+                type="button"
+                onClick={() =>
+                  setWeights((prev) => ({
+                    ...prev,
+                    // Cycle off, 1x, 2x, 3x
+                    [folder.id]: weight >= SESSION_WEIGHTS[SESSION_WEIGHTS.length - 1] ? 0 : weight + 1,
+                  }))
+                }
+                className={cn(
+                  "flex items-center gap-1.5 rounded-lg border px-2.5 py-1 text-xs transition-colors",
+                  weight > 0
+                    ? "border-primary bg-primary/10 text-primary"
+                    : "border-border text-muted-foreground hover:bg-muted"
+                )}
+              >
+                <span>{folder.emoji || "\u{1F4C1}"}</span>
+                <span className="max-w-[140px] truncate">{folder.name}</span>
+                <span className="opacity-70">{dueByFolder.get(folder.id)?.stats.due_today ?? 0}</span>
+                {weight > 0 && <span className="font-semibold">{weight}x</span>}
+              </button>
+            );
+          })}
+        </div>
+      </CardContent>
+    </Card>
+  );
+}
+
 function EmojiPicker({
   currentEmoji,
   onSelect,
@@ -0,0 +1,336 @@
+import { useState, useEffect, useMemo, useCallback } from "react";
+import { Link, useNavigate, useSearchParams } from "react-router-dom";
+import * as commands from "@/lib/commands";
+import type { GenerationJob, GenerationJobItem } from "@/lib/types";
+import { formatDate } from "@/lib/utils";
+import { linkFlashcardsToImport } from "@/lib/import-library";
+import { ImageDisplay } from "@/components/ImageDisplay";
+import { LaTeXRenderer } from "@/components/LaTeXRenderer";
+import { Card, CardContent } from "@/components/ui/card";
+import { Button } from "@/components/ui/button";
+import { Badge } from "@/components/ui/badge";
+import { Check, Inbox, Loader2, Pause, Play, X } from "lucide-react";
+
+export default function DraftsPage() {
+  const [searchParams] = useSearchParams();
+  const jobId = searchParams.get("jobId");
+
+  return jobId ? (
+    <JobDrafts jobId={jobId} importId={searchParams.get("importId")} />
+  ) : (
+    <JobList />
+  );
+}
+
+function JobList() {
+  const [jobs, setJobs] = useState<GenerationJob[]>([]);
+  const [loading, setLoading] = useState(true);
+  const [error, setError] = useState<string | null>(null);
+
+  const loadJobs = useCallback(async () => {
+    try {
+      setJobs(await commands.listGenerationJobs());
+    } catch (err) {
+      setError(String(err));
+    } finally {
+      setLoading(false);
+    }
+  }, []);
+
+  useEffect(() => {
+    loadJobs();
+    const unlisten = commands.onGenerationJobProgress(() => {
+      loadJobs();
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, [loadJobs]);
+
+  return (
+    <div className="space-y-6 animate-fade-up">
+      <div>
+        <h1 className="text-2xl font-extrabold tracking-tight">Drafts</h1>
+        <p className="text-muted-foreground mt-1">
+          Cards generated in the background. Review them before adding them to a deck.
+        </p>
+      </div>
+
+      {error && <p className="text-sm text-destructive">{error}</p>}
+
+      {loading ? (
+        <div className="flex justify-center py-12">
+          <Loader2 className="h-6 w-6 animate-spin text-muted-foreground" />
+        </div>
+      ) : jobs.length === 0 ? (
+        <Card className="border-dashed">
+          <CardContent className="flex flex-col items-center gap-3 py-12 text-center">
+            <Inbox className="h-10 w-10 text-muted-foreground/40" />
+            <p className="text-muted-foreground">
+              No drafts. Importing an image creates them.
+            </p>
+          </CardContent>
+        </Card>
+      ) : (
+        <div className="space-y-2">
+          {jobs.map((job) => (
+            <Link key={job.id} to={`/drafts?jobId=${job.id}`} className="block">
+              <Card className="hover:border-primary/40">
+                <CardContent className="flex items-center gap-3 p-4 text-sm">
+                  <JobStatusBadge status={job.status} />
+                  <span>
+                    {job.done_items} of {job.total_items} drafted
+                    {job.failed_items > 0 && `, ${job.failed_items} failed`}
+                  </span>
+                  <span className="ml-auto text-xs text-muted-foreground">
+                    {formatDate(job.created_at)}
+                  </span>
+                </CardContent>
+              </Card>
+            </Link>
+          ))}
+        </div>
+      )}
+    </div>
+  );
+}
+
+function JobDrafts({ jobId, importId }: { jobId: string; importId: string | null }) {
+  const navigate = useNavigate();
+  const [job, setJob] = useState<GenerationJob | null>(null);
+  const [items, setItems] = useState<GenerationJobItem[]>([]);
+  const [selected, setSelected] = useState<Set<string>>(new Set());
+  const [accepting, setAccepting] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  const loadJob = useCallback(async () => {
+    try {
+      const [jobs, jobItems] = await Promise.all([
+        commands.listGenerationJobs(),
+        commands.getGenerationJobItems(jobId),
+      ]);
+      setJob(jobs.find((j) => j.id === jobId) ?? null);
+      setItems(jobItems);
+    } catch (err) {
+      setError(String(err));
+    }
+  }, [jobId]);
+
+  useEffect(() => {
+    loadJob();
+    const unlisten = commands.onGenerationJobProgress((progress) => {
+      if (progress.job_id === jobId) loadJob();
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, [jobId, loadJob]);
+
+  // Finished drafts start out selected so accepting everything is one click.
+  const doneIds = useMemo(
+    () => items.filter((item) => item.status === "done").map((item) => item.id),
+    [items]
+  );
+  useEffect(() => {
+    setSelected((prev) => {
+      const next = new Set(prev);
+      for (const id of doneIds) next.add(id);
+      return next;
+    });
+  }, [doneIds]);
+
+  const toggle = (id: string) => {
+    setSelected((prev) => {
+      const next = new Set(prev);
+      if (next.has(id)) next.delete(id);
+      else next.add(id);
+      return next;
+    });
+  };
+
+  const runControl = async (action: (id: string) => Promise<void>) => {
+    setError(null);
+    try {
+      await action(jobId);
+      await loadJob();
+    } catch (err) {
+      setError(String(err));
+    }
+  };
+
+  const handleAccept = async () => {
+    const ids = doneIds.filter((id) => selected.has(id));
+    if (ids.length === 0) return;
+    setAccepting(true);
+    setError(null);
+    try {
+      const created = await commands.acceptGenerationJobItems(jobId, ids);
+      if (importId && created.length > 0) {
+        await linkFlashcardsToImport(importId, created);
+      }
+      await loadJob();
+    } catch (err) {
+      setError(String(err));
+    } finally {
+      setAccepting(false);
+    }
+  };
+
+  const active = job?.status === "queued" || job?.status === "running";
+  const resumable = job?.status === "paused";
+  const finished = job?.status === "completed" || job?.status === "cancelled";
+  const acceptCount = doneIds.filter((id) => selected.has(id)).length;
+
+  return (
+    <div className="space-y-6 animate-fade-up">
+      <div className="flex flex-wrap items-start justify-between gap-4">
+        <div>
+          <h1 className="text-2xl font-extrabold tracking-tight">Drafts</h1>
+          {job && (
+            <p className="text-muted-foreground mt-1">
+              {job.done_items} of {job.total_items} drafted
+              {job.failed_items > 0 && `, ${job.failed_items} failed`}
+            </p>
+          )}
+        </div>
+        <div className="flex flex-wrap items-center gap-2">
+          {job && <JobStatusBadge status={job.status} />}
+          {active && (
+            <Button
+              size="sm"
+              variant="outline"
+              onClick={() => runControl(commands.pauseGenerationJob)}
+            >
+              <Pause className="h-3.5 w-3.5 mr-1.5" />
+              Pause
+            </Button>
+          )}
+          {resumable && (
+            <Button
+              size="sm"
+              variant="outline"
+              onClick={() => runControl(commands.resumeGenerationJob)}
+            >
+              <Play className="h-3.5 w-3.5 mr-1.5" />
+              Resume
+            </Button>
+          )}
+          {job && !finished && (
+            <Button
+              size="sm"
+              variant="outline"
+              onClick={() => runControl(commands.cancelGenerationJob)}
+            >
+              <X className="h-3.5 w-3.5 mr-1.5" />
+              Cancel
+            </Button>
+          )}
+          <Button size="sm" onClick={handleAccept} disabled={accepting || acceptCount === 0}>
+            {accepting ? (
+              <Loader2 className="h-3.5 w-3.5 mr-1.5 animate-spin" />
+            ) : (
+              <Check className="h-3.5 w-3.5 mr-1.5" />
+            )}
+            Add {acceptCount} Card{acceptCount !== 1 ? "s" : ""}
+          </Button>
+          {job?.folder_id && (
+            <Button
+              size="sm"
+              variant="ghost"
+              onClick={() => navigate(`/folder?id=${job.folder_id}`)}
+            >
+              Open deck
+            </Button>
+          )}
+        </div>
+      </div>
+
+      {error && <p className="text-sm text-destructive">{error}</p>}
+
+      <div className="space-y-3">
+        {items.map((item) => (
+          <DraftItem
+            key={item.id}
+            item={item}
+            selected={selected.has(item.id)}
+            onToggle={() => toggle(item.id)}
+          />
+        ))}
+      </div>
+    </div>
+  );
+}
+
+function JobStatusBadge({ status }: { status: GenerationJob["status"] }) {
+  return (
+    <Badge variant={status === "cancelled" ? "destructive" : "secondary"} className="capitalize">
+      {status}
+    </Badge>
+  );
+}
+
+function DraftItem({
+  item,
+  selected,
+  onToggle,
+}: {
+  item: GenerationJobItem;
+  selected: boolean;
+  onToggle: () => void;
+}) {
+  return (
+    <Card>
+      <CardContent className="flex items-start gap-3 p-4">
+        {item.status === "done" ? (
+          <input
+            type="checkbox"
+            checked={selected}
+            onChange={onToggle}
+            className="mt-1 h-4 w-4 rounded border-border accent-primary"
+          />
+        ) : (
+          <Badge
+            variant={item.status === "failed" ? "destructive" : "secondary"}
+            className="text-[10px] capitalize"
+          >
+            {item.status === "running" && <Loader2 className="h-3 w-3 mr-1 animate-spin" />}
+            {item.status}
+          </Badge>
+        )}
+
+        <div className="min-w-0 flex-1 space-y-2 text-sm">
+          {item.title && <p className="truncate font-semibold">{item.title}</p>}
+          {item.question_content &&
+            (item.question_type === "image" ? (
+              <ImageDisplay
+                src={item.question_content}
+                alt="Question"
+                className="h-32 w-full object-contain rounded"
+              />
+            ) : (
+              <LaTeXRenderer content={item.question_content} />
+            ))}
+          {item.answer_content && (
+            <div className="text-muted-foreground">
+              {item.answer_type === "image" ? (
+                <ImageDisplay
+                  src={item.answer_content}
+                  alt="Answer"
+                  className="h-24 w-full object-contain rounded"
+                />
+              ) : (
+                <LaTeXRenderer content={item.answer_content} />
+              )}
+            </div>
+          )}
+          {item.error && <p className="text-destructive">{item.error}</p>}
+          {item.flashcard_id && (
+            <Link to={`/card?id=${item.flashcard_id}`} className="text-xs text-primary">
+              Open card
+            </Link>
+          )}
+        </div>
+      </CardContent>
+    </Card>
+  );
+}
@@ -0,0 +1,266 @@
+import { useState, useEffect, useMemo } from "react";
+import { useAppStore } from "@/stores/app-store";
+import * as commands from "@/lib/commands";
+import type {
+  DuplicateCard,
+  DuplicateCluster,
+  EmbeddingProgress,
+  EmbeddingRunResult,
+  Folder,
+} from "@/lib/types";
+import { cn, formatDate } from "@/lib/utils";
+import { ImageDisplay } from "@/components/ImageDisplay";
+import { LaTeXRenderer } from "@/components/LaTeXRenderer";
+import { Card, CardContent } from "@/components/ui/card";
+import { Button } from "@/components/ui/button";
+import { Badge } from "@/components/ui/badge";
+import { Copy, Loader2, Merge, RefreshCw } from "lucide-react";
+import { unlinkFlashcardFromImports } from "@/lib/import-library";
+import { confirmDestructive } from "@/lib/dialogs";
+
+const THRESHOLDS = [
+  [0.97, "Strict"],
+  [0.92, "Default"],
+  [0.85, "Loose"],
+] as const;
+
+export default function DuplicatesPage() {
+  const { folders, setFolders } = useAppStore();
+  const [threshold, setThreshold] = useState<number>(0.92);
+  const [clusters, setClusters] = useState<DuplicateCluster[]>([]);
+  const [keepIds, setKeepIds] = useState<Map<number, string>>(new Map());
+  const [progress, setProgress] = useState<EmbeddingProgress | null>(null);
+  const [lastRun, setLastRun] = useState<EmbeddingRunResult | null>(null);
+  const [scanning, setScanning] = useState(false);
+  const [merging, setMerging] = useState<number | null>(null);
+  const [error, setError] = useState<string | null>(null);
+
+  useEffect(() => {
+    commands.getFolders().then(setFolders).catch(() => {});
+  }, [setFolders]);
+
+  useEffect(() => {
+    let unlisten: (() => void) | null = null;
+    commands
+      .onEmbeddingProgress(setProgress)
+      .then((fn) => {
+        unlisten = fn;
+      })
+      .catch(() => {});
+    return () => {
+      unlisten?.();
+    };
+  }, []);
+
+  useEffect(() => {
+    commands
+      .findDuplicateCards(threshold)
+      .then((found) => {
+        setClusters(found);
+        setKeepIds(new Map());
+      })
+      .catch(() => {});
+  }, [threshold]);
+
+  const folderMap = useMemo(() => {
+    const map = new Map<string, Folder>();
+    for (const f of folders) map.set(f.id, f);
+    return map;
+  }, [folders]);
+
+  const handleScan = async () => {
+    setScanning(true);
+    setError(null);
+    setProgress(null);
+    try {
+      setLastRun(await commands.embedCards());
+      setClusters(await commands.findDuplicateCards(threshold));
+      setKeepIds(new Map());
+    } catch (err) {
+      setError(String(err));
+    } finally {
+      setScanning(false);
+      setProgress(null);
+    }
+  };
+
+  const handleMerge = async (index: number) => {
+    const cluster = clusters[index];
+    const keepId = keepIds.get(index) ?? cluster.keep_id;
+    const removeIds = cluster.cards.map((c) => c.id).filter((id) => id !== keepId);
+    const confirmed = await confirmDestructive(
+      `Delete ${removeIds.length} duplicate card${removeIds.length !== 1 ? "s" : ""}? Their review history moves to the card you keep.`,
+      "Merge Duplicates"
+    );
+    if (!confirmed) {
+      return;
+    }
+    setMerging(index);
+    try {
+      await commands.mergeDuplicateCards(keepId, removeIds);
+      await Promise.all(removeIds.map((id) => unlinkFlashcardFromImports(id)));
+      setClusters((prev) => prev.filter((_, i) => i !== index));
+      setKeepIds(new Map());
+    } catch (err) {
+      setError(String(err));
+    } finally {
+      setMerging(null);
+    }
+  };
+
+  return (
+    <div className="space-y-6 animate-fade-up">
+      <div>
+        <h1 className="text-2xl font-extrabold tracking-tight">Duplicates</h1>
+        <p className="text-muted-foreground mt-1">
+          Find the same problem imported more than once and merge the copies.
+        </p>
+      </div>
+
+      <div className="flex flex-wrap items-center gap-3">
+        <Button onClick={handleScan} disabled={scanning}>
+          {scanning ? (
+            <Loader2 className="h-4 w-4 mr-1.5 animate-spin" />
+          ) : (
+            <RefreshCw className="h-4 w-4 mr-1.5" />
+          )}
+          {scanning
+            ? progress
+              ? `Embedding ${progress.done}/${progress.total}...`
+              : "Reading cards..."
+            : "Scan for duplicates"}
+        </Button>
+
+        <div className="flex rounded-lg border border-input overflow-hidden">
+          {THRESHOLDS.map(([value, label]) => (
+            <button
+              key={value}
+              onClick={() => setThreshold(value)}
+              className={cn(
+                "px-3 py-1.5 text-xs font-medium transition-colors",
+                threshold === value
+                  ? "bg-primary text-primary-foreground"
+                  : "text-muted-foreground hover:text-foreground hover:bg-accent"
+              )}
+            >
+              {label}
+            </button>
+          ))}
+        </div>
+
+        {lastRun && (
+          <p className="text-xs text-muted-foreground">
+            {lastRun.embedded} embedded, {lastRun.unchanged} unchanged
+            {lastRun.failed > 0 && `, ${lastRun.failed} unreadable`}
+            {lastRun.unindexed > 0 &&
+              `, ${lastRun.unindexed} image card${lastRun.unindexed !== 1 ? "s" : ""} waiting for OCR indexing`}
+          </p>
+        )}
+      </div>
+
+      {error && <p className="text-sm text-destructive">{error}</p>}
+
+      {clusters.length === 0 ? (
+        <Card className="border-dashed">
+          <CardContent className="flex flex-col items-center gap-3 py-12 text-center">
+            <Copy className="h-10 w-10 text-muted-foreground/40" />
+            <p className="text-muted-foreground">
+              No duplicates found. Scan again after importing new cards.
+            </p>
+          </CardContent>
+        </Card>
+      ) : (
+        <div className="space-y-4">
+          {clusters.map((cluster, index) => {
+            const keepId = keepIds.get(index) ?? cluster.keep_id;
+            return (
+              <Card key={cluster.cards.map((c) => c.id).join()}>
+                <CardContent className="p-4 space-y-3">
+                  <div className="flex items-center justify-between gap-3">
+                    <p className="text-sm font-semibold">
+                      {cluster.cards.length} copies
+                      <span className="ml-2 text-xs font-normal text-muted-foreground">
+                        {Math.round(cluster.similarity * 100)}% similar
+                      </span>
+                    </p>
+                    <Button
+                      size="sm"
+                      onClick={() => handleMerge(index)}
+                      disabled={merging !== null}
+                    >
+                      {merging === index ? (
+                        <Loader2 className="h-3.5 w-3.5 mr-1.5 animate-spin" />
+                      ) : (
+                        <Merge className="h-3.5 w-3.5 mr-1.5" />
+                      )}
+                      Merge
+                    </Button>
+                  </div>
+                  <div className="grid gap-2 sm:grid-cols-2">
+                    {cluster.cards.map((card) => (
+                      <DuplicateCardItem
+                        key={card.id}
+                        card={card}
+                        folderName={
+                          card.folder_id ? folderMap.get(card.folder_id)?.name : undefined
+                        }
+                        keep={card.id === keepId}
+                        onKeep={() =>
+                          setKeepIds((prev) => new Map(prev).set(index, card.id))
+                        }
+                      />
+                    ))}
+                  </div>
+                </CardContent>
+              </Card>
+            );
+          })}
+        </div>
+      )}
+    </div>
+  );
+}
+
+function DuplicateCardItem({
+  card,
+  folderName,
+  keep,
+  onKeep,
+}: {
+  card: DuplicateCard;
+  folderName: string | undefined;
+  keep: boolean;
+  onKeep: () => void;
+}) {
+  return (
+    <button
+      type="button"
+      onClick={onKeep}
+      className={cn(
+        "rounded-lg border p-3 text-left space-y-2 transition-colors",
+        keep ? "border-primary bg-primary/5" : "border-border hover:border-primary/40"
+      )}
+    >
+      <div className="flex flex-wrap items-center gap-2 text-xs text-muted-foreground">
+        {keep && <Badge className="text-[10px]">Keep</Badge>}
+        {folderName && <span className="truncate max-w-[140px]">{folderName}</span>}
+        <span>
+          {card.review_count} review{card.review_count !== 1 ? "s" : ""}
+        </span>
+        <span>Added {formatDate(card.created_at)}</span>
+      </div>
+      {card.title && <p className="truncate text-sm font-semibold">{card.title}</p>}
+      <div className="max-h-24 overflow-hidden text-sm">
+        {card.question_type === "image" ? (
+          <ImageDisplay
+            src={card.question_content.split("|||")[0]}
+            alt="Question"
+            className="h-24 w-full object-cover rounded"
+          />
+        ) : (
+          <LaTeXRenderer content={card.question_content} />
+        )}
+      </div>
+    </button>
+  );
+}
@@ -2,23 +2,39 @@ import { useCallback, useEffect, useMemo, useState, type ReactNode } from "react
 import { Link, useSearchParams } from "react-router-dom";
 import { useAppStore } from "@/stores/app-store";
 import * as commands from "@/lib/commands";
-import type { Flashcard, Folder } from "@/lib/types";
+import type { ExamPlan, Flashcard, Folder, FolderMilestone, NewCardOrder } from "@/lib/types";
 import {
   calculateDynamicDailyReviewTarget,
   DEFAULT_AUTO_TARGET_REPS,
+  DEFAULT_LEARNING_STEPS,
+  DEFAULT_LEECH_THRESHOLD,
+  DEFAULT_NEW_CARDS_PER_DAY,
+  DEFAULT_RELEARNING_STEPS,
   DEFAULT_REVIEW_CARDS_PER_DAY,
+  DEFAULT_TARGET_RECALL,
+  getCardState,
   getDaysRemaining,
   getEffectiveDailyReviewLimit,
+  formatStepDelay,
   getFolderReviewMode,
+  milestoneCardIds,
+  isNewCard,
+  parseLearningSteps,
   sanitizeAutoTargetReps,
+  sanitizeLearningSteps,
+  sanitizeLeechThreshold,
+  sanitizeNewCardOrder,
+  sanitizeNewCardsPerDay,
   sanitizeReviewCardsPerDay,
+  sanitizeTargetRecall,
 } from "@/lib/review-policy";
-import { cn, formatDate } from "@/lib/utils";
+import { cn, formatDate, toDateKey } from "@/lib/utils";
+import { confirmDestructive } from "@/lib/dialogs";
 import { Button } from "@/components/ui/button";
 import { Input } from "@/components/ui/input";
 import { Switch } from "@/components/ui/switch";
 import { Separator } from "@/components/ui/separator";
-import { ArrowLeft, Save } from "lucide-react";
+import { ArrowLeft, CalendarCheck, Plus, Save, Trash2 } from "lucide-react";
 
 export default function FolderOptionsPage() {
   const [searchParams] = useSearchParams();
@@ -37,6 +53,22 @@ export default function FolderOptionsPage() {
     String(DEFAULT_AUTO_TARGET_REPS)
   );
   const [dynamicReviewTarget, setDynamicReviewTarget] = useState(false);
+  const [leechThresholdInput, setLeechThresholdInput] = useState(
+    String(DEFAULT_LEECH_THRESHOLD)
+  );
+  const [suspendLeeches, setSuspendLeeches] = useState(true);
+  const [learningStepsInput, setLearningStepsInput] = useState(DEFAULT_LEARNING_STEPS);
+  const [relearningStepsInput, setRelearningStepsInput] = useState(
+    DEFAULT_RELEARNING_STEPS
+  );
+  const [newCardsInput, setNewCardsInput] = useState(String(DEFAULT_NEW_CARDS_PER_DAY));
+  const [newCardOrder, setNewCardOrder] = useState<NewCardOrder>("created");
+  const [targetRecallInput, setTargetRecallInput] = useState(
+    String(Math.round(DEFAULT_TARGET_RECALL * 100))
+  );
+  const [examPlan, setExamPlan] = useState<ExamPlan | null>(null);
+  const [planning, setPlanning] = useState(false);
+  const [milestones, setMilestones] = useState<FolderMilestone[]>([]);
 
   const loadData = useCallback(async () => {
     if (!folderId) {
@@ -68,6 +100,13 @@ export default function FolderOptionsPage() {
     loadData();
   }, [loadData]);
 
+  useEffect(() => {
+    if (!folderId) return;
+    // Plans come from the native backend; the section stays empty without it
+    commands.getExamPlan(folderId).then(setExamPlan).catch(() => setExamPlan(null));
+    commands.getFolderMilestones(folderId).then(setMilestones).catch(() => setMilestones([]));
+  }, [folderId]);
+
   useEffect(() => {
     if (!folder) return;
     setDeadlineInput(folder.deadline ?? "");
@@ -78,6 +117,15 @@ export default function FolderOptionsPage() {
       String(sanitizeAutoTargetReps(folder.auto_target_reps))
     );
     setDynamicReviewTarget(getFolderReviewMode(folder) === "dynamic");
+    setLeechThresholdInput(
+      String(sanitizeLeechThreshold(folder.leech_threshold))
+    );
+    setSuspendLeeches(folder.leech_action !== "tag");
+    setLearningStepsInput(folder.learning_steps);
+    setRelearningStepsInput(folder.relearning_steps);
+    setNewCardsInput(String(sanitizeNewCardsPerDay(folder.new_cards_per_day)));
+    setNewCardOrder(sanitizeNewCardOrder(folder.new_card_order));
+    setTargetRecallInput(String(Math.round(sanitizeTargetRecall(folder.target_recall) * 100)));
   }, [folder]);
 
   const previewFolder = useMemo(() => {
@@ -88,11 +136,37 @@ export default function FolderOptionsPage() {
       review_cards_per_day: sanitizeReviewCardsPerDay(Number(reviewCardsInput)),
       review_target_mode: dynamicReviewTarget ? "dynamic" : "fixed",
       auto_target_reps: sanitizeAutoTargetReps(Number(autoTargetRepsInput)),
-    };
-  }, [autoTargetRepsInput, deadlineInput, dynamicReviewTarget, folder, reviewCardsInput]);
+      leech_threshold: sanitizeLeechThreshold(Number(leechThresholdInput)),
+      leech_action: suspendLeeches ? "suspend" : "tag",
+      learning_steps: sanitizeLearningSteps(learningStepsInput, DEFAULT_LEARNING_STEPS),
+      relearning_steps: sanitizeLearningSteps(relearningStepsInput, DEFAULT_RELEARNING_STEPS),
+      new_cards_per_day: sanitizeNewCardsPerDay(Number(newCardsInput)),
+      new_card_order: newCardOrder,
+      target_recall: sanitizeTargetRecall(Number(targetRecallInput) / 100),
+    } as Folder;
+  }, [
+    autoTargetRepsInput,
+    deadlineInput,
+    dynamicReviewTarget,
+    folder,
+    learningStepsInput,
+    leechThresholdInput,
+    newCardOrder,
+    newCardsInput,
+    relearningStepsInput,
+    reviewCardsInput,
+    suspendLeeches,
+    targetRecallInput,
+  ]);
 
   const dueCount = flashcards.filter(
-    (card) => !card.due_date || new Date(card.due_date) <= new Date()
+    (card) =>
+      getCardState(card) === "active" &&
+      (!card.due_date || new Date(card.due_date) <= new Date())
+  ).length;
+  const leechCount = flashcards.filter((card) => card.leech).length;
+  const newCount = flashcards.filter(
+    (card) => getCardState(card) === "active" && isNewCard(card)
   ).length;
   const masteredCount = flashcards.filter((card) => card.interval_days >= 7).length;
   const dynamicRecommendation = calculateDynamicDailyReviewTarget(
@@ -116,14 +190,38 @@ export default function FolderOptionsPage() {
         previewFolder.review_target_mode,
         previewFolder.auto_target_reps
       );
+      await commands.setFolderLeechSettings(
+        folder.id,
+        previewFolder.leech_threshold,
+        previewFolder.leech_action
+      );
+      await commands.setFolderLearningSteps(
+        folder.id,
+        previewFolder.learning_steps,
+        previewFolder.relearning_steps
+      );
+      await commands.setFolderNewCardSettings(
+        folder.id,
+        previewFolder.new_cards_per_day,
+        previewFolder.new_card_order
+      );
+      await commands.setFolderTargetRecall(folder.id, previewFolder.target_recall);
 
       updateFolder(folder.id, {
         deadline: previewFolder.deadline,
         review_cards_per_day: previewFolder.review_cards_per_day,
         review_target_mode: previewFolder.review_target_mode,
         auto_target_reps: previewFolder.auto_target_reps,
+        leech_threshold: previewFolder.leech_threshold,
+        leech_action: previewFolder.leech_action,
+        learning_steps: previewFolder.learning_steps,
+        relearning_steps: previewFolder.relearning_steps,
+        new_cards_per_day: previewFolder.new_cards_per_day,
+        new_card_order: previewFolder.new_card_order,
+        target_recall: previewFolder.target_recall,
       });
       setFolder(previewFolder);
+      await handlePlan();
     } catch {
       /* noop */
     } finally {
@@ -131,6 +229,41 @@ export default function FolderOptionsPage() {
     }
   };
 
+  const handlePlan = async () => {
+    if (!folderId) return;
+    setPlanning(true);
+    try {
+      setExamPlan(await commands.planExam(folderId));
+    } catch {
+      /* local mode has no planner */
+    } finally {
+      setPlanning(false);
+    }
+  };
+
+  const handleAddMilestone = async (name: string, date: string, cardIds: string[] | null) => {
+    if (!folderId) return;
+    try {
+      await commands.createFolderMilestone(folderId, name, date, cardIds);
+      setMilestones(await commands.getFolderMilestones(folderId));
+      await handlePlan();
+    } catch (err) {
+      console.error("Failed to add milestone:", err);
+    }
+  };
+
+  const handleDeleteMilestone = async (milestone: FolderMilestone) => {
+    const confirmed = await confirmDestructive(`Delete the milestone "${milestone.name}"?`);
+    if (!confirmed) return;
+    try {
+      await commands.deleteFolderMilestone(milestone.id);
+      setMilestones((current) => current.filter((item) => item.id !== milestone.id));
+      await handlePlan();
+    } catch (err) {
+      console.error("Failed to delete milestone:", err);
+    }
+  };
+
   if (loading) {
     return (
       <p className="py-8 text-center text-muted-foreground">Loading options...</p>
@@ -216,6 +349,43 @@ export default function FolderOptionsPage() {
         />
       </div>
 
+      <div className="overflow-hidden rounded-2xl border border-border bg-background">
+        <SectionHeader
+          title="New Cards"
+          description="Cards you haven't studied yet are introduced separately from reviews, so a large import doesn't flood a single day."
+        />
+        <SettingsRow
+          title="New cards per day"
+          description="Set to 0 to pause new cards while reviews continue."
+          control={
+            <Input
+              type="number"
+              min={0}
+              step={1}
+              value={newCardsInput}
+              onChange={(e) => setNewCardsInput(e.target.value)}
+              className="w-full sm:w-28"
+            />
+          }
+        />
+        <Separator />
+        <SettingsRow
+          title="Order"
+          description="Which new cards come first. Difficulty uses each card's assessed time, easiest first."
+          control={
+            <select
+              value={newCardOrder}
+              onChange={(e) => setNewCardOrder(sanitizeNewCardOrder(e.target.value))}
+              className="h-9 w-full rounded-lg border border-input bg-background px-3 text-sm sm:w-36"
+            >
+              <option value="created">Date added</option>
+              <option value="random">Random</option>
+              <option value="difficulty">Difficulty</option>
+            </select>
+          }
+        />
+      </div>
+
       <div className="overflow-hidden rounded-2xl border border-border bg-background">
         <SectionHeader
           title="Deadline"
@@ -237,6 +407,124 @@ export default function FolderOptionsPage() {
             />
           }
         />
+        <Separator />
+        <SettingsRow
+          title="Target recall"
+          description="How much you want to remember on the day, in percent. Higher targets plan more reviews."
+          control={
+            <Input
+              type="number"
+              min={70}
+              max={99}
+              step={1}
+              value={targetRecallInput}
+              onChange={(e) => setTargetRecallInput(e.target.value)}
+              className="w-full sm:w-28"
+            />
+          }
+        />
+        {(folder.deadline || milestones.length > 0) && (
+          <>
+            <Separator />
+            <ExamPlanView plan={examPlan} planning={planning} onPlan={handlePlan} />
+          </>
+        )}
+      </div>
+
+      <div className="overflow-hidden rounded-2xl border border-border bg-background">
+        <SectionHeader
+          title="Milestones"
+          description="Quizzes and midterms before the deadline. Each card is paced for the nearest one that covers it, then for the next once it has passed."
+        />
+        {milestones.map((milestone) => {
+          const cardCount = milestoneCardIds(milestone)?.length;
+          const days = milestone.date < toDateKey() ? null : getDaysRemaining(milestone.date);
+          return (
+            <div key={milestone.id}>
+              <SettingsRow
+                title={milestone.name}
+                description={`${formatDate(milestone.date)}, ${
+                  days ? `${days} day${days === 1 ? "" : "s"} remaining` : "passed"
+                } · ${cardCount === undefined ? "Whole deck" : `${cardCount} card${cardCount === 1 ? "" : "s"}`}`}
+                control={
+                  <Button
+                    size="sm"
+                    variant="ghost"
+                    className="text-muted-foreground hover:text-destructive"
+                    onClick={() => handleDeleteMilestone(milestone)}
+                  >
+                    <Trash2 className="h-3.5 w-3.5" />
+                    Delete
+                  </Button>
+                }
+              />
+              <Separator />
+            </div>
+          );
+        })}
+        <MilestoneForm flashcards={flashcards} onAdd={handleAddMilestone} />
+      </div>
+
+      <div className="overflow-hidden rounded-2xl border border-border bg-background">
+        <SectionHeader
+          title="Learning Steps"
+          description="Delays such as 1m 10m 1h. New and forgotten cards repeat at these within the day before moving to daily intervals."
+        />
+        <SettingsRow
+          title="Learning steps"
+          description={describeSteps(previewFolder.learning_steps, "New cards go straight to a 1 day interval.")}
+          control={
+            <Input
+              value={learningStepsInput}
+              onChange={(e) => setLearningStepsInput(e.target.value)}
+              placeholder="1m 10m 1h"
+              className="w-full sm:w-36"
+            />
+          }
+        />
+        <Separator />
+        <SettingsRow
+          title="Relearning steps"
+          description={describeSteps(previewFolder.relearning_steps, "Forgotten cards come back the next day.")}
+          control={
+            <Input
+              value={relearningStepsInput}
+              onChange={(e) => setRelearningStepsInput(e.target.value)}
+              placeholder="10m"
+              className="w-full sm:w-36"
+            />
+          }
+        />
+      </div>
+
+      <div className="overflow-hidden rounded-2xl border border-border bg-background">
+        <SectionHeader
+          title="Leeches"
+          description="Cards that keep being forgotten waste review time until they're broken down."
+        />
+        <SettingsRow
+          title="Leech threshold"
+          description="Number of lapses after which a card is tagged as a leech."
+          control={
+            <Input
+              type="number"
+              min={1}
+              max={99}
+              step={1}
+              value={leechThresholdInput}
+              onChange={(e) => setLeechThresholdInput(e.target.value)}
+              className="w-full sm:w-28"
+            />
+          }
+        />
+        <Separator />
+        <SettingsRow
+          title="Suspend leeches"
+          description="Take leeches out of review until you unsuspend them. When off, leeches are only tagged."
+          control={
+            <Switch checked={suspendLeeches} onCheckedChange={setSuspendLeeches} />
+          }
+        />
       </div>
 
       <div className="overflow-hidden rounded-2xl border border-border bg-background">
@@ -249,11 +537,21 @@ export default function FolderOptionsPage() {
           value={String(dueCount)}
         />
         <Separator />
+        <SettingsValueRow
+          label="New cards waiting"
+          value={String(newCount)}
+        />
+        <Separator />
         <SettingsValueRow
           label="Mastered cards"
           value={`${masteredCount}/${flashcards.length}`}
         />
         <Separator />
+        <SettingsValueRow
+          label="Leeches"
+          value={String(leechCount)}
+        />
+        <Separator />
         <SettingsValueRow
           label="Automatic recommendation"
           value={`${dynamicRecommendation} cards/day`}
@@ -269,6 +567,176 @@ export default function FolderOptionsPage() {
   );
 }
 
+/** Adds a milestone for the whole deck or a chosen subset of its cards. */
+function MilestoneForm({
+  flashcards,
+  onAdd,
+}: {
+  flashcards: Flashcard[];
+  onAdd: (name: string, date: string, cardIds: string[] | null) => Promise<void>;
+}) {
+  const [name, setName] = useState("");
+  const [date, setDate] = useState("");
+  const [scope, setScope] = useState<"deck" | "cards">("deck");
+  const [selectedIds, setSelectedIds] = useState<Set<string>>(new Set());
+  const [adding, setAdding] = useState(false);
+
+  const canAdd =
+    name.trim().length > 0 && date.length > 0 && (scope === "deck" || selectedIds.size > 0);
+
+  const toggleCard = (id: string) => {
+    setSelectedIds((current) => {
+      const next = new Set(current);
+      if (next.has(id)) next.delete(id);
+      else next.add(id);
+      return next;
+    });
+  };
+
+  const handleAdd = async () => {
+    if (!canAdd) return;
+    setAdding(true);
+    try {
+      await onAdd(name.trim(), date, scope === "cards" ? [...selectedIds] : null);
+      setName("");
+      setDate("");
+      setScope("deck");
+      setSelectedIds(new Set());
+    } finally {
+      setAdding(false);
+    }
+  };
+
+  return (
+    <div className="space-y-3 px-5 py-4 sm:px-6">
+      <div className="flex flex-col gap-2 sm:flex-row">
+        <Input
+          value={name}
+          onChange={(e) => setName(e.target.value)}
+          placeholder="Quiz 1"
+          className="sm:flex-1"
+        />
+        <Input
+          type="date"
+          value={date}
+          min={toDateKey()}
+          onChange={(e) => setDate(e.target.value)}
+          className="sm:w-44"
+        />
+        <select
+          value={scope}
+          onChange={(e) => setScope(e.target.value === "cards" ? "cards" : "deck")}
+          className="h-9 rounded-lg border border-input bg-background px-3 text-sm sm:w-40"
+        >
+          <option value="deck">Whole deck</option>
+          <option value="cards">Selected cards</option>
+        </select>
+        <Button onClick={handleAdd} disabled={!canAdd || adding}>
+          <Plus className="h-4 w-4" />
+          Add
+        </Button>
+      </div>
+      {scope === "cards" && (
+        <div className="max-h-56 space-y-1 overflow-y-auto rounded-lg border border-border p-2">
+          {flashcards.length === 0 ? (
+            <p className="px-1 py-2 text-sm text-muted-foreground">This deck has no cards yet.</p>
+          ) : (
+            flashcards.map((card) => (
+              <label
+                key={card.id}
+                className="flex cursor-pointer items-center gap-2 rounded-md px-1 py-1 text-sm hover:bg-muted"
+              >
+                <input
+                  type="checkbox"
+                  checked={selectedIds.has(card.id)}
+                  onChange={() => toggleCard(card.id)}
+                  className="h-4 w-4 rounded accent-primary border-border"
+                />
+                <span className="truncate">
+                  {card.title ||
+                    (card.question_type === "latex"
+                      ? card.question_content.slice(0, 80)
+                      : "Image question")}
+                </span>
+              </label>
+            ))
+          )}
+        </div>
+      )}
+    </div>
+  );
+}
+
+const PLAN_PREVIEW_DAYS = 14;
+
+/**
+ * The planned reviews per day up to the deadline, against the daily limit.
+ * Re-planned after each study session and whenever these options are saved.
+ */
+function ExamPlanView({
+  plan,
+  planning,
+  onPlan,
+}: {
+  plan: ExamPlan | null;
+  planning: boolean;
+  onPlan: () => void;
+}) {
+  const days = plan?.days.slice(0, PLAN_PREVIEW_DAYS) ?? [];
+  const peak = Math.max(plan?.daily_cap ?? 1, ...days.map((day) => day.card_ids.length));
+
+  return (
+    <div className="space-y-3 px-5 py-4 sm:px-6">
+      <div className="flex items-start justify-between gap-4">
+        <div>
+          <div className="text-sm font-medium">Exam plan</div>
+          <p className="mt-1 text-sm leading-6 text-muted-foreground">
+            {plan && plan.days.length > 0
+              ? `${plan.days.reduce((sum, day) => sum + day.card_ids.length, 0)} reviews over ${plan.days.length} day${plan.days.length === 1 ? "" : "s"}, ending with a final review of every card 1–2 days before the exam.`
+              : "No reviews planned yet."}
+            {plan && plan.overloaded_days > 0 &&
+              ` ${plan.overloaded_days} day${plan.overloaded_days === 1 ? " is" : "s are"} over the daily limit of ${plan.daily_cap}.`}
+          </p>
+        </div>
+        <Button size="sm" variant="outline" onClick={onPlan} disabled={planning}>
+          <CalendarCheck className="h-4 w-4" />
+          {planning ? "Planning..." : "Re-plan"}
+        </Button>
+      </div>
+      {days.length > 0 && (
+        <div className="flex h-24 items-end gap-1">
+          {days.map((day) => (
+            <div
+              key={day.date}
+              className="flex flex-1 flex-col items-center gap-1"
+              title={`${formatDate(day.date)}: ${day.card_ids.length} reviews${day.final_reviews > 0 ? `, ${day.final_reviews} final` : ""}`}
+            >
+              <div
+                className={cn(
+                  "w-full rounded-sm",
+                  day.card_ids.length > (plan?.daily_cap ?? Infinity)
+                    ? "bg-destructive/70"
+                    : day.final_reviews > 0
+                      ? "bg-success/70"
+                      : "bg-primary/60"
+                )}
+                style={{ height: `${(day.card_ids.length / peak) * 80}px` }}
+              />
+              <span className="text-[9px] text-muted-foreground">{day.date.slice(8)}</span>
+            </div>
+          ))}
+        </div>
+      )}
+    </div>
+  );
+}
+
+function describeSteps(steps: string, emptyLabel: string): string {
+  const minutes = parseLearningSteps(steps);
+  if (minutes.length === 0) return emptyLabel;
+  return `Shown again after ${minutes.map(formatStepDelay).join(", then ")}.`;
+}
+
 function SectionHeader({
   title,
   description,
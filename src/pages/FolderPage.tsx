@@ -2,11 +2,12 @@ import { useState, useEffect, useCallback } from "react";
 import { useSearchParams, Link } from "react-router-dom";
 import { useAppStore } from "@/stores/app-store";
 import * as commands from "@/lib/commands";
-import { cn, formatDate, daysUntil } from "@/lib/utils";
-import type { Flashcard, Folder } from "@/lib/types";
-import { getEffectiveDailyReviewLimit } from "@/lib/review-policy";
+import { cn, formatDate, daysUntil, parseSolutionSteps, toDateKey } from "@/lib/utils";
+import type { Flashcard, Folder, FolderMilestone } from "@/lib/types";
+import { getCardState, getEffectiveDailyReviewLimit, isLearning } from "@/lib/review-policy";
 import { ImageDisplay } from "@/components/ImageDisplay";
 import { LaTeXRenderer } from "@/components/LaTeXRenderer";
+import { SolutionSteps } from "@/components/SolutionSteps";
 import { Badge } from "@/components/ui/badge";
 import { Button } from "@/components/ui/button";
 import { Card, CardContent } from "@/components/ui/card";
@@ -22,6 +23,8 @@ import {
   Clock,
   Settings2,
   RotateCcw,
+  Layers,
+  Loader2,
 } from "lucide-react";
 import { CardPreviewModal } from "@/components/CardPreviewModal";
 import { unlinkFlashcardFromImports } from "@/lib/import-library";
@@ -37,6 +40,7 @@ export default function FolderPage() {
   const [folderLoading, setFolderLoading] = useState(true);
   const [previewIndex, setPreviewIndex] = useState<number | null>(null);
   const [selectedIds, setSelectedIds] = useState<Set<string>>(new Set());
+  const [milestones, setMilestones] = useState<FolderMilestone[]>([]);
 
   useEffect(() => {
     let active = true;
@@ -74,6 +78,7 @@ export default function FolderPage() {
     try {
       const cards = await commands.getFlashcards(folderId);
       setFlashcards(cards);
+      setMilestones(await commands.getFolderMilestones(folderId));
     } catch {
       /* noop */
     } finally {
@@ -91,12 +96,16 @@ export default function FolderPage() {
     return () => window.removeEventListener("flashmath:data-changed", refresh);
   }, [loadFlashcards]);
 
+  const upcomingMilestones = milestones.filter((milestone) => milestone.date >= toDateKey());
   const now = new Date();
   const todayStart = new Date();
   todayStart.setHours(0, 0, 0, 0);
-  const dueCards = flashcards.filter((c) => !c.due_date || new Date(c.due_date) <= now);
+  const dueCards = flashcards.filter(
+    (c) => getCardState(c) === "active" && (!c.due_date || new Date(c.due_date) <= now)
+  );
   const reviewedCorrectlyToday = flashcards.filter(
     (c) =>
+      !isLearning(c) &&
       c.last_reviewed && new Date(c.last_reviewed) >= todayStart &&
       c.due_date && new Date(c.due_date) > now
   );
@@ -258,6 +267,18 @@ export default function FolderPage() {
                 No deadline set.
               </p>
             )}
+            {upcomingMilestones.length > 0 && (
+              <div className="mt-3 space-y-1 border-t border-border pt-3">
+                {upcomingMilestones.map((milestone) => (
+                  <div key={milestone.id} className="flex justify-between gap-3 text-xs">
+                    <span className="truncate font-medium">{milestone.name}</span>
+                    <span className="shrink-0 text-muted-foreground">
+                      {formatDate(milestone.date)}, {daysUntil(milestone.date)}d
+                    </span>
+                  </div>
+                ))}
+              </div>
+            )}
           </CardContent>
         </Card>
       </div>
@@ -387,6 +408,21 @@ function FlashcardRow({
   selected: boolean;
   onToggleSelect: () => void;
 }) {
+  const [generatingVariants, setGeneratingVariants] = useState(false);
+
+  const handleGenerateVariants = async () => {
+    if (generatingVariants) return;
+    setGeneratingVariants(true);
+    try {
+      await commands.generateVariants(card.id);
+      window.dispatchEvent(new CustomEvent("flashmath:data-changed"));
+    } catch (err) {
+      console.error("Failed to generate variants:", err);
+    } finally {
+      setGeneratingVariants(false);
+    }
+  };
+
   const isDue = !card.due_date || new Date(card.due_date) <= new Date();
   const hasAnswer = Boolean(card.answer_content);
   const statusLabel = isDue
@@ -446,6 +482,20 @@ function FlashcardRow({
                 aria-label="Select flashcard"
               />
             </label>
+            <Button
+              variant="ghost"
+              size="icon"
+              className="h-8 w-8"
+              onClick={handleGenerateVariants}
+              disabled={generatingVariants}
+              title="Generate variant problems"
+            >
+              {generatingVariants ? (
+                <Loader2 className="h-3.5 w-3.5 animate-spin" />
+              ) : (
+                <Layers className="h-3.5 w-3.5" />
+              )}
+            </Button>
             <Button variant="ghost" size="icon" className="h-8 w-8" asChild>
               <Link to={`/card?id=${card.id}`}>
                 <Pencil className="h-3.5 w-3.5" />
@@ -536,7 +586,11 @@ function PreviewColumn({
       ) : (
         <div className="overflow-hidden rounded-lg border border-border/60 bg-background p-3.5">
           <div className="max-h-32 overflow-hidden text-sm leading-6">
-            <LaTeXRenderer content={content} />
+            {type === "solution" ? (
+              <SolutionSteps steps={parseSolutionSteps(content)} />
+            ) : (
+              <LaTeXRenderer content={content} />
+            )}
           </div>
         </div>
       )}
@@ -17,7 +17,6 @@ import {
 import {
   getImportById,
   listRecentImports,
-  linkFlashcardsToImport,
   saveImageImport,
   saveRegionsToImport,
   touchImport,
@@ -51,6 +50,7 @@ export default function ImportImagePage() {
   const [creating, setCreating] = useState(false);
   const [createError, setCreateError] = useState<string | null>(null);
   const [useOcr, setUseOcr] = useState(false);
+  const [generateAnswers, setGenerateAnswers] = useState(false);
   const [isDragOver, setIsDragOver] = useState(false);
   const [recentImports, setRecentImports] = useState<ImageImportItem[]>([]);
   const [activeImportId, setActiveImportId] = useState<string | null>(null);
@@ -231,93 +231,34 @@ export default function ImportImagePage() {
         return;
       }
 
-      const createdCardIds: string[] = [];
-      const titleJobs: { cardId: string; imagePath: string }[] = [];
-
-      for (let i = 0; i < sortedQuestions.length; i++) {
-        const q = sortedQuestions[i];
-        const a = sortedAnswers.find(ans => ans.labelNumber === q.labelNumber);
-
-        const qPath = await commands.cropRegion(
-          resolvedPath,
-          q.x,
-          q.y,
-          q.width,
-          q.height
-        );
-
-        let qType: "image" | "latex" = "image";
-        let qContent = qPath;
-
-        if (useOcr) {
-          try {
-            const latex = await commands.ocrImage(qPath);
-            qType = "latex";
-            qContent = latex;
-          } catch {
-            /* fall back to image */
-          }
-        }
-
-        let aType: "image" | "latex" | undefined;
-        let aContent: string | undefined;
-
-        if (a) {
-          const aPath = await commands.cropRegion(
-            resolvedPath,
-            a.x,
-            a.y,
-            a.width,
-            a.height
-          );
-
-          if (useOcr) {
-            try {
-              const latex = await commands.ocrImage(aPath);
-              aType = "latex";
-              aContent = latex;
-            } catch {
-              aType = "image";
-              aContent = aPath;
-            }
-          } else {
-            aType = "image";
-            aContent = aPath;
-          }
-        }
-
-        const placeholderTitle = qType === "image" ? "Generating title…" : null;
-
-        const created = await commands.createFlashcard({
-          folder_id: folderId,
-          title: placeholderTitle,
-          question_type: qType,
-          question_content: qContent,
-          answer_type: aType,
-          answer_content: aContent,
-        });
-        createdCardIds.push(created.id);
-
-        if (qType === "image") {
-          titleJobs.push({ cardId: created.id, imagePath: qPath });
-        }
-      }
-
-      if (activeImportId && createdCardIds.length > 0) {
-        await linkFlashcardsToImport(activeImportId, createdCardIds);
-      }
-
-      // Fire-and-forget: generate titles in the background
-      for (const job of titleJobs) {
-        commands.generateImageTitle(job.imagePath).then(
-          (title) => commands.updateFlashcard(job.cardId, { title }),
-          () => commands.updateFlashcard(job.cardId, { title: null }),
-        );
-      }
+      const sourcePath = resolvedPath;
+      const toRect = (r: Region) => ({
+        x: Math.round(r.x),
+        y: Math.round(r.y),
+        width: Math.round(r.width),
+        height: Math.round(r.height),
+      });
+      const job = await commands.createGenerationJob({
+        folder_id: folderId,
+        items: sortedQuestions.map((q) => {
+          const a = sortedAnswers.find(ans => ans.labelNumber === q.labelNumber);
+          return {
+            image_path: sourcePath,
+            region: toRect(q),
+            answer_region: a ? toRect(a) : null,
+          };
+        }),
+        ocr: useOcr,
+        generate_answer: generateAnswers,
+        generate_title: true,
+        assess_difficulty: useOcr,
+      });
 
-      navigate(`/folder?id=${folderId}`);
+      const params = new URLSearchParams({ jobId: job.id });
+      if (activeImportId) params.set("importId", activeImportId);
+      navigate(`/drafts?${params}`);
     } catch (err) {
-      console.error("Failed to create flashcards:", err);
+      console.error("Failed to start card generation:", err);
       setCreateError(
         `Failed to create cards: ${err instanceof Error ? err.message : String(err)}`
       );
@@ -490,6 +431,15 @@ export default function ImportImagePage() {
                 />
                 Convert to LaTeX (OCR)
               </label>
+              <label className="flex items-center gap-2 text-sm cursor-pointer select-none">
+                <input
+                  type="checkbox"
+                  checked={generateAnswers}
+                  onChange={(e) => setGenerateAnswers(e.target.checked)}
+                  className="h-4 w-4 rounded border-border accent-primary"
+                />
+                Generate missing answers
+              </label>
               <div className="ml-auto flex items-center gap-3">
                 <Badge variant="secondary">
                   {sortedQuestions.length} question{sortedQuestions.length !== 1 ? "s" : ""}, {sortedAnswers.length} answer{sortedAnswers.length !== 1 ? "s" : ""}
@@ -0,0 +1,222 @@
+import { useState, useEffect, useMemo, useCallback } from "react";
+import { Link } from "react-router-dom";
+import { useAppStore } from "@/stores/app-store";
+import * as commands from "@/lib/commands";
+import type { Folder, LeechCard, PrerequisiteCard } from "@/lib/types";
+import { formatDate } from "@/lib/utils";
+import { ImageDisplay } from "@/components/ImageDisplay";
+import { LaTeXRenderer } from "@/components/LaTeXRenderer";
+import { Card, CardContent } from "@/components/ui/card";
+import { Button } from "@/components/ui/button";
+import { Badge } from "@/components/ui/badge";
+import { Bug, Check, Loader2, Play, Plus, Sparkles } from "lucide-react";
+
+export default function LeechesPage() {
+  const { folders, setFolders } = useAppStore();
+  const [leeches, setLeeches] = useState<LeechCard[]>([]);
+  const [loading, setLoading] = useState(true);
+  const [error, setError] = useState<string | null>(null);
+
+  const loadLeeches = useCallback(async () => {
+    try {
+      setLeeches(await commands.getLeeches());
+    } catch (err) {
+      setError(String(err));
+    } finally {
+      setLoading(false);
+    }
+  }, []);
+
+  useEffect(() => {
+    commands.getFolders().then(setFolders).catch(() => {});
+    loadLeeches();
+  }, [setFolders, loadLeeches]);
+
+  const folderMap = useMemo(() => {
+    const map = new Map<string, Folder>();
+    for (const f of folders) map.set(f.id, f);
+    return map;
+  }, [folders]);
+
+  const handleUnsuspend = async (id: string) => {
+    try {
+      await commands.setFlashcardSuspended(id, false);
+      setLeeches((prev) =>
+        prev.map((card) => (card.id === id ? { ...card, suspended: false } : card))
+      );
+    } catch (err) {
+      setError(String(err));
+    }
+  };
+
+  return (
+    <div className="space-y-6 animate-fade-up">
+      <div>
+        <h1 className="text-2xl font-extrabold tracking-tight">Leeches</h1>
+        <p className="text-muted-foreground mt-1">
+          Cards you keep forgetting. Break them down into simpler prerequisite cards,
+          then bring them back into review.
+        </p>
+      </div>
+
+      {error && <p className="text-sm text-destructive">{error}</p>}
+
+      {loading ? (
+        <div className="flex justify-center py-12">
+          <Loader2 className="h-6 w-6 animate-spin text-muted-foreground" />
+        </div>
+      ) : leeches.length === 0 ? (
+        <Card className="border-dashed">
+          <CardContent className="flex flex-col items-center gap-3 py-12 text-center">
+            <Bug className="h-10 w-10 text-muted-foreground/40" />
+            <p className="text-muted-foreground">
+              No leeches. Cards show up here after failing too many times.
+            </p>
+          </CardContent>
+        </Card>
+      ) : (
+        <div className="space-y-4">
+          {leeches.map((card) => (
+            <LeechItem
+              key={card.id}
+              card={card}
+              folderName={card.folder_id ? folderMap.get(card.folder_id)?.name : undefined}
+              onUnsuspend={() => handleUnsuspend(card.id)}
+            />
+          ))}
+        </div>
+      )}
+    </div>
+  );
+}
+
+function LeechItem({
+  card,
+  folderName,
+  onUnsuspend,
+}: {
+  card: LeechCard;
+  folderName: string | undefined;
+  onUnsuspend: () => void;
+}) {
+  const [suggestions, setSuggestions] = useState<PrerequisiteCard[]>([]);
+  const [added, setAdded] = useState<Set<number>>(new Set());
+  const [suggesting, setSuggesting] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  const handleSuggest = async () => {
+    setSuggesting(true);
+    setError(null);
+    try {
+      setSuggestions(await commands.suggestPrerequisiteCards(card.id));
+      setAdded(new Set());
+    } catch (err) {
+      setError(String(err));
+    } finally {
+      setSuggesting(false);
+    }
+  };
+
+  const handleAdd = async (index: number) => {
+    const suggestion = suggestions[index];
+    try {
+      await commands.createFlashcard({
+        folder_id: card.folder_id,
+        title: suggestion.skill,
+        question_type: "latex",
+        question_content: suggestion.question,
+        answer_type: "latex",
+        answer_content: suggestion.answer,
+      });
+      setAdded((prev) => new Set(prev).add(index));
+    } catch (err) {
+      setError(String(err));
+    }
+  };
+
+  return (
+    <Card>
+      <CardContent className="p-4 space-y-3">
+        <div className="flex flex-wrap items-center gap-2 text-xs text-muted-foreground">
+          <Badge variant="destructive" className="text-[10px]">
+            {card.lapses} lapse{card.lapses !== 1 ? "s" : ""}
+          </Badge>
+          {card.suspended && (
+            <Badge variant="secondary" className="text-[10px]">
+              Suspended
+            </Badge>
+          )}
+          {folderName && <span className="truncate max-w-[160px]">{folderName}</span>}
+          {card.last_reviewed && <span>Last reviewed {formatDate(card.last_reviewed)}</span>}
+        </div>
+
+        <Link to={`/card?id=${card.id}`} className="block space-y-1 hover:opacity-80">
+          {card.title && <p className="truncate text-sm font-semibold">{card.title}</p>}
+          <div className="max-h-32 overflow-hidden text-sm">
+            {card.question_type === "image" ? (
+              <ImageDisplay
+                src={card.question_content.split("|||")[0]}
+                alt="Question"
+                className="h-32 w-full object-contain rounded"
+              />
+            ) : (
+              <LaTeXRenderer content={card.question_content} />
+            )}
+          </div>
+        </Link>
+
+        <div className="flex flex-wrap gap-2">
+          <Button size="sm" onClick={handleSuggest} disabled={suggesting}>
+            {suggesting ? (
+              <Loader2 className="h-3.5 w-3.5 mr-1.5 animate-spin" />
+            ) : (
+              <Sparkles className="h-3.5 w-3.5 mr-1.5" />
+            )}
+            Suggest prerequisites
+          </Button>
+          {card.suspended && (
+            <Button size="sm" variant="outline" onClick={onUnsuspend}>
+              <Play className="h-3.5 w-3.5 mr-1.5" />
+              Unsuspend
+            </Button>
+          )}
+        </div>
+
+        {error && <p className="text-sm text-destructive">{error}</p>}
+
+        {suggestions.length > 0 && (
+          <div className="space-y-2">
+            {suggestions.map((suggestion, index) => (
+              <div
+                key={index}
+                className="flex items-start gap-3 rounded-lg border border-border p-3"
+              >
+                <div className="min-w-0 flex-1 space-y-1 text-sm">
+                  <p className="text-xs font-medium text-muted-foreground">
+                    {suggestion.skill}
+                  </p>
+                  <LaTeXRenderer content={suggestion.question} />
+                  <div className="text-muted-foreground">
+                    <LaTeXRenderer content={suggestion.answer} />
+                  </div>
+                </div>
+                <Button
+                  size="sm"
+                  variant="outline"
+                  onClick={() => handleAdd(index)}
+                  disabled={added.has(index)}
+                >
+                  {added.has(index) ? (
+                    <Check className="h-3.5 w-3.5" />
+                  ) : (
+                    <Plus className="h-3.5 w-3.5" />
+                  )}
+                </Button>
+              </div>
+            ))}
+          </div>
+        )}
+      </CardContent>
+    </Card>
+  );
+}
@@ -1,6 +1,13 @@
 import { useState, useEffect } from "react";
 import * as commands from "@/lib/commands";
-import type { LLMConfig } from "@/lib/types";
+import type {
+  EmbeddingConfig,
+  LLMCacheStats,
+  LLMConfig,
+  LLMUsageSummary,
+  OcrConfig,
+  OcrIndexStatus,
+} from "@/lib/types";
 import { cn } from "@/lib/utils";
 import { Button } from "@/components/ui/button";
 import { Card, CardContent } from "@/components/ui/card";
@@ -15,7 +22,7 @@ import {
   SelectTrigger,
   SelectValue,
 } from "@/components/ui/select";
-import { Cpu, Save, Plug, PencilRuler } from "lucide-react";
+import { Cpu, Save, Plug, PencilRuler, Trash2, ScanText, Copy } from "lucide-react";
 
 const PROVIDERS = [
   { value: "openai", label: "OpenAI" },
@@ -44,6 +51,8 @@ const DEFAULT_URLS: Record<string, string> = {
 export default function SettingsPage() {
   const shuffleCards = useAppStore((state) => state.shuffleCards);
   const setShuffleCards = useAppStore((state) => state.setShuffleCards);
+  const typedAnswers = useAppStore((state) => state.typedAnswers);
+  const setTypedAnswers = useAppStore((state) => state.setTypedAnswers);
   const theme = useAppStore((state) => state.theme);
   const setTheme = useAppStore((state) => state.setTheme);
 
@@ -56,20 +65,74 @@ export default function SettingsPage() {
   const [testing, setTesting] = useState(false);
   const [testResult, setTestResult] = useState<string | null>(null);
   const [saveMessage, setSaveMessage] = useState<string | null>(null);
+  const [savedConfig, setSavedConfig] = useState<LLMConfig | null>(null);
+  const [cacheStats, setCacheStats] = useState<LLMCacheStats | null>(null);
+  const [clearingCache, setClearingCache] = useState(false);
+  const [monthlyBudget, setMonthlyBudget] = useState("");
+  const [inputPrice, setInputPrice] = useState("");
+  const [outputPrice, setOutputPrice] = useState("");
+  const [usage, setUsage] = useState<LLMUsageSummary | null>(null);
+  const [ocrConfig, setOcrConfig] = useState<OcrConfig>({
+    provider: "llm",
+    model_path: "",
+    background_indexing: false,
+  });
+  const [localOcrAvailable, setLocalOcrAvailable] = useState(false);
+  const [ocrIndexStatus, setOcrIndexStatus] = useState<OcrIndexStatus | null>(null);
+  const [ocrMessage, setOcrMessage] = useState<string | null>(null);
+  const [embeddingConfig, setEmbeddingConfig] = useState<EmbeddingConfig>({
+    provider: "local",
+    model: "",
+  });
+  const [embeddingMessage, setEmbeddingMessage] = useState<string | null>(null);
 
   useEffect(() => {
+    commands
+      .getLLMCacheStats()
+      .then(setCacheStats)
+      .catch(() => { });
+    commands
+      .getLLMUsageSummary()
+      .then(setUsage)
+      .catch(() => { });
+    commands
+      .getOcrConfig()
+      .then(setOcrConfig)
+      .catch(() => { });
+    commands
+      .localOcrAvailable()
+      .then(setLocalOcrAvailable)
+      .catch(() => { });
+    commands
+      .getOcrIndexStatus()
+      .then(setOcrIndexStatus)
+      .catch(() => { });
+    commands
+      .getEmbeddingConfig()
+      .then(setEmbeddingConfig)
+      .catch(() => { });
     commands
       .getLLMConfig()
       .then((config) => {
+        setSavedConfig(config);
         setProvider(config.provider);
         setApiKey(config.api_key);
         setModel(config.model);
         setBaseUrl(config.base_url);
+        setMonthlyBudget(
+          config.monthly_budget_usd != null ? String(config.monthly_budget_usd) : ""
+        );
       })
       .catch(() => { })
       .finally(() => setLoading(false));
   }, []);
 
+  useEffect(() => {
+    const price = savedConfig?.price_table?.[model];
+    setInputPrice(price ? String(price.input_per_million) : "");
+    setOutputPrice(price ? String(price.output_per_million) : "");
+  }, [savedConfig, model]);
+
   const handleProviderChange = (newProvider: string) => {
     setProvider(newProvider);
     setModel(DEFAULT_MODELS[newProvider] || "");
@@ -80,13 +143,31 @@ export default function SettingsPage() {
     setSaving(true);
     setSaveMessage(null);
     try {
+      const priceTable = { ...savedConfig?.price_table };
+      const input = Number(inputPrice);
+      const output = Number(outputPrice);
+      if (inputPrice.trim() && outputPrice.trim() && Number.isFinite(input) && Number.isFinite(output)) {
+        priceTable[model] = {
+          input_per_million: Math.max(0, input),
+          output_per_million: Math.max(0, output),
+        };
+      } else if (!inputPrice.trim() && !outputPrice.trim()) {
+        delete priceTable[model];
+      }
       const config: LLMConfig = {
+        ...savedConfig,
         provider: provider as LLMConfig["provider"],
         api_key: apiKey,
         model,
         base_url: baseUrl,
+        price_table: priceTable,
+        monthly_budget_usd:
+          monthlyBudget.trim() && Number.isFinite(Number(monthlyBudget))
+            ? Math.max(0, Number(monthlyBudget))
+            : null,
       };
       await commands.setLLMConfig(config);
+      setSavedConfig(config);
       setSaveMessage("Settings saved.");
     } catch (err) {
       setSaveMessage(`Error: ${err}`);
@@ -114,6 +195,42 @@ export default function SettingsPage() {
     }
   };
 
+  const handleClearCache = async () => {
+    setClearingCache(true);
+    try {
+      await commands.clearLLMCache();
+      setCacheStats(await commands.getLLMCacheStats());
+    } catch (err) {
+      console.error("Failed to clear AI response cache:", err);
+    } finally {
+      setClearingCache(false);
+    }
+  };
+
+  const handleSaveOcr = async () => {
+    setOcrMessage(null);
+    try {
+      // Builds without the local engine fall back to the LLM
+      await commands.setOcrConfig(
+        localOcrAvailable ? ocrConfig : { ...ocrConfig, provider: "llm" }
+      );
+      setOcrMessage("OCR settings saved.");
+      setOcrIndexStatus(await commands.getOcrIndexStatus());
+    } catch (err) {
+      setOcrMessage(`Error: ${err}`);
+    }
+  };
+
+  const handleSaveEmbeddings = async () => {
+    setEmbeddingMessage(null);
+    try {
+      await commands.setEmbeddingConfig(embeddingConfig);
+      setEmbeddingMessage("Embedding settings saved.");
+    } catch (err) {
+      setEmbeddingMessage(`Error: ${err}`);
+    }
+  };
+
   if (loading)
     return <p className="text-muted-foreground py-8 text-center">Loading...</p>;
 
@@ -188,6 +305,58 @@ export default function SettingsPage() {
             </div>
           )}
 
+          <div>
+            <label className="block text-sm font-medium mb-1.5">
+              Monthly Budget (USD)
+            </label>
+            <Input
+              type="number"
+              min={0}
+              step="0.01"
+              value={monthlyBudget}
+              onChange={(e) => setMonthlyBudget(e.target.value)}
+              placeholder="No limit"
+            />
+            {usage && (
+              <p className="text-xs text-muted-foreground mt-1.5">
+                Estimated spend this month: ${usage.month_to_date_usd.toFixed(2)}
+                {usage.by_command.length > 0 &&
+                  ` — most on ${usage.by_command[0].key} ($${usage.by_command[0].cost_usd.toFixed(2)})`}
+                . AI features pause once the budget is reached.
+              </p>
+            )}
+          </div>
+
+          {monthlyBudget.trim() && provider !== "ollama" && (
+            <div>
+              <label className="block text-sm font-medium mb-1.5">
+                Price for {model || "this model"} (USD per million tokens)
+              </label>
+              <div className="flex gap-2">
+                <Input
+                  type="number"
+                  min={0}
+                  step="0.01"
+                  value={inputPrice}
+                  onChange={(e) => setInputPrice(e.target.value)}
+                  placeholder="Input"
+                />
+                <Input
+                  type="number"
+                  min={0}
+                  step="0.01"
+                  value={outputPrice}
+                  onChange={(e) => setOutputPrice(e.target.value)}
+                  placeholder="Output"
+                />
+              </div>
+              <p className="text-xs text-muted-foreground mt-1.5">
+                Leave empty to use the built-in price. Models without a known price
+                are blocked while a budget is set.
+              </p>
+            </div>
+          )}
+
           <div className="flex gap-3 pt-2">
             <Button onClick={handleSave} disabled={saving}>
               <Save className="h-4 w-4 mr-1.5" />
@@ -221,6 +390,162 @@ export default function SettingsPage() {
               {testResult}
             </Badge>
           )}
+
+          <div className="h-px bg-border" />
+
+          <div className="flex items-center justify-between">
+            <div>
+              <label className="text-sm font-medium">Response Cache</label>
+              <p className="text-xs text-muted-foreground">
+                {cacheStats
+                  ? `${cacheStats.entries} cached responses (${(cacheStats.total_bytes / (1024 * 1024)).toFixed(1)} MB). Re-running OCR or answers on the same content is free.`
+                  : "Re-running OCR or answers on the same content is served from disk."}
+              </p>
+            </div>
+            <Button
+              variant="secondary"
+              onClick={handleClearCache}
+              disabled={clearingCache}
+            >
+              <Trash2 className="h-4 w-4 mr-1.5" />
+              {clearingCache ? "Clearing..." : "Clear"}
+            </Button>
+          </div>
+        </CardContent>
+      </Card>
+
+      <Card>
+        <CardContent className="p-6 space-y-5">
+          <div className="flex items-center gap-2 mb-2">
+            <ScanText className="h-4 w-4 text-primary" />
+            <h2 className="text-sm font-bold">OCR</h2>
+          </div>
+
+          {localOcrAvailable && (
+            <div className="flex items-center justify-between">
+              <div>
+                <label className="text-sm font-medium">Local OCR Model</label>
+                <p className="text-xs text-muted-foreground">
+                  Convert images to LaTeX on this computer instead of sending them
+                  to the LLM. Works offline.
+                </p>
+              </div>
+              <Switch
+                checked={ocrConfig.provider === "local"}
+                onCheckedChange={(checked) =>
+                  setOcrConfig({ ...ocrConfig, provider: checked ? "local" : "llm" })
+                }
+              />
+            </div>
+          )}
+
+          <div className="flex items-center justify-between">
+            <div>
+              <label className="text-sm font-medium">Read Image Cards in Background</label>
+              <p className="text-xs text-muted-foreground">
+                Store the text of image cards for search, screen readers and
+                duplicate detection.
+                {ocrConfig.provider === "llm" &&
+                  " Each image is sent to your LLM provider once."}
+                {ocrIndexStatus &&
+                  ocrIndexStatus.pending > 0 &&
+                  ` ${ocrIndexStatus.pending} card${ocrIndexStatus.pending !== 1 ? "s" : ""} waiting${ocrIndexStatus.running ? " (reading now)" : ""}.`}
+              </p>
+            </div>
+            <Switch
+              checked={ocrConfig.background_indexing}
+              onCheckedChange={(checked) =>
+                setOcrConfig({ ...ocrConfig, background_indexing: checked })
+              }
+            />
+          </div>
+
+          {localOcrAvailable && ocrConfig.provider === "local" && (
+            <div>
+              <label className="block text-sm font-medium mb-1.5">
+                Model Folder
+              </label>
+              <Input
+                type="text"
+                value={ocrConfig.model_path}
+                onChange={(e) =>
+                  setOcrConfig({ ...ocrConfig, model_path: e.target.value })
+                }
+                placeholder="Folder with encoder.onnx, decoder.onnx and tokenizer.json"
+              />
+            </div>
+          )}
+
+          <div className="flex gap-3">
+            <Button variant="secondary" onClick={handleSaveOcr}>
+              <Save className="h-4 w-4 mr-1.5" />
+              Save
+            </Button>
+          </div>
+
+          {ocrMessage && (
+            <Badge
+              variant={ocrMessage.startsWith("Error") ? "destructive" : "success"}
+            >
+              {ocrMessage}
+            </Badge>
+          )}
+        </CardContent>
+      </Card>
+
+      <Card>
+        <CardContent className="p-6 space-y-5">
+          <div className="flex items-center gap-2 mb-2">
+            <Copy className="h-4 w-4 text-primary" />
+            <h2 className="text-sm font-bold">Duplicate Detection</h2>
+          </div>
+
+          <div className="flex items-center justify-between">
+            <div>
+              <label className="text-sm font-medium">Provider Embeddings</label>
+              <p className="text-xs text-muted-foreground">
+                Compare cards with your provider's embedding model instead of
+                the built-in offline one. Better at reworded problems.
+              </p>
+            </div>
+            <Switch
+              checked={embeddingConfig.provider === "llm"}
+              onCheckedChange={(checked) =>
+                setEmbeddingConfig({ ...embeddingConfig, provider: checked ? "llm" : "local" })
+              }
+            />
+          </div>
+
+          {embeddingConfig.provider === "llm" && (
+            <div>
+              <label className="block text-sm font-medium mb-1.5">
+                Embedding Model
+              </label>
+              <Input
+                type="text"
+                value={embeddingConfig.model}
+                onChange={(e) =>
+                  setEmbeddingConfig({ ...embeddingConfig, model: e.target.value })
+                }
+                placeholder="text-embedding-3-small"
+              />
+            </div>
+          )}
+
+          <div className="flex gap-3">
+            <Button variant="secondary" onClick={handleSaveEmbeddings}>
+              <Save className="h-4 w-4 mr-1.5" />
+              Save
+            </Button>
+          </div>
+
+          {embeddingMessage && (
+            <Badge
+              variant={embeddingMessage.startsWith("Error") ? "destructive" : "success"}
+            >
+              {embeddingMessage}
+            </Badge>
+          )}
         </CardContent>
       </Card>
 
@@ -246,6 +571,21 @@ export default function SettingsPage() {
             />
           </div>
 
+          <div className="flex items-center justify-between">
+            <div>
+              <label className="text-sm font-medium">
+                Type Answers
+              </label>
+              <p className="text-xs text-muted-foreground">
+                Type your answer during review and have it graded automatically.
+              </p>
+            </div>
+            <Switch
+              checked={typedAnswers}
+              onCheckedChange={setTypedAnswers}
+            />
+          </div>
+
           <div className="h-px bg-border" />
 
           <div className="flex items-center justify-between">
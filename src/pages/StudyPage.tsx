@@ -4,15 +4,43 @@ import { useAppStore } from "@/stores/app-store";
 import * as commands from "@/lib/commands";
 import { Timer } from "@/components/Timer";
 import { StudyCard } from "@/components/StudyCard";
+import { WorkSubmission } from "@/components/WorkSubmission";
 import { SessionSummary } from "@/components/SessionSummary";
-import type { Flashcard, ReviewResult } from "@/lib/types";
+import type {
+  Flashcard,
+  ReviewResult,
+  SessionAnswer,
+  SessionFolder,
+  TemplateInstance,
+  WorkGrade,
+} from "@/lib/types";
 import { Button } from "@/components/ui/button";
 import { X } from "lucide-react";
 import { shuffleArray } from "@/lib/utils";
-import { getScheduledDueCards } from "@/lib/review-policy";
+import {
+  buildSessionQueue,
+  getScheduledDueCards,
+  isLearning,
+  rotateVariants,
+} from "@/lib/review-policy";
 
 type SessionState = "loading" | "studying" | "revealing" | "summary";
 
+// Learning cards due within this window come back later in the same session
+const LEARN_AHEAD_MINUTES = 20;
+
+/** Parses `folders=id:weight,id:weight` from a mixed-session link. */
+function parseSessionFolders(value: string | null): SessionFolder[] | null {
+  if (!value) return null;
+  return value
+    .split(",")
+    .map((entry) => {
+      const [folderId, weight] = entry.split(":");
+      return { folder_id: folderId, weight: Number(weight) || 1 };
+    })
+    .filter((entry) => entry.folder_id);
+}
+
 interface CompletedCard {
   card: Flashcard;
   result: ReviewResult;
@@ -24,36 +52,121 @@ export default function StudyPage() {
   const navigate = useNavigate();
   const folderId = searchParams.get("folderId") || "all";
   const reviewAll = searchParams.get("mode") === "all";
-  const { folders } = useAppStore();
+  // A mixed session interleaves several decks (all of them when none are picked)
+  const foldersParam = searchParams.get("folders");
+  const mixed = foldersParam !== null || folderId === "all";
+  const { folders, typedAnswers } = useAppStore();
   const folder = folders.find((f) => f.id === folderId);
 
   const [state, setState] = useState<SessionState>("loading");
   const [dueCards, setDueCards] = useState<Flashcard[]>([]);
   const [currentIndex, setCurrentIndex] = useState(0);
   const [completed, setCompleted] = useState<CompletedCard[]>([]);
+  // Persisted so an interrupted session resumes where it was
+  const [sessionId, setSessionId] = useState<string | null>(null);
   const [timerRunning, setTimerRunning] = useState(false);
   const [startTime, setStartTime] = useState(0);
+  // Hints for the current card: fetched on first request, then shown one at a time
+  const [hints, setHints] = useState<string[] | null>(null);
+  const [hintsShown, setHintsShown] = useState(0);
+  const [loadingHint, setLoadingHint] = useState(false);
+  // Parametric cards get fresh values each time they are shown
+  const [instance, setInstance] = useState<TemplateInstance | null>(null);
+  // A typed answer and its automatic grade, which then decides the rating
+  const [typedAnswer, setTypedAnswer] = useState<string | null>(null);
+  const [typedCheck, setTypedCheck] = useState<{ correct: boolean; detail: string | null } | null>(
+    null
+  );
+  // Graded photo of written work; the verdict is a suggestion the student can override
+  const [work, setWork] = useState<{ grade: WorkGrade; imagePath: string } | null>(null);
 
   const currentCard = dueCards[currentIndex] || null;
+  const shownCard =
+    currentCard && instance
+      ? {
+          ...currentCard,
+          question_content: instance.question,
+          answer_type: "latex" as const,
+          answer_content: `$${instance.answer}$`,
+        }
+      : currentCard;
 
   const loadDueCards = useCallback(async () => {
     try {
       const availableFolders =
         folders.length > 0 ? folders : await commands.getFolders();
+      const sessionFolders = parseSessionFolders(foldersParam);
+      const scope = mixed ? undefined : folderId;
+      const inSession = (card: Flashcard) =>
+        !sessionFolders || sessionFolders.some((f) => f.folder_id === card.folder_id);
+      const scopeIds = sessionFolders
+        ? sessionFolders.map((f) => f.folder_id)
+        : mixed
+          ? []
+          : [folderId];
+      const mode = reviewAll ? "all" : "due";
+
+      const unfinished = await commands
+        .getUnfinishedStudySession(scopeIds, mode)
+        .catch(() => null);
+      if (unfinished && unfinished.position < unfinished.queue.length) {
+        const byId = new Map(
+          (await commands.getFlashcards(scope)).map((card) => [card.id, card])
+        );
+        // Cards deleted since are dropped, shifting the position to match
+        const resumed = unfinished.queue.flatMap((id) => byId.get(id) ?? []);
+        const position = unfinished.queue
+          .slice(0, unfinished.position)
+          .filter((id) => byId.has(id)).length;
+        if (position < resumed.length) {
+          setSessionId(unfinished.id);
+          setCompleted(
+            unfinished.answers.flatMap((answer) => {
+              const card = byId.get(answer.flashcard_id);
+              return card
+                ? [{ card, result: answer.result, responseTime: answer.response_time_seconds }]
+                : [];
+            })
+          );
+          setDueCards(resumed);
+          setCurrentIndex(position);
+          setState("studying");
+          setTimerRunning(true);
+          setStartTime(Date.now());
+          return;
+        }
+        await commands.endStudySession(unfinished.id);
+      }
+
       let cards: Flashcard[];
       let allCards: Flashcard[] | undefined;
       if (reviewAll) {
-        cards = await commands.getFlashcards(
-          folderId === "all" ? undefined : folderId
-        );
+        cards = (await commands.getFlashcards(scope)).filter(inSession);
       } else {
-        allCards = await commands.getFlashcards(
-          folderId === "all" ? undefined : folderId
-        );
-        cards = await commands.getDueFlashcards(
-          folderId === "all" ? undefined : folderId
-        );
-        cards = getScheduledDueCards(cards, availableFolders, allCards);
+        allCards = (await commands.getFlashcards(scope)).filter(inSession);
+        cards = (await commands.getDueFlashcards(scope)).filter(inSession);
+        const families = await commands.getCardFamilies();
+        cards = rotateVariants(cards, families, allCards);
+        try {
+          const ids = cards.map((card) => card.id);
+          const queue = mixed
+            ? await commands.buildSessionQueue(sessionFolders, ids)
+            : await commands.buildDailyQueue(folderId, ids);
+          const byId = new Map(cards.map((card) => [card.id, card]));
+          cards = queue.card_ids.flatMap((id) => byId.get(id) ?? []);
+        } catch {
+          // Local mode has no backend; apply the same limits here
+          cards = mixed
+            ? buildSessionQueue(
+                cards,
+                availableFolders,
+                allCards,
+                sessionFolders
+                  ? Object.fromEntries(sessionFolders.map((f) => [f.folder_id, f.weight]))
+                  : undefined
+              )
+            : getScheduledDueCards(cards, availableFolders, allCards);
+        }
       }
 
       if (useAppStore.getState().shuffleCards) {
@@ -63,6 +176,17 @@ export default function StudyPage() {
       if (cards.length === 0) {
         setState("summary");
       } else {
+        try {
+          const session = await commands.startStudySession(
+            scopeIds,
+            mode,
+            cards.map((card) => card.id)
+          );
+          setSessionId(session.id);
+        } catch (err) {
+          console.error("Failed to start study session:", err);
+          setSessionId(null);
+        }
         setDueCards(cards);
         setState("studying");
         setTimerRunning(true);
@@ -71,17 +195,116 @@ export default function StudyPage() {
     } catch {
       setState("summary");
     }
-  }, [folderId, folders, reviewAll]);
+  }, [folderId, folders, foldersParam, mixed, reviewAll]);
 
   useEffect(() => {
     loadDueCards();
   }, [loadDueCards]);
 
+  useEffect(() => {
+    setHints(null);
+    setHintsShown(0);
+    setInstance(null);
+    setTypedAnswer(null);
+    setTypedCheck(null);
+    setWork(null);
+    if (!currentCard?.template_params) return;
+    let cancelled = false;
+    commands
+      .instantiateTemplate(
+        currentCard.question_content,
+        currentCard.answer_content ?? "",
+        currentCard.template_params
+      )
+      .then((result) => {
+        if (!cancelled) setInstance(result);
+      })
+      .catch((err) => console.error("Failed to instantiate template:", err));
+    return () => {
+      cancelled = true;
+    };
+  }, [currentCard?.id]);
+
+  const handleHint = async () => {
+    if (!currentCard || loadingHint) return;
+    if (hints) {
+      setHintsShown((n) => Math.min(n + 1, hints.length));
+      return;
+    }
+    setLoadingHint(true);
+    try {
+      const result = await commands.generateHints(currentCard.id);
+      setHints(result);
+      setHintsShown(result.length > 0 ? 1 : 0);
+    } catch (err) {
+      console.error("Failed to load hints:", err);
+      setHints([]);
+    } finally {
+      setLoadingHint(false);
+    }
+  };
+
   const handleReveal = () => {
     setState("revealing");
     setTimerRunning(false);
   };
 
+  // Parametric cards are always typed; other cards when typed answers are on
+  const canType = currentCard?.template_params
+    ? instance !== null
+    : typedAnswers &&
+      Boolean(currentCard?.answer_content) &&
+      currentCard?.answer_type !== "image";
+
+  const handleCheckAnswer = async (typed: string) => {
+    if (!currentCard) return;
+    try {
+      if (currentCard.template_params && instance) {
+        const result = await commands.checkTemplateAnswer(
+          currentCard.answer_content ?? "",
+          currentCard.template_params,
+          instance.seed,
+          typed
+        );
+        setTypedCheck({
+          correct: result.correct,
+          detail: result.correct ? null : `Expected ${result.expected}`,
+        });
+      } else {
+        const result = await commands.gradeTypedAnswer(
+          currentCard.answer_type,
+          currentCard.answer_content ?? "",
+          typed
+        );
+        setTypedCheck({ correct: result.correct, detail: result.detail });
+      }
+      setTypedAnswer(typed);
+      handleReveal();
+    } catch (err) {
+      console.error("Failed to check answer:", err);
+    }
+  };
+
+  const handleWorkGraded = (grade: WorkGrade, imagePath: string) => {
+    setWork({ grade, imagePath });
+    handleReveal();
+  };
+
+  // Ends the persisted session and re-plans decks with deadlines around the new results
+  const finishSession = () => {
+    if (sessionId) {
+      commands
+        .endStudySession(sessionId)
+        .catch((err) => console.error("Failed to end study session:", err));
+    }
+    // Planning needs the native backend; local mode keeps the plain schedule
+    commands
+      .replanExams()
+      .catch((err) => console.error("Failed to re-plan exams:", err));
+    setState("summary");
+    setTimerRunning(false);
+  };
+
   const handleRate = async (correct: boolean) => {
     if (!currentCard) return;
     const responseTime = (Date.now() - startTime) / 1000;
@@ -91,20 +314,56 @@ export default function StudyPage() {
         flashcard_id: currentCard.id,
         correct,
         response_time_seconds: responseTime,
+        hints_used: hintsShown,
+        template_seed: instance?.seed ?? null,
+        typed_answer: typedAnswer,
+        work_image: work?.imagePath ?? null,
+        work_verdict: work?.grade.verdict ?? null,
+        session_id: sessionId,
       });
 
-      setCompleted((prev) => [
-        ...prev,
-        { card: currentCard, result, responseTime },
-      ]);
+      const answered = [...completed, { card: currentCard, result, responseTime }];
+      setCompleted(answered);
 
-      if (currentIndex + 1 < dueCards.length) {
+      const queue =
+        isLearning(result) &&
+        new Date(result.due_date).getTime() - Date.now() <= LEARN_AHEAD_MINUTES * 60 * 1000
+          ? [
+              ...dueCards,
+              {
+                ...currentCard,
+                ease_factor: result.ease_factor,
+                interval_days: result.interval_days,
+                due_date: result.due_date,
+                learning_state: result.learning_state,
+              },
+            ]
+          : dueCards;
+      setDueCards(queue);
+
+      if (sessionId) {
+        const answers: SessionAnswer[] = answered.map((item) => ({
+          flashcard_id: item.card.id,
+          response_time_seconds: item.responseTime,
+          result: item.result,
+        }));
+        commands
+          .saveStudySessionProgress(
+            sessionId,
+            queue.map((card) => card.id),
+            Math.min(currentIndex + 1, queue.length),
+            answers
+          )
+          .catch((err) => console.error("Failed to save study session:", err));
+      }
+
+      if (currentIndex + 1 < queue.length) {
         setCurrentIndex((prev) => prev + 1);
         setState("studying");
         setTimerRunning(true);
         setStartTime(Date.now());
       } else {
-        setState("summary");
+        finishSession();
       }
     } catch (err) {
       console.error("Failed to submit review:", err);
@@ -129,6 +388,7 @@ export default function StudyPage() {
         }
         onStudyAgain={() => {
           setCompleted([]);
+          setSessionId(null);
           setCurrentIndex(0);
           setState("loading");
           loadDueCards();
@@ -137,7 +397,7 @@ export default function StudyPage() {
     );
   }
 
-  if (!currentCard) {
+  if (!currentCard || !shownCard) {
     return (
       <p className="text-muted-foreground py-8 text-center">
         No cards to study.
@@ -161,10 +421,7 @@ export default function StudyPage() {
           variant="ghost"
           size="icon"
           className="h-8 w-8"
-          onClick={() => {
-            setState("summary");
-            setTimerRunning(false);
-          }}
+          onClick={finishSession}
           title="End session"
         >
           <X className="h-4 w-4" />
@@ -203,11 +460,22 @@ export default function StudyPage() {
       />
 
       <StudyCard
-        card={currentCard}
+        card={shownCard}
         showAnswer={state === "revealing"}
         onReveal={handleReveal}
         onRate={handleRate}
+        hints={hints ? hints.slice(0, hintsShown) : []}
+        canHint={hints === null || hintsShown < hints.length}
+        loadingHint={loadingHint}
+        onHint={handleHint}
+        onCheckAnswer={canType ? handleCheckAnswer : undefined}
+        typedCheck={typedCheck}
+        workGrade={work?.grade ?? null}
       />
+
+      {state === "studying" && !currentCard.template_params && (
+        <WorkSubmission flashcardId={currentCard.id} onGraded={handleWorkGraded} />
+      )}
     </div>
   );
 }
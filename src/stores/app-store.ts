@@ -58,6 +58,8 @@ interface AppState {
   // Study Settings
   shuffleCards: boolean;
   setShuffleCards: (shuffle: boolean) => void;
+  typedAnswers: boolean;
+  setTypedAnswers: (typed: boolean) => void;
 
   theme: "light" | "dark" | "system";
   setTheme: (theme: "light" | "dark" | "system") => void;
@@ -138,6 +140,19 @@ export const useAppStore = create<AppState>((set) => ({
     set({ shuffleCards: shuffle });
   },
 
+  typedAnswers: (() => {
+    if (typeof window !== "undefined") {
+      return localStorage.getItem("flashmath_typed_answers") === "true";
+    }
+    return false;
+  })(),
+  setTypedAnswers: (typed) => {
+    if (typeof window !== "undefined") {
+      localStorage.setItem("flashmath_typed_answers", String(typed));
+    }
+    set({ typedAnswers: typed });
+  },
+
   theme: (() => {
     if (typeof window !== "undefined") {
       const stored = localStorage.getItem("flashmath_theme");